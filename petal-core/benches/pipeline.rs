@@ -0,0 +1,175 @@
+//! Decode→playback throughput and allocations, per second of audio.
+//!
+//! ```sh
+//! cargo bench -p petal-core --features bench
+//! ```
+//!
+//! Runs on generated silent MP3 unless `PETAL_BENCH_STREAM` names a recorded stream, which
+//! exercises the decoder more like a real broadcast.
+
+use criterion::measurement::{Measurement, ValueFormatter};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use petal_core::listen::bench::Pipeline;
+use petal_core::listen::Preset;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// Length of the generated stream.
+const GENERATED_SECS: usize = 30;
+
+/// Counts every allocation, growth included, for [`Allocations`].
+struct Counting;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Measures allocations instead of time; with the throughput in seconds of audio, criterion
+/// reports allocations per second of audio.
+struct Allocations;
+
+impl Measurement for Allocations {
+    type Intermediate = u64;
+    type Value = u64;
+
+    fn start(&self) -> u64 {
+        ALLOCATIONS.load(Ordering::Relaxed)
+    }
+
+    fn end(&self, start: u64) -> u64 {
+        ALLOCATIONS.load(Ordering::Relaxed) - start
+    }
+
+    fn add(&self, a: &u64, b: &u64) -> u64 {
+        a + b
+    }
+
+    fn zero(&self) -> u64 {
+        0
+    }
+
+    fn to_f64(&self, value: &u64) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        self
+    }
+}
+
+impl ValueFormatter for Allocations {
+    fn scale_values(&self, _typical: f64, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+
+    fn scale_throughputs(
+        &self,
+        _typical: f64,
+        throughput: &Throughput,
+        values: &mut [f64],
+    ) -> &'static str {
+        let secs = match *throughput {
+            Throughput::Elements(secs) => secs as f64,
+            _ => return "allocs",
+        };
+        for value in values {
+            *value /= secs;
+        }
+        "allocs/s of audio"
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+}
+
+/// Silent MPEG-1 Layer III frames, stereo, 128 kbit/s at 44.1 kHz.
+fn generated_mp3(secs: usize) -> Vec<u8> {
+    const SAMPLES_PER_FRAME: usize = 1152;
+    let count = secs * 44_100 / SAMPLES_PER_FRAME;
+    // All-zero side info and main data decode to silence.
+    let mut frame = vec![0; 144 * 128_000 / 44_100];
+    frame[..4].copy_from_slice(&[0xff, 0xfb, 0x90, 0x00]);
+    frame.repeat(count)
+}
+
+fn stream() -> &'static [u8] {
+    static STREAM: OnceLock<Vec<u8>> = OnceLock::new();
+    STREAM.get_or_init(|| match std::env::var_os("PETAL_BENCH_STREAM") {
+        Some(path) => std::fs::read(&path)
+            .unwrap_or_else(|err| panic!("cannot read {}: {err}", path.to_string_lossy())),
+        None => generated_mp3(GENERATED_SECS),
+    })
+}
+
+/// The pipeline as configured by each case, ready to play the stream from the start.
+fn open(setup: Setup) -> Pipeline {
+    let mut pipeline = Pipeline::open(stream().to_vec()).expect("stream should open");
+    setup(&mut pipeline);
+    pipeline
+}
+
+/// Configures a [`Pipeline`] for one case.
+type Setup = fn(&mut Pipeline);
+
+const CASES: [(&str, Setup); 3] = [
+    ("spectrum", |_| {}),
+    ("hidden", |pipeline| pipeline.set_bars(false)),
+    ("eq+normalize", |pipeline| {
+        pipeline.set_equalizer(Some(Preset::BassBoost.gains()));
+        pipeline.set_normalize(true);
+    }),
+];
+
+fn bench_pipeline<M: Measurement>(c: &mut Criterion<M>, group: &str) {
+    let audio = open(|_| {}).run().expect("stream should play");
+    let mut group = c.benchmark_group(group);
+    // Elements are seconds of audio: the time results read as "× realtime".
+    group.throughput(Throughput::Elements(audio.as_secs().max(1)));
+    group.sample_size(20);
+    for (name, setup) in CASES {
+        group.bench_function(name, |b| {
+            b.iter_batched_ref(|| open(setup), |p| p.run(), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+fn throughput(c: &mut Criterion) {
+    bench_pipeline(c, "decode");
+}
+
+fn allocations(c: &mut Criterion<Allocations>) {
+    bench_pipeline(c, "allocations");
+}
+
+criterion_group!(speed, throughput);
+criterion_group! {
+    name = memory;
+    config = Criterion::default().with_measurement(Allocations);
+    targets = allocations
+}
+criterion_main!(speed, memory);
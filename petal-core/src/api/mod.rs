@@ -0,0 +1,291 @@
+//! The LISTEN.moe GraphQL API (<https://listen.moe/graphql>), for features that need an
+//! account. Calls are blocking; make them from a worker thread.
+
+mod session;
+mod types;
+
+pub use session::Session;
+pub use types::{Login, Song, User};
+
+use reqwest::blocking::Client as HttpClient;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+use crate::settings::Timeouts;
+use types::{
+    songs_from, LoginData, LoginMfaVars, LoginPayload, LoginVars, SearchData, SearchVars, SongVars,
+    UserData, UserVars,
+};
+
+const API_URL: &str = "https://listen.moe/graphql";
+
+pub type ApiResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+const SONG_FIELDS: &str = "id title artists { name nameRomaji } sources { name nameRomaji }";
+const LOGIN: &str = "mutation login($username: String!, $password: String!) {
+  login(username: $username, password: $password) { user { username displayName } token mfa }
+}";
+const LOGIN_MFA: &str = "mutation loginMFA($token: String!) {
+  loginMFA(token: $token) { user { username displayName } token }
+}";
+const REQUEST_SONG: &str = "mutation requestSong($id: Int!) { requestSong(id: $id) { id } }";
+/// Toggles: only send it for songs that are not favorites yet.
+const FAVORITE_SONG: &str = "mutation favoriteSong($id: Int!) { favoriteSong(id: $id) { id } }";
+/// Favorites fetched per call while listing all of them.
+const FAVORITES_PAGE: u32 = 100;
+
+fn search_query() -> String {
+    format!(
+        "query search($query: String!, $limit: Int) {{
+  search(query: $query, limit: $limit) {{ ... on Song {{ {SONG_FIELDS} }} }}
+}}"
+    )
+}
+
+fn user_query() -> String {
+    format!(
+        "query user($username: String!, $offset: Int, $count: Int) {{
+  user(username: $username) {{
+    username displayName
+    favorites(offset: $offset, count: $count) {{ count favorites {{ song {{ {SONG_FIELDS} }} }} }}
+  }}
+}}"
+    )
+}
+
+/// Body of a GraphQL call.
+#[derive(Debug, Serialize)]
+struct Request<'a, V> {
+    query: &'a str,
+    variables: V,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+#[derive(Clone)]
+pub struct Client {
+    http: HttpClient,
+    token: Option<String>,
+}
+
+impl Client {
+    pub fn new(proxy: Option<&str>, timeouts: Timeouts) -> ApiResult<Self> {
+        let builder = HttpClient::builder()
+            .connect_timeout(timeouts.connect())
+            .timeout(timeouts.read())
+            .user_agent(concat!(
+                env!("CARGO_PKG_NAME"),
+                " ",
+                env!("CARGO_PKG_VERSION"),
+                " (",
+                env!("CARGO_PKG_REPOSITORY"),
+                ")"
+            ));
+        let http = crate::proxy::apply(builder, proxy)?.build()?;
+        Ok(Self { http, token: None })
+    }
+
+    /// Send the token of `session` with every call.
+    pub fn with_session(mut self, session: &Session) -> Self {
+        self.token = Some(session.token.clone());
+        self
+    }
+
+    pub fn login(&self, username: &str, password: &str) -> ApiResult<Login> {
+        let data: LoginData = self.call(LOGIN, LoginVars { username, password }, None)?;
+        if data.login.mfa {
+            return Ok(Login::NeedsCode(data.login.token));
+        }
+        session_from(data.login).map(Login::Done)
+    }
+
+    /// Finish a two-factor login with the code from the authenticator app.
+    pub fn login_mfa(&self, pending_token: &str, code: &str) -> ApiResult<Session> {
+        let vars = LoginMfaVars { token: code.trim() };
+        let data: LoginData = self.call(LOGIN_MFA, vars, Some(pending_token))?;
+        session_from(data.login)
+    }
+
+    pub fn search(&self, query: &str, limit: u32) -> ApiResult<Vec<Song>> {
+        let data: SearchData = self.call(
+            &search_query(),
+            SearchVars { query, limit },
+            self.token.as_deref(),
+        )?;
+        Ok(songs_from(data.search))
+    }
+
+    /// Profile of `username`, with up to `favorites` of their favorite songs.
+    pub fn user(&self, username: &str, favorites: u32) -> ApiResult<User> {
+        self.user_page(username, 0, favorites)
+    }
+
+    /// Every favorite song of `username`, fetched a page at a time.
+    pub fn favorites(&self, username: &str) -> ApiResult<Vec<Song>> {
+        let mut songs = Vec::new();
+        loop {
+            let offset = songs.len() as u32;
+            let page = self.user_page(username, offset, FAVORITES_PAGE)?.favorites;
+            let before = songs.len();
+            songs.extend(page.songs().cloned());
+            // An empty page also ends the loop should the total change meanwhile.
+            if songs.len() as u32 >= page.count || songs.len() == before {
+                return Ok(songs);
+            }
+        }
+    }
+
+    fn user_page(&self, username: &str, offset: u32, count: u32) -> ApiResult<User> {
+        let vars = UserVars {
+            username,
+            offset,
+            count,
+        };
+        let data: UserData = self.call(&user_query(), vars, self.token.as_deref())?;
+        data.user
+            .ok_or_else(|| format!("No LISTEN.moe user named {username}").into())
+    }
+
+    /// Add `song_id` to the favorites of the logged-in account.
+    pub fn favorite_song(&self, song_id: u32) -> ApiResult<()> {
+        let Some(token) = self.token.as_deref() else {
+            return Err("Not logged in".into());
+        };
+        let _: serde_json::Value =
+            self.call(FAVORITE_SONG, SongVars { id: song_id }, Some(token))?;
+        Ok(())
+    }
+
+    /// Ask for `song_id` to be played. The API answers with an error message when the user
+    /// is out of requests or the song was played too recently.
+    pub fn request_song(&self, song_id: u32) -> ApiResult<()> {
+        let Some(token) = self.token.as_deref() else {
+            return Err("Not logged in".into());
+        };
+        let _: serde_json::Value =
+            self.call(REQUEST_SONG, SongVars { id: song_id }, Some(token))?;
+        Ok(())
+    }
+
+    fn call<V: Serialize, T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: V,
+        token: Option<&str>,
+    ) -> ApiResult<T> {
+        let body = serde_json::to_vec(&Request { query, variables })?;
+        let mut request = self
+            .http
+            .post(API_URL)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let resp = request.send()?;
+        let status = resp.status();
+        let body = resp.bytes()?;
+        // GraphQL errors come with a 200, but some servers also use 4xx with a GraphQL body.
+        match parse_response(&body) {
+            Err(_) if !status.is_success() => Err(format!("Non-success status: {status}").into()),
+            result => result,
+        }
+    }
+}
+
+fn parse_response<T: DeserializeOwned>(body: &[u8]) -> ApiResult<T> {
+    let response: Response<T> = serde_json::from_slice(body)?;
+    if let Some(error) = response.errors.into_iter().next() {
+        return Err(error.message.into());
+    }
+    response
+        .data
+        .ok_or_else(|| "Empty response from the API".into())
+}
+
+fn session_from(payload: LoginPayload) -> ApiResult<Session> {
+    let Some(account) = payload.user else {
+        return Err("The API returned no account".into());
+    };
+    Ok(Session {
+        username: account.username,
+        display_name: account.display_name,
+        token: payload.token,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn graphql_errors_become_the_message() {
+        let body = br#"{"data":null,"errors":[{"message":"You have no requests left"}]}"#;
+        let err = parse_response::<serde_json::Value>(body).unwrap_err();
+        assert_eq!(err.to_string(), "You have no requests left");
+    }
+
+    #[test]
+    fn search_keeps_songs_only() {
+        let body = r#"{"data":{"search":[
+            {"id":12,"title":"Connect",
+             "artists":[{"name":"ClariS","nameRomaji":null}],
+             "sources":[{"name":"魔法少女まどか☆マギカ","nameRomaji":"Mahou Shoujo Madoka Magica"}]},
+            {"name":"Artist result"}
+        ]}}"#;
+        let data: SearchData = parse_response(body.as_bytes()).unwrap();
+        let songs = songs_from(data.search);
+        assert_eq!(songs.len(), 1);
+        assert_eq!(songs[0].id, 12);
+        assert_eq!(songs[0].artists(), "ClariS");
+        assert_eq!(songs[0].sources(), "Mahou Shoujo Madoka Magica");
+    }
+
+    #[test]
+    fn login_with_two_factor_needs_a_code() {
+        let body = br#"{"data":{"login":{"user":null,"token":"pending","mfa":true}}}"#;
+        let data: LoginData = parse_response(body).unwrap();
+        assert!(data.login.mfa);
+        assert_eq!(data.login.token, "pending");
+    }
+
+    #[test]
+    fn user_with_favorites() {
+        let body = br#"{"data":{"user":{"username":"kana","displayName":"Kana",
+            "favorites":{"count":2,"favorites":[
+            {"song":{"id":1,"title":"One","artists":[],"sources":[]}},
+            {"song":{"id":2,"title":"Two","artists":[],"sources":[]}}]}}}}"#;
+        let data: UserData = parse_response(body).unwrap();
+        let user = data.user.unwrap();
+        assert_eq!(user.display_name.as_deref(), Some("Kana"));
+        assert_eq!(user.favorites.count, 2);
+        let ids: Vec<u32> = user.favorites.songs().map(|song| song.id).collect();
+        assert_eq!(ids, [1, 2]);
+    }
+
+    #[test]
+    fn variables_use_graphql_names() {
+        let request = Request {
+            query: "q",
+            variables: SearchVars {
+                query: "connect",
+                limit: 5,
+            },
+        };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"query":"q","variables":{"query":"connect","limit":5}}"#
+        );
+    }
+}
@@ -0,0 +1,92 @@
+//! The logged-in account, remembered between runs.
+//!
+//! The login token is kept in its own file next to the settings, so it never ends up in a
+//! diagnostics bundle.
+
+use dirs_next as dirs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A logged-in account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub username: String,
+    pub display_name: Option<String>,
+    pub token: String,
+}
+
+impl Session {
+    /// The saved session, if the user logged in before.
+    pub fn load() -> Option<Self> {
+        let path = session_path()?;
+        let json = fs::read_to_string(&path).ok()?;
+        match serde_json::from_str(&json) {
+            Ok(session) => Some(session),
+            Err(err) => {
+                eprintln!("Ignoring unreadable session {}: {err}", path.display());
+                None
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = session_path() else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| {
+                let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+                write_private(&path, json.as_bytes())
+            });
+        if let Err(err) = result {
+            eprintln!("Failed to save session to {}: {err}", path.display());
+        }
+    }
+
+    /// Forget the saved session.
+    pub fn clear() {
+        let Some(path) = session_path() else {
+            return;
+        };
+        if let Err(err) = fs::remove_file(&path) {
+            if err.kind() != io::ErrorKind::NotFound {
+                eprintln!("Failed to remove session {}: {err}", path.display());
+            }
+        }
+    }
+
+    /// Name to greet the user with.
+    pub fn name(&self) -> &str {
+        self.display_name
+            .as_deref()
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&self.username)
+    }
+}
+
+fn session_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(crate::APP_ID).join("session.json"))
+}
+
+/// The token grants access to the account: keep it readable by the user only.
+#[cfg(unix)]
+fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(contents)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    fs::write(path, contents)
+}
@@ -0,0 +1,169 @@
+//! Request variables and response data of the GraphQL operations, as serde types.
+
+use serde::{Deserialize, Serialize};
+
+use super::Session;
+
+/// A song from the LISTEN.moe library.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Song {
+    pub id: u32,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub artists: Vec<Named>,
+    #[serde(default)]
+    pub sources: Vec<Named>,
+}
+
+impl Song {
+    pub fn title(&self) -> &str {
+        self.title.as_deref().unwrap_or_default()
+    }
+
+    /// Artist names, comma separated.
+    pub fn artists(&self) -> String {
+        join_names(&self.artists)
+    }
+
+    /// Anime or game names, comma separated.
+    pub fn sources(&self) -> String {
+        join_names(&self.sources)
+    }
+}
+
+/// An artist or source, with its romanized name when there is one.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Named {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(rename = "nameRomaji", default)]
+    pub name_romaji: Option<String>,
+}
+
+impl Named {
+    pub fn display_name(&self) -> Option<&str> {
+        self.name_romaji
+            .as_deref()
+            .or(self.name.as_deref())
+            .filter(|name| !name.is_empty())
+    }
+}
+
+fn join_names(names: &[Named]) -> String {
+    names
+        .iter()
+        .filter_map(Named::display_name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Public profile of an account, with the first page of its favorites.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct User {
+    pub username: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    pub favorites: Favorites,
+}
+
+/// A page of favorites and how many there are in total.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Favorites {
+    pub count: u32,
+    #[serde(rename = "favorites", default)]
+    entries: Vec<Favorite>,
+}
+
+impl Favorites {
+    pub fn songs(&self) -> impl Iterator<Item = &Song> {
+        self.entries.iter().map(|favorite| &favorite.song)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Favorite {
+    song: Song,
+}
+
+/// Outcome of a login attempt.
+#[derive(Debug)]
+pub enum Login {
+    Done(Session),
+    /// The account has two-factor authentication; finish with
+    /// [`Client::login_mfa`](super::Client::login_mfa) using this intermediate token.
+    NeedsCode(String),
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct LoginVars<'a> {
+    pub username: &'a str,
+    pub password: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct LoginMfaVars<'a> {
+    pub token: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct LoginData {
+    #[serde(alias = "loginMFA")]
+    pub login: LoginPayload,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct LoginPayload {
+    #[serde(default)]
+    pub user: Option<Account>,
+    pub token: String,
+    #[serde(default)]
+    pub mfa: bool,
+}
+
+/// The account a login belongs to.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct Account {
+    pub username: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct SearchVars<'a> {
+    pub query: &'a str,
+    pub limit: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct SearchData {
+    /// Songs, artists and albums mixed; see [`songs_from`].
+    pub search: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct UserVars<'a> {
+    pub username: &'a str,
+    pub offset: u32,
+    pub count: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct UserData {
+    pub user: Option<User>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct SongVars {
+    pub id: u32,
+}
+
+/// Search results can include artists and albums; keep the songs.
+pub(super) fn songs_from(results: Vec<serde_json::Value>) -> Vec<Song> {
+    results
+        .into_iter()
+        .filter_map(|value| serde_json::from_value(value).ok())
+        .collect()
+}
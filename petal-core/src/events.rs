@@ -0,0 +1,149 @@
+use std::sync::{mpsc, Arc, Mutex};
+
+use crate::meta::TrackInfo;
+use crate::station::Station;
+
+/// Audio pipeline state, from the user's intent down to what the stream is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaybackState {
+    Stopped,
+    /// Opening the first connection.
+    Connecting,
+    /// Connected, queueing audio before playing: at first, or after the output ran dry.
+    Buffering,
+    Playing,
+    Paused,
+    /// The connection dropped; trying again.
+    Reconnecting,
+    /// The stream gave up; playback needs a restart.
+    Error,
+}
+
+impl PlaybackState {
+    /// Lowercase name for machine-readable outputs.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Stopped => "stopped",
+            Self::Connecting => "connecting",
+            Self::Buffering => "buffering",
+            Self::Playing => "playing",
+            Self::Paused => "paused",
+            Self::Reconnecting => "reconnecting",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// Metadata websocket state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GatewayState {
+    Connecting,
+    Connected,
+    Reconnecting,
+}
+
+/// Everything the player reports to its frontends.
+#[derive(Debug, Clone)]
+pub enum PlayerEvent {
+    /// Playback reached a new track (already corrected for pause lag).
+    TrackChanged(Box<TrackInfo>),
+    /// The track playing was sent again, e.g. after a resume, reconnect or wake. Frontends
+    /// refresh what they show; anything that counts plays should ignore it.
+    TrackResynced(Box<TrackInfo>),
+    StateChanged(PlaybackState),
+    GatewayStateChanged(GatewayState),
+    StationChanged(Station),
+    /// The output device went away (e.g. headphones unplugged); playback moved to the
+    /// new default output.
+    OutputLost,
+    /// The stream carried nothing but silence for too long and is being reconnected.
+    Silence,
+    /// Little audio is left queued while playing; the stream may run dry and buffer.
+    BufferLow,
+    /// The system woke from sleep; the audio and metadata connections are likely dead.
+    Resumed,
+    /// A user-facing, translated description of a failure.
+    Error(String),
+}
+
+/// Broadcast channel: every subscriber gets its own copy of each event.
+///
+/// Emitting never blocks; subscribers that dropped their receiver are pruned. A
+/// `TrackChanged` for the track already playing goes out as `TrackResynced`.
+#[derive(Debug, Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<mpsc::Sender<PlayerEvent>>>>,
+    current: Arc<Mutex<Option<TrackInfo>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(&self) -> mpsc::Receiver<PlayerEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    pub fn emit(&self, event: PlayerEvent) {
+        let event = match event {
+            PlayerEvent::TrackChanged(info) => {
+                let mut current = self.current.lock().unwrap();
+                if current
+                    .as_ref()
+                    .is_some_and(|current| current.same_play(&info))
+                {
+                    PlayerEvent::TrackResynced(info)
+                } else {
+                    *current = Some((*info).clone());
+                    PlayerEvent::TrackChanged(info)
+                }
+            }
+            event => event,
+        };
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|tx| tx.send(event.clone()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn track(song_id: u32, start: u64) -> Box<TrackInfo> {
+        Box::new(TrackInfo {
+            song_id: Some(song_id),
+            artist: "Artist".into(),
+            title: "Title".into(),
+            album_cover: None,
+            artist_image: None,
+            start_time_utc: SystemTime::UNIX_EPOCH + Duration::from_secs(start),
+            duration_secs: 200,
+            source: None,
+            requester: None,
+            event: None,
+            listeners: None,
+        })
+    }
+
+    #[test]
+    fn repeated_track_is_a_resync() {
+        let bus = EventBus::new();
+        let rx = bus.subscribe();
+        bus.emit(PlayerEvent::TrackChanged(track(1, 100)));
+        bus.emit(PlayerEvent::TrackChanged(track(1, 100)));
+        bus.emit(PlayerEvent::TrackChanged(track(2, 300)));
+        // The same song again later is a new play.
+        bus.emit(PlayerEvent::TrackChanged(track(1, 500)));
+        let events: Vec<_> = rx.try_iter().collect();
+        assert!(matches!(events[0], PlayerEvent::TrackChanged(_)));
+        assert!(matches!(events[1], PlayerEvent::TrackResynced(_)));
+        assert!(matches!(events[2], PlayerEvent::TrackChanged(_)));
+        assert!(matches!(events[3], PlayerEvent::TrackChanged(_)));
+    }
+}
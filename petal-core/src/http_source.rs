@@ -0,0 +1,71 @@
+use crate::record::{RecordEvent, RecordTap, HEAD_CAPTURE_BYTES};
+
+#[derive(Debug)]
+pub struct HttpSource {
+    pub inner: reqwest::blocking::Response,
+    tap: RecordTap,
+    head: Vec<u8>,
+    head_sent: bool,
+}
+
+impl HttpSource {
+    pub fn new(inner: reqwest::blocking::Response, tap: RecordTap) -> Self {
+        Self {
+            inner,
+            tap,
+            head: Vec::new(),
+            head_sent: false,
+        }
+    }
+
+    /// Hand the received bytes, untouched, to the recorder if one is attached.
+    fn tee(&mut self, bytes: &[u8]) {
+        if self.head.len() < HEAD_CAPTURE_BYTES {
+            let take = bytes.len().min(HEAD_CAPTURE_BYTES - self.head.len());
+            self.head.extend_from_slice(&bytes[..take]);
+        }
+        let tap = self.tap.lock().unwrap();
+        match tap.as_ref() {
+            Some(tx) => {
+                if !self.head_sent {
+                    let _ = tx.send(RecordEvent::Head(self.head.clone()));
+                    self.head_sent = true;
+                }
+                let _ = tx.send(RecordEvent::Data(bytes.to_vec()));
+            }
+            None => self.head_sent = false,
+        }
+    }
+}
+
+impl std::io::Read for HttpSource {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self
+            .inner
+            .read(buf)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        if n > 0 {
+            self.tee(&buf[..n]);
+        }
+        Ok(n)
+    }
+}
+
+impl std::io::Seek for HttpSource {
+    fn seek(&mut self, _pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "seeking not supported on HTTP stream",
+        ))
+    }
+}
+
+impl symphonia::core::io::MediaSource for HttpSource {
+    fn is_seekable(&self) -> bool {
+        false
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        None
+    }
+}
@@ -0,0 +1,26 @@
+//! Everything petal does besides drawing windows: the stations, the audio stream, track
+//! info from the LISTEN.moe gateway, recording and the persisted settings.
+//!
+//! A frontend creates an [`events::EventBus`], hands it to [`listen::Listen`] for audio
+//! and [`meta::Meta`] for track info, and drains a subscription to show
+//! [`events::PlayerEvent`]s. Nothing here depends on GTK, so it also serves headless
+//! frontends.
+
+pub mod api;
+pub mod cache;
+pub mod events;
+pub mod http_source;
+pub mod listen;
+pub mod locale;
+pub mod log;
+pub mod meta;
+pub mod proxy;
+pub mod record;
+pub mod settings;
+pub mod station;
+
+/// Application id; also names the per-user config, cache and data directories.
+#[cfg(debug_assertions)]
+pub const APP_ID: &str = "io.github.noobping.listenmoe_beta";
+#[cfg(not(debug_assertions))]
+pub const APP_ID: &str = "io.github.noobping.listenmoe";
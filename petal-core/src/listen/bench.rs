@@ -0,0 +1,172 @@
+//! The decode→playback path on a stream held in memory, without a network connection or an
+//! output device, for the benchmarks in `benches/`. Every stage runs as during playback:
+//! decoding, the spectrum, equalizer, normalization, level meter and the pooled chunks
+//! queued for the sink.
+
+use std::collections::VecDeque;
+use std::io::Cursor;
+use std::sync::atomic::AtomicU32;
+use std::sync::Arc;
+use std::time::Duration;
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatReader;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::probe::Hint;
+
+use super::dsp::Dsp;
+use super::levels::Levels;
+use super::mix::ChannelMix;
+use super::pool::{PooledSamples, SamplePool};
+use super::silence::Silence;
+use super::stream::CHUNK_MS;
+use super::viz::{
+    decode_and_process_packet, make_fft_state, DecodeState, FftVizState, PacketOutcome, VizParams,
+};
+use super::{Gains, Result, N_BARS};
+
+/// Chunks waiting for the sink: a second of audio, as when playback keeps up.
+const QUEUED_CHUNKS: usize = 1000 / CHUNK_MS as usize;
+/// Long enough that silent test streams never count as dead air.
+const SILENCE_LIMIT: Duration = Duration::from_secs(24 * 60 * 60);
+
+pub struct Pipeline {
+    format: Box<dyn FormatReader>,
+    track_id: u32,
+    decoder: Box<dyn Decoder>,
+    decoder_opts: DecoderOptions,
+    decode_state: DecodeState,
+    fft_state: FftVizState,
+    spectrum_bits: Arc<Vec<AtomicU32>>,
+    bars: bool,
+    dsp: Dsp,
+    levels: Levels,
+    silence: Silence,
+    pool: SamplePool,
+    queue: VecDeque<PooledSamples>,
+}
+
+impl Pipeline {
+    /// Probes `stream`, a complete MP3 or Ogg stream.
+    pub fn open(stream: Vec<u8>) -> Result<Self> {
+        let mss = MediaSourceStream::new(Box::new(Cursor::new(stream)), Default::default());
+        let probed = symphonia::default::get_probe().format(
+            &Hint::new(),
+            mss,
+            &Default::default(),
+            &Default::default(),
+        )?;
+        let format = probed.format;
+        let track = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| "no supported audio tracks".to_string())?;
+        let track_id = track.id;
+        let decoder_opts = DecoderOptions::default();
+        let decoder = symphonia::default::get_codecs().make(&track.codec_params, &decoder_opts)?;
+
+        Ok(Self {
+            format,
+            track_id,
+            decoder,
+            decoder_opts,
+            decode_state: DecodeState {
+                sample_buf: None,
+                channels: 0,
+                sample_rate: 0,
+                decode_errors: 0,
+            },
+            fft_state: make_fft_state(N_BARS),
+            spectrum_bits: Arc::new((0..N_BARS).map(|_| AtomicU32::new(0)).collect()),
+            bars: true,
+            dsp: Dsp::new(None, false, ChannelMix::default()),
+            levels: Levels::default(),
+            silence: Silence::new(Some(SILENCE_LIMIT)),
+            pool: SamplePool::default(),
+            queue: VecDeque::with_capacity(QUEUED_CHUNKS + 1),
+        })
+    }
+
+    /// Whether the spectrum is computed, as while the window shows it.
+    pub fn set_bars(&mut self, bars: bool) {
+        self.bars = bars;
+    }
+
+    pub fn set_equalizer(&mut self, gains: Option<Gains>) {
+        self.dsp.eq.set_gains(gains);
+    }
+
+    pub fn set_normalize(&mut self, on: bool) {
+        self.dsp.loudness.set_enabled(on);
+    }
+
+    /// Plays the rest of the stream and returns how much audio that was.
+    pub fn run(&mut self) -> Result<Duration> {
+        let viz = VizParams {
+            peak_attack: 0.35,
+            peak_release: 0.995,
+            sensitivity: 1.25,
+            curve: 0.75,
+        };
+        let mut frames = 0u64;
+        let mut rate = 0;
+
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(err))
+                    if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    break
+                }
+                Err(err) => return Err(err.into()),
+            };
+            let (outcome, audio) = decode_and_process_packet(
+                &packet,
+                &mut self.format,
+                &mut self.track_id,
+                &mut self.decoder,
+                &self.decoder_opts,
+                self.bars,
+                &self.spectrum_bits,
+                &mut self.decode_state,
+                &mut self.fft_state,
+                viz,
+            )?;
+            if matches!(outcome, PacketOutcome::Reconnect) {
+                return Err("fatal decode error".into());
+            }
+            let Some((channels, sample_rate)) = audio else {
+                continue;
+            };
+            let state = &mut self.decode_state;
+            self.silence.feed(channels, sample_rate, state.samples());
+            self.dsp.process(channels, sample_rate, state.samples_mut());
+            self.levels.measure(channels, state.samples());
+            for chunk in self
+                .pool
+                .chunks(channels, sample_rate, state.samples(), CHUNK_MS)
+            {
+                self.queue.push_back(chunk);
+                if self.queue.len() > QUEUED_CHUNKS {
+                    play(self.queue.pop_front());
+                }
+            }
+            frames += (state.samples().len() / usize::from(channels.max(1))) as u64;
+            rate = sample_rate;
+        }
+        self.queue.drain(..).for_each(|chunk| play(Some(chunk)));
+
+        Ok(Duration::from_secs_f64(
+            frames as f64 / f64::from(rate.max(1)),
+        ))
+    }
+}
+
+/// Reads a chunk the way the sink does, which hands its buffer back to the pool.
+fn play(chunk: Option<PooledSamples>) {
+    if let Some(chunk) = chunk {
+        std::hint::black_box(chunk.sum::<f32>());
+    }
+}
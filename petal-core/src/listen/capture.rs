@@ -0,0 +1,80 @@
+//! Reading a station's stream without playing it, for scheduled recordings. The bytes
+//! go through the same record tap as during playback and are otherwise dropped.
+
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::http_source::HttpSource;
+use crate::record::RecordTap;
+use crate::settings::Timeouts;
+use crate::station::{Station, StreamFormat};
+
+use super::stream::{build_client, build_useragent};
+
+/// Pause before trying the next endpoint after a failure.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Keeps a connection to the stream open, reconnecting as needed, until dropped.
+#[derive(Debug)]
+pub struct Capture {
+    stop: Arc<AtomicBool>,
+}
+
+impl Capture {
+    pub fn start(
+        station: Station,
+        format: StreamFormat,
+        proxy: Option<String>,
+        timeouts: Timeouts,
+        tap: RecordTap,
+    ) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        thread::spawn(move || {
+            let useragent = build_useragent();
+            let mut buf = vec![0; 16 * 1024];
+            for (_, url) in station.stream_endpoints(format).into_iter().cycle() {
+                if stopped.load(Ordering::Relaxed) {
+                    return;
+                }
+                let response = build_client(proxy.as_deref(), timeouts).and_then(|client| {
+                    Ok(client.get(url).header("User-Agent", &useragent).send()?)
+                });
+                let response = match response {
+                    Ok(response) if response.status().is_success() => response,
+                    Ok(response) => {
+                        eprintln!("Recording {url} failed: HTTP status {}", response.status());
+                        thread::sleep(RETRY_DELAY);
+                        continue;
+                    }
+                    Err(err) => {
+                        eprintln!("Recording {url} failed: {err}");
+                        thread::sleep(RETRY_DELAY);
+                        continue;
+                    }
+                };
+                let mut source = HttpSource::new(response, tap.clone());
+                while !stopped.load(Ordering::Relaxed) {
+                    match source.read(&mut buf) {
+                        Ok(0) => break,
+                        Ok(_) => {}
+                        Err(err) => {
+                            eprintln!("Recording {url} dropped: {err}");
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        Self { stop }
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
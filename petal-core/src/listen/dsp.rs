@@ -0,0 +1,28 @@
+use super::eq::{Equalizer, Gains};
+use super::loudness::Loudness;
+use super::mix::ChannelMix;
+
+/// Processing between the decoder and the output, applied in field order.
+#[derive(Debug)]
+pub(super) struct Dsp {
+    pub(super) eq: Equalizer,
+    pub(super) loudness: Loudness,
+    pub(super) mix: ChannelMix,
+}
+
+impl Dsp {
+    pub(super) fn new(equalizer: Option<Gains>, normalize: bool, mix: ChannelMix) -> Self {
+        Self {
+            eq: Equalizer::new(equalizer),
+            loudness: Loudness::new(normalize),
+            mix,
+        }
+    }
+
+    /// Process interleaved samples in place.
+    pub(super) fn process(&mut self, channels: u16, rate: u32, samples: &mut [f32]) {
+        self.eq.process(channels, rate, samples);
+        self.loudness.process(channels, rate, samples);
+        self.mix.process(channels, samples);
+    }
+}
@@ -0,0 +1,212 @@
+//! Ten-band graphic equalizer between the decoder and the output: one peaking filter per
+//! octave (biquads from the RBJ audio EQ cookbook), with a preamp that keeps boosted
+//! bands from clipping.
+
+use std::f64::consts::PI;
+
+/// Center frequencies in Hz.
+pub const BANDS: [f32; 10] = [
+    31.0, 62.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0,
+];
+/// Gain range of each band, in dB either way.
+pub const MAX_GAIN_DB: f32 = 12.0;
+/// About one octave wide.
+const Q: f64 = 1.41;
+
+/// Gain per band in dB.
+pub type Gains = [f32; BANDS.len()];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    Flat,
+    BassBoost,
+    Vocal,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Flat, Preset::BassBoost, Preset::Vocal];
+
+    pub fn gains(self) -> Gains {
+        match self {
+            Preset::Flat => [0.0; BANDS.len()],
+            Preset::BassBoost => [6.0, 5.0, 4.0, 2.5, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            Preset::Vocal => [-2.0, -2.0, -1.0, 0.0, 2.0, 3.5, 3.5, 2.0, 0.0, -1.0],
+        }
+    }
+
+    /// The preset with exactly these gains, if any.
+    pub fn matching(gains: &Gains) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.gains() == *gains)
+    }
+}
+
+/// Filter coefficients, normalized so that a0 is 1.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Biquad {
+    pub(super) b0: f64,
+    pub(super) b1: f64,
+    pub(super) b2: f64,
+    pub(super) a1: f64,
+    pub(super) a2: f64,
+}
+
+impl Biquad {
+    /// Run one sample through the filter, updating its memory.
+    pub(super) fn run(&self, h: &mut History, x: f64) -> f64 {
+        let y = self.b0 * x + self.b1 * h.x1 + self.b2 * h.x2 - self.a1 * h.y1 - self.a2 * h.y2;
+        *h = History {
+            x1: x,
+            x2: h.x1,
+            y1: y,
+            y2: h.y1,
+        };
+        y
+    }
+
+    fn peaking(freq: f64, gain_db: f64, rate: f64) -> Self {
+        let a = 10f64.powf(gain_db / 40.0);
+        let w0 = 2.0 * PI * freq / rate;
+        let alpha = w0.sin() / (2.0 * Q);
+        let cos = w0.cos();
+        let a0 = 1.0 + alpha / a;
+        Self {
+            b0: (1.0 + alpha * a) / a0,
+            b1: -2.0 * cos / a0,
+            b2: (1.0 - alpha * a) / a0,
+            a1: -2.0 * cos / a0,
+            a2: (1.0 - alpha / a) / a0,
+        }
+    }
+}
+
+/// Filter memory of one band on one channel: the last two inputs and outputs.
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct History {
+    x1: f64,
+    x2: f64,
+    y1: f64,
+    y2: f64,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Equalizer {
+    gains: Option<Gains>,
+    /// Designed for this rate and channel count; rebuilt when either changes.
+    rate: u32,
+    channels: usize,
+    filters: Vec<Biquad>,
+    /// `filters.len()` entries per channel.
+    history: Vec<History>,
+    preamp: f32,
+}
+
+impl Equalizer {
+    /// `None`, or all bands flat, bypasses the equalizer.
+    pub(super) fn new(gains: Option<Gains>) -> Self {
+        let mut eq = Self::default();
+        eq.set_gains(gains);
+        eq
+    }
+
+    pub(super) fn set_gains(&mut self, gains: Option<Gains>) {
+        self.gains = gains.filter(|gains| gains.iter().any(|gain| *gain != 0.0));
+        self.rate = 0; // redesign on the next block
+    }
+
+    /// Filter interleaved samples in place.
+    pub(super) fn process(&mut self, channels: u16, rate: u32, samples: &mut [f32]) {
+        let Some(gains) = self.gains else {
+            return;
+        };
+        let channels = usize::from(channels);
+        if channels == 0 || rate == 0 {
+            return;
+        }
+        if rate != self.rate || channels != self.channels {
+            self.design(&gains, channels, rate);
+        }
+
+        let bands = self.filters.len();
+        for frame in samples.chunks_exact_mut(channels) {
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                let history = &mut self.history[channel * bands..(channel + 1) * bands];
+                let mut x = f64::from(*sample * self.preamp);
+                for (filter, h) in self.filters.iter().zip(history) {
+                    x = filter.run(h, x);
+                }
+                *sample = x as f32;
+            }
+        }
+    }
+
+    fn design(&mut self, gains: &Gains, channels: usize, rate: u32) {
+        let nyquist = rate as f32 / 2.0;
+        self.filters = BANDS
+            .iter()
+            .zip(gains)
+            // Bands at or above Nyquist cannot be shaped; skip them.
+            .filter(|(freq, gain)| **gain != 0.0 && **freq < nyquist * 0.9)
+            .map(|(freq, gain)| {
+                let gain = gain.clamp(-MAX_GAIN_DB, MAX_GAIN_DB);
+                Biquad::peaking(f64::from(*freq), f64::from(gain), f64::from(rate))
+            })
+            .collect();
+        self.history = vec![History::default(); self.filters.len() * channels];
+        let boost = gains.iter().fold(0.0f32, |max, gain| max.max(*gain));
+        self.preamp = 10f32.powf(-boost.min(MAX_GAIN_DB) / 20.0);
+        self.rate = rate;
+        self.channels = channels;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Peak level of a sine at `freq` after settling through the equalizer.
+    fn level(eq: &mut Equalizer, freq: f32) -> f32 {
+        let rate = 48_000;
+        let mut samples: Vec<f32> = (0..rate)
+            .map(|i| (2.0 * std::f32::consts::PI * freq * i as f32 / rate as f32).sin() * 0.25)
+            .collect();
+        eq.process(1, rate, &mut samples);
+        samples[rate as usize / 2..]
+            .iter()
+            .fold(0.0, |max, s| max.max(s.abs()))
+    }
+
+    #[test]
+    fn flat_is_bypassed() {
+        let mut eq = Equalizer::new(Some(Preset::Flat.gains()));
+        let mut samples = [0.5, -0.25];
+        eq.process(2, 44_100, &mut samples);
+        assert_eq!(samples, [0.5, -0.25]);
+    }
+
+    #[test]
+    fn boosts_and_cuts_bands() {
+        let mut gains = [0.0; BANDS.len()];
+        gains[0] = 12.0;
+        gains[9] = -12.0;
+        let mut eq = Equalizer::new(Some(gains));
+        let preamp = 10f32.powf(-12.0 / 20.0);
+        // +12 dB at 31 Hz, less the preamp: back to the input level.
+        let low = level(&mut eq, 31.0);
+        assert!((low - 0.25).abs() < 0.02, "{low}");
+        let mid = level(&mut eq, 1000.0);
+        assert!((mid - 0.25 * preamp).abs() < 0.01, "{mid}");
+        let high = level(&mut eq, 16000.0);
+        assert!(high < 0.25 * preamp * 0.4, "{high}");
+    }
+
+    #[test]
+    fn finds_presets() {
+        assert_eq!(
+            Preset::matching(&Preset::Vocal.gains()),
+            Some(Preset::Vocal)
+        );
+        assert_eq!(Preset::matching(&[1.0; BANDS.len()]), None);
+    }
+}
@@ -0,0 +1,127 @@
+//! Volume ramps, so playback does not start or end with a full-volume cut. They move the
+//! output volume rather than the samples: decoded audio is played well after it is
+//! decoded, but a volume change is heard right away.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::output::AudioOut;
+
+/// How often the volume moves while fading out.
+const STEP: Duration = Duration::from_millis(10);
+
+/// The user's volume and any fade-in applied on top of it. The only writer of the output
+/// volume while a stream runs.
+#[derive(Debug)]
+pub(super) struct Fade {
+    length: Duration,
+    volume: f32,
+    /// When the current fade-in started.
+    started: Option<Instant>,
+}
+
+impl Fade {
+    /// A zero `length` turns fading off.
+    pub(super) fn new(length: Duration, volume: f32) -> Self {
+        Self {
+            length,
+            volume,
+            started: None,
+        }
+    }
+
+    pub(super) fn set_length(&mut self, length: Duration) {
+        self.length = length;
+    }
+
+    /// Change the volume; during a fade-in it becomes the level the fade ends at.
+    pub(super) fn set_volume(&mut self, out: &mut AudioOut, volume: f32) {
+        self.volume = volume;
+        if self.started.is_none() {
+            out.set_volume(volume);
+        }
+    }
+
+    /// The volume to restore on a new output, without the fade.
+    pub(super) fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// Start from silence; call right before the output plays. Without fading, this
+    /// restores the volume after a `fade_out`.
+    pub(super) fn fade_in(&mut self, out: &mut AudioOut) {
+        if self.length.is_zero() {
+            self.started = None;
+            out.set_volume(self.volume);
+            return;
+        }
+        self.started = Some(Instant::now());
+        out.set_volume(0.0);
+    }
+
+    /// Move a running fade-in along. Returns true while it lasts, to be called again soon.
+    pub(super) fn update(&mut self, out: &mut AudioOut) -> bool {
+        let Some(started) = self.started else {
+            return false;
+        };
+        let progress = self.progress(started.elapsed());
+        out.set_volume(self.volume * progress * progress);
+        if progress >= 1.0 {
+            self.started = None;
+        }
+        self.started.is_some()
+    }
+
+    /// Lower the volume to silence while the queued audio plays on, then leave it there
+    /// until the next `fade_in`. Blocks for the length of the fade.
+    pub(super) fn fade_out(&mut self, out: &mut AudioOut) {
+        if self.length.is_zero() {
+            return;
+        }
+        // Down from wherever a fade-in got to.
+        let from = match self.started.take() {
+            Some(started) => {
+                let progress = self.progress(started.elapsed());
+                self.volume * progress * progress
+            }
+            None => self.volume,
+        };
+        let started = Instant::now();
+        loop {
+            let left = 1.0 - self.progress(started.elapsed());
+            out.set_volume(from * left * left);
+            if left <= 0.0 {
+                return;
+            }
+            thread::sleep(STEP);
+        }
+    }
+
+    /// Share of the fade that has passed, 0.0 to 1.0.
+    fn progress(&self, elapsed: Duration) -> f32 {
+        if self.length.is_zero() {
+            return 1.0;
+        }
+        (elapsed.as_secs_f32() / self.length.as_secs_f32()).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_runs_over_the_length() {
+        let fade = Fade::new(Duration::from_millis(400), 1.0);
+        assert_eq!(fade.progress(Duration::ZERO), 0.0);
+        assert_eq!(fade.progress(Duration::from_millis(100)), 0.25);
+        assert_eq!(fade.progress(Duration::from_millis(400)), 1.0);
+        assert_eq!(fade.progress(Duration::from_secs(5)), 1.0);
+    }
+
+    #[test]
+    fn done_right_away_when_off() {
+        let fade = Fade::new(Duration::ZERO, 1.0);
+        assert_eq!(fade.progress(Duration::ZERO), 1.0);
+    }
+}
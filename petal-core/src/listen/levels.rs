@@ -0,0 +1,86 @@
+//! Per-channel signal levels of the audio on its way to the output, for the level meter.
+//! The worker stores them for every decoded packet; the UI reads whenever it redraws.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+#[derive(Debug, Default)]
+struct ChannelLevel {
+    rms: AtomicU32,
+    peak: AtomicU32,
+}
+
+/// Left and right; mono sources show the same level on both.
+#[derive(Debug, Default)]
+pub struct Levels {
+    channels: [ChannelLevel; 2],
+}
+
+/// Linear levels of one channel, 0.0 to 1.0 for full scale.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Level {
+    pub rms: f32,
+    pub peak: f32,
+}
+
+impl Levels {
+    pub fn get(&self) -> [Level; 2] {
+        self.channels.each_ref().map(|channel| Level {
+            rms: f32::from_bits(channel.rms.load(Ordering::Relaxed)),
+            peak: f32::from_bits(channel.peak.load(Ordering::Relaxed)),
+        })
+    }
+
+    pub(super) fn measure(&self, channels: u16, samples: &[f32]) {
+        let channels = usize::from(channels);
+        let frames = samples.len() / channels.max(1);
+        if frames == 0 {
+            return;
+        }
+        for (i, level) in self.channels.iter().enumerate() {
+            let channel = i.min(channels - 1);
+            let (sum, peak) = samples
+                .iter()
+                .skip(channel)
+                .step_by(channels)
+                .fold((0.0f32, 0.0f32), |(sum, peak), s| {
+                    (sum + s * s, peak.max(s.abs()))
+                });
+            let rms = (sum / frames as f32).sqrt();
+            level.rms.store(rms.to_bits(), Ordering::Relaxed);
+            level.peak.store(peak.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    pub(super) fn clear(&self) {
+        for level in &self.channels {
+            level.rms.store(0, Ordering::Relaxed);
+            level.peak.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_each_channel() {
+        let levels = Levels::default();
+        levels.measure(2, &[0.5, 0.0, -0.5, 0.25]);
+        let [left, right] = levels.get();
+        assert_eq!(
+            left,
+            Level {
+                rms: 0.5,
+                peak: 0.5
+            }
+        );
+        assert_eq!(right.peak, 0.25);
+        assert!((right.rms - 0.25 / 2f32.sqrt()).abs() < 1e-6);
+
+        levels.measure(1, &[0.1, -0.1]);
+        assert_eq!(levels.get()[0], levels.get()[1]);
+        levels.clear();
+        assert_eq!(levels.get(), [Level::default(); 2]);
+    }
+}
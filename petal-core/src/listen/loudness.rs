@@ -0,0 +1,264 @@
+//! Loudness normalization after EBU R128: the stream is K-weighted and measured in 400 ms
+//! blocks (ITU-R BS.1770), and the gated loudness of the last stretch of blocks steers a
+//! gain toward the target level. The gain moves slowly, so a loud song fades down over a
+//! few seconds instead of jumping, and it never pushes recent peaks past full scale.
+
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+use super::eq::{Biquad, History};
+
+/// Target level in LUFS; the ReplayGain 2.0 reference, which leaves room for peaks.
+pub const TARGET_LUFS: f64 = -18.0;
+/// Largest correction either way, in dB.
+const MAX_GAIN_DB: f64 = 12.0;
+/// How fast the applied gain may change, in dB per second.
+const SLEW_DB_PER_SEC: f64 = 3.0;
+/// Blocks are 400 ms long and start every 100 ms.
+const HOP_MS: u32 = 100;
+const HOPS_PER_BLOCK: usize = 4;
+/// Blocks that make up the measurement: the last 20 seconds.
+const WINDOW_BLOCKS: usize = 200;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+/// The two K-weighting stages for `rate`: a high shelf for the head, then a high pass.
+fn k_weighting(rate: f64) -> [Biquad; 2] {
+    let f0 = 1681.974450955533;
+    let gain = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (PI * f0 / rate).tan();
+    let vh = 10f64.powf(gain / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b0: (vh + vb * k / q + k * k) / a0,
+        b1: 2.0 * (k * k - vh) / a0,
+        b2: (vh - vb * k / q + k * k) / a0,
+        a1: 2.0 * (k * k - 1.0) / a0,
+        a2: (1.0 - k / q + k * k) / a0,
+    };
+
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b0: 1.0,
+        b1: -2.0,
+        b2: 1.0,
+        a1: 2.0 * (k * k - 1.0) / a0,
+        a2: (1.0 - k / q + k * k) / a0,
+    };
+    [shelf, high_pass]
+}
+
+fn lufs(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.max(1e-20).log10()
+}
+
+/// One measured block.
+#[derive(Debug, Clone, Copy)]
+struct Block {
+    mean_square: f64,
+    peak: f32,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Loudness {
+    enabled: bool,
+    rate: u32,
+    channels: usize,
+    filters: Vec<Biquad>,
+    /// Two stages per channel.
+    history: Vec<History>,
+    hop_frames: usize,
+    /// The hop being measured: frames so far, summed squares and peak.
+    frames: usize,
+    energy: f64,
+    peak: f32,
+    /// The last `HOPS_PER_BLOCK` hops.
+    hops: VecDeque<Block>,
+    blocks: VecDeque<Block>,
+    /// Applied gain and where it is heading, in dB.
+    gain_db: f64,
+    target_db: f64,
+}
+
+impl Loudness {
+    pub(super) fn new(enabled: bool) -> Self {
+        let mut loudness = Self::default();
+        loudness.set_enabled(enabled);
+        loudness
+    }
+
+    /// Turning it on starts a fresh measurement at unity gain.
+    pub(super) fn set_enabled(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            *self = Self {
+                enabled,
+                ..Self::default()
+            };
+        }
+        self.enabled = enabled;
+    }
+
+    /// Gated loudness of the measured stretch in LUFS, once there is any audio above the
+    /// absolute gate.
+    pub(super) fn loudness(&self) -> Option<f64> {
+        let loud: Vec<f64> = self
+            .blocks
+            .iter()
+            .map(|block| block.mean_square)
+            .filter(|ms| lufs(*ms) > ABSOLUTE_GATE_LUFS)
+            .collect();
+        if loud.is_empty() {
+            return None;
+        }
+        let relative_gate = lufs(loud.iter().sum::<f64>() / loud.len() as f64) + RELATIVE_GATE_LU;
+        let (sum, count) = loud
+            .iter()
+            .filter(|ms| lufs(**ms) > relative_gate)
+            .fold((0.0, 0), |(sum, count), ms| (sum + ms, count + 1));
+        (count > 0).then(|| lufs(sum / f64::from(count)))
+    }
+
+    /// Measure interleaved samples and apply the current gain in place.
+    pub(super) fn process(&mut self, channels: u16, rate: u32, samples: &mut [f32]) {
+        let channels = usize::from(channels);
+        if !self.enabled || channels == 0 || rate == 0 {
+            return;
+        }
+        if rate != self.rate || channels != self.channels {
+            self.reset(channels, rate);
+        }
+
+        let slew = SLEW_DB_PER_SEC / f64::from(rate);
+        for frame in samples.chunks_exact_mut(channels) {
+            for (channel, sample) in frame.iter().enumerate() {
+                let history = &mut self.history[channel * 2..channel * 2 + 2];
+                let mut x = f64::from(*sample);
+                for (filter, h) in self.filters.iter().zip(history) {
+                    x = filter.run(h, x);
+                }
+                // Left, right and center count fully; surround channels are rare here.
+                self.energy += x * x;
+                self.peak = self.peak.max(sample.abs());
+            }
+            self.frames += 1;
+            if self.frames == self.hop_frames {
+                self.finish_hop();
+            }
+
+            self.gain_db += (self.target_db - self.gain_db).clamp(-slew, slew);
+            let gain = 10f64.powf(self.gain_db / 20.0) as f32;
+            for sample in frame {
+                *sample *= gain;
+            }
+        }
+    }
+
+    fn reset(&mut self, channels: usize, rate: u32) {
+        self.filters = k_weighting(f64::from(rate)).to_vec();
+        self.history = vec![History::default(); channels * 2];
+        self.rate = rate;
+        self.channels = channels;
+        self.hop_frames = (rate * HOP_MS / 1000) as usize;
+        self.frames = 0;
+        self.energy = 0.0;
+        self.peak = 0.0;
+        self.hops.clear();
+    }
+
+    fn finish_hop(&mut self) {
+        self.hops.push_back(Block {
+            mean_square: self.energy / self.frames as f64,
+            peak: self.peak,
+        });
+        self.frames = 0;
+        self.energy = 0.0;
+        self.peak = 0.0;
+        if self.hops.len() < HOPS_PER_BLOCK {
+            return;
+        }
+        if self.hops.len() > HOPS_PER_BLOCK {
+            self.hops.pop_front();
+        }
+        let block = self.hops.iter().fold(
+            Block {
+                mean_square: 0.0,
+                peak: 0.0,
+            },
+            |acc, hop| Block {
+                mean_square: acc.mean_square + hop.mean_square / HOPS_PER_BLOCK as f64,
+                peak: acc.peak.max(hop.peak),
+            },
+        );
+        self.blocks.push_back(block);
+        if self.blocks.len() > WINDOW_BLOCKS {
+            self.blocks.pop_front();
+        }
+
+        if let Some(loudness) = self.loudness() {
+            let peak = self.blocks.iter().fold(0.0f32, |max, b| max.max(b.peak));
+            let headroom = -20.0 * f64::from(peak.max(1e-6)).log10();
+            self.target_db = (TARGET_LUFS - loudness)
+                .clamp(-MAX_GAIN_DB, MAX_GAIN_DB)
+                .min(headroom);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48_000;
+
+    /// `secs` of a stereo 997 Hz sine with the given amplitude.
+    fn sine(amplitude: f32, secs: u32) -> Vec<f32> {
+        (0..RATE * secs)
+            .flat_map(|i| {
+                let s = (2.0 * std::f32::consts::PI * 997.0 * i as f32 / RATE as f32).sin();
+                [s * amplitude, s * amplitude]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn measures_a_full_scale_sine() {
+        // BS.1770: a full-scale 1 kHz sine on both channels reads 0 LUFS.
+        let mut loudness = Loudness::new(true);
+        loudness.process(2, RATE, &mut sine(1.0, 2));
+        let measured = loudness.loudness().unwrap();
+        assert!(measured.abs() < 0.2, "{measured}");
+    }
+
+    #[test]
+    fn brings_quiet_audio_up_to_the_target() {
+        let amplitude = 10f32.powf(-24.0 / 20.0);
+        let mut loudness = Loudness::new(true);
+        let mut samples = sine(amplitude, 10);
+        loudness.process(2, RATE, &mut samples);
+        // -24 LUFS is 6 dB short of the target.
+        let tail = samples[samples.len() - 2000..]
+            .iter()
+            .fold(0.0f32, |max, s| max.max(s.abs()));
+        let gain_db = 20.0 * (tail / amplitude).log10();
+        assert!((gain_db - 6.0).abs() < 0.3, "{gain_db}");
+    }
+
+    #[test]
+    fn silence_and_disabled_leave_audio_alone() {
+        let mut loudness = Loudness::new(true);
+        let mut silence = vec![0.0; 2 * RATE as usize];
+        loudness.process(2, RATE, &mut silence);
+        assert_eq!(loudness.loudness(), None);
+
+        let mut off = Loudness::new(false);
+        let mut samples = sine(0.1, 1);
+        let before = samples.clone();
+        off.process(2, RATE, &mut samples);
+        assert_eq!(samples, before);
+    }
+}
@@ -0,0 +1,75 @@
+//! Channel options for listeners with hearing loss in one ear or a single earbud: fold
+//! everything into mono, and shift the balance between left and right.
+
+/// Mono downmix and left/right balance, applied to interleaved samples.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChannelMix {
+    pub mono: bool,
+    /// -1.0 is left only, 0.0 centered, 1.0 right only.
+    pub balance: f32,
+}
+
+impl ChannelMix {
+    pub(super) fn process(&self, channels: u16, samples: &mut [f32]) {
+        let channels = usize::from(channels);
+        if channels < 2 || (!self.mono && self.balance == 0.0) {
+            return;
+        }
+        let balance = self.balance.clamp(-1.0, 1.0);
+        // Turn down the side the balance points away from; the other stays at full level.
+        let left = (1.0 - balance).min(1.0);
+        let right = (1.0 + balance).min(1.0);
+        for frame in samples.chunks_exact_mut(channels) {
+            if self.mono {
+                let mean = frame.iter().sum::<f32>() / channels as f32;
+                frame.fill(mean);
+            }
+            frame[0] *= left;
+            frame[1] *= right;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_to_mono() {
+        let mix = ChannelMix {
+            mono: true,
+            balance: 0.0,
+        };
+        let mut samples = [1.0, 0.0, 0.2, 0.4];
+        mix.process(2, &mut samples);
+        assert_eq!(samples, [0.5, 0.5, 0.3, 0.3]);
+    }
+
+    #[test]
+    fn shifts_the_balance() {
+        let mut samples = [1.0, 1.0];
+        ChannelMix {
+            mono: false,
+            balance: 0.5,
+        }
+        .process(2, &mut samples);
+        assert_eq!(samples, [0.5, 1.0]);
+
+        let mut samples = [1.0, 1.0];
+        ChannelMix {
+            mono: false,
+            balance: -1.0,
+        }
+        .process(2, &mut samples);
+        assert_eq!(samples, [1.0, 0.0]);
+
+        // Mono sources have no balance to shift.
+        let mut samples = [1.0];
+        ChannelMix {
+            mono: true,
+            balance: 1.0,
+        }
+        .process(1, &mut samples);
+        assert_eq!(samples, [1.0]);
+    }
+}
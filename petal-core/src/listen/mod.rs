@@ -0,0 +1,549 @@
+use std::cell::RefCell;
+use std::error::Error;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicU32};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    mpsc, Arc,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::events::{EventBus, PlaybackState, PlayerEvent};
+use crate::locale::t;
+use crate::record::{self, RecordTap};
+use crate::settings::Timeouts;
+use crate::station::{Station, StreamFormat};
+
+#[cfg(feature = "bench")]
+pub mod bench;
+mod capture;
+mod dsp;
+mod eq;
+mod fade;
+mod levels;
+mod loudness;
+mod mix;
+mod network;
+mod output;
+mod pool;
+mod prebuffer;
+mod silence;
+mod stream;
+mod timeshift;
+mod viz;
+
+pub use capture::Capture;
+pub use eq::{Gains, Preset, BANDS, MAX_GAIN_DB};
+pub use levels::{Level, Levels};
+pub use mix::ChannelMix;
+pub use network::SinkTarget;
+pub use timeshift::Clip;
+use timeshift::{Seek, Timeshift};
+
+type DynError = Box<dyn Error + Send + Sync + 'static>;
+type Result<T> = std::result::Result<T, DynError>;
+
+const N_BARS: usize = 48;
+/// Further behind live than this counts as timeshifted.
+const BEHIND_LIVE_MS: u64 = 2000;
+/// How much longer than the fade-out `shutdown` waits for the stream to end.
+const SHUTDOWN_GRACE: Duration = Duration::from_millis(500);
+/// How long `replay` waits for the worker to step back.
+const SEEK_WAIT: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone)]
+enum Control {
+    Stop,
+    Pause,
+    Resume,
+    SetVolume(f32),
+    SetFade(Duration),
+    SetEqualizer(Option<Gains>),
+    SetNormalize(bool),
+    SetChannelMix(ChannelMix),
+    /// Answered once `lag_ms` matches the new position.
+    Seek(Seek, Option<mpsc::Sender<()>>),
+    Switch(Station, StreamFormat),
+}
+
+/// Everything a stream worker needs to know at startup.
+#[derive(Debug, Clone)]
+struct StreamOptions {
+    station: Station,
+    format: StreamFormat,
+    volume: f32,
+    fade: Duration,
+    /// Upper bound on decoded audio queued ahead of the output device.
+    buffer_secs: u32,
+    proxy: Option<String>,
+    timeouts: Timeouts,
+    /// Network sink to play to instead of the output device.
+    audio_sink: Option<SinkTarget>,
+    equalizer: Option<Gains>,
+    /// Loudness normalization toward a common level.
+    normalize: bool,
+    channel_mix: ChannelMix,
+    /// How far back the encoded stream is kept; `None` reads it straight off the network.
+    timeshift_window: Option<Duration>,
+    /// Played instead of the station's endpoints.
+    stream_url: Option<String>,
+}
+
+#[derive(Debug)]
+enum State {
+    Stopped,
+    Paused { tx: mpsc::Sender<Control> },
+    Playing { tx: mpsc::Sender<Control> },
+}
+
+#[derive(Debug)]
+struct Inner {
+    station: Station,
+    format: StreamFormat,
+    /// Sink gain, 0.0 to 1.0, independent of the system mixer.
+    volume: f32,
+    /// Length of the fade-in on start and resume and the fade-out on pause and stop.
+    fade: Duration,
+    buffer_secs: u32,
+    proxy: Option<String>,
+    timeouts: Timeouts,
+    audio_sink: Option<SinkTarget>,
+    equalizer: Option<Gains>,
+    normalize: bool,
+    channel_mix: ChannelMix,
+    timeshift_window: Option<Duration>,
+    stream_url: Option<String>,
+    state: State,
+    /// Disconnects once the stream worker has ended.
+    worker: Option<mpsc::Receiver<()>>,
+    record_tap: RecordTap,
+    events: EventBus,
+}
+
+#[derive(Debug)]
+pub struct Listen {
+    inner: RefCell<Inner>,
+    lag_ms: Arc<AtomicU64>,
+    pause_started: RefCell<Option<Instant>>,
+    spectrum_bits: Arc<Vec<AtomicU32>>,
+    /// The spectrum is only computed while this is set.
+    viz_enabled: Arc<AtomicBool>,
+    levels: Arc<Levels>,
+    timeshift: Arc<Timeshift>,
+}
+
+impl Listen {
+    pub fn new(
+        station: Station,
+        format: StreamFormat,
+        buffer_secs: u32,
+        events: EventBus,
+    ) -> Rc<Self> {
+        let lag_ms = Arc::new(AtomicU64::new(0));
+        Rc::new(Self {
+            inner: RefCell::new(Inner {
+                station,
+                format,
+                volume: 1.0,
+                fade: Duration::ZERO,
+                buffer_secs,
+                proxy: None,
+                timeouts: Timeouts::default(),
+                audio_sink: None,
+                equalizer: None,
+                normalize: false,
+                channel_mix: ChannelMix::default(),
+                timeshift_window: None,
+                stream_url: None,
+                state: State::Stopped,
+                worker: None,
+                record_tap: record::new_tap(),
+                events,
+            }),
+            timeshift: Arc::new(Timeshift::new(lag_ms.clone())),
+            lag_ms,
+            pause_started: RefCell::new(None),
+            spectrum_bits: Arc::new((0..N_BARS).map(|_| AtomicU32::new(0)).collect()),
+            viz_enabled: Arc::new(AtomicBool::new(true)),
+            levels: Arc::new(Levels::default()),
+        })
+    }
+
+    pub fn spectrum_bars(&self) -> Arc<Vec<AtomicU32>> {
+        self.spectrum_bits.clone()
+    }
+
+    /// Levels of the audio being played, for a level meter.
+    pub fn levels(&self) -> Arc<Levels> {
+        self.levels.clone()
+    }
+
+    /// Compute the spectrum for the visualizer; turning it off saves the FFT work.
+    pub fn set_visualizer(&self, enabled: bool) {
+        self.viz_enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn record_tap(&self) -> RecordTap {
+        self.inner.borrow().record_tap.clone()
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.inner.borrow().volume
+    }
+
+    pub fn set_volume(&self, volume: f32) {
+        let mut inner = self.inner.borrow_mut();
+        inner.volume = volume.clamp(0.0, 1.0);
+        if let State::Playing { tx } | State::Paused { tx } = &inner.state {
+            let _ = tx.send(Control::SetVolume(inner.volume));
+        }
+    }
+
+    /// Fade in when playback starts or resumes and out when it pauses or stops; zero turns
+    /// fading off. Applies right away.
+    pub fn set_fade(&self, length: Duration) {
+        let mut inner = self.inner.borrow_mut();
+        inner.fade = length;
+        if let State::Playing { tx } | State::Paused { tx } = &inner.state {
+            let _ = tx.send(Control::SetFade(length));
+        }
+    }
+
+    /// Equalizer gains; `None` turns it off. Applies right away.
+    pub fn set_equalizer(&self, gains: Option<Gains>) {
+        let mut inner = self.inner.borrow_mut();
+        inner.equalizer = gains;
+        if let State::Playing { tx } | State::Paused { tx } = &inner.state {
+            let _ = tx.send(Control::SetEqualizer(gains));
+        }
+    }
+
+    /// Turn loudness normalization on or off. Applies right away.
+    pub fn set_normalize(&self, on: bool) {
+        let mut inner = self.inner.borrow_mut();
+        inner.normalize = on;
+        if let State::Playing { tx } | State::Paused { tx } = &inner.state {
+            let _ = tx.send(Control::SetNormalize(on));
+        }
+    }
+
+    /// Mono downmix and balance. Applies right away.
+    pub fn set_channel_mix(&self, mix: ChannelMix) {
+        let mut inner = self.inner.borrow_mut();
+        inner.channel_mix = mix;
+        if let State::Playing { tx } | State::Paused { tx } = &inner.state {
+            let _ = tx.send(Control::SetChannelMix(mix));
+        }
+    }
+
+    pub fn channel_mix(&self) -> ChannelMix {
+        self.inner.borrow().channel_mix
+    }
+
+    pub fn lag_ms(&self) -> Arc<AtomicU64> {
+        self.lag_ms.clone()
+    }
+
+    pub fn get_station(&self) -> Station {
+        self.inner.borrow_mut().station
+    }
+
+    /// Proxy for the stream request, overriding the environment; used from the next connect.
+    pub fn set_proxy(&self, proxy: Option<String>) {
+        self.inner.borrow_mut().proxy = proxy;
+    }
+
+    /// Connect and read timeouts for the stream request; used from the next connect.
+    pub fn set_timeouts(&self, timeouts: Timeouts) {
+        self.inner.borrow_mut().timeouts = timeouts;
+    }
+
+    /// Play to a network sink instead of the output device; used from the next start.
+    pub fn set_audio_sink(&self, target: Option<SinkTarget>) {
+        self.inner.borrow_mut().audio_sink = target;
+    }
+
+    /// Stream to play instead of the station's, e.g. a local test server; used from the
+    /// next start.
+    pub fn set_stream_url(&self, url: Option<String>) {
+        self.inner.borrow_mut().stream_url = url;
+    }
+
+    /// Keep up to `minutes` of the stream so that pausing does not lose any of it; 0 turns
+    /// timeshift off. Used from the next start.
+    pub fn set_timeshift(&self, minutes: u32) {
+        self.inner.borrow_mut().timeshift_window =
+            (minutes > 0).then(|| Duration::from_secs(u64::from(minutes) * 60));
+    }
+
+    pub fn is_paused(&self) -> bool {
+        matches!(self.inner.borrow().state, State::Paused { .. })
+    }
+
+    /// Whether playback runs behind the live stream, or is paused, with timeshift on.
+    pub fn can_go_live(&self) -> bool {
+        let inner = self.inner.borrow();
+        inner.timeshift_window.is_some()
+            && match inner.state {
+                State::Stopped => false,
+                State::Paused { .. } => true,
+                State::Playing { .. } => self.lag_ms.load(Ordering::Relaxed) >= BEHIND_LIVE_MS,
+            }
+    }
+
+    /// Skip ahead to the live stream. Playback stays paused if it was; resuming then
+    /// continues from live.
+    pub fn go_live(&self) {
+        let inner = self.inner.borrow();
+        let (State::Playing { tx } | State::Paused { tx }) = &inner.state else {
+            return;
+        };
+        if inner.timeshift_window.is_none() {
+            return;
+        }
+        let _ = tx.send(Control::Seek(Seek::Live, None));
+        self.lag_ms.store(0, Ordering::Relaxed);
+        if self.pause_started.borrow().is_some() {
+            *self.pause_started.borrow_mut() = Some(Instant::now());
+        }
+    }
+
+    /// The last `length` of what is playing, as received, when timeshift is on.
+    pub fn clip(&self, length: Duration) -> Option<Clip> {
+        let inner = self.inner.borrow();
+        if inner.timeshift_window.is_none() || matches!(inner.state, State::Stopped) {
+            return None;
+        }
+        self.timeshift.clip(length)
+    }
+
+    /// Whether the timeshift buffer reaches `by` behind the current position.
+    pub fn can_replay(&self, by: Duration) -> bool {
+        let inner = self.inner.borrow();
+        inner.timeshift_window.is_some()
+            && !matches!(inner.state, State::Stopped)
+            && self.timeshift.rewindable() >= by
+    }
+
+    /// Step back `by`, e.g. to hear an intro again. The position moves further behind
+    /// live, and with it the track and progress display.
+    pub fn replay(&self, by: Duration) {
+        if !self.can_replay(by) {
+            return;
+        }
+        if let State::Playing { tx } | State::Paused { tx } = &self.inner.borrow().state {
+            // The worker moves `lag_ms` by how far back it got; the track display is put
+            // in step with it right after.
+            let (done, stepped) = mpsc::channel();
+            let _ = tx.send(Control::Seek(Seek::Back(by), Some(done)));
+            let _ = stepped.recv_timeout(SEEK_WAIT);
+        }
+    }
+
+    pub fn set_station(&self, station: Station) {
+        // The new station plays from live.
+        self.lag_ms.store(0, Ordering::Relaxed);
+        let mut inner = self.inner.borrow_mut();
+        inner.station = station;
+        inner.events.emit(PlayerEvent::StationChanged(station));
+        Self::switch_inner(&mut inner);
+    }
+
+    /// Switch the preferred stream encoding, reconnecting if the stream is active.
+    pub fn set_stream_format(&self, format: StreamFormat) {
+        let mut inner = self.inner.borrow_mut();
+        if inner.format == format {
+            return;
+        }
+        self.lag_ms.store(0, Ordering::Relaxed);
+        inner.format = format;
+        Self::switch_inner(&mut inner);
+    }
+
+    /// Point a running stream at the current station and format. The worker keeps its
+    /// output device open, so there is no pop or gap from reopening it. Like a restart,
+    /// switching while paused resumes playback.
+    fn switch_inner(inner: &mut Inner) {
+        let (State::Playing { tx } | State::Paused { tx }) = &inner.state else {
+            return;
+        };
+        let tx = tx.clone();
+        let _ = tx.send(Control::Switch(inner.station, inner.format));
+        if matches!(inner.state, State::Paused { .. }) {
+            let _ = tx.send(Control::Resume);
+            inner
+                .events
+                .emit(PlayerEvent::StateChanged(PlaybackState::Connecting));
+        }
+        inner.state = State::Playing { tx };
+    }
+
+    pub fn start(&self) {
+        if matches!(self.inner.borrow().state, State::Paused { .. }) {
+            if let Some(t0) = self.pause_started.borrow_mut().take() {
+                let add = t0.elapsed().as_millis() as u64;
+                self.lag_ms.fetch_add(add, Ordering::Relaxed);
+            }
+        }
+        let mut inner = self.inner.borrow_mut();
+        let state = match inner.state {
+            State::Playing { .. } => return,
+            State::Paused { .. } => PlaybackState::Playing,
+            State::Stopped => {
+                // A fresh connection plays from live.
+                self.lag_ms.store(0, Ordering::Relaxed);
+                PlaybackState::Connecting
+            }
+        };
+        Self::start_inner(
+            &mut inner,
+            self.spectrum_bits.clone(),
+            self.viz_enabled.clone(),
+            self.levels.clone(),
+            self.timeshift.clone(),
+        );
+        inner.events.emit(PlayerEvent::StateChanged(state));
+    }
+
+    pub fn pause(&self) {
+        let mut inner = self.inner.borrow_mut();
+        match &inner.state {
+            State::Playing { tx } => {
+                let _ = tx.send(Control::Pause);
+                inner.state = State::Paused { tx: tx.clone() };
+                inner
+                    .events
+                    .emit(PlayerEvent::StateChanged(PlaybackState::Paused));
+            }
+            _ => {}
+        }
+        *self.pause_started.borrow_mut() = Some(Instant::now());
+        self.levels.clear();
+    }
+
+    pub fn stop(&self) {
+        let mut inner = self.inner.borrow_mut();
+        Self::stop_inner(&mut inner);
+        self.lag_ms.store(0, Ordering::Relaxed);
+        self.pause_started.borrow_mut().take();
+        self.levels.clear();
+        inner
+            .events
+            .emit(PlayerEvent::StateChanged(PlaybackState::Stopped));
+    }
+
+    /// Stop and wait for the stream to fade out, e.g. right before the process exits.
+    pub fn shutdown(&self) {
+        self.stop();
+        let (worker, fade) = {
+            let mut inner = self.inner.borrow_mut();
+            (inner.worker.take(), inner.fade)
+        };
+        if let Some(worker) = worker {
+            let _ = worker.recv_timeout(fade + SHUTDOWN_GRACE);
+        }
+    }
+
+    fn start_inner(
+        inner: &mut Inner,
+        spectrum_bits: Arc<Vec<AtomicU32>>,
+        viz_enabled: Arc<AtomicBool>,
+        levels: Arc<Levels>,
+        timeshift: Arc<Timeshift>,
+    ) {
+        match &inner.state {
+            State::Playing { .. } => {
+                // already playing
+                return;
+            }
+            State::Paused { tx } => {
+                let _ = tx.send(Control::Resume);
+                inner.state = State::Playing { tx: tx.clone() };
+                return;
+            }
+            State::Stopped => {
+                let (tx, rx) = mpsc::channel::<Control>();
+                let options = StreamOptions {
+                    station: inner.station,
+                    format: inner.format,
+                    volume: inner.volume,
+                    fade: inner.fade,
+                    buffer_secs: inner.buffer_secs,
+                    proxy: inner.proxy.clone(),
+                    timeouts: inner.timeouts,
+                    audio_sink: inner.audio_sink.clone(),
+                    equalizer: inner.equalizer,
+                    normalize: inner.normalize,
+                    channel_mix: inner.channel_mix,
+                    timeshift_window: inner.timeshift_window,
+                    stream_url: inner.stream_url.clone(),
+                };
+                let record_tap = inner.record_tap.clone();
+                let events = inner.events.clone();
+                let (alive, worker) = mpsc::channel::<()>();
+
+                inner.state = State::Playing { tx: tx.clone() };
+                inner.worker = Some(worker);
+
+                // detached worker thread; will exit on Stop or error
+                thread::spawn(move || {
+                    let _alive = alive;
+                    if let Err(err) = stream::run_listenmoe_stream(
+                        options,
+                        rx,
+                        spectrum_bits,
+                        viz_enabled,
+                        levels,
+                        timeshift,
+                        record_tap,
+                        events.clone(),
+                    ) {
+                        eprintln!("stream error: {err}");
+                        events.emit(PlayerEvent::StateChanged(PlaybackState::Error));
+                        events.emit(PlayerEvent::Error(t!("Playback stopped: {}", err)));
+                    }
+                });
+            }
+        }
+    }
+
+    fn stop_inner(inner: &mut Inner) {
+        if let State::Playing { tx } | State::Paused { tx } = &inner.state {
+            let _ = tx.send(Control::Stop);
+        }
+        inner.state = State::Stopped;
+    }
+}
+
+impl Drop for Listen {
+    fn drop(&mut self) {
+        let mut inner = self.inner.borrow_mut();
+        Self::stop_inner(&mut inner);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listen() -> Rc<Listen> {
+        Listen::new(Station::Jpop, StreamFormat::default(), 2, EventBus::new())
+    }
+
+    #[test]
+    fn stopping_forgets_the_lag() {
+        let radio = listen();
+        radio.lag_ms.store(30_000, Ordering::Relaxed);
+        radio.stop();
+        assert_eq!(radio.lag_ms.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn another_station_plays_live() {
+        let radio = listen();
+        radio.lag_ms.store(30_000, Ordering::Relaxed);
+        radio.set_station(Station::Kpop);
+        assert_eq!(radio.lag_ms.load(Ordering::Relaxed), 0);
+    }
+}
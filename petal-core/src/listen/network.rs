@@ -0,0 +1,251 @@
+//! Raw PCM output to a Snapcast server, or any other TCP or FIFO reader, instead of the
+//! local audio device. Audio goes out as 48 kHz 16-bit little-endian stereo, Snapcast's
+//! default `sampleformat=48000:16:2`. The reader sets the pace: writes block while it is
+//! behind, which in turn holds back decoding.
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const SAMPLE_RATE: u32 = 48_000;
+/// A reader that takes this long for one write has stalled.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where the audio goes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SinkTarget {
+    /// `host:port`, e.g. a Snapcast `tcp://` source in server mode.
+    Tcp(String),
+    /// A named pipe, e.g. Snapcast's `/tmp/snapfifo`.
+    Fifo(PathBuf),
+}
+
+impl SinkTarget {
+    /// `tcp://host:port`, or the path of a FIFO.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        match value.strip_prefix("tcp://") {
+            Some(addr) => {
+                let addr = addr.trim_end_matches('/');
+                (addr.contains(':') && !addr.ends_with(':')).then(|| Self::Tcp(addr.to_string()))
+            }
+            None if value.is_empty() => None,
+            None => Some(Self::Fifo(value.into())),
+        }
+    }
+}
+
+pub(super) struct NetworkSink {
+    out: Box<dyn Write + Send>,
+    volume: f32,
+    paused: bool,
+    resampler: Resampler,
+    bytes: Vec<u8>,
+}
+
+impl NetworkSink {
+    /// Connect to `target`. Opening a FIFO waits until something reads from it.
+    pub(super) fn open(target: &SinkTarget, connect_timeout: Duration) -> io::Result<Self> {
+        let out: Box<dyn Write + Send> = match target {
+            SinkTarget::Tcp(addr) => {
+                let mut last_err = io::Error::new(io::ErrorKind::NotFound, "no address");
+                let mut connected = None;
+                for addr in std::net::ToSocketAddrs::to_socket_addrs(addr.as_str())? {
+                    match TcpStream::connect_timeout(&addr, connect_timeout) {
+                        Ok(stream) => {
+                            connected = Some(stream);
+                            break;
+                        }
+                        Err(err) => last_err = err,
+                    }
+                }
+                let stream = connected.ok_or(last_err)?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                stream.set_nodelay(true)?;
+                Box::new(stream)
+            }
+            SinkTarget::Fifo(path) => Box::new(OpenOptions::new().write(true).open(path)?),
+        };
+        Ok(Self {
+            out,
+            volume: 1.0,
+            paused: false,
+            resampler: Resampler::default(),
+            bytes: Vec::new(),
+        })
+    }
+
+    pub(super) fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+    }
+
+    pub(super) fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub(super) fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Forget the previous stream position, e.g. after a reconnect.
+    pub(super) fn clear(&mut self) {
+        self.resampler = Resampler::default();
+    }
+
+    /// Convert interleaved samples and write them out.
+    pub(super) fn write(
+        &mut self,
+        channels: u16,
+        sample_rate: u32,
+        samples: &[f32],
+    ) -> io::Result<()> {
+        self.bytes.clear();
+        let volume = self.volume;
+        let bytes = &mut self.bytes;
+        self.resampler
+            .process(channels, sample_rate, samples, |left, right| {
+                for sample in [left, right] {
+                    let sample = (sample * volume).clamp(-1.0, 1.0);
+                    bytes.extend_from_slice(&((sample * f32::from(i16::MAX)) as i16).to_le_bytes());
+                }
+            });
+        self.out.write_all(&self.bytes)
+    }
+}
+
+/// Linear interpolation to stereo at [`SAMPLE_RATE`], carried across calls.
+#[derive(Debug, Default)]
+struct Resampler {
+    /// Position of the next output frame, in input frames after `previous`.
+    position: f64,
+    /// The last input frame of the previous call.
+    previous: Option<(f32, f32)>,
+    rate: u32,
+}
+
+impl Resampler {
+    fn process(
+        &mut self,
+        channels: u16,
+        rate: u32,
+        samples: &[f32],
+        mut emit: impl FnMut(f32, f32),
+    ) {
+        let ch = usize::from(channels);
+        if ch == 0 || rate == 0 {
+            return;
+        }
+        if rate != self.rate {
+            *self = Self {
+                rate,
+                ..Self::default()
+            };
+        }
+        let frame = |i: usize| {
+            let left = samples[i * ch];
+            (left, if ch > 1 { samples[i * ch + 1] } else { left })
+        };
+        let frames = samples.len() / ch;
+        if rate == SAMPLE_RATE {
+            (0..frames).map(frame).for_each(|(l, r)| emit(l, r));
+            return;
+        }
+
+        // Input frame -1 is `previous`; without one, start at frame 0.
+        let step = f64::from(rate) / f64::from(SAMPLE_RATE);
+        let first = if self.previous.is_some() { -1.0 } else { 0.0 };
+        let mut position = self.position.max(first);
+        let at = |i: isize| match i {
+            -1 => self.previous.unwrap_or_else(|| frame(0)),
+            i => frame(i as usize),
+        };
+        while frames > 0 && position < (frames - 1) as f64 {
+            let i = position.floor() as isize;
+            let t = (position - i as f64) as f32;
+            let (a, b) = (at(i), at(i + 1));
+            emit(a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+            position += step;
+        }
+        if frames > 0 {
+            self.previous = Some(frame(frames - 1));
+            self.position = position - frames as f64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resample(
+        resampler: &mut Resampler,
+        channels: u16,
+        rate: u32,
+        samples: &[f32],
+    ) -> Vec<(f32, f32)> {
+        let mut out = Vec::new();
+        resampler.process(channels, rate, samples, |l, r| out.push((l, r)));
+        out
+    }
+
+    #[test]
+    fn parses_targets() {
+        assert_eq!(
+            SinkTarget::parse("tcp://snapserver:4953"),
+            Some(SinkTarget::Tcp("snapserver:4953".into()))
+        );
+        assert_eq!(
+            SinkTarget::parse(" /tmp/snapfifo "),
+            Some(SinkTarget::Fifo("/tmp/snapfifo".into()))
+        );
+        assert_eq!(SinkTarget::parse("tcp://snapserver"), None);
+        assert_eq!(SinkTarget::parse(""), None);
+    }
+
+    #[test]
+    fn passes_48k_through_as_stereo() {
+        let mut resampler = Resampler::default();
+        let out = resample(&mut resampler, 1, SAMPLE_RATE, &[0.25, -0.5]);
+        assert_eq!(out, [(0.25, 0.25), (-0.5, -0.5)]);
+        let out = resample(&mut resampler, 3, SAMPLE_RATE, &[0.1, 0.2, 0.3]);
+        assert_eq!(out, [(0.1, 0.2)]);
+    }
+
+    #[test]
+    fn resamples_across_calls() {
+        let mut resampler = Resampler::default();
+        // 24 kHz to 48 kHz doubles the frames, interpolating between neighbours.
+        let ramp: Vec<f32> = (0..8).map(|i| i as f32).collect();
+        let mut out = resample(&mut resampler, 1, 24_000, &ramp[..4]);
+        out.extend(resample(&mut resampler, 1, 24_000, &ramp[4..]));
+        let left: Vec<f32> = out.iter().map(|(l, _)| *l).collect();
+        assert_eq!(
+            left,
+            [0.0, 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5, 5.0, 5.5, 6.0, 6.5]
+        );
+
+        let mut resampler = Resampler::default();
+        let second = vec![0.0; 44_100];
+        let frames = resample(&mut resampler, 1, 44_100, &second).len();
+        assert!((47_999..=48_000).contains(&frames), "{frames}");
+    }
+
+    #[test]
+    fn writes_16_bit_pcm() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let target = SinkTarget::Tcp(listener.local_addr().unwrap().to_string());
+        let mut sink = NetworkSink::open(&target, Duration::from_secs(1)).unwrap();
+        sink.set_volume(0.5);
+        sink.write(2, SAMPLE_RATE, &[1.0, -1.0]).unwrap();
+        drop(sink);
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut bytes = Vec::new();
+        io::Read::read_to_end(&mut stream, &mut bytes).unwrap();
+        assert_eq!(
+            bytes,
+            [16383i16.to_le_bytes(), (-16383i16).to_le_bytes()].concat()
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use rodio::cpal::{
+    self,
+    traits::{DeviceTrait, HostTrait},
+    StreamError,
+};
+use rodio::{OutputStream, OutputStreamBuilder, Sink};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::Duration;
+
+use super::network::{NetworkSink, SinkTarget};
+use super::pool::SamplePool;
+use super::Result;
+
+/// How often the default output device is checked for a change.
+const DEVICE_POLL: Duration = Duration::from_secs(2);
+
+/// The open output device, with a flag raised once it disappears (e.g. a USB DAC is
+/// unplugged or a Bluetooth headset turns off).
+pub(super) struct Output {
+    stream: OutputStream,
+    lost: Arc<AtomicBool>,
+}
+
+impl Output {
+    fn open() -> Result<Self> {
+        let lost = Arc::new(AtomicBool::new(false));
+        let flag = lost.clone();
+        let on_error = move |err: StreamError| {
+            eprintln!("audio stream error: {err}");
+            if matches!(err, StreamError::DeviceNotAvailable) {
+                flag.store(true, Ordering::Relaxed);
+            }
+        };
+        let stream = OutputStreamBuilder::from_default_device()
+            .and_then(|builder| {
+                builder
+                    .with_error_callback(on_error)
+                    .open_stream_or_fallback()
+            })
+            .map_err(|e| format!("audio device unavailable: {e}"))?;
+        if let Some(name) = default_device_name() {
+            watch_device(name, Arc::downgrade(&lost));
+        }
+        Ok(Self { stream, lost })
+    }
+}
+
+fn default_device_name() -> Option<String> {
+    cpal::default_host().default_output_device()?.name().ok()
+}
+
+/// Raises `lost` once the device called `name` stops being the default and is no longer
+/// listed at all, i.e. it was unplugged rather than just superseded by a new one. Sound
+/// servers usually keep the ALSA "default" device in place and reroute behind it; then
+/// only a stream error tells us (see `Output::open`).
+fn watch_device(name: String, lost: Weak<AtomicBool>) {
+    thread::spawn(move || loop {
+        thread::sleep(DEVICE_POLL);
+        let Some(lost) = lost.upgrade() else {
+            return; // the output was closed
+        };
+        if lost.load(Ordering::Relaxed) {
+            return;
+        }
+        if default_device_name().as_deref() == Some(name.as_str()) {
+            continue;
+        }
+        let still_there = cpal::default_host()
+            .output_devices()
+            .map(|mut devices| devices.any(|d| d.name().ok().as_deref() == Some(name.as_str())))
+            .unwrap_or(false);
+        if !still_there {
+            lost.store(true, Ordering::Relaxed);
+            return;
+        }
+    });
+}
+
+/// Where decoded audio goes: the default output device through a rodio sink, or raw PCM
+/// to a network sink.
+pub(super) enum AudioOut {
+    Device { output: Output, sink: Sink },
+    Network { sink: NetworkSink, lost: bool },
+}
+
+impl AudioOut {
+    pub(super) fn open(target: Option<&SinkTarget>, connect_timeout: Duration) -> Result<Self> {
+        match target {
+            Some(target) => {
+                let sink = NetworkSink::open(target, connect_timeout)
+                    .map_err(|e| format!("audio sink unavailable: {e}"))?;
+                Ok(Self::Network { sink, lost: false })
+            }
+            None => {
+                let output = Output::open()?;
+                let sink = Sink::connect_new(output.stream.mixer());
+                Ok(Self::Device { output, sink })
+            }
+        }
+    }
+
+    pub(super) fn is_network(&self) -> bool {
+        matches!(self, Self::Network { .. })
+    }
+
+    pub(super) fn set_volume(&mut self, volume: f32) {
+        match self {
+            Self::Device { sink, .. } => sink.set_volume(volume),
+            Self::Network { sink, .. } => sink.set_volume(volume),
+        }
+    }
+
+    pub(super) fn pause(&mut self) {
+        match self {
+            Self::Device { sink, .. } => sink.pause(),
+            Self::Network { sink, .. } => sink.set_paused(true),
+        }
+    }
+
+    pub(super) fn play(&mut self) {
+        match self {
+            Self::Device { sink, .. } => sink.play(),
+            Self::Network { sink, .. } => sink.set_paused(false),
+        }
+    }
+
+    /// Drop queued audio, e.g. when the stream reconnects.
+    pub(super) fn clear(&mut self) {
+        match self {
+            Self::Device { sink, .. } => sink.stop(),
+            Self::Network { sink, .. } => sink.clear(),
+        }
+    }
+
+    /// Start over with an empty queue, keeping the volume and pause state.
+    pub(super) fn reset(&mut self, paused: bool) {
+        match self {
+            Self::Device { output, sink } => {
+                let volume = sink.volume();
+                sink.stop();
+                *sink = Sink::connect_new(output.stream.mixer());
+                sink.set_volume(volume);
+                if paused {
+                    sink.pause();
+                }
+            }
+            Self::Network { sink, .. } => sink.clear(),
+        }
+    }
+
+    /// Whether decoding should wait: the device queue holds more than `max_chunks`, or the
+    /// network sink is paused (its reader paces it otherwise).
+    pub(super) fn is_full(&self, max_chunks: usize) -> bool {
+        match self {
+            Self::Device { sink, .. } => sink.len() > max_chunks,
+            Self::Network { sink, .. } => sink.is_paused(),
+        }
+    }
+
+    /// Chunks queued on the device, or `None` for a network sink, whose reader keeps its
+    /// own buffer.
+    pub(super) fn queued(&self) -> Option<usize> {
+        match self {
+            Self::Device { sink, .. } => Some(sink.len()),
+            Self::Network { .. } => None,
+        }
+    }
+
+    /// The device went away, or the network sink stopped taking audio.
+    pub(super) fn is_lost(&self) -> bool {
+        match self {
+            Self::Device { output, .. } => output.lost.load(Ordering::Relaxed),
+            Self::Network { lost, .. } => *lost,
+        }
+    }
+
+    pub(super) fn append(
+        &mut self,
+        pool: &SamplePool,
+        channels: u16,
+        sample_rate: u32,
+        samples: &[f32],
+        chunk_ms: u32,
+    ) {
+        match self {
+            Self::Device { sink, .. } => {
+                append_samples_in_chunks(sink, pool, channels, sample_rate, samples, chunk_ms)
+            }
+            Self::Network { sink, lost } => {
+                if let Err(err) = sink.write(channels, sample_rate, samples) {
+                    eprintln!("audio sink error: {err}");
+                    *lost = true;
+                }
+            }
+        }
+    }
+}
+
+fn append_samples_in_chunks(
+    sink: &Sink,
+    pool: &SamplePool,
+    channels: u16,
+    sample_rate: u32,
+    samples: &[f32],
+    chunk_ms: u32,
+) {
+    // Copied into recycled buffers; they go back to the pool once played.
+    for chunk in pool.chunks(channels, sample_rate, samples, chunk_ms) {
+        sink.append(chunk);
+    }
+}
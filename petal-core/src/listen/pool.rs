@@ -0,0 +1,122 @@
+use rodio::{ChannelCount, SampleRate, Source};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Idle buffers kept for reuse; anything beyond this is freed.
+const MAX_IDLE: usize = 256;
+
+/// Recycles the small sample buffers handed to the sink, so hours of playback don't
+/// allocate a fresh `Vec` for every 10 ms of audio.
+#[derive(Clone, Default)]
+pub(super) struct SamplePool {
+    idle: Arc<Mutex<Vec<Vec<f32>>>>,
+}
+
+impl SamplePool {
+    /// Copies `samples` into a recycled buffer and wraps it as a rodio source.
+    pub(super) fn chunk(
+        &self,
+        channels: ChannelCount,
+        sample_rate: SampleRate,
+        samples: &[f32],
+    ) -> PooledSamples {
+        let mut buf = self
+            .idle
+            .lock()
+            .ok()
+            .and_then(|mut idle| idle.pop())
+            .unwrap_or_default();
+        buf.clear();
+        buf.extend_from_slice(samples);
+        PooledSamples {
+            samples: buf,
+            pos: 0,
+            channels,
+            sample_rate,
+            pool: self.clone(),
+        }
+    }
+
+    /// Splits `samples` into chunks of `chunk_ms` each, as queued on the sink.
+    pub(super) fn chunks<'a>(
+        &'a self,
+        channels: u16,
+        sample_rate: u32,
+        samples: &'a [f32],
+        chunk_ms: u32,
+    ) -> impl Iterator<Item = PooledSamples> + 'a {
+        // frames per chunk = sr * ms / 1000
+        let frames_per_chunk = (sample_rate * chunk_ms / 1000).max(1) as usize;
+        let samples_per_chunk = frames_per_chunk * channels as usize;
+        let playable = channels > 0 && sample_rate > 0;
+
+        samples
+            .chunks(samples_per_chunk.max(1))
+            .filter(move |_| playable)
+            .map(move |chunk| self.chunk(channels, sample_rate, chunk))
+    }
+
+    fn give_back(&self, buf: Vec<f32>) {
+        if let Ok(mut idle) = self.idle.lock() {
+            if idle.len() < MAX_IDLE {
+                idle.push(buf);
+            }
+        }
+    }
+}
+
+/// A chunk of interleaved samples that returns its buffer to the pool once played.
+pub(super) struct PooledSamples {
+    samples: Vec<f32>,
+    pos: usize,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    pool: SamplePool,
+}
+
+impl Iterator for PooledSamples {
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.samples.get(self.pos).copied()?;
+        self.pos += 1;
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.samples.len() - self.pos;
+        (left, Some(left))
+    }
+}
+
+impl Source for PooledSamples {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        let frames = self.samples.len() as u64 / u64::from(self.channels.max(1));
+        Some(Duration::from_nanos(
+            frames * 1_000_000_000 / u64::from(self.sample_rate.max(1)),
+        ))
+    }
+}
+
+impl Drop for PooledSamples {
+    fn drop(&mut self) {
+        self.pool.give_back(std::mem::take(&mut self.samples));
+    }
+}
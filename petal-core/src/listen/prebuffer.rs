@@ -0,0 +1,79 @@
+//! How much audio to queue before playback starts or resumes after the output ran dry.
+//! It starts short so that tuning in is quick, and doubles whenever underruns keep
+//! coming, so a flaky connection settles into a longer delay instead of stuttering.
+
+use std::time::{Duration, Instant};
+
+/// The first target, in milliseconds.
+const INITIAL_MS: u32 = 250;
+/// Underruns this close together count as a pattern rather than a one-off hiccup.
+const UNDERRUN_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+pub(super) struct Prebuffer {
+    target_ms: u32,
+    max_ms: u32,
+    last_underrun: Option<Instant>,
+}
+
+impl Prebuffer {
+    /// `max_ms` caps the target; it must stay below what the queue may hold.
+    pub(super) fn new(max_ms: u32) -> Self {
+        Self {
+            target_ms: INITIAL_MS.min(max_ms),
+            max_ms,
+            last_underrun: None,
+        }
+    }
+
+    /// Chunks of `chunk_ms` to queue before playing.
+    pub(super) fn target_chunks(&self, chunk_ms: u32) -> usize {
+        (self.target_ms / chunk_ms.max(1)).max(1) as usize
+    }
+
+    /// Note that the output ran dry at `now`. Returns the new target in milliseconds if
+    /// this was a repeat and the target grew.
+    pub(super) fn underrun(&mut self, now: Instant) -> Option<u32> {
+        let repeated = self
+            .last_underrun
+            .is_some_and(|last| now.duration_since(last) < UNDERRUN_WINDOW);
+        self.last_underrun = Some(now);
+        if !repeated || self.target_ms >= self.max_ms {
+            return None;
+        }
+        self.target_ms = (self.target_ms * 2).min(self.max_ms);
+        Some(self.target_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grows_after_repeated_underruns() {
+        let start = Instant::now();
+        let mut prebuffer = Prebuffer::new(1000);
+        assert_eq!(prebuffer.target_chunks(10), 25);
+        assert_eq!(prebuffer.underrun(start), None);
+        assert_eq!(
+            prebuffer.underrun(start + Duration::from_secs(5)),
+            Some(500)
+        );
+        assert_eq!(
+            prebuffer.underrun(start + Duration::from_secs(10)),
+            Some(1000)
+        );
+        assert_eq!(prebuffer.underrun(start + Duration::from_secs(15)), None);
+        assert_eq!(prebuffer.target_chunks(10), 100);
+    }
+
+    #[test]
+    fn isolated_underruns_leave_it_alone() {
+        let start = Instant::now();
+        let mut prebuffer = Prebuffer::new(30_000);
+        assert_eq!(prebuffer.underrun(start), None);
+        assert_eq!(prebuffer.underrun(start + Duration::from_secs(120)), None);
+        assert_eq!(prebuffer.target_chunks(10), 25);
+    }
+}
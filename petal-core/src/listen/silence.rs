@@ -0,0 +1,77 @@
+//! Dead-air detection: a broken relay can stay connected and keep sending valid frames
+//! of nothing. Silence is counted in decoded audio rather than wall-clock time, so a
+//! long pause or a full queue does not count towards it.
+
+use std::time::Duration;
+
+/// Quieter than this, about -60 dBFS, counts as silence; lossy codecs rarely decode
+/// digital silence to exact zeros.
+const THRESHOLD: f32 = 0.001;
+
+#[derive(Debug)]
+pub(super) struct Silence {
+    limit: Option<Duration>,
+    /// Silent frames in a row, at `rate`.
+    frames: u64,
+    rate: u32,
+}
+
+impl Silence {
+    /// `None` turns detection off.
+    pub(super) fn new(limit: Option<Duration>) -> Self {
+        Self {
+            limit,
+            frames: 0,
+            rate: 0,
+        }
+    }
+
+    pub(super) fn reset(&mut self) {
+        self.frames = 0;
+    }
+
+    /// Feed decoded interleaved samples. Returns true once the stream has been silent for
+    /// longer than the limit.
+    pub(super) fn feed(&mut self, channels: u16, rate: u32, samples: &[f32]) -> bool {
+        let Some(limit) = self.limit else {
+            return false;
+        };
+        if channels == 0 || rate == 0 {
+            return false;
+        }
+        if rate != self.rate {
+            self.rate = rate;
+            self.frames = 0;
+        }
+        if samples.iter().any(|s| s.abs() > THRESHOLD) {
+            self.frames = 0;
+            return false;
+        }
+        self.frames += (samples.len() / usize::from(channels)) as u64;
+        Duration::from_secs_f64(self.frames as f64 / f64::from(rate)) > limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trips_after_sustained_silence() {
+        let mut silence = Silence::new(Some(Duration::from_secs(2)));
+        let second = vec![0.0001; 2 * 1000];
+        assert!(!silence.feed(2, 1000, &second));
+        assert!(!silence.feed(2, 1000, &second));
+        // Any sound starts the count over.
+        assert!(!silence.feed(2, 1000, &[0.5, 0.5]));
+        assert!(!silence.feed(2, 1000, &second));
+        assert!(!silence.feed(2, 1000, &second));
+        assert!(silence.feed(2, 1000, &second));
+    }
+
+    #[test]
+    fn off_without_a_limit() {
+        let mut silence = Silence::new(None);
+        assert!(!silence.feed(1, 1000, &vec![0.0; 10_000]));
+    }
+}
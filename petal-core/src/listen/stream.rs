@@ -0,0 +1,661 @@
+use gettextrs::gettext;
+use reqwest::blocking::Client;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use crate::events::{EventBus, PlaybackState, PlayerEvent};
+use crate::http_source::HttpSource;
+#[cfg(debug_assertions)]
+use crate::log::now_string;
+use crate::proxy;
+use crate::record::RecordTap;
+use crate::settings::Timeouts;
+use crate::station::{Station, StreamFormat};
+
+use super::dsp::Dsp;
+use super::fade::Fade;
+use super::levels::Levels;
+use super::output::AudioOut;
+use super::pool::SamplePool;
+use super::prebuffer::Prebuffer;
+use super::silence::Silence;
+use super::timeshift::Timeshift;
+use super::viz::{
+    clear_spectrum, decode_and_process_packet, make_fft_state, reset_fft_state, DecodeState,
+    FftVizState, PacketOutcome, VizParams,
+};
+use super::{Control, Result, StreamOptions};
+
+/// A connection that ends sooner than this counts as a drop against its endpoint.
+const STABLE_CONNECTION: Duration = Duration::from_secs(30);
+/// Consecutive quick drops before failing over to the next endpoint.
+const MAX_QUICK_DROPS: u32 = 3;
+/// Pause after every endpoint failed to connect, to avoid a reconnect storm.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+/// Length of each buffer handed to the sink (tweak to 5..20ms).
+pub(super) const CHUNK_MS: u32 = 10;
+/// How often a full queue is checked for room.
+const BACKPRESSURE_POLL: Duration = Duration::from_millis(50);
+/// Below this share of the prebuffer target, the queue counts as running low.
+const LOW_BUFFER_DIVISOR: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+enum RunOutcome {
+    Stop,
+    Reconnect,
+    /// Reconnect to another station or encoding, keeping the output device open.
+    Switch(Station, StreamFormat),
+    /// The output device went away; reopen the default one and keep the connection.
+    DeviceLost,
+}
+
+/// What every connection of a stream thread shares: its channels and fixed settings.
+struct ConnectionContext<'a> {
+    rx: &'a mpsc::Receiver<Control>,
+    spectrum_bits: &'a Arc<Vec<AtomicU32>>,
+    decoder_opts: &'a DecoderOptions,
+    timeshift: &'a Timeshift,
+    viz_enabled: &'a AtomicBool,
+    levels: &'a Levels,
+    viz: VizParams,
+    events: &'a EventBus,
+    max_queued_chunks: usize,
+}
+
+/// The output side, which outlives connections: a reconnect picks up where it left off.
+struct PlayerState {
+    out: AudioOut,
+    fade: Fade,
+    dsp: Dsp,
+    paused: bool,
+    buffering: bool,
+    bars_enabled: bool,
+    fft_state: FftVizState,
+    prebuffer: Prebuffer,
+    silence: Silence,
+}
+
+pub(super) fn build_client(proxy: Option<&str>, timeouts: Timeouts) -> Result<Client> {
+    // On a blocking response the timeout applies to each read of the body, so a stalled
+    // stream is noticed without cutting off a healthy one.
+    let builder = Client::builder()
+        .pool_max_idle_per_host(0)
+        .connect_timeout(timeouts.connect())
+        .timeout(timeouts.read());
+    Ok(proxy::apply(builder, proxy)?.build()?)
+}
+
+pub(super) fn build_useragent() -> String {
+    let platform = if cfg!(target_os = "linux") {
+        "linux"
+    } else if cfg!(target_os = "windows") {
+        "windows"
+    } else {
+        "other"
+    };
+
+    format!(
+        "{}-v{}-{}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        platform
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn open_stream(
+    url: &str,
+    client: &Client,
+    useragent: &str,
+    format_opts: &FormatOptions,
+    metadata_opts: &MetadataOptions,
+    decoder_opts: &DecoderOptions,
+    record_tap: &RecordTap,
+    timeshift: &Arc<Timeshift>,
+    timeshift_window: Option<Duration>,
+) -> Result<(
+    Box<dyn symphonia::core::formats::FormatReader>,
+    u32,
+    Box<dyn symphonia::core::codecs::Decoder>,
+)> {
+    #[cfg(debug_assertions)]
+    println!("[{}] Connecting to {url}…", now_string());
+
+    let response = client.get(url).header("User-Agent", useragent).send()?;
+    #[cfg(debug_assertions)]
+    println!("[{}] HTTP status: {}", now_string(), response.status());
+
+    if !response.status().is_success() {
+        return Err(format!("HTTP status {}", response.status()).into());
+    }
+
+    let http_source = HttpSource::new(response, record_tap.clone());
+    let source: Box<dyn MediaSource> = match timeshift_window {
+        Some(window) => Box::new(timeshift.attach(http_source, window)),
+        None => Box::new(http_source),
+    };
+    let mss = MediaSourceStream::new(source, Default::default());
+
+    let hint = Hint::new(); // let symphonia probe
+
+    let probed = symphonia::default::get_probe().format(&hint, mss, format_opts, metadata_opts)?;
+
+    let format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| "no supported audio tracks".to_string())?;
+
+    let track_id = track.id;
+    let decoder = symphonia::default::get_codecs().make(&track.codec_params, decoder_opts)?;
+
+    Ok((format, track_id, decoder))
+}
+
+fn handle_control(ctx: &ConnectionContext, player: &mut PlayerState) -> Result<Option<RunOutcome>> {
+    let PlayerState {
+        out,
+        fade,
+        dsp,
+        paused,
+        buffering,
+        bars_enabled,
+        ..
+    } = player;
+    // returns the outcome if the current connection has to end
+    while let Ok(cmd) = ctx.rx.try_recv() {
+        match cmd {
+            Control::Stop => {
+                #[cfg(debug_assertions)]
+                println!("[{}] Stop requested, shutting down stream.", now_string());
+                if !*paused && !*buffering {
+                    fade.fade_out(out);
+                }
+                out.clear();
+                return Ok(Some(RunOutcome::Stop));
+            }
+            Control::Switch(station, format) => {
+                #[cfg(debug_assertions)]
+                println!(
+                    "[{}] Switching to {} ({}).",
+                    now_string(),
+                    station.display_name(),
+                    format.display_name()
+                );
+                return Ok(Some(RunOutcome::Switch(station, format)));
+            }
+            Control::Pause => {
+                if !*paused {
+                    #[cfg(debug_assertions)]
+                    println!("[{}] Pausing playback.", now_string());
+                    if !*buffering {
+                        fade.fade_out(out);
+                    }
+                    *paused = true;
+                    out.pause();
+                }
+                *bars_enabled = false;
+                clear_spectrum(ctx.spectrum_bits);
+            }
+            Control::Resume => {
+                if *paused {
+                    #[cfg(debug_assertions)]
+                    println!("[{}] Resuming playback.", now_string());
+                    *paused = false;
+                    fade.fade_in(out);
+                    out.play();
+                    *bars_enabled = true;
+                }
+            }
+            Control::SetVolume(volume) => fade.set_volume(out, volume),
+            Control::SetFade(length) => fade.set_length(length),
+            Control::SetEqualizer(gains) => dsp.eq.set_gains(gains),
+            Control::SetNormalize(on) => dsp.loudness.set_enabled(on),
+            Control::SetChannelMix(mix) => dsp.mix = mix,
+            Control::Seek(seek, done) => {
+                // The decoder is ahead of what is audible by the queue.
+                let queued = out.queued().unwrap_or(0) as u64 * u64::from(CHUNK_MS);
+                ctx.timeshift.seek(seek, Duration::from_millis(queued));
+                if let Some(done) = done {
+                    let _ = done.send(());
+                }
+                // Queued audio is from the old position; fill up again from the new one.
+                out.clear();
+                out.pause();
+                *buffering = true;
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn run_one_connection(
+    ctx: &ConnectionContext,
+    player: &mut PlayerState,
+    format: &mut Box<dyn symphonia::core::formats::FormatReader>,
+    track_id: &mut u32,
+    decoder: &mut Box<dyn symphonia::core::codecs::Decoder>,
+) -> Result<RunOutcome> {
+    let mut decode_state = DecodeState {
+        sample_buf: None,
+        channels: 0,
+        sample_rate: 0,
+        decode_errors: 0,
+    };
+    let pool = SamplePool::default();
+    // Hold the output until enough audio is queued; see `Prebuffer`.
+    player.buffering = true;
+    player.out.pause();
+    // Reported once per dip, until the queue fills up again.
+    let mut low = false;
+
+    loop {
+        if let Some(outcome) = handle_control(ctx, player)? {
+            return Ok(outcome);
+        }
+        if player.buffering {
+            // A resume while buffering waits for the queue to fill as well.
+            player.out.pause();
+        }
+        player.fade.update(&mut player.out);
+        if player.out.is_lost() {
+            return Ok(RunOutcome::DeviceLost);
+        }
+
+        let packet = match format.next_packet() {
+            Ok(p) => p,
+            Err(SymphoniaError::ResetRequired) => {
+                #[cfg(debug_assertions)]
+                println!("[{}] Stream reset, reconfiguring decoder…", now_string());
+
+                let new_track = format
+                    .tracks()
+                    .iter()
+                    .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+                    .ok_or_else(|| "no supported audio tracks after reset".to_string())?;
+
+                *track_id = new_track.id;
+                *decoder = symphonia::default::get_codecs()
+                    .make(&new_track.codec_params, ctx.decoder_opts)?;
+
+                decode_state.sample_buf = None;
+                let fft_state = &mut player.fft_state;
+                reset_fft_state(
+                    &mut fft_state.mono_ring,
+                    &mut fft_state.bars_smooth,
+                    &mut fft_state.bar_peak,
+                    ctx.spectrum_bits,
+                );
+                continue;
+            }
+            Err(err) => {
+                eprintln!("Error reading packet: {err:?}");
+                return Ok(RunOutcome::Reconnect);
+            }
+        };
+
+        // The next packet took longer to arrive than the queue lasted.
+        if !player.buffering && !player.paused && player.out.queued() == Some(0) {
+            match player.prebuffer.underrun(Instant::now()) {
+                Some(ms) => eprintln!("Audio buffer ran dry again, buffering {ms} ms from now on"),
+                None => eprintln!("Audio buffer ran dry, buffering…"),
+            }
+            player.buffering = true;
+            player.out.pause();
+            ctx.events
+                .emit(PlayerEvent::StateChanged(PlaybackState::Buffering));
+        }
+        if let Some(queued) = player
+            .out
+            .queued()
+            .filter(|_| !player.buffering && !player.paused)
+        {
+            let target = player.prebuffer.target_chunks(CHUNK_MS);
+            if !low && queued < target / LOW_BUFFER_DIVISOR {
+                low = true;
+                ctx.events.emit(PlayerEvent::BufferLow);
+            } else if queued >= target {
+                low = false;
+            }
+        }
+
+        let (outcome, audio) = decode_and_process_packet(
+            &packet,
+            format,
+            track_id,
+            decoder,
+            ctx.decoder_opts,
+            player.bars_enabled && ctx.viz_enabled.load(Ordering::Relaxed),
+            ctx.spectrum_bits,
+            &mut decode_state,
+            &mut player.fft_state,
+            ctx.viz,
+        )?;
+
+        match outcome {
+            PacketOutcome::Continue => {}
+            PacketOutcome::Reconnect => return Ok(RunOutcome::Reconnect),
+            PacketOutcome::SpecChanged { .. } => {
+                // Drop audio in the old format and fill up again before playing on.
+                player.out.clear();
+                player.out.pause();
+                player.buffering = true;
+
+                let fft_state = &mut player.fft_state;
+                reset_fft_state(
+                    &mut fft_state.mono_ring,
+                    &mut fft_state.bars_smooth,
+                    &mut fft_state.bar_peak,
+                    ctx.spectrum_bits,
+                );
+
+                // Continue; next decoded buffer will create a new SampleBuffer and then deliver audio.
+                continue;
+            }
+        }
+
+        if let Some((channels, sample_rate)) = audio {
+            if player
+                .silence
+                .feed(channels, sample_rate, decode_state.samples())
+            {
+                eprintln!("Only silence on the stream for too long, reconnecting…");
+                ctx.events.emit(PlayerEvent::Silence);
+                return Ok(RunOutcome::Reconnect);
+            }
+            player
+                .dsp
+                .process(channels, sample_rate, decode_state.samples_mut());
+            if player.paused {
+                ctx.levels.clear();
+            } else {
+                ctx.levels.measure(channels, decode_state.samples());
+            }
+            player.out.append(
+                &pool,
+                channels,
+                sample_rate,
+                decode_state.samples(),
+                CHUNK_MS,
+            );
+            let ready = player
+                .out
+                .queued()
+                .is_none_or(|queued| queued >= player.prebuffer.target_chunks(CHUNK_MS));
+            if player.buffering && ready {
+                player.buffering = false;
+                if !player.paused {
+                    player.fade.fade_in(&mut player.out);
+                    player.out.play();
+                    ctx.events
+                        .emit(PlayerEvent::StateChanged(PlaybackState::Playing));
+                }
+            }
+        }
+
+        // Backpressure: while the queue is full, stop decoding (and so stop reading from
+        // the network) instead of growing memory, e.g. when the device stalls or during a
+        // long pause.
+        while player.out.is_full(ctx.max_queued_chunks) {
+            if let Some(outcome) = handle_control(ctx, player)? {
+                return Ok(outcome);
+            }
+            if player.out.is_lost() {
+                return Ok(RunOutcome::DeviceLost);
+            }
+            // Fade-ins move in small steps; a full queue does not make them wait.
+            thread::sleep(if player.fade.update(&mut player.out) {
+                Duration::from_millis(u64::from(CHUNK_MS))
+            } else {
+                BACKPRESSURE_POLL
+            });
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn run_listenmoe_stream(
+    options: StreamOptions,
+    rx: mpsc::Receiver<Control>,
+    spectrum_bits: Arc<Vec<AtomicU32>>,
+    viz_enabled: Arc<AtomicBool>,
+    levels: Arc<Levels>,
+    timeshift: Arc<Timeshift>,
+    record_tap: RecordTap,
+    events: EventBus,
+) -> Result<()> {
+    let StreamOptions {
+        mut station,
+        format: mut preferred,
+        volume,
+        fade,
+        buffer_secs,
+        proxy,
+        timeouts,
+        audio_sink,
+        equalizer,
+        normalize,
+        channel_mix,
+        timeshift_window,
+        stream_url,
+    } = options;
+    let max_queued_chunks = (buffer_secs.max(1) * 1000 / CHUNK_MS) as usize;
+    let mut endpoints = playable_endpoints(station, preferred, stream_url.as_deref())?;
+    let mut current = 0;
+    let mut failed_connects = 0;
+    let mut quick_drops = 0;
+
+    let mut client = build_client(proxy.as_deref(), timeouts)?;
+    let useragent = build_useragent();
+
+    let format_opts: FormatOptions = Default::default();
+    let metadata_opts: MetadataOptions = Default::default();
+    let decoder_opts: DecoderOptions = Default::default();
+
+    let mut out = AudioOut::open(audio_sink.as_ref(), timeouts.connect())?;
+    out.set_volume(volume);
+    let mut player = PlayerState {
+        out,
+        fade: Fade::new(fade, volume),
+        dsp: Dsp::new(equalizer, normalize, channel_mix),
+        paused: false,
+        buffering: true,
+        bars_enabled: true,
+        fft_state: make_fft_state(spectrum_bits.len()),
+        // Never more than half the queue, so that it can always fill up.
+        prebuffer: Prebuffer::new(buffer_secs.max(1) * 1000 / 2),
+        silence: Silence::new(timeouts.silence()),
+    };
+    let ctx = ConnectionContext {
+        rx: &rx,
+        spectrum_bits: &spectrum_bits,
+        decoder_opts: &decoder_opts,
+        timeshift: &timeshift,
+        viz_enabled: &viz_enabled,
+        levels: &levels,
+        viz: VizParams {
+            peak_attack: 0.35,
+            peak_release: 0.995,
+            sensitivity: 1.25,
+            curve: 0.75,
+        },
+        events: &events,
+        max_queued_chunks,
+    };
+
+    let mut connecting = PlaybackState::Connecting;
+    loop {
+        let (endpoint_format, url) = endpoints[current];
+        if !player.paused {
+            events.emit(PlayerEvent::StateChanged(connecting));
+        }
+        connecting = PlaybackState::Reconnecting;
+
+        let (mut format, mut track_id, mut decoder) = match open_stream(
+            url,
+            &client,
+            &useragent,
+            &format_opts,
+            &metadata_opts,
+            &decoder_opts,
+            &record_tap,
+            &timeshift,
+            timeshift_window,
+        ) {
+            Ok(x) => x,
+            Err(e) => {
+                eprintln!("connect/probe error on {url}: {e}");
+                current = (current + 1) % endpoints.len();
+                quick_drops = 0;
+                failed_connects += 1;
+                if failed_connects >= endpoints.len() {
+                    failed_connects = 0;
+                    events.emit(PlayerEvent::Error(gettext(
+                        "Stream disconnected, retrying…",
+                    )));
+                    thread::sleep(RETRY_DELAY);
+                    match handle_control(&ctx, &mut player)? {
+                        Some(RunOutcome::Stop) => return Ok(()),
+                        Some(RunOutcome::Switch(next_station, next_format)) => {
+                            station = next_station;
+                            preferred = next_format;
+                            endpoints =
+                                playable_endpoints(station, preferred, stream_url.as_deref())?;
+                            current = 0;
+                        }
+                        Some(RunOutcome::Reconnect | RunOutcome::DeviceLost) | None => {}
+                    }
+                }
+                client = build_client(proxy.as_deref(), timeouts)?;
+                continue;
+            }
+        };
+        failed_connects = 0;
+        player.silence.reset();
+        let connected_at = Instant::now();
+        if !player.paused {
+            events.emit(PlayerEvent::StateChanged(PlaybackState::Buffering));
+        }
+
+        // On reconnect: clear the output queue + reset viz
+        player.out.reset(false);
+        let fft_state = &mut player.fft_state;
+        reset_fft_state(
+            &mut fft_state.mono_ring,
+            &mut fft_state.bars_smooth,
+            &mut fft_state.bar_peak,
+            &spectrum_bits,
+        );
+
+        #[cfg(debug_assertions)]
+        println!(
+            "[{}] Started decoding + playback ({}).",
+            now_string(),
+            endpoint_format.display_name()
+        );
+
+        let outcome = loop {
+            let outcome =
+                run_one_connection(&ctx, &mut player, &mut format, &mut track_id, &mut decoder)?;
+            if !matches!(outcome, RunOutcome::DeviceLost) {
+                break outcome;
+            }
+
+            // Only the output side is replaced; the HTTP stream and decoder carry on.
+            let network = player.out.is_network();
+            if network {
+                eprintln!("Audio sink disconnected, reconnecting…");
+            } else {
+                eprintln!("Audio device disappeared, reopening the default output…");
+            }
+            let reopened = loop {
+                match AudioOut::open(audio_sink.as_ref(), timeouts.connect()) {
+                    Ok(reopened) => break Ok(reopened),
+                    Err(e) => eprintln!("{e}"),
+                }
+                thread::sleep(RETRY_DELAY);
+                if let Some(outcome) = handle_control(&ctx, &mut player)? {
+                    break Err(outcome);
+                }
+            };
+            match reopened {
+                Ok(reopened) => {
+                    player.out.clear();
+                    player.out = reopened;
+                    player.out.set_volume(player.fade.volume());
+                    if player.paused {
+                        player.out.pause();
+                    }
+                    if !network {
+                        events.emit(PlayerEvent::OutputLost);
+                        events.emit(PlayerEvent::Error(gettext(
+                            "Audio device disconnected, switched to the default output",
+                        )));
+                    }
+                }
+                Err(outcome) => break outcome,
+            }
+        };
+
+        match outcome {
+            RunOutcome::Stop => return Ok(()),
+            RunOutcome::Switch(next_station, next_format) => {
+                // Only the network/decoder side is replaced; the output stream stays open.
+                station = next_station;
+                preferred = next_format;
+                endpoints = playable_endpoints(station, preferred, stream_url.as_deref())?;
+                current = 0;
+                quick_drops = 0;
+                connecting = PlaybackState::Connecting;
+                continue;
+            }
+            RunOutcome::Reconnect | RunOutcome::DeviceLost => {
+                events.emit(PlayerEvent::Error(gettext(
+                    "Stream disconnected, retrying…",
+                )));
+                if connected_at.elapsed() >= STABLE_CONNECTION {
+                    // The endpoint worked for a while: go back to the preferred one.
+                    quick_drops = 0;
+                    current = 0;
+                } else {
+                    quick_drops += 1;
+                    if quick_drops >= MAX_QUICK_DROPS {
+                        eprintln!(
+                            "{} stream keeps dropping, failing over…",
+                            endpoint_format.display_name()
+                        );
+                        quick_drops = 0;
+                        current = (current + 1) % endpoints.len();
+                    }
+                }
+                continue;
+            }
+        }
+    }
+}
+
+/// The endpoints to try in turn: `stream_url` alone when set, else the station's.
+fn playable_endpoints(
+    station: Station,
+    format: StreamFormat,
+    stream_url: Option<&str>,
+) -> Result<Vec<(StreamFormat, &str)>> {
+    if let Some(url) = stream_url {
+        return Ok(vec![(format, url)]);
+    }
+    let endpoints = station.stream_endpoints(format);
+    if endpoints.is_empty() {
+        return Err(format!("no playable stream for {}", station.display_name()).into());
+    }
+    Ok(endpoints)
+}
@@ -0,0 +1,476 @@
+//! Timeshift: the encoded stream is read into a rolling buffer on a thread of its own,
+//! and the decoder reads from a position in that buffer. Pausing only stops the decoder,
+//! so the connection stays healthy and playback resumes where it left off for as long
+//! as the buffer reaches back. Jumping to live or replaying a stretch moves the read
+//! position; decoders resync on the next frame or page.
+
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::record::HEAD_CAPTURE_BYTES;
+
+/// Bytes read from the network at a time.
+const READ_SIZE: usize = 16 * 1024;
+
+/// Where to move the read position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Seek {
+    /// The newest data.
+    Live,
+    /// This much earlier than the current position.
+    Back(Duration),
+}
+
+/// The encoded stream leading up to the read position, for saving the last part of it.
+#[derive(Debug)]
+pub struct Clip {
+    /// The start of the connection, which holds the codec headers.
+    pub head: Vec<u8>,
+    /// Everything buffered up to the read position.
+    pub data: Vec<u8>,
+    /// Where in `data` the clip starts; the bytes before it only carry headers of chained
+    /// streams.
+    pub start: usize,
+}
+
+#[derive(Debug)]
+struct Chunk {
+    /// When it arrived.
+    at: Instant,
+    /// Stream offset of the first byte.
+    offset: u64,
+    bytes: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+struct Ring {
+    window: Duration,
+    chunks: VecDeque<Chunk>,
+    /// Offset just past the newest byte.
+    end: u64,
+    /// Counts connections; readers and fillers of an older one stop.
+    connection: u64,
+    /// The network side ended, with the error if it failed.
+    closed: Option<Option<io::Error>>,
+    /// Where the reader goes on its next read.
+    seek: Option<u64>,
+    /// Where the reader is.
+    read_pos: Option<u64>,
+    /// Arrival time of the data the reader is at.
+    read_at: Option<Instant>,
+    /// The first bytes of the connection.
+    head: Vec<u8>,
+}
+
+impl Ring {
+    fn start(&self) -> u64 {
+        self.chunks.front().map_or(self.end, |chunk| chunk.offset)
+    }
+
+    /// Index of the chunk holding `offset`.
+    fn find(&self, offset: u64) -> Option<usize> {
+        let i = self.chunks.partition_point(|chunk| chunk.offset <= offset);
+        let chunk = self.chunks.get(i.checked_sub(1)?)?;
+        (offset < chunk.offset + chunk.bytes.len() as u64).then_some(i - 1)
+    }
+
+    fn push(&mut self, at: Instant, bytes: Vec<u8>) {
+        if self.head.len() < HEAD_CAPTURE_BYTES {
+            let take = bytes.len().min(HEAD_CAPTURE_BYTES - self.head.len());
+            self.head.extend_from_slice(&bytes[..take]);
+        }
+        let offset = self.end;
+        self.end += bytes.len() as u64;
+        self.chunks.push_back(Chunk { at, offset, bytes });
+        while self
+            .chunks
+            .front()
+            .is_some_and(|oldest| at.duration_since(oldest.at) > self.window)
+        {
+            self.chunks.pop_front();
+        }
+    }
+
+    /// Where `seek` lands from `pos`, at a chunk boundary.
+    fn seek_target(&self, seek: Seek, pos: u64) -> u64 {
+        match seek {
+            Seek::Live => self.chunks.back().map_or(self.end, |chunk| chunk.offset),
+            Seek::Back(by) => {
+                let Some(now) = self.find(pos).or(self.chunks.len().checked_sub(1)) else {
+                    return pos;
+                };
+                let target = self.chunks[now].at.checked_sub(by);
+                // The chunk that was arriving at that time.
+                let i = target.map_or(0, |target| {
+                    self.chunks
+                        .partition_point(|chunk| chunk.at <= target)
+                        .saturating_sub(1)
+                });
+                self.chunks[i.min(now)].offset
+            }
+        }
+    }
+}
+
+/// The rolling buffer, shared between the stream worker and the thread that fills it.
+#[derive(Debug)]
+pub(super) struct Timeshift {
+    ring: Mutex<Ring>,
+    changed: Condvar,
+    /// How far playback runs behind live; lowered when the reader falls off the buffer.
+    lag_ms: Arc<AtomicU64>,
+}
+
+impl Timeshift {
+    pub(super) fn new(lag_ms: Arc<AtomicU64>) -> Self {
+        Self {
+            ring: Mutex::new(Ring::default()),
+            changed: Condvar::new(),
+            lag_ms,
+        }
+    }
+
+    /// Start buffering a new connection, keeping up to `window` of it, and return the
+    /// reader for the decoder. Whatever was buffered before is dropped.
+    pub(super) fn attach(
+        self: &Arc<Self>,
+        mut source: impl Read + Send + 'static,
+        window: Duration,
+    ) -> TimeshiftReader {
+        // A new connection plays from live.
+        self.lag_ms.store(0, Ordering::Relaxed);
+        let connection = {
+            let mut ring = self.ring.lock().unwrap();
+            ring.connection += 1;
+            *ring = Ring {
+                window,
+                connection: ring.connection,
+                ..Ring::default()
+            };
+            ring.connection
+        };
+
+        let timeshift = self.clone();
+        thread::spawn(move || {
+            let mut buf = vec![0; READ_SIZE];
+            loop {
+                let read = source.read(&mut buf);
+                let mut ring = timeshift.ring.lock().unwrap();
+                if ring.connection != connection {
+                    return; // the reader went away; drop the connection
+                }
+                match read {
+                    Ok(0) => ring.closed = Some(None),
+                    Ok(n) => ring.push(Instant::now(), buf[..n].to_vec()),
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => ring.closed = Some(Some(err)),
+                }
+                let done = ring.closed.is_some();
+                drop(ring);
+                timeshift.changed.notify_all();
+                if done {
+                    return;
+                }
+            }
+        });
+
+        TimeshiftReader {
+            timeshift: self.clone(),
+            connection,
+            pos: 0,
+            last_at: None,
+        }
+    }
+
+    /// Move the read position of the current connection. The reader is `queued` ahead of
+    /// what is heard, so a step back is taken from there; `lag_ms` grows by as much as the
+    /// buffer allowed.
+    pub(super) fn seek(&self, seek: Seek, queued: Duration) {
+        let mut ring = self.ring.lock().unwrap();
+        let pos = ring.read_pos.unwrap_or(0);
+        let target = match seek {
+            Seek::Live => ring.seek_target(Seek::Live, pos),
+            Seek::Back(by) => {
+                let target = ring.seek_target(Seek::Back(by + queued), pos);
+                let to = ring.find(target).map(|i| ring.chunks[i].at);
+                if let (Some(from), Some(to)) = (ring.read_at, to) {
+                    let back = from.saturating_duration_since(to).saturating_sub(queued);
+                    self.lag_ms
+                        .fetch_add(back.as_millis() as u64, Ordering::Relaxed);
+                }
+                target
+            }
+        };
+        ring.seek = Some(target);
+        drop(ring);
+        self.changed.notify_all();
+    }
+
+    /// The last `length` before the read position, if anything was read yet.
+    pub(super) fn clip(&self, length: Duration) -> Option<Clip> {
+        let ring = self.ring.lock().unwrap();
+        let read_at = ring.read_at?;
+        let from = read_at.checked_sub(length);
+        let mut data = Vec::new();
+        let mut start = None;
+        for chunk in ring.chunks.iter().take_while(|chunk| chunk.at <= read_at) {
+            if start.is_none() && from.is_none_or(|from| chunk.at >= from) {
+                start = Some(data.len());
+            }
+            data.extend_from_slice(&chunk.bytes);
+        }
+        Some(Clip {
+            head: ring.head.clone(),
+            data,
+            start: start?,
+        })
+    }
+
+    /// How far back from the read position the buffer reaches.
+    pub(super) fn rewindable(&self) -> Duration {
+        let ring = self.ring.lock().unwrap();
+        match (ring.read_at, ring.chunks.front()) {
+            (Some(read_at), Some(oldest)) => read_at.saturating_duration_since(oldest.at),
+            _ => Duration::ZERO,
+        }
+    }
+}
+
+/// The decoder's view of the buffer.
+#[derive(Debug)]
+pub(super) struct TimeshiftReader {
+    timeshift: Arc<Timeshift>,
+    connection: u64,
+    pos: u64,
+    /// Arrival time of the last data read.
+    last_at: Option<Instant>,
+}
+
+impl Read for TimeshiftReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let timeshift = &self.timeshift;
+        let mut ring = timeshift.ring.lock().unwrap();
+        loop {
+            if ring.connection != self.connection {
+                return Ok(0);
+            }
+            if let Some(target) = ring.seek.take() {
+                self.pos = target;
+                self.last_at = None;
+            }
+            if self.pos < ring.start() {
+                // Paused for longer than the buffer reaches back: carry on from the oldest
+                // data, which is that much closer to live.
+                if let (Some(last), Some(oldest)) = (self.last_at, ring.chunks.front()) {
+                    let skipped = oldest.at.saturating_duration_since(last).as_millis() as u64;
+                    let _ = timeshift.lag_ms.fetch_update(
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                        |lag| Some(lag.saturating_sub(skipped)),
+                    );
+                }
+                self.pos = ring.start();
+            }
+            if let Some(i) = ring.find(self.pos) {
+                let chunk = &ring.chunks[i];
+                let from = (self.pos - chunk.offset) as usize;
+                let n = buf.len().min(chunk.bytes.len() - from);
+                buf[..n].copy_from_slice(&chunk.bytes[from..from + n]);
+                self.pos += n as u64;
+                self.last_at = Some(chunk.at);
+                ring.read_pos = Some(self.pos);
+                ring.read_at = self.last_at;
+                return Ok(n);
+            }
+            if let Some(closed) = ring.closed.as_mut() {
+                return match closed.take() {
+                    Some(err) => Err(err),
+                    None => Ok(0),
+                };
+            }
+            ring = timeshift.changed.wait(ring).unwrap();
+        }
+    }
+}
+
+impl Drop for TimeshiftReader {
+    fn drop(&mut self) {
+        let mut ring = self.timeshift.ring.lock().unwrap();
+        if ring.connection == self.connection {
+            // Stops the filler at its next read and frees the buffer.
+            ring.connection += 1;
+            ring.chunks.clear();
+        }
+    }
+}
+
+impl io::Seek for TimeshiftReader {
+    fn seek(&mut self, _pos: io::SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "seeking not supported on a live stream",
+        ))
+    }
+}
+
+impl symphonia::core::io::MediaSource for TimeshiftReader {
+    fn is_seekable(&self) -> bool {
+        false
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    /// A source fed from the test, one chunk per message.
+    struct Feed(mpsc::Receiver<Vec<u8>>);
+
+    impl Read for Feed {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.recv() {
+                Ok(bytes) => {
+                    buf[..bytes.len()].copy_from_slice(&bytes);
+                    Ok(bytes.len())
+                }
+                Err(_) => Ok(0),
+            }
+        }
+    }
+
+    fn attach(window: Duration) -> (Arc<Timeshift>, TimeshiftReader, mpsc::Sender<Vec<u8>>) {
+        let timeshift = Arc::new(Timeshift::new(Arc::new(AtomicU64::new(0))));
+        let (tx, rx) = mpsc::channel();
+        let reader = timeshift.attach(Feed(rx), window);
+        (timeshift, reader, tx)
+    }
+
+    /// Wait until the filler has buffered `end` bytes.
+    fn wait_for(timeshift: &Timeshift, end: u64) {
+        let mut ring = timeshift.ring.lock().unwrap();
+        while ring.end < end {
+            ring = timeshift.changed.wait(ring).unwrap();
+        }
+    }
+
+    #[test]
+    fn reads_in_order_and_ends_with_the_source() {
+        let (_timeshift, mut reader, tx) = attach(Duration::from_secs(60));
+        tx.send(b"abc".to_vec()).unwrap();
+        tx.send(b"de".to_vec()).unwrap();
+        drop(tx);
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"abcde");
+    }
+
+    #[test]
+    fn keeps_buffering_while_nobody_reads_and_jumps_to_live() {
+        let (timeshift, mut reader, tx) = attach(Duration::from_secs(60));
+        for chunk in [b"one", b"two", b"six"] {
+            tx.send(chunk.to_vec()).unwrap();
+        }
+        wait_for(&timeshift, 9);
+        let mut buf = [0; 3];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"one");
+
+        timeshift.seek(Seek::Live, Duration::ZERO);
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"six");
+    }
+
+    #[test]
+    fn steps_back_in_time() {
+        let (timeshift, mut reader, tx) = attach(Duration::from_secs(60));
+        for (end, chunk) in [(3, b"one"), (6, b"two"), (9, b"six")] {
+            tx.send(chunk.to_vec()).unwrap();
+            wait_for(&timeshift, end);
+            thread::sleep(Duration::from_millis(20));
+        }
+        let mut buf = [0; 3];
+        timeshift.seek(Seek::Live, Duration::ZERO);
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"six");
+        assert!(timeshift.rewindable() >= Duration::from_millis(40));
+
+        timeshift.seek(Seek::Back(Duration::from_millis(10)), Duration::ZERO);
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"two");
+        timeshift.seek(Seek::Back(Duration::from_secs(30)), Duration::ZERO);
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"one");
+    }
+
+    #[test]
+    fn lag_grows_by_what_the_buffer_allowed() {
+        let (timeshift, mut reader, tx) = attach(Duration::from_secs(60));
+        for (end, chunk) in [(3, b"one"), (6, b"two"), (9, b"six")] {
+            tx.send(chunk.to_vec()).unwrap();
+            wait_for(&timeshift, end);
+            thread::sleep(Duration::from_millis(20));
+        }
+        let mut buf = [0; 9];
+        reader.read_exact(&mut buf).unwrap();
+
+        // Near the start of the buffer the step back falls short of 30 seconds.
+        timeshift.seek(Seek::Back(Duration::from_secs(30)), Duration::ZERO);
+        let lag = timeshift.lag_ms.load(Ordering::Relaxed);
+        assert!((40..1000).contains(&lag), "lag {lag}");
+        reader.read_exact(&mut buf[..3]).unwrap();
+        assert_eq!(&buf[..3], b"one");
+
+        // What was already queued for the output is not heard again.
+        timeshift.seek(Seek::Back(Duration::ZERO), Duration::from_secs(1));
+        assert_eq!(timeshift.lag_ms.load(Ordering::Relaxed), lag);
+    }
+
+    #[test]
+    fn a_new_connection_plays_live() {
+        let (timeshift, _reader, _tx) = attach(Duration::from_secs(60));
+        timeshift.lag_ms.store(30_000, Ordering::Relaxed);
+        let (_, rx) = mpsc::channel();
+        let _reader = timeshift.attach(Feed(rx), Duration::from_secs(60));
+        assert_eq!(timeshift.lag_ms.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn clips_up_to_the_read_position() {
+        let (timeshift, mut reader, tx) = attach(Duration::from_secs(60));
+        assert!(timeshift.clip(Duration::from_secs(1)).is_none());
+        for (end, chunk) in [(3, b"one"), (6, b"two"), (9, b"six")] {
+            tx.send(chunk.to_vec()).unwrap();
+            wait_for(&timeshift, end);
+            thread::sleep(Duration::from_millis(20));
+        }
+        let mut buf = [0; 6];
+        reader.read_exact(&mut buf).unwrap();
+
+        let clip = timeshift.clip(Duration::from_millis(10)).unwrap();
+        assert_eq!(clip.head, b"onetwosix");
+        assert_eq!(clip.data, b"onetwo");
+        assert_eq!(&clip.data[clip.start..], b"two");
+    }
+
+    #[test]
+    fn falls_forward_when_the_buffer_moved_on() {
+        let (timeshift, mut reader, tx) = attach(Duration::ZERO);
+        tx.send(b"old".to_vec()).unwrap();
+        wait_for(&timeshift, 3);
+        thread::sleep(Duration::from_millis(5));
+        tx.send(b"new".to_vec()).unwrap();
+        wait_for(&timeshift, 6);
+        let mut buf = [0; 3];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"new");
+    }
+}
@@ -14,6 +14,8 @@ use super::Result;
 
 const FFT_SIZE: usize = 1024;
 const HOP: usize = 512;
+/// Undecodable packets in a row before the decoder is rebuilt.
+const MAX_DECODE_ERRORS: u32 = 16;
 
 pub(super) struct FftVizState {
     pub(super) mono_ring: Vec<f32>,
@@ -30,6 +32,21 @@ pub(super) struct DecodeState {
     pub(super) sample_buf: Option<SampleBuffer<f32>>,
     pub(super) channels: u16,
     pub(super) sample_rate: u32,
+    /// Packets in a row the decoder rejected.
+    pub(super) decode_errors: u32,
+}
+
+impl DecodeState {
+    /// Interleaved samples of the most recently decoded packet.
+    pub(super) fn samples(&self) -> &[f32] {
+        self.sample_buf.as_ref().map_or(&[], |buf| buf.samples())
+    }
+
+    pub(super) fn samples_mut(&mut self) -> &mut [f32] {
+        self.sample_buf
+            .as_mut()
+            .map_or(&mut [], |buf| buf.samples_mut())
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -63,8 +80,38 @@ pub(super) fn make_fft_state(num_bars: usize) -> FftVizState {
     }
 }
 
+/// Start over with a new decoder for the stream's current track.
+fn rebuild_decoder(
+    format: &mut Box<dyn symphonia::core::formats::FormatReader>,
+    track_id: &mut u32,
+    decoder: &mut Box<dyn symphonia::core::codecs::Decoder>,
+    decoder_opts: &DecoderOptions,
+    spectrum_bits: &Arc<Vec<AtomicU32>>,
+    decode_state: &mut DecodeState,
+    fft_state: &mut FftVizState,
+) -> Result<()> {
+    let new_track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| "no supported audio tracks after decoder reset".to_string())?;
+
+    *track_id = new_track.id;
+    *decoder = symphonia::default::get_codecs().make(&new_track.codec_params, decoder_opts)?;
+
+    decode_state.sample_buf = None;
+    decode_state.decode_errors = 0;
+    reset_fft_state(
+        &mut fft_state.mono_ring,
+        &mut fft_state.bars_smooth,
+        &mut fft_state.bar_peak,
+        spectrum_bits,
+    );
+    Ok(())
+}
+
 /// Returns:
-/// - PacketOutcome::Continue + Some((ch, sr, samples)) when audio is ready
+/// - PacketOutcome::Continue + Some((ch, sr)) when audio is ready in `decode_state.samples()`
 /// - PacketOutcome::SpecChanged when SR/ch changed (caller should recreate sink + reset FFT)
 /// - PacketOutcome::Reconnect on fatal decode
 pub(super) fn decode_and_process_packet(
@@ -78,38 +125,50 @@ pub(super) fn decode_and_process_packet(
     decode_state: &mut DecodeState,
     fft_state: &mut FftVizState,
     viz: VizParams,
-) -> Result<(PacketOutcome, Option<(u16, u32, Vec<f32>)>)> {
+) -> Result<(PacketOutcome, Option<(u16, u32)>)> {
     if packet.track_id() != *track_id {
         return Ok((PacketOutcome::Continue, None));
     }
 
     let decoded: AudioBufferRef<'_> = match decoder.decode(packet) {
-        Ok(buf) => buf,
-        Err(SymphoniaError::DecodeError(_)) => return Ok((PacketOutcome::Continue, None)),
+        Ok(buf) => {
+            decode_state.decode_errors = 0;
+            buf
+        }
+        Err(SymphoniaError::DecodeError(err)) => {
+            decode_state.decode_errors += 1;
+            if decode_state.decode_errors < MAX_DECODE_ERRORS {
+                return Ok((PacketOutcome::Continue, None));
+            }
+            // The stream likely changed format mid-way, e.g. another source on the mount;
+            // an MPEG decoder keeps the format of its first frame until rebuilt.
+            eprintln!("Repeated decode errors ({err}), rebuilding decoder…");
+            rebuild_decoder(
+                format,
+                track_id,
+                decoder,
+                decoder_opts,
+                spectrum_bits,
+                decode_state,
+                fft_state,
+            )?;
+            return Ok((PacketOutcome::Continue, None));
+        }
         Err(SymphoniaError::ResetRequired) => {
             #[cfg(debug_assertions)]
             println!(
                 "[{}] Decoder reset required, rebuilding decoder…",
                 now_string()
             );
-
-            let new_track = format
-                .tracks()
-                .iter()
-                .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
-                .ok_or_else(|| "no supported audio tracks after decoder reset".to_string())?;
-
-            *track_id = new_track.id;
-            *decoder =
-                symphonia::default::get_codecs().make(&new_track.codec_params, decoder_opts)?;
-
-            decode_state.sample_buf = None;
-            reset_fft_state(
-                &mut fft_state.mono_ring,
-                &mut fft_state.bars_smooth,
-                &mut fft_state.bar_peak,
+            rebuild_decoder(
+                format,
+                track_id,
+                decoder,
+                decoder_opts,
                 spectrum_bits,
-            );
+                decode_state,
+                fft_state,
+            )?;
             return Ok((PacketOutcome::Continue, None));
         }
         Err(err) => {
@@ -144,7 +203,15 @@ pub(super) fn decode_and_process_packet(
         .as_mut()
         .expect("sample_buf must be initialized");
     buf.copy_interleaved_ref(decoded);
-    let samples = buf.samples().to_owned();
+    let samples = buf.samples();
+    let audio = Some((decode_state.channels, decode_state.sample_rate));
+
+    // Paused or hidden: skip the FFT altogether.
+    if !bars_enabled {
+        fft_state.mono_ring.clear();
+        clear_spectrum(spectrum_bits);
+        return Ok((PacketOutcome::Continue, audio));
+    }
 
     // Downmix to mono ring buffer for FFT
     let ch = decode_state.channels as usize;
@@ -209,22 +276,15 @@ pub(super) fn decode_and_process_packet(
             fft_state.bars_smooth[i] = fft_state.bars_smooth[i] * 0.80 + fft_state.bars[i] * 0.20;
         }
 
-        if bars_enabled {
-            for (i, v) in fft_state.bars_smooth.iter().enumerate() {
-                spectrum_bits[i].store(v.to_bits(), Ordering::Relaxed);
-            }
-        } else {
-            clear_spectrum(spectrum_bits);
+        for (i, v) in fft_state.bars_smooth.iter().enumerate() {
+            spectrum_bits[i].store(v.to_bits(), Ordering::Relaxed);
         }
 
         let hop = HOP.min(fft_state.mono_ring.len());
         fft_state.mono_ring.drain(0..hop);
     }
 
-    Ok((
-        PacketOutcome::Continue,
-        Some((decode_state.channels, decode_state.sample_rate, samples)),
-    ))
+    Ok((PacketOutcome::Continue, audio))
 }
 
 pub(super) fn reset_fft_state(
@@ -0,0 +1,137 @@
+use dirs_next as dirs;
+use gettextrs::{
+    bind_textdomain_codeset, bindtextdomain, setlocale, textdomain, LocaleCategory,
+};
+use std::{env, path::{Path, PathBuf}};
+
+const APP_ID: &str = "io.github.noobping.listenmoe";
+
+fn find_locale_dir() -> PathBuf {
+    // Developer directory (cargo run)
+    let dev_dir = Path::new("data").join("locale");
+    if dev_dir.is_dir() {
+        return dev_dir;
+    }
+
+    // AppImage
+    if let Ok(appdir) = env::var("APPDIR") {
+        let candidate = Path::new(&appdir).join("usr").join("share").join("locale");
+        if candidate.is_dir() {
+            return candidate;
+        }
+    }
+
+    // exe dir
+    if let Ok(exe) = env::current_exe() {
+        if let Some(exe_dir) = exe.parent() {
+            let candidate = exe_dir.join("locale");
+            if candidate.is_dir() {
+                return candidate;
+            }
+        }
+    }
+
+    // Flatpak
+    let app_share_locale = Path::new("/app/share/locale");
+    if app_share_locale.is_dir() {
+        return app_share_locale.to_path_buf();
+    }
+
+    // User-level data dir
+    if let Some(base) = dirs::data_local_dir() {
+        let candidate = base.join(APP_ID).join("locale");
+        if candidate.is_dir() {
+            return candidate;
+        }
+    }
+
+    // System locale directory
+    let sys_dir = Path::new("/usr/share/locale");
+    if sys_dir.is_dir() {
+        return sys_dir.to_path_buf();
+    }
+
+    // Fallback
+    dev_dir.to_path_buf()
+}
+
+pub fn init_i18n() {
+    setlocale(LocaleCategory::LcAll, "");
+
+    let dir = find_locale_dir();
+    #[cfg(debug_assertions)]
+    println!("Using locale dir: {}", dir.display());
+
+    let dir_str = dir
+        .to_str()
+        .expect("Locale path must be UTF-8 for gettext");
+
+    bindtextdomain(APP_ID, dir_str).expect("bindtextdomain failed");
+    bind_textdomain_codeset(APP_ID, "UTF-8").expect("bind codeset failed");
+    textdomain(APP_ID).expect("textdomain failed");
+}
+
+/// Fill the `{}` placeholders of a translated `template` with `args`, in order.
+/// Placeholders without an argument are left as they are.
+pub fn fill(template: &str, args: &[String]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut rest = template;
+    while let Some(pos) = rest.find("{}") {
+        out.push_str(&rest[..pos]);
+        out.push_str(args.next().map_or("{}", String::as_str));
+        rest = &rest[pos + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Translate a message, filling its `{}` placeholders with the arguments:
+/// `t!("Requested by: {}", name)`. Extract with `xgettext --keyword=t`.
+#[macro_export]
+macro_rules! t {
+    ($msgid:literal) => {
+        gettextrs::gettext($msgid)
+    };
+    ($msgid:literal, $($arg:expr),+ $(,)?) => {
+        $crate::locale::fill(&gettextrs::gettext($msgid), &[$($arg.to_string()),+])
+    };
+}
+
+/// Translate a message with plural forms picked by the `u32` count, which fills the
+/// first `{}`; further arguments fill the rest: `n!("{} listening", "{} listening", count)`.
+/// Extract with `xgettext --keyword=n:1,2`.
+#[macro_export]
+macro_rules! n {
+    ($singular:literal, $plural:literal, $count:expr $(, $arg:expr)* $(,)?) => {{
+        let count: u32 = $count;
+        $crate::locale::fill(
+            &gettextrs::ngettext($singular, $plural, count),
+            &[count.to_string() $(, $arg.to_string())*],
+        )
+    }};
+}
+
+pub use crate::{n, t};
+
+#[cfg(test)]
+mod tests {
+    use super::fill;
+
+    #[test]
+    fn fills_placeholders_in_order() {
+        let args = ["a".to_string(), "b".to_string()];
+        assert_eq!(fill("{} and {}", &args), "a and b");
+    }
+
+    #[test]
+    fn arguments_are_not_reparsed() {
+        let args = ["{}".to_string(), "x".to_string()];
+        assert_eq!(fill("[{}] [{}]", &args), "[{}] [x]");
+    }
+
+    #[test]
+    fn missing_arguments_keep_the_placeholder() {
+        assert_eq!(fill("{} of {}", &["1".to_string()]), "1 of {}");
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Wall-clock drift against the monotonic clock that counts as a jump.
+const JUMP_THRESHOLD: Duration = Duration::from_secs(2);
+/// Recent track updates the skew estimate is taken from.
+const SKEW_SAMPLES: usize = 8;
+/// Anything further off is an update for a song that started well before it was sent.
+const MAX_SKEW: Duration = Duration::from_secs(60 * 60);
+
+/// Detects system suspend/resume and wall-clock steps (NTP, manual changes).
+///
+/// The monotonic clock does not advance while the machine sleeps and is not affected by
+/// clock changes, so any large disagreement between the two clocks is a jump.
+#[derive(Debug)]
+pub struct ClockJumpDetector {
+    mono: Instant,
+    wall: SystemTime,
+}
+
+impl ClockJumpDetector {
+    pub fn new() -> Self {
+        Self {
+            mono: Instant::now(),
+            wall: SystemTime::now(),
+        }
+    }
+
+    /// Returns the jump in milliseconds (positive when the wall clock moved forward)
+    /// since the previous check, if it exceeds the threshold.
+    pub fn check(&mut self) -> Option<i64> {
+        let mono = Instant::now();
+        let wall = SystemTime::now();
+
+        let mono_elapsed = mono.duration_since(self.mono).as_millis() as i64;
+        let wall_elapsed = signed_millis(wall, self.wall);
+
+        self.mono = mono;
+        self.wall = wall;
+
+        let jump = wall_elapsed - mono_elapsed;
+        (jump.unsigned_abs() > JUMP_THRESHOLD.as_millis() as u64).then_some(jump)
+    }
+}
+
+/// Estimates how far the local clock runs ahead of the server's.
+///
+/// A live track update goes out as the song starts, so receipt time minus `startTime` is
+/// the skew plus the delivery delay. The delay is never negative, so the smallest recent
+/// sample is the best guess.
+#[derive(Debug, Default)]
+pub struct ClockSkew {
+    samples: VecDeque<i64>,
+}
+
+impl ClockSkew {
+    pub fn observe(&mut self, received: SystemTime, start: SystemTime) {
+        let sample = signed_millis(received, start);
+        if sample.unsigned_abs() > MAX_SKEW.as_millis() as u64 {
+            return;
+        }
+        if self.samples.len() == SKEW_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Milliseconds the local clock is ahead of the server (negative when behind).
+    pub fn millis(&self) -> i64 {
+        self.samples.iter().copied().min().unwrap_or(0)
+    }
+}
+
+/// `a - b` in milliseconds.
+fn signed_millis(a: SystemTime, b: SystemTime) -> i64 {
+    match a.duration_since(b) {
+        Ok(d) => d.as_millis() as i64,
+        Err(e) => -(e.duration().as_millis() as i64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skew_takes_the_fastest_delivery() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_735_732_800);
+        let mut skew = ClockSkew::default();
+        assert_eq!(skew.millis(), 0);
+        // Local clock 3 s behind, updates delivered after 400 ms and 150 ms.
+        skew.observe(start - Duration::from_millis(2_600), start);
+        skew.observe(start - Duration::from_millis(2_850), start);
+        assert_eq!(skew.millis(), -2_850);
+        // An update for a song that began two hours ago says nothing about the clock.
+        skew.observe(start + Duration::from_secs(7_200), start);
+        assert_eq!(skew.millis(), -2_850);
+    }
+}
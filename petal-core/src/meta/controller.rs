@@ -0,0 +1,195 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+};
+use std::thread;
+use std::time::Duration;
+
+use crate::events::EventBus;
+use crate::settings::Timeouts;
+use crate::station::Station;
+
+use super::gateway::{run_meta_loop, SharedHistory};
+
+#[derive(Debug)]
+pub enum Control {
+    Stop,
+    Pause,
+    Resume,
+    /// Stay connected without updating the UI, then stop unless resumed in time.
+    Linger(Duration),
+}
+
+#[derive(Debug)]
+enum State {
+    Stopped,
+    Running {
+        tx: mpsc::Sender<Control>,
+        /// Set once the loop ended on its own, after lingering.
+        done: Arc<AtomicBool>,
+    },
+    /// Stopped by the user, but still connected for a quick restart.
+    Lingering {
+        tx: mpsc::Sender<Control>,
+        done: Arc<AtomicBool>,
+    },
+}
+
+#[derive(Debug)]
+struct Inner {
+    station: Station,
+    state: State,
+    events: EventBus,
+    lag_ms: Arc<AtomicU64>,
+    ui_sched_id: Arc<AtomicU64>,
+    /// Kept per station, so switching back has the lag-corrected context right away.
+    histories: HashMap<Station, SharedHistory>,
+    proxy: Option<String>,
+    timeouts: Timeouts,
+    gateway_url: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Meta {
+    inner: RefCell<Inner>,
+}
+
+impl Meta {
+    pub fn new(station: Station, events: EventBus, lag_ms: Arc<AtomicU64>) -> Rc<Self> {
+        Rc::new(Self {
+            inner: RefCell::new(Inner {
+                station,
+                state: State::Stopped,
+                events,
+                lag_ms,
+                ui_sched_id: Arc::new(AtomicU64::new(0)),
+                histories: HashMap::new(),
+                proxy: None,
+                timeouts: Timeouts::default(),
+                gateway_url: None,
+            }),
+        })
+    }
+
+    /// Proxy for the websocket, overriding the environment; used from the next connect.
+    pub fn set_proxy(&self, proxy: Option<String>) {
+        self.inner.borrow_mut().proxy = proxy;
+    }
+
+    /// Connect and read timeouts for the websocket, used from the next connect, and how
+    /// long [`Meta::stop`] keeps it open for a quick restart.
+    pub fn set_timeouts(&self, timeouts: Timeouts) {
+        self.inner.borrow_mut().timeouts = timeouts;
+    }
+
+    /// Gateway to connect to instead of the station's own, e.g. a local test server; used
+    /// from the next connect.
+    pub fn set_gateway_url(&self, url: Option<String>) {
+        self.inner.borrow_mut().gateway_url = url;
+    }
+
+    pub fn set_station(&self, station: Station) {
+        let mut inner = self.inner.borrow_mut();
+        let was_running = matches!(inner.state, State::Running { .. });
+        Self::stop_inner(&mut inner);
+        inner.station = station;
+        if was_running {
+            Self::start_inner(&mut inner);
+        }
+    }
+
+    pub fn start(&self) {
+        let mut inner = self.inner.borrow_mut();
+        match std::mem::replace(&mut inner.state, State::Stopped) {
+            State::Running { tx, done } | State::Lingering { tx, done }
+                if !done.load(Ordering::Relaxed) =>
+            {
+                let _ = tx.send(Control::Resume);
+                inner.state = State::Running { tx, done };
+            }
+            // stopped: actually start thread
+            _ => Self::start_inner(&mut inner),
+        }
+    }
+
+    pub fn pause(&self) {
+        let inner = self.inner.borrow();
+        if let State::Running { tx, .. } = &inner.state {
+            let _ = tx.send(Control::Pause);
+        }
+    }
+
+    pub fn stop(&self) {
+        let mut inner = self.inner.borrow_mut();
+        let linger = inner.timeouts.linger();
+        if linger.is_zero() {
+            Self::stop_inner(&mut inner);
+            return;
+        }
+        match std::mem::replace(&mut inner.state, State::Stopped) {
+            State::Running { tx, done } => {
+                let _ = tx.send(Control::Linger(linger));
+                inner.state = State::Lingering { tx, done };
+            }
+            state => inner.state = state,
+        }
+    }
+
+    fn start_inner(inner: &mut Inner) {
+        match inner.state {
+            State::Running { .. } | State::Lingering { .. } => {}
+            State::Stopped => {
+                let (tx, rx) = mpsc::channel::<Control>();
+                let station = inner.station;
+                let events = inner.events.clone();
+                let lag_ms = inner.lag_ms.clone();
+                let ui_sched_id = inner.ui_sched_id.clone();
+                let history = inner.histories.entry(station).or_default().clone();
+                let proxy = inner.proxy.clone();
+                let timeouts = inner.timeouts;
+                let gateway_url = inner.gateway_url.clone();
+
+                let done = Arc::new(AtomicBool::new(false));
+                inner.state = State::Running {
+                    tx: tx.clone(),
+                    done: done.clone(),
+                };
+
+                thread::spawn(move || {
+                    if let Err(err) = run_meta_loop(
+                        station,
+                        events,
+                        rx,
+                        lag_ms,
+                        ui_sched_id,
+                        history,
+                        proxy,
+                        timeouts,
+                        gateway_url,
+                    ) {
+                        eprintln!("Gateway error in metadata loop: {err}");
+                    }
+                    done.store(true, Ordering::Relaxed);
+                });
+            }
+        }
+    }
+
+    fn stop_inner(inner: &mut Inner) {
+        if let State::Running { tx, .. } | State::Lingering { tx, .. } = &inner.state {
+            let _ = tx.send(Control::Stop);
+        }
+        inner.state = State::Stopped;
+    }
+}
+
+impl Drop for Meta {
+    fn drop(&mut self) {
+        let mut inner = self.inner.borrow_mut();
+        Self::stop_inner(&mut inner);
+    }
+}
@@ -0,0 +1,1255 @@
+use gettextrs::gettext;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::mpsc;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use tungstenite::client_tls;
+use tungstenite::protocol::WebSocket;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::Message;
+
+#[cfg(debug_assertions)]
+use crate::log::now_string;
+
+use super::clock::{ClockJumpDetector, ClockSkew};
+use super::controller::Control;
+use super::error::MetaResult;
+use super::schedule::{pick_track_for_playback, schedule_next_from_history, Scheduler, Timeline};
+use super::time_parse::parse_rfc3339_system_time;
+use super::track::{TrackInfo, ALBUM_COVER_BASE, ARTIST_IMAGE_BASE};
+use crate::events::{EventBus, GatewayState, PlayerEvent};
+use crate::proxy;
+use crate::settings::Timeouts;
+use crate::station::Station;
+
+/// Protocol-level types for the LISTEN.moe gateway
+
+#[derive(Debug, Deserialize)]
+struct GatewayHello {
+    heartbeat: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GatewaySongPayload {
+    song: Song,
+    #[serde(default)]
+    requester: Option<Requester>,
+    #[serde(default)]
+    event: Option<Event>,
+    #[serde(rename = "startTime")]
+    start_time: String,
+    #[serde(default)]
+    listeners: Option<u32>,
+    #[serde(rename = "lastPlayed", default)]
+    last_played: Vec<Song>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Song {
+    #[serde(default)]
+    id: Option<u32>,
+    title: Option<String>,
+    #[serde(default)]
+    artists: Vec<Artist>,
+    #[serde(default)]
+    albums: Vec<Album>,
+    #[serde(default)]
+    sources: Vec<Source>,
+    duration: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Artist {
+    name: Option<String>,
+    image: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Album {
+    image: Option<String>,
+}
+
+/// Anime, game or other work a song is from.
+#[derive(Debug, Deserialize)]
+struct Source {
+    name: Option<String>,
+    #[serde(rename = "nameRomaji")]
+    name_romaji: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Requester {
+    username: Option<String>,
+    #[serde(rename = "displayName")]
+    display_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Event {
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GatewayEnvelope {
+    op: u8,
+    #[serde(default)]
+    t: Option<String>,
+    #[serde(default)]
+    d: Value,
+}
+
+const OP_HELLO: u8 = 0;
+const OP_DISPATCH: u8 = 1;
+const OP_HEARTBEAT_ACK: u8 = 10;
+const EVENT_TRACK_UPDATE: &str = "TRACK_UPDATE";
+
+const MAX_INACTIVITY: Duration = Duration::from_secs(30);
+/// Heartbeats in a row that may go unacknowledged before the connection counts as dead.
+const MAX_MISSED_ACKS: u32 = 2;
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+/// Tracks kept per station for matching buffered playback.
+const HISTORY_LEN: usize = 32;
+/// Upper bound on idle sleeps, so suspend/resume is noticed without a socket event.
+const CLOCK_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// A forward jump at least this long is taken as a suspend; connections rarely survive it.
+const SLEEP_JUMP_MS: i64 = 10_000;
+
+/// A station's recent tracks, kept by the controller across restarts of the loop.
+pub type SharedHistory = Arc<Mutex<VecDeque<TrackInfo>>>;
+
+/// A decoded gateway frame, reduced to what the metadata loop acts on.
+#[derive(Debug)]
+enum GatewayFrame {
+    Hello {
+        heartbeat_ms: u64,
+    },
+    HeartbeatAck,
+    /// The current track and the recently played ones, oldest first.
+    TrackUpdate(Box<TrackInfo>, Vec<TrackInfo>),
+    /// Unknown ops, other dispatch events and track updates without a usable payload.
+    Ignored,
+}
+
+/// Everything that can wake the metadata thread.
+enum Wake {
+    Control(Control),
+    /// Output of a websocket reader, tagged with the session it belongs to.
+    Gateway(u64, GatewayEvent),
+}
+
+enum GatewayEvent {
+    Frame(GatewayFrame),
+    HeartbeatSent,
+    /// The reader stopped; `Some` carries the error that ended the session.
+    Closed(Option<String>),
+}
+
+enum SessionEnd {
+    Stopped,
+    Disconnected,
+    /// Paused for long enough to close the socket until playback resumes.
+    Suspended,
+}
+
+/// Outer reconnect loop using blocking tungstenite.
+///
+/// The thread blocks on a single wake channel fed by the control channel and the socket
+/// reader, so it only runs when something actually happens. `shared_history` is the
+/// station's history from earlier runs; it is restored right away and written back after
+/// every session. `gateway_url` replaces the station's gateway when set.
+#[allow(clippy::too_many_arguments)]
+pub fn run_meta_loop(
+    station: Station,
+    events: EventBus,
+    rx: mpsc::Receiver<Control>,
+    lag_ms: Arc<AtomicU64>,
+    ui_sched_id: Arc<AtomicU64>,
+    shared_history: SharedHistory,
+    proxy: Option<String>,
+    timeouts: Timeouts,
+    gateway_url: Option<String>,
+) -> MetaResult<()> {
+    let scheduler = Scheduler::spawn(events.clone(), ui_sched_id.clone());
+    let mut history = shared_history.lock().unwrap().clone();
+    // Outlives sessions: a reconnect starts mid-song and has nothing to sample at first.
+    let mut skew = ClockSkew::default();
+    // Worth showing until the first update only while the newest track is still on air.
+    if history.back().is_some_and(|t| {
+        t.start_time_utc + Duration::from_secs(t.duration_secs.into()) > SystemTime::now()
+    }) {
+        let timeline = Timeline::now(skew.millis());
+        resync_ui(
+            &events,
+            &scheduler,
+            &history,
+            &lag_ms,
+            &ui_sched_id,
+            &timeline,
+        );
+    }
+    let (wake_tx, wake_rx) = mpsc::channel::<Wake>();
+    {
+        let wake_tx = wake_tx.clone();
+        thread::spawn(move || {
+            for control in rx {
+                if wake_tx.send(Wake::Control(control)).is_err() {
+                    return;
+                }
+            }
+            // The controller went away: treat it as a stop.
+            let _ = wake_tx.send(Wake::Control(Control::Stop));
+        });
+    }
+
+    // While set, the user stopped playback and the connection is only kept until then.
+    let mut linger: Option<Instant> = None;
+    let mut session = 0;
+    loop {
+        session += 1;
+        events.emit(PlayerEvent::GatewayStateChanged(if session == 1 {
+            GatewayState::Connecting
+        } else {
+            GatewayState::Reconnecting
+        }));
+        let result = run_once(
+            station,
+            &events,
+            &scheduler,
+            &wake_rx,
+            &wake_tx,
+            session,
+            &lag_ms,
+            &ui_sched_id,
+            &mut history,
+            &mut skew,
+            &mut linger,
+            proxy.as_deref(),
+            timeouts,
+            gateway_url.as_deref(),
+        );
+        *shared_history.lock().unwrap() = history.clone();
+        let retry_in = match result {
+            Ok(SessionEnd::Stopped) => return Ok(()),
+            Ok(SessionEnd::Suspended) => {
+                if wait_for_resume(&wake_rx) {
+                    return Ok(());
+                }
+                continue;
+            }
+            // Normal end (server closed the connection): retry.
+            Ok(SessionEnd::Disconnected) => RETRY_DELAY,
+            Err(err) => {
+                eprintln!("Gateway connection error: {err}, retrying in 5s…");
+                events.emit(PlayerEvent::Error(gettext(
+                    "Track info disconnected, retrying…",
+                )));
+                RETRY_DELAY
+            }
+        };
+        if wait_for_retry(&wake_rx, retry_in, &mut linger) {
+            return Ok(());
+        }
+    }
+}
+
+/// Sleep until the retry delay passes; returns `true` if a stop was requested meanwhile
+/// or the linger time ran out. Any other control cuts the wait short so a resume
+/// reconnects right away.
+fn wait_for_retry(
+    wake_rx: &mpsc::Receiver<Wake>,
+    delay: Duration,
+    linger: &mut Option<Instant>,
+) -> bool {
+    let deadline = Instant::now() + delay;
+    loop {
+        let now = Instant::now();
+        if linger.is_some_and(|until| now >= until) {
+            return true;
+        }
+        let left = deadline
+            .min(linger.unwrap_or(deadline))
+            .saturating_duration_since(now);
+        match wake_rx.recv_timeout(left) {
+            Ok(Wake::Control(Control::Stop)) => return true,
+            Ok(Wake::Control(Control::Linger(time))) => *linger = Some(Instant::now() + time),
+            Ok(Wake::Control(Control::Resume)) => {
+                *linger = None;
+                return false;
+            }
+            Ok(Wake::Control(_)) => return false,
+            // Leftovers from a finished session.
+            Ok(Wake::Gateway(..)) => continue,
+            Err(mpsc::RecvTimeoutError::Timeout) => return false,
+            Err(mpsc::RecvTimeoutError::Disconnected) => return true,
+        }
+    }
+}
+
+/// Stay disconnected until playback resumes; returns `true` if it was stopped instead.
+fn wait_for_resume(wake_rx: &mpsc::Receiver<Wake>) -> bool {
+    loop {
+        match wake_rx.recv() {
+            Ok(Wake::Control(Control::Resume)) => return false,
+            // There is no connection left to keep open for a quick restart.
+            Ok(Wake::Control(Control::Stop | Control::Linger(_))) | Err(_) => return true,
+            Ok(Wake::Control(Control::Pause) | Wake::Gateway(..)) => continue,
+        }
+    }
+}
+
+/// Single websocket session. A reader thread owns the socket and sends heartbeats;
+/// this thread keeps history and does "snap-to-buffered-track" on Resume.
+#[allow(clippy::too_many_arguments)]
+fn run_once(
+    station: Station,
+    events: &EventBus,
+    scheduler: &Scheduler,
+    wake_rx: &mpsc::Receiver<Wake>,
+    wake_tx: &mpsc::Sender<Wake>,
+    session: u64,
+    lag_ms: &Arc<AtomicU64>,
+    ui_sched_id: &Arc<AtomicU64>,
+    history: &mut VecDeque<TrackInfo>,
+    skew: &mut ClockSkew,
+    linger: &mut Option<Instant>,
+    proxy: Option<&str>,
+    timeouts: Timeouts,
+    gateway_url: Option<&str>,
+) -> MetaResult<SessionEnd> {
+    let url = gateway_url.unwrap_or(station.ws_url());
+    let (mut ws, _response) = connect_ws(url, proxy, timeouts)?;
+    set_maybe_tls_read_timeout(ws.get_mut(), Some(timeouts.read()))?;
+    #[cfg(debug_assertions)]
+    println!("[{}] Gateway connected to LISTEN.moe", now_string());
+
+    // Read hello and get heartbeat interval (if any).
+    let heartbeat_ms = read_hello_heartbeat(&mut ws)?;
+    // Send an immediate heartbeat once after HELLO, then continue on the interval.
+    let _ = ws.send(Message::Text(r#"{"op":9}"#.into()));
+    let heartbeat_dur = heartbeat_ms.map(Duration::from_millis);
+    events.emit(PlayerEvent::GatewayStateChanged(GatewayState::Connected));
+
+    // Shutting the raw socket down is the only way to unblock the reader from here.
+    let _shutdown = SocketShutdown(socket_handle(ws.get_ref())?);
+    spawn_reader(ws, heartbeat_dur, session, wake_tx.clone());
+
+    // Liveness tracking: when the network interface changes, the socket may stop delivering
+    // messages without cleanly closing. Heartbeats sent since the last ACK, counting the
+    // one sent right after HELLO.
+    let mut unacked_heartbeats: u32 = 1;
+    let mut last_any_msg = Instant::now();
+
+    let mut paused = linger.is_some();
+    // The first update of a session is for a song already under way.
+    let mut caught_up = false;
+    // When a long pause closes the socket to save power.
+    let mut suspend_at: Option<Instant> = None;
+    let mut clock = ClockJumpDetector::new();
+    let mut timeline = Timeline::now(skew.millis());
+
+    loop {
+        let mut timeout = CLOCK_CHECK_INTERVAL;
+        if let Some(at) = suspend_at {
+            let left = at.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                #[cfg(debug_assertions)]
+                println!("[{}] Paused for long; disconnecting", now_string());
+                return Ok(SessionEnd::Suspended);
+            }
+            timeout = timeout.min(left);
+        }
+        if let Some(until) = *linger {
+            let left = until.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                #[cfg(debug_assertions)]
+                println!("[{}] Linger time over; disconnecting", now_string());
+                return Ok(SessionEnd::Stopped);
+            }
+            timeout = timeout.min(left);
+        }
+        if heartbeat_ms.is_none() {
+            // No heartbeat info from the server — fall back to a generic inactivity timeout.
+            let deadline = last_any_msg + MAX_INACTIVITY;
+            if Instant::now() >= deadline {
+                eprintln!("Gateway inactivity timeout (>{MAX_INACTIVITY:?}); reconnecting…");
+                return Ok(SessionEnd::Disconnected);
+            }
+            timeout = timeout.min(deadline.saturating_duration_since(Instant::now()));
+        }
+
+        let wake = match wake_rx.recv_timeout(timeout) {
+            Ok(wake) => Some(wake),
+            Err(mpsc::RecvTimeoutError::Timeout) => None,
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                ui_sched_id.fetch_add(1, Ordering::Relaxed);
+                return Ok(SessionEnd::Stopped);
+            }
+        };
+
+        // The monotonic clock stops while suspended, so pending switches would fire late
+        // after a resume: reconnect for fresh history. A plain clock step doesn't matter,
+        // switches are timed against the timeline until the next track update re-anchors it.
+        if let Some(jump_ms) = clock.check() {
+            if jump_ms >= SLEEP_JUMP_MS {
+                eprintln!("Woke up after ~{} s asleep; reconnecting…", jump_ms / 1000);
+                events.emit(PlayerEvent::Resumed);
+                return Ok(SessionEnd::Disconnected);
+            }
+            eprintln!("System clock jumped by {jump_ms} ms");
+        }
+
+        let frame = match wake {
+            None => continue,
+            Some(Wake::Control(Control::Stop)) => {
+                ui_sched_id.fetch_add(1, Ordering::Relaxed);
+                return Ok(SessionEnd::Stopped);
+            }
+            Some(Wake::Control(Control::Pause)) => {
+                #[cfg(debug_assertions)]
+                println!("[{}] Pausing meta data", now_string());
+                paused = true;
+                suspend_at = timeouts.suspend().map(|after| Instant::now() + after);
+                ui_sched_id.fetch_add(1, Ordering::Relaxed); // invalidate any pending scheduled sends
+                continue;
+            }
+            Some(Wake::Control(Control::Linger(time))) => {
+                // Like a pause, but the loop ends once `time` passes without a resume.
+                paused = true;
+                *linger = Some(Instant::now() + time);
+                ui_sched_id.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            Some(Wake::Control(Control::Resume)) => {
+                #[cfg(debug_assertions)]
+                println!("[{}] Resuming meta data", now_string());
+                paused = false;
+                suspend_at = None;
+                *linger = None;
+                resync_ui(events, scheduler, history, lag_ms, ui_sched_id, &timeline);
+                continue;
+            }
+            Some(Wake::Gateway(from, _)) if from != session => continue,
+            Some(Wake::Gateway(_, GatewayEvent::Closed(None))) => {
+                return Ok(SessionEnd::Disconnected)
+            }
+            Some(Wake::Gateway(_, GatewayEvent::Closed(Some(err)))) => return Err(err.into()),
+            Some(Wake::Gateway(_, GatewayEvent::HeartbeatSent)) => {
+                if unacked_heartbeats >= MAX_MISSED_ACKS {
+                    eprintln!(
+                        "Gateway missed {unacked_heartbeats} heartbeat ACKs in a row; reconnecting…"
+                    );
+                    return Ok(SessionEnd::Disconnected);
+                }
+                unacked_heartbeats += 1;
+                continue;
+            }
+            Some(Wake::Gateway(_, GatewayEvent::Frame(frame))) => frame,
+        };
+
+        last_any_msg = Instant::now();
+
+        match frame {
+            GatewayFrame::HeartbeatAck => {
+                unacked_heartbeats = 0;
+                #[cfg(debug_assertions)]
+                println!("[{}] Gateway heartbeat", now_string());
+            }
+            GatewayFrame::TrackUpdate(info, previous) => {
+                #[cfg(debug_assertions)]
+                println!(
+                    "[{}] live track update: {} - {} (duration={})",
+                    now_string(),
+                    info.artist,
+                    info.title,
+                    info.duration_secs
+                );
+                if !caught_up {
+                    // Connected mid-session: playback may still be in an earlier track, and
+                    // history kept from before may be missing the songs played since.
+                    caught_up = true;
+                    for track in previous.into_iter().chain([*info]) {
+                        if !is_stale(history, &track) {
+                            push_history(history, track);
+                        }
+                    }
+                    timeline = Timeline::now(skew.millis());
+                    if !paused {
+                        resync_ui(events, scheduler, history, lag_ms, ui_sched_id, &timeline);
+                    }
+                    continue;
+                }
+                if is_stale(history, &info) {
+                    #[cfg(debug_assertions)]
+                    println!("[{}] stale track update ignored", now_string());
+                    continue;
+                }
+                skew.observe(SystemTime::now(), info.start_time_utc);
+                timeline = Timeline::now(skew.millis());
+                push_history(history, *info);
+
+                if !paused {
+                    let lag = lag_ms.load(Ordering::Relaxed);
+                    let my_id = ui_sched_id.fetch_add(1, Ordering::Relaxed) + 1;
+                    #[cfg(debug_assertions)]
+                    println!(
+                        "[{}] ui {} scheduled: {} - {} (lag_ms={})",
+                        now_string(),
+                        my_id,
+                        history.back().unwrap().artist,
+                        history.back().unwrap().title,
+                        lag
+                    );
+                    // Schedule the *new* track to appear when playback reaches it
+                    scheduler.schedule(history.back().unwrap().clone(), lag, my_id, &timeline);
+                }
+            }
+            GatewayFrame::Hello { .. } | GatewayFrame::Ignored => {}
+        }
+    }
+}
+
+/// Own the socket: block on reads, send heartbeats when due and forward decoded frames.
+fn spawn_reader(
+    mut ws: WebSocket<MaybeTlsStream<TcpStream>>,
+    heartbeat: Option<Duration>,
+    session: u64,
+    wake_tx: mpsc::Sender<Wake>,
+) {
+    thread::spawn(move || {
+        let mut next_heartbeat = heartbeat.map(|hb| Instant::now() + hb);
+        let closed = loop {
+            let timeout = next_heartbeat.map(|at| {
+                at.saturating_duration_since(Instant::now())
+                    .max(Duration::from_millis(1))
+            });
+            if let Err(err) = set_maybe_tls_read_timeout(ws.get_mut(), timeout) {
+                break Some(err.to_string());
+            }
+
+            let msg = match ws.read() {
+                Ok(msg) => msg,
+                Err(tungstenite::Error::ConnectionClosed) => break None,
+                Err(tungstenite::Error::Io(ref e))
+                    if e.kind() == std::io::ErrorKind::WouldBlock
+                        || e.kind() == std::io::ErrorKind::TimedOut =>
+                {
+                    if let (Some(interval), Some(at)) = (heartbeat, next_heartbeat.as_mut()) {
+                        if let Err(err) = ws.send(Message::Text(r#"{"op":9}"#.into())) {
+                            break Some(format!("heartbeat send error: {err}"));
+                        }
+                        *at = Instant::now() + interval;
+                        let event = GatewayEvent::HeartbeatSent;
+                        if wake_tx.send(Wake::Gateway(session, event)).is_err() {
+                            return;
+                        }
+                    }
+                    continue;
+                }
+                Err(err) => break Some(err.to_string()),
+            };
+
+            if !msg.is_text() {
+                continue;
+            }
+            let frame = match msg
+                .into_text()
+                .map_err(|e| e.to_string())
+                .and_then(|txt| decode_frame(&txt).map_err(|e| e.to_string()))
+            {
+                Ok(frame) => frame,
+                Err(err) => {
+                    eprintln!("Gateway JSON parse error: {err}");
+                    continue;
+                }
+            };
+            let event = GatewayEvent::Frame(frame);
+            if wake_tx.send(Wake::Gateway(session, event)).is_err() {
+                return;
+            }
+        };
+        let _ = wake_tx.send(Wake::Gateway(session, GatewayEvent::Closed(closed)));
+    });
+}
+
+/// Shuts the socket down when the session ends, which ends its reader thread too.
+struct SocketShutdown(Option<TcpStream>);
+
+impl Drop for SocketShutdown {
+    fn drop(&mut self) {
+        if let Some(tcp) = &self.0 {
+            let _ = tcp.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+fn socket_handle(stream: &MaybeTlsStream<TcpStream>) -> std::io::Result<Option<TcpStream>> {
+    match stream {
+        MaybeTlsStream::Plain(tcp) => tcp.try_clone().map(Some),
+        MaybeTlsStream::Rustls(tls) => tls.get_ref().try_clone().map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Open the websocket, tunneling through a proxy when one is configured or set in the
+/// environment.
+fn connect_ws(
+    url: &str,
+    proxy: Option<&str>,
+    timeouts: Timeouts,
+) -> MetaResult<(
+    WebSocket<MaybeTlsStream<TcpStream>>,
+    tungstenite::handshake::client::Response,
+)> {
+    let parsed = Url::parse(url)?;
+    let host = parsed.host_str().ok_or("websocket URL has no host")?;
+    let port = parsed.port_or_known_default().unwrap_or(443);
+    let stream = match proxy::for_url(proxy, &parsed) {
+        Some(proxy) => proxy::tunnel(&proxy, host, port, timeouts)?,
+        None => proxy::connect_tcp(host, port, timeouts)?,
+    };
+    client_tls(url, stream).map_err(|e| format!("websocket handshake failed: {e}").into())
+}
+
+/// Invalidate pending UI switches, snap the UI to the track matching buffered playback
+/// time and schedule the next switch.
+fn resync_ui(
+    events: &EventBus,
+    scheduler: &Scheduler,
+    history: &VecDeque<TrackInfo>,
+    lag_ms: &Arc<AtomicU64>,
+    ui_sched_id: &Arc<AtomicU64>,
+    timeline: &Timeline,
+) {
+    ui_sched_id.fetch_add(1, Ordering::Relaxed);
+
+    let lag = lag_ms.load(Ordering::Relaxed);
+    if let Some(correct) = pick_track_for_playback(history, lag, timeline) {
+        #[cfg(debug_assertions)]
+        println!(
+            "[{}] ui snap: {} - {}",
+            now_string(),
+            correct.artist,
+            correct.title
+        );
+        events.emit(PlayerEvent::TrackChanged(Box::new(correct)));
+    }
+    schedule_next_from_history(scheduler, history, lag, ui_sched_id, timeline);
+}
+
+fn push_history(history: &mut VecDeque<TrackInfo>, track: TrackInfo) {
+    if history.len() == HISTORY_LEN {
+        history.pop_front();
+    }
+    history.push_back(track);
+}
+
+/// Whether a track update repeats one already in history or predates the newest entry.
+/// The gateway sometimes re-sends a song or delivers updates out of order.
+fn is_stale(history: &VecDeque<TrackInfo>, info: &TrackInfo) -> bool {
+    let Some(newest) = history.back() else {
+        return false;
+    };
+    info.start_time_utc < newest.start_time_utc
+        || history
+            .iter()
+            .any(|t| t.song_id == info.song_id && t.start_time_utc == info.start_time_utc)
+}
+
+/// Decode a text frame into the subset of the protocol the metadata loop understands.
+fn decode_frame(txt: &str) -> serde_json::Result<GatewayFrame> {
+    let env: GatewayEnvelope = serde_json::from_str(txt)?;
+    let frame = match (env.op, env.t.as_deref()) {
+        (OP_HELLO, _) => {
+            let hello: GatewayHello = serde_json::from_value(env.d)?;
+            GatewayFrame::Hello {
+                heartbeat_ms: hello.heartbeat,
+            }
+        }
+        (OP_HEARTBEAT_ACK, _) => GatewayFrame::HeartbeatAck,
+        (OP_DISPATCH, Some(EVENT_TRACK_UPDATE)) => match parse_track_info(&env.d) {
+            Some((info, previous)) => GatewayFrame::TrackUpdate(Box::new(info), previous),
+            None => GatewayFrame::Ignored,
+        },
+        _ => GatewayFrame::Ignored,
+    };
+    Ok(frame)
+}
+
+/// Read the initial hello and extract the heartbeat interval (if any).
+fn read_hello_heartbeat<S>(ws: &mut WebSocket<S>) -> MetaResult<Option<u64>>
+where
+    S: Read + Write,
+{
+    match ws.read() {
+        Ok(msg) => {
+            if msg.is_text() {
+                let txt = msg.into_text()?;
+                if let GatewayFrame::Hello { heartbeat_ms } = decode_frame(&txt)? {
+                    return Ok(Some(heartbeat_ms));
+                }
+            }
+            Ok(None)
+        }
+        Err(tungstenite::Error::ConnectionClosed) => Ok(None),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+/// Extract the current track, plus the recently played ones, from the gateway payload.
+///
+/// `lastPlayed` carries no start times; they are chained backwards from the current
+/// track's start using each song's duration, stopping at the first unknown duration.
+fn parse_track_info(d: &Value) -> Option<(TrackInfo, Vec<TrackInfo>)> {
+    let payload: GatewaySongPayload = serde_json::from_value(d.clone()).ok()?;
+    let start_time_utc = parse_rfc3339_system_time(&payload.start_time)?;
+
+    let requester = payload
+        .requester
+        .and_then(|r| {
+            let display_name = r.display_name.filter(|name| !name.is_empty());
+            display_name.or(r.username)
+        })
+        .filter(|name| !name.is_empty());
+
+    let event = payload
+        .event
+        .and_then(|e| e.name)
+        .filter(|name| !name.is_empty());
+
+    let current = TrackInfo {
+        requester,
+        event,
+        listeners: payload.listeners,
+        ..track_from_song(payload.song, start_time_utc)
+    };
+
+    let mut previous = Vec::new();
+    let mut start = start_time_utc;
+    for song in payload.last_played {
+        let Some(secs) = song.duration.filter(|&d| d > 0) else {
+            break;
+        };
+        let Some(song_start) = start.checked_sub(Duration::from_secs(secs as u64)) else {
+            break;
+        };
+        start = song_start;
+        previous.push(track_from_song(song, start));
+    }
+    // Oldest first, matching the order of the history deque.
+    previous.reverse();
+
+    Some((current, previous))
+}
+
+fn track_from_song(song: Song, start_time_utc: SystemTime) -> TrackInfo {
+    let Song {
+        id,
+        title,
+        artists,
+        albums,
+        sources,
+        duration,
+    } = song;
+
+    let title = title.unwrap_or_else(|| "unknown title".to_owned());
+
+    let artist = if artists.is_empty() {
+        "Unknown artist".to_owned()
+    } else {
+        artists
+            .iter()
+            .filter_map(|a| a.name.as_deref())
+            .map(str::to_owned)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let album_cover = albums
+        .first()
+        .and_then(|album| album.image.as_deref())
+        .map(|name| format!("{ALBUM_COVER_BASE}{name}"));
+
+    let artist_image = artists
+        .first()
+        .and_then(|a| a.image.as_deref())
+        .map(|name| format!("{ARTIST_IMAGE_BASE}{name}"));
+
+    let source = sources
+        .first()
+        .and_then(|s| s.name_romaji.clone().or_else(|| s.name.clone()))
+        .filter(|name| !name.is_empty());
+
+    TrackInfo {
+        song_id: id,
+        artist,
+        title,
+        album_cover,
+        artist_image,
+        start_time_utc,
+        duration_secs: duration.unwrap_or(0),
+        source,
+        requester: None,
+        event: None,
+        listeners: None,
+    }
+}
+
+fn set_maybe_tls_read_timeout(
+    stream: &mut MaybeTlsStream<TcpStream>,
+    dur: Option<Duration>,
+) -> std::io::Result<()> {
+    match stream {
+        MaybeTlsStream::Plain(tcp) => tcp.set_read_timeout(dur),
+        MaybeTlsStream::Rustls(tls) => tls.get_mut().set_read_timeout(dur),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    /// `startTime` used by every payload below: 2025-01-01T12:00:00Z.
+    const START_UNIX_SECS: u64 = 1_735_732_800;
+
+    fn start_time() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(START_UNIX_SECS)
+    }
+
+    fn track_update(song: &str) -> String {
+        format!(
+            r#"{{"op":1,"t":"TRACK_UPDATE","d":{{"song":{song},"requester":null,"event":null,"startTime":"2025-01-01T12:00:00.000Z","lastPlayed":[],"listeners":321}}}}"#
+        )
+    }
+
+    struct Case {
+        name: &'static str,
+        song: &'static str,
+        artist: &'static str,
+        title: &'static str,
+        album_cover: Option<&'static str>,
+        artist_image: Option<&'static str>,
+        duration_secs: u32,
+    }
+
+    const CASES: &[Case] = &[
+        Case {
+            name: "full payload",
+            song: r#"{"id":1,"title":"Renai Circulation","sources":[],"artists":[{"id":7,"name":"Kana Hanazawa","nameRomaji":null,"image":"kana.jpg"}],"albums":[{"id":3,"name":"Bakemonogatari","image":"bake.jpg"}],"duration":255}"#,
+            artist: "Kana Hanazawa",
+            title: "Renai Circulation",
+            album_cover: Some("https://cdn.listen.moe/covers/bake.jpg"),
+            artist_image: Some("https://cdn.listen.moe/artists/kana.jpg"),
+            duration_secs: 255,
+        },
+        Case {
+            name: "multiple artists",
+            song: r#"{"id":2,"title":"Duet","artists":[{"id":1,"name":"A","image":null},{"id":2,"name":"B","image":"b.png"}],"albums":[],"duration":180}"#,
+            artist: "A, B",
+            title: "Duet",
+            album_cover: None,
+            artist_image: None,
+            duration_secs: 180,
+        },
+        Case {
+            name: "missing artists",
+            song: r#"{"id":3,"title":"Instrumental","albums":[{"id":1,"image":null}],"duration":120}"#,
+            artist: "Unknown artist",
+            title: "Instrumental",
+            album_cover: None,
+            artist_image: None,
+            duration_secs: 120,
+        },
+        Case {
+            name: "null duration",
+            song: r#"{"id":4,"title":"Live","artists":[{"name":"C"}],"duration":null}"#,
+            artist: "C",
+            title: "Live",
+            album_cover: None,
+            artist_image: None,
+            duration_secs: 0,
+        },
+        Case {
+            name: "null title",
+            song: r#"{"id":5,"title":null,"artists":[{"name":"D"}],"duration":60}"#,
+            artist: "D",
+            title: "unknown title",
+            album_cover: None,
+            artist_image: None,
+            duration_secs: 60,
+        },
+    ];
+
+    #[test]
+    fn track_update_payloads() {
+        for case in CASES {
+            let frame = decode_frame(&track_update(case.song))
+                .unwrap_or_else(|err| panic!("{}: {err}", case.name));
+            let GatewayFrame::TrackUpdate(info, _) = frame else {
+                panic!("{}: expected a track update, got {frame:?}", case.name);
+            };
+            assert_eq!(info.artist, case.artist, "{}", case.name);
+            assert_eq!(info.title, case.title, "{}", case.name);
+            assert_eq!(
+                info.album_cover.as_deref(),
+                case.album_cover,
+                "{}",
+                case.name
+            );
+            assert_eq!(
+                info.artist_image.as_deref(),
+                case.artist_image,
+                "{}",
+                case.name
+            );
+            assert_eq!(info.duration_secs, case.duration_secs, "{}", case.name);
+            assert_eq!(info.start_time_utc, start_time(), "{}", case.name);
+        }
+    }
+
+    #[test]
+    fn source_requester_and_event() {
+        let txt = r#"{"op":1,"t":"TRACK_UPDATE","d":{"song":{"id":6,"title":"Op","sources":[{"id":9,"name":"化物語","nameRomaji":"Bakemonogatari","image":null}],"artists":[],"duration":90},"requester":{"uuid":"u","username":"kana","displayName":"Kana"},"event":{"name":"Anime Night","image":null},"startTime":"2025-01-01T12:00:00.000Z"}}"#;
+        let GatewayFrame::TrackUpdate(info, _) = decode_frame(txt).unwrap() else {
+            panic!("expected a track update");
+        };
+        assert_eq!(info.song_id, Some(6));
+        assert_eq!(info.source.as_deref(), Some("Bakemonogatari"));
+        assert_eq!(info.requester.as_deref(), Some("Kana"));
+        assert_eq!(info.event.as_deref(), Some("Anime Night"));
+        assert_eq!(info.listeners, None);
+
+        let txt = track_update(r#"{"id":7,"title":"Plain","sources":[],"duration":90}"#);
+        let GatewayFrame::TrackUpdate(info, _) = decode_frame(&txt).unwrap() else {
+            panic!("expected a track update");
+        };
+        assert_eq!(info.source, None);
+        assert_eq!(info.requester, None);
+        assert_eq!(info.event, None);
+        assert_eq!(info.listeners, Some(321));
+    }
+
+    #[test]
+    fn last_played_is_chained_backwards() {
+        let txt = r#"{"op":1,"t":"TRACK_UPDATE","d":{"song":{"id":1,"title":"Now","duration":200},"startTime":"2025-01-01T12:00:00.000Z","lastPlayed":[{"id":2,"title":"Before","duration":100},{"id":3,"title":"Earlier","duration":50},{"id":4,"title":"Unknown length","duration":0},{"id":5,"title":"Too old","duration":60}]}}"#;
+        let GatewayFrame::TrackUpdate(info, previous) = decode_frame(txt).unwrap() else {
+            panic!("expected a track update");
+        };
+        assert_eq!(info.title, "Now");
+        let titles: Vec<_> = previous.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, ["Earlier", "Before"]);
+        assert_eq!(
+            previous[0].start_time_utc,
+            start_time() - Duration::from_secs(150)
+        );
+        assert_eq!(
+            previous[1].start_time_utc,
+            start_time() - Duration::from_secs(100)
+        );
+    }
+
+    #[test]
+    fn stale_track_updates() {
+        let update = |id: u32, offset_secs: u64| {
+            let song = format!(r#"{{"id":{id},"title":"T","duration":90}}"#);
+            let GatewayFrame::TrackUpdate(mut info, _) =
+                decode_frame(&track_update(&song)).unwrap()
+            else {
+                panic!("expected a track update");
+            };
+            info.start_time_utc += Duration::from_secs(offset_secs);
+            *info
+        };
+        let history = VecDeque::from([update(1, 0), update(2, 90)]);
+        assert!(!is_stale(&VecDeque::new(), &update(1, 0)));
+        assert!(!is_stale(&history, &update(3, 180)));
+        // Re-sent with a fresh listener count.
+        assert!(is_stale(&history, &update(2, 90)));
+        // Delivered after the song that followed it.
+        assert!(is_stale(&history, &update(1, 0)));
+        // A different song in the same slot is a correction.
+        assert!(!is_stale(&history, &update(3, 90)));
+    }
+
+    #[test]
+    fn unusable_track_updates_are_ignored() {
+        let frames = [
+            // No song at all.
+            r#"{"op":1,"t":"TRACK_UPDATE","d":{"startTime":"2025-01-01T12:00:00.000Z"}}"#,
+            // Unparseable start time.
+            r#"{"op":1,"t":"TRACK_UPDATE","d":{"song":{"title":"X"},"startTime":"yesterday"}}"#,
+            // Missing payload.
+            r#"{"op":1,"t":"TRACK_UPDATE"}"#,
+        ];
+        for txt in frames {
+            assert!(
+                matches!(decode_frame(txt), Ok(GatewayFrame::Ignored)),
+                "{txt}"
+            );
+        }
+    }
+
+    #[test]
+    fn envelope_ops() {
+        assert!(matches!(
+            decode_frame(r#"{"op":0,"d":{"message":"Welcome to LISTEN.moe!","heartbeat":35000}}"#),
+            Ok(GatewayFrame::Hello {
+                heartbeat_ms: 35000
+            })
+        ));
+        assert!(matches!(
+            decode_frame(r#"{"op":10}"#),
+            Ok(GatewayFrame::HeartbeatAck)
+        ));
+        // Other dispatch events and unknown ops are not errors.
+        assert!(matches!(
+            decode_frame(r#"{"op":1,"t":"TRACK_UPDATE_REQUEST","d":{}}"#),
+            Ok(GatewayFrame::Ignored)
+        ));
+        assert!(matches!(
+            decode_frame(r#"{"op":1,"t":"QUEUE_UPDATE","d":{"amount":3}}"#),
+            Ok(GatewayFrame::Ignored)
+        ));
+        assert!(matches!(
+            decode_frame(r#"{"op":42,"d":null}"#),
+            Ok(GatewayFrame::Ignored)
+        ));
+    }
+
+    /// Recorded `TRACK_UPDATE` frames, see `tests/fixtures/gateway`.
+    fn fixture(name: &str) -> (TrackInfo, Vec<TrackInfo>) {
+        let txt = match name {
+            "multiple_artists" => {
+                include_str!("../../tests/fixtures/gateway/multiple_artists.json")
+            }
+            "missing_duration" => {
+                include_str!("../../tests/fixtures/gateway/missing_duration.json")
+            }
+            "event" => include_str!("../../tests/fixtures/gateway/event.json"),
+            "album_without_image" => {
+                include_str!("../../tests/fixtures/gateway/album_without_image.json")
+            }
+            "odd_timestamps" => include_str!("../../tests/fixtures/gateway/odd_timestamps.json"),
+            "before_epoch" => include_str!("../../tests/fixtures/gateway/before_epoch.json"),
+            _ => unreachable!("no fixture {name}"),
+        };
+        match decode_frame(txt) {
+            Ok(GatewayFrame::TrackUpdate(info, previous)) => (*info, previous),
+            other => panic!("{name}: expected a track update, got {other:?}"),
+        }
+    }
+
+    fn unix(secs: i64, millis: u32) -> SystemTime {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_millis(millis.into());
+        if secs >= 0 {
+            time + Duration::from_secs(secs as u64)
+        } else {
+            time - Duration::from_secs(secs.unsigned_abs())
+        }
+    }
+
+    #[test]
+    fn fixture_multiple_artists() {
+        let (info, previous) = fixture("multiple_artists");
+        assert_eq!(info.song_id, Some(21503));
+        assert_eq!(info.title, "Kimi no Shiranai Monogatari");
+        assert_eq!(info.artist, "supercell, やなぎなぎ");
+        assert_eq!(
+            info.album_cover.as_deref(),
+            Some("https://cdn.listen.moe/covers/today_is_a_beautiful_day.jpg")
+        );
+        assert_eq!(
+            info.artist_image.as_deref(),
+            Some("https://cdn.listen.moe/artists/supercell.jpg")
+        );
+        assert_eq!(info.source.as_deref(), Some("Bakemonogatari"));
+        assert_eq!(info.duration_secs, 335);
+        assert_eq!(info.listeners, Some(1342));
+        // 2025-03-14T18:25:43.512Z
+        let start = unix(1_741_976_743, 512);
+        assert_eq!(info.start_time_utc, start);
+
+        let titles: Vec<_> = previous.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, ["God knows...", "only my railgun"]);
+        assert_eq!(previous[0].artist, "平野綾");
+        assert_eq!(previous[0].source, None);
+        assert_eq!(
+            previous[1].source.as_deref(),
+            Some("Toaru Kagaku no Railgun")
+        );
+        assert_eq!(
+            previous[0].start_time_utc,
+            start - Duration::from_secs(255 + 278)
+        );
+        assert_eq!(previous[1].start_time_utc, start - Duration::from_secs(255));
+        assert!(previous.iter().all(|t| t.listeners.is_none()));
+    }
+
+    #[test]
+    fn fixture_missing_duration() {
+        let (info, previous) = fixture("missing_duration");
+        assert_eq!(info.duration_secs, 0);
+        // The chain back stops at the first song without a duration.
+        let titles: Vec<_> = previous.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, ["Radio Drama Part 1"]);
+        assert_eq!(
+            previous[0].start_time_utc,
+            info.start_time_utc - Duration::from_secs(240)
+        );
+    }
+
+    #[test]
+    fn fixture_event() {
+        let (info, previous) = fixture("event");
+        assert_eq!(info.requester.as_deref(), Some("Klaus"));
+        assert_eq!(info.event.as_deref(), Some("Anime Night"));
+        assert_eq!(info.source.as_deref(), Some("Kekkai Sensen"));
+        assert_eq!(info.start_time_utc, unix(1_741_986_187, 1));
+        assert!(previous.is_empty());
+    }
+
+    #[test]
+    fn fixture_album_without_image() {
+        let (info, _) = fixture("album_without_image");
+        assert_eq!(info.album_cover, None);
+        assert_eq!(info.artist_image, None);
+        // No romanized name: the original one.
+        assert_eq!(info.source.as_deref(), Some("四月は君の嘘"));
+        // An empty display name falls back to the username; an unnamed event is none.
+        assert_eq!(info.requester.as_deref(), Some("kousei"));
+        assert_eq!(info.event, None);
+        assert_eq!(info.start_time_utc, unix(1_741_990_200, 0));
+    }
+
+    #[test]
+    fn fixture_odd_timestamps() {
+        let (info, previous) = fixture("odd_timestamps");
+        // 2025-03-15T07:30:00.123456+09:00 is 2025-03-14T22:30:00.123456Z.
+        assert_eq!(
+            info.start_time_utc,
+            unix(1_741_991_400, 123) + Duration::from_micros(456)
+        );
+        // A zero duration ends the chain like a missing one.
+        assert!(previous.is_empty());
+    }
+
+    #[test]
+    fn fixture_before_epoch() {
+        let (info, previous) = fixture("before_epoch");
+        // 1969-12-31T23:59:50.500Z
+        assert_eq!(info.start_time_utc, unix(-10, 500));
+        assert_eq!(previous.len(), 1);
+        assert_eq!(previous[0].start_time_utc, unix(-70, 500));
+    }
+
+    #[test]
+    fn malformed_envelopes_are_errors() {
+        for txt in [
+            "",
+            "not json",
+            r#"{"t":"TRACK_UPDATE"}"#,
+            r#"{"op":"1"}"#,
+            r#"{"op":0,"d":{}}"#,
+        ] {
+            assert!(decode_frame(txt).is_err(), "{txt}");
+        }
+    }
+
+    mod fuzz {
+        use super::*;
+        use proptest::collection::{btree_map, vec};
+        use proptest::prelude::*;
+
+        /// Any JSON document, a few levels deep.
+        fn json() -> impl Strategy<Value = Value> {
+            let leaf = prop_oneof![
+                Just(Value::Null),
+                any::<bool>().prop_map(Value::from),
+                any::<i64>().prop_map(Value::from),
+                any::<f64>().prop_map(Value::from),
+                "\\PC{0,12}".prop_map(Value::from),
+            ];
+            leaf.prop_recursive(3, 24, 4, |inner| {
+                prop_oneof![
+                    vec(inner.clone(), 0..4).prop_map(Value::Array),
+                    btree_map("[a-zA-Z]{0,10}", inner, 0..4)
+                        .prop_map(|map| Value::Object(map.into_iter().collect())),
+                ]
+            })
+        }
+
+        /// A recorded track update with one field of the payload or its song replaced.
+        fn mutated_update() -> impl Strategy<Value = String> {
+            let keys = prop_oneof![
+                Just("song"),
+                Just("startTime"),
+                Just("lastPlayed"),
+                Just("requester"),
+                Just("event"),
+                Just("listeners"),
+                Just("id"),
+                Just("title"),
+                Just("artists"),
+                Just("albums"),
+                Just("sources"),
+                Just("duration"),
+            ];
+            (keys, json(), any::<bool>()).prop_map(|(key, value, in_song)| {
+                let mut frame: Value = serde_json::from_str(include_str!(
+                    "../../tests/fixtures/gateway/multiple_artists.json"
+                ))
+                .unwrap();
+                let target = if in_song {
+                    &mut frame["d"]["song"]
+                } else {
+                    &mut frame["d"]
+                };
+                target[key] = value;
+                frame.to_string()
+            })
+        }
+
+        fn check_update(frame: serde_json::Result<GatewayFrame>) {
+            if let Ok(GatewayFrame::TrackUpdate(info, previous)) = frame {
+                // Played before the current track, oldest first.
+                let mut start = info.start_time_utc;
+                for track in previous.iter().rev() {
+                    assert!(track.start_time_utc <= start);
+                    start = track.start_time_utc;
+                }
+            }
+        }
+
+        proptest! {
+            #[test]
+            fn any_text(txt in "\\PC*") {
+                let _ = decode_frame(&txt);
+            }
+
+            #[test]
+            fn any_envelope(op in 0u8..12, t in prop_oneof![
+                Just(None),
+                Just(Some(EVENT_TRACK_UPDATE.to_string())),
+                "[A-Z_]{0,16}".prop_map(Some),
+            ], d in json()) {
+                let txt = serde_json::json!({ "op": op, "t": t, "d": d }).to_string();
+                check_update(decode_frame(&txt));
+            }
+
+            #[test]
+            fn mutated_track_update(txt in mutated_update()) {
+                check_update(decode_frame(&txt));
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+mod clock;
 mod controller;
 mod error;
 mod gateway;
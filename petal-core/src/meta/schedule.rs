@@ -0,0 +1,450 @@
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::{BinaryHeap, VecDeque};
+use std::sync::mpsc;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use super::track::TrackInfo;
+use crate::events::{EventBus, PlayerEvent};
+
+/// Upper bound for a single sleep while waiting for a scheduled UI switch.
+const MAX_SLEEP_SLICE: Duration = Duration::from_secs(1);
+
+/// Maps the gateway's wall-clock start times onto the monotonic clock.
+///
+/// Captured once per track update, so waits and "what is playing now" are measured with
+/// `Instant` and a later NTP step or manual clock change can't move a pending switch.
+/// The anchor is in server time: the local clock is corrected by the estimated skew.
+#[derive(Debug, Clone, Copy)]
+pub struct Timeline {
+    mono: Instant,
+    wall: SystemTime,
+}
+
+impl Timeline {
+    /// `skew_ms` is how far the local clock runs ahead of the server.
+    pub fn now(skew_ms: i64) -> Self {
+        let local = SystemTime::now();
+        let skew = Duration::from_millis(skew_ms.unsigned_abs());
+        let wall = if skew_ms >= 0 {
+            local.checked_sub(skew)
+        } else {
+            local.checked_add(skew)
+        };
+        Self {
+            mono: Instant::now(),
+            wall: wall.unwrap_or(local),
+        }
+    }
+
+    /// Server time as of the anchor, advanced by the monotonic clock since.
+    pub fn wall_now(&self) -> SystemTime {
+        self.wall + self.mono.elapsed()
+    }
+
+    /// When the monotonic clock reaches the wall-clock time `at`.
+    pub fn instant_at(&self, at: SystemTime) -> Option<Instant> {
+        match at.duration_since(self.wall) {
+            Ok(ahead) => self.mono.checked_add(ahead),
+            // Further back than the monotonic clock reaches is overdue all the same.
+            Err(behind) => Some(
+                self.mono
+                    .checked_sub(behind.duration())
+                    .unwrap_or(self.mono),
+            ),
+        }
+    }
+}
+
+/// Where playback is on the server's clock, running `lag_ms` behind live.
+pub fn playback_time(timeline: &Timeline, lag_ms: u64) -> Option<SystemTime> {
+    timeline
+        .wall_now()
+        .checked_sub(Duration::from_millis(lag_ms))
+}
+
+/// The track audible at `playback_now`: the newest whose known duration covers it, else
+/// the newest that started by then. Takes the time rather than reading a clock, like the
+/// rest of the selection logic below.
+pub fn track_at(history: &VecDeque<TrackInfo>, playback_now: SystemTime) -> Option<&TrackInfo> {
+    // Prefer a proper [start, end) window when duration is known and > 0.
+    let covering = history.iter().rev().find(|t| {
+        if t.duration_secs == 0 {
+            return false;
+        }
+        let start = t.start_time_utc;
+        let end = start.checked_add(Duration::from_secs(t.duration_secs as u64));
+        end.is_some_and(|end| playback_now >= start && playback_now < end)
+    });
+
+    // Fallback: duration is missing/0 => pick the latest track that started before playback_now.
+    covering.or_else(|| {
+        history
+            .iter()
+            .rev()
+            .find(|t| playback_now >= t.start_time_utc)
+    })
+}
+
+/// The earliest track that starts after `playback_now`: the next switch to schedule.
+pub fn next_track_after(
+    history: &VecDeque<TrackInfo>,
+    playback_now: SystemTime,
+) -> Option<&TrackInfo> {
+    history
+        .iter()
+        .filter(|t| playback_now < t.start_time_utc)
+        .min_by_key(|t| t.start_time_utc)
+}
+
+/// When to show `track` on the monotonic clock, with playback running `lag_ms` behind.
+pub fn switch_at(track: &TrackInfo, lag_ms: u64, timeline: &Timeline) -> Option<Instant> {
+    track
+        .start_time_utc
+        .checked_add(Duration::from_millis(lag_ms))
+        .and_then(|at| timeline.instant_at(at))
+}
+
+pub fn pick_track_for_playback(
+    history: &VecDeque<TrackInfo>,
+    lag_ms: u64,
+    timeline: &Timeline,
+) -> Option<TrackInfo> {
+    track_at(history, playback_time(timeline, lag_ms)?).cloned()
+}
+
+/// One thread that fires UI switches at their playback time.
+///
+/// Pending switches sit in a min-heap keyed by monotonic target. Only the switch whose id
+/// matches `ui_sched_id` may fire; bumping the id cancels everything queued before it.
+#[derive(Debug)]
+pub struct Scheduler {
+    tx: mpsc::Sender<Pending>,
+}
+
+#[derive(Debug)]
+struct Pending {
+    at: Instant,
+    id: u64,
+    track: TrackInfo,
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        (self.at, self.id) == (other.at, other.id)
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    // Reversed so the earliest target is at the top of the max-heap.
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        (other.at, other.id).cmp(&(self.at, self.id))
+    }
+}
+
+impl Scheduler {
+    /// The thread exits once the returned handle is dropped.
+    pub fn spawn(events: EventBus, ui_sched_id: Arc<AtomicU64>) -> Self {
+        let (tx, rx) = mpsc::channel::<Pending>();
+        thread::spawn(move || {
+            let mut heap: BinaryHeap<Pending> = BinaryHeap::new();
+            loop {
+                let current = ui_sched_id.load(Ordering::Relaxed);
+                heap.retain(|p| p.id == current);
+
+                // Fire everything that is due, then wait for the next target or a new entry.
+                // Waits are sliced so a bumped id drops stale entries promptly.
+                let now = Instant::now();
+                while heap.peek().is_some_and(|p| p.at <= now) {
+                    let due = heap.pop().unwrap();
+                    events.emit(PlayerEvent::TrackChanged(Box::new(due.track)));
+                }
+                let wait = match heap.peek() {
+                    Some(next) => next.at.saturating_duration_since(now).min(MAX_SLEEP_SLICE),
+                    None => MAX_SLEEP_SLICE,
+                };
+                match rx.recv_timeout(wait) {
+                    Ok(pending) => heap.push(pending),
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        });
+        Self { tx }
+    }
+
+    /// Queue `track` to be shown once playback (running `lag_ms` behind live) reaches it.
+    pub fn schedule(&self, track: TrackInfo, lag_ms: u64, id: u64, timeline: &Timeline) {
+        let Some(at) = switch_at(&track, lag_ms, timeline) else {
+            return;
+        };
+        let _ = self.tx.send(Pending { at, id, track });
+    }
+}
+
+pub fn schedule_next_from_history(
+    scheduler: &Scheduler,
+    history: &VecDeque<TrackInfo>,
+    lag_ms: u64,
+    ui_sched_id: &AtomicU64,
+    timeline: &Timeline,
+) {
+    let Some(playback_now) = playback_time(timeline, lag_ms) else {
+        return;
+    };
+    let Some(next) = next_track_after(history, playback_now).cloned() else {
+        return;
+    };
+
+    let my_id = ui_sched_id.fetch_add(1, Ordering::Relaxed) + 1;
+
+    #[cfg(debug_assertions)]
+    println!(
+        "[{}] ui {} resched-next: {} - {} (lag_ms={})",
+        crate::log::now_string(),
+        my_id,
+        next.artist,
+        next.title,
+        lag_ms
+    );
+
+    scheduler.schedule(next, lag_ms, my_id, timeline);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(title: &str, start_time_utc: SystemTime, duration_secs: u32) -> TrackInfo {
+        TrackInfo {
+            song_id: None,
+            artist: String::new(),
+            title: title.to_string(),
+            album_cover: None,
+            artist_image: None,
+            start_time_utc,
+            duration_secs,
+            source: None,
+            requester: None,
+            event: None,
+            listeners: None,
+        }
+    }
+
+    #[test]
+    fn instant_at_follows_the_anchor() {
+        let timeline = Timeline::now(0);
+        let ahead = timeline.wall + Duration::from_secs(30);
+        assert_eq!(
+            timeline.instant_at(ahead),
+            Some(timeline.mono + Duration::from_secs(30))
+        );
+        let behind = timeline.wall - Duration::from_secs(30);
+        assert!(timeline.instant_at(behind).unwrap() <= timeline.mono);
+    }
+
+    #[test]
+    fn picks_the_track_playback_is_in() {
+        let timeline = Timeline::now(0);
+        let now = timeline.wall;
+        let history = VecDeque::from([
+            track("old", now - Duration::from_secs(200), 100),
+            track("buffered", now - Duration::from_secs(100), 100),
+            track("live", now - Duration::from_secs(5), 100),
+        ]);
+        let live = pick_track_for_playback(&history, 0, &timeline).unwrap();
+        assert_eq!(live.title, "live");
+        let lagging = pick_track_for_playback(&history, 30_000, &timeline).unwrap();
+        assert_eq!(lagging.title, "buffered");
+    }
+
+    #[test]
+    fn skew_shifts_playback_time() {
+        let local = SystemTime::now();
+        let history = VecDeque::from([
+            track("before", local - Duration::from_secs(100), 100),
+            track("after", local, 100),
+        ]);
+        // The local clock is 10 s fast, so on the server "after" hasn't started yet.
+        let timeline = Timeline::now(10_000);
+        let picked = pick_track_for_playback(&history, 0, &timeline).unwrap();
+        assert_eq!(picked.title, "before");
+    }
+
+    mod props {
+        use super::*;
+        use proptest::collection::vec;
+        use proptest::prelude::*;
+
+        /// 2025-01-01T12:00:00Z; every time below is an offset in seconds from it.
+        fn base() -> SystemTime {
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_735_732_800)
+        }
+
+        fn at(offset_secs: i64) -> SystemTime {
+            if offset_secs >= 0 {
+                base() + Duration::from_secs(offset_secs as u64)
+            } else {
+                base() - Duration::from_secs(offset_secs.unsigned_abs())
+            }
+        }
+
+        /// Tracks are titled by their position in history, so the title identifies them.
+        fn id(track: Option<&TrackInfo>) -> Option<&str> {
+            track.map(|t| t.title.as_str())
+        }
+
+        fn covers(t: &TrackInfo, now: SystemTime) -> bool {
+            t.duration_secs > 0
+                && t.start_time_utc <= now
+                && now < t.start_time_utc + Duration::from_secs(t.duration_secs.into())
+        }
+
+        /// Any history: overlapping, out of order, with gaps and unknown durations.
+        fn history() -> impl Strategy<Value = VecDeque<TrackInfo>> {
+            vec(
+                (-3_600i64..3_600, prop_oneof![Just(0u32), 1u32..600]),
+                0..12,
+            )
+            .prop_map(|tracks| {
+                tracks
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (start, duration))| track(&i.to_string(), at(start), duration))
+                    .collect()
+            })
+        }
+
+        /// A history as the gateway builds it: each track starts when the one before ends.
+        fn back_to_back() -> impl Strategy<Value = VecDeque<TrackInfo>> {
+            (-3_600i64..0, vec(1u32..600, 1..12)).prop_map(|(first, durations)| {
+                let mut start = at(first);
+                durations
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, duration)| {
+                        let t = track(&i.to_string(), start, duration);
+                        start += Duration::from_secs(duration.into());
+                        t
+                    })
+                    .collect()
+            })
+        }
+
+        proptest! {
+            #[test]
+            fn never_picks_a_future_track(history in history(), now in -4_000i64..4_000) {
+                let now = at(now);
+                if let Some(picked) = track_at(&history, now) {
+                    prop_assert!(picked.start_time_utc <= now);
+                }
+                // Nothing has started yet: nothing to show.
+                if history.iter().all(|t| t.start_time_utc > now) {
+                    prop_assert!(track_at(&history, now).is_none());
+                }
+            }
+
+            #[test]
+            fn prefers_the_newest_covering_track(history in history(), now in -4_000i64..4_000) {
+                let now = at(now);
+                let picked = track_at(&history, now);
+                match history.iter().rev().find(|t| covers(t, now)) {
+                    Some(newest) => prop_assert_eq!(id(picked), id(Some(newest))),
+                    // Unknown or ended durations: the newest one that started.
+                    None => prop_assert_eq!(
+                        id(picked),
+                        id(history.iter().rev().find(|t| t.start_time_utc <= now))
+                    ),
+                }
+            }
+
+            #[test]
+            fn back_to_back_tracks_cover_their_slot(
+                history in back_to_back(),
+                into in 0u64..7_200,
+            ) {
+                let first = history.front().unwrap().start_time_utc;
+                let last = history.back().unwrap();
+                let end = last.start_time_utc + Duration::from_secs(last.duration_secs.into());
+                let now = first + Duration::from_secs(into);
+                let picked = track_at(&history, now).unwrap();
+                if now < end {
+                    prop_assert!(covers(picked, now), "{} does not cover the time", picked.title);
+                } else {
+                    // Past the end of history: the last track stays up.
+                    prop_assert_eq!(&picked.title, &last.title);
+                }
+            }
+
+            #[test]
+            fn more_lag_never_skips_ahead(
+                history in back_to_back(),
+                into in 0u64..7_200,
+                lag in 0u64..600,
+                more in 0u64..600,
+            ) {
+                let first = history.front().unwrap().start_time_utc;
+                let now = first + Duration::from_secs(into);
+                let behind = |lag: u64| track_at(&history, now - Duration::from_secs(lag));
+                if let (Some(less), Some(more)) = (behind(lag), behind(lag + more)) {
+                    prop_assert!(more.start_time_utc <= less.start_time_utc);
+                }
+            }
+
+            #[test]
+            fn next_is_the_earliest_upcoming(history in history(), now in -4_000i64..4_000) {
+                let now = at(now);
+                match next_track_after(&history, now) {
+                    Some(next) => {
+                        prop_assert!(next.start_time_utc > now);
+                        prop_assert!(history
+                            .iter()
+                            .all(|t| t.start_time_utc <= now || t.start_time_utc >= next.start_time_utc));
+                    }
+                    None => prop_assert!(history.iter().all(|t| t.start_time_utc <= now)),
+                }
+            }
+
+            #[test]
+            fn next_switch_shows_the_next_track(history in back_to_back(), into in 0u64..7_200) {
+                // At the moment of the scheduled switch, the track it shows is the one playing.
+                let now = history.front().unwrap().start_time_utc + Duration::from_secs(into);
+                if let Some(next) = next_track_after(&history, now) {
+                    prop_assert_eq!(id(track_at(&history, next.start_time_utc)), id(Some(next)));
+                }
+            }
+
+            #[test]
+            fn switches_follow_start_order(
+                a in -3_600i64..3_600,
+                b in -3_600i64..3_600,
+                lag_ms in 0u64..600_000,
+            ) {
+                let timeline = Timeline { mono: Instant::now(), wall: base() };
+                let switch = |start| switch_at(&track("", at(start), 60), lag_ms, &timeline).unwrap();
+                if a <= b {
+                    prop_assert!(switch(a) <= switch(b));
+                }
+                // Upcoming switches are exactly their distance ahead of the anchor.
+                if a >= 0 {
+                    prop_assert_eq!(
+                        switch(a),
+                        timeline.mono + Duration::from_secs(a as u64) + Duration::from_millis(lag_ms)
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::time::{Duration, SystemTime};
+
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+pub fn parse_rfc3339_system_time(s: &str) -> Option<SystemTime> {
+    let odt = OffsetDateTime::parse(s, &Rfc3339).ok()?;
+    let unix = odt.unix_timestamp(); // seconds
+    let nanos = odt.nanosecond(); // 0..1e9
+
+    let t = if unix >= 0 {
+        SystemTime::UNIX_EPOCH
+            .checked_add(Duration::from_secs(unix as u64))?
+            .checked_add(Duration::from_nanos(nanos as u64))?
+    } else {
+        SystemTime::UNIX_EPOCH
+            .checked_sub(Duration::from_secs((-unix) as u64))?
+            .checked_add(Duration::from_nanos(nanos as u64))?
+    };
+
+    Some(t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unix_nanos(nanos: i128) -> SystemTime {
+        let offset = Duration::from_nanos(nanos.unsigned_abs() as u64);
+        if nanos >= 0 {
+            SystemTime::UNIX_EPOCH + offset
+        } else {
+            SystemTime::UNIX_EPOCH - offset
+        }
+    }
+
+    #[test]
+    fn parses_gateway_timestamps() {
+        let cases = [
+            ("1970-01-01T00:00:00Z", 0),
+            ("2025-01-01T12:00:00.000Z", 1_735_732_800_000_000_000),
+            ("2025-03-14T18:25:43.512Z", 1_741_976_743_512_000_000),
+            // Offsets are folded into UTC.
+            (
+                "2025-03-15T07:30:00.123456+09:00",
+                1_741_991_400_123_456_000,
+            ),
+            ("2025-03-14T17:30:00-05:00", 1_741_991_400_000_000_000),
+            ("2025-03-14t22:30:00z", 1_741_991_400_000_000_000),
+            // RFC 3339 allows a space instead of the `T`.
+            ("2025-03-14 22:30:00Z", 1_741_991_400_000_000_000),
+            // Before the epoch, the fraction still counts forward.
+            ("1969-12-31T23:59:50.500Z", -9_500_000_000),
+            ("1969-12-31T23:59:59.999999999Z", -1),
+        ];
+        for (text, nanos) in cases {
+            assert_eq!(
+                parse_rfc3339_system_time(text),
+                Some(unix_nanos(nanos)),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        for text in [
+            "",
+            "yesterday",
+            "2025-03-14",
+            "2025-03-14T18:25:43",
+            "2025-13-01T00:00:00Z",
+            "2025-02-30T00:00:00Z",
+            "1741976743",
+        ] {
+            assert_eq!(parse_rfc3339_system_time(text), None, "{text}");
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use std::time::SystemTime;
+
+pub const ALBUM_COVER_BASE: &str = "https://cdn.listen.moe/covers/";
+pub const ARTIST_IMAGE_BASE: &str = "https://cdn.listen.moe/artists/";
+
+/// Track info sent to the UI thread.
+#[derive(Debug, Clone)]
+pub struct TrackInfo {
+    /// LISTEN.moe library id, used for favorites.
+    pub song_id: Option<u32>,
+    pub artist: String,
+    pub title: String,
+    pub album_cover: Option<String>,
+    pub artist_image: Option<String>,
+    pub start_time_utc: SystemTime,
+    pub duration_secs: u32,
+    /// Anime or game the song is from.
+    pub source: Option<String>,
+    /// Listener who requested the song.
+    pub requester: Option<String>,
+    /// Special programming the song is part of.
+    pub event: Option<String>,
+    /// Live listener count at the time of the update.
+    pub listeners: Option<u32>,
+}
+
+impl TrackInfo {
+    /// Whether both describe the same play of a song, e.g. a track sent again after a
+    /// resume or reconnect.
+    pub fn same_play(&self, other: &TrackInfo) -> bool {
+        self.song_id == other.song_id
+            && self.start_time_utc == other.start_time_utc
+            && self.title == other.title
+    }
+}
@@ -0,0 +1,124 @@
+//! Proxy support for the stream, cover and websocket connections.
+//!
+//! reqwest already honors the proxy environment variables, so for HTTP an explicit proxy
+//! only has to override them. Tungstenite has no proxy support: the websocket is tunneled
+//! through an HTTP `CONNECT` request here.
+
+use reqwest::{blocking::ClientBuilder, Proxy, Url};
+use std::env;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+
+use crate::settings::Timeouts;
+
+/// Longest proxy response header we accept while setting up a tunnel.
+const MAX_RESPONSE_HEADER: usize = 8 * 1024;
+
+/// Route all requests of `builder` through `configured`, if set.
+pub fn apply(builder: ClientBuilder, configured: Option<&str>) -> reqwest::Result<ClientBuilder> {
+    match configured {
+        Some(url) => Ok(builder.proxy(Proxy::all(url)?)),
+        None => Ok(builder),
+    }
+}
+
+/// The proxy to use for `url`: the configured one, else `https_proxy`/`http_proxy` (by the
+/// scheme of `url`) or `all_proxy` from the environment, unless `no_proxy` lists the host.
+pub fn for_url(configured: Option<&str>, url: &Url) -> Option<String> {
+    if let Some(proxy) = configured {
+        return Some(proxy.to_string());
+    }
+    let host = url.host_str()?;
+    if env_var("no_proxy").is_some_and(|list| excluded(&list, host)) {
+        return None;
+    }
+    let scheme_var = match url.scheme() {
+        "https" | "wss" => "https_proxy",
+        _ => "http_proxy",
+    };
+    env_var(scheme_var).or_else(|| env_var("all_proxy"))
+}
+
+/// Lowercase wins, like curl.
+fn env_var(name: &str) -> Option<String> {
+    env::var(name)
+        .or_else(|_| env::var(name.to_uppercase()))
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
+fn excluded(no_proxy: &str, host: &str) -> bool {
+    no_proxy.split(',').map(str::trim).any(|entry| {
+        let entry = entry.trim_start_matches('.');
+        entry == "*"
+            || host == entry
+            || host
+                .strip_suffix(entry)
+                .is_some_and(|rest| rest.ends_with('.'))
+    })
+}
+
+/// Open a TCP connection to `host:port` through the HTTP proxy at `proxy`.
+/// Only plain `http://` proxies without credentials are supported.
+pub fn tunnel(proxy: &str, host: &str, port: u16, timeouts: Timeouts) -> io::Result<TcpStream> {
+    let proxy = Url::parse(proxy).map_err(|e| invalid(format!("bad proxy URL: {e}")))?;
+    if proxy.scheme() != "http" {
+        return Err(invalid(format!(
+            "unsupported proxy scheme for the websocket: {}",
+            proxy.scheme()
+        )));
+    }
+    if !proxy.username().is_empty() {
+        return Err(invalid(
+            "proxy credentials are not supported for the websocket".into(),
+        ));
+    }
+    let proxy_host = proxy
+        .host_str()
+        .ok_or_else(|| invalid("proxy URL has no host".into()))?;
+    let port_or_default = proxy.port_or_known_default().unwrap_or(8080);
+    let mut stream = connect_tcp(proxy_host, port_or_default, timeouts)?;
+    write!(
+        stream,
+        "CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n\r\n"
+    )?;
+
+    // Byte by byte, so nothing after the header is consumed.
+    let mut header = Vec::new();
+    let mut byte = [0u8; 1];
+    while !header.ends_with(b"\r\n\r\n") {
+        if header.len() >= MAX_RESPONSE_HEADER {
+            return Err(invalid("proxy response header too long".into()));
+        }
+        stream.read_exact(&mut byte)?;
+        header.push(byte[0]);
+    }
+    let status_line = String::from_utf8_lossy(&header);
+    let status_line = status_line.lines().next().unwrap_or_default();
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        return Err(io::Error::other(format!(
+            "proxy refused tunnel: {status_line}"
+        )));
+    }
+    Ok(stream)
+}
+
+/// Connect to the first reachable address of `host:port`, with `timeouts` applied to the
+/// connect and to reads on the returned socket.
+pub fn connect_tcp(host: &str, port: u16, timeouts: Timeouts) -> io::Result<TcpStream> {
+    let mut last_err = None;
+    for addr in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeouts.connect()) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(timeouts.read()))?;
+                return Ok(stream);
+            }
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| invalid(format!("cannot resolve {host}"))))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
@@ -0,0 +1,520 @@
+use dirs_next as dirs;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+
+use crate::meta::TrackInfo;
+
+mod ogg;
+
+#[cfg(debug_assertions)]
+use crate::log::now_string;
+
+/// Bytes from the start of a connection kept so the codec headers can be replayed
+/// when recording is switched on mid-stream.
+pub const HEAD_CAPTURE_BYTES: usize = 64 * 1024;
+
+/// Shared between the stream worker and the UI: when set, raw stream bytes are teed
+/// into the recorder.
+pub type RecordTap = Arc<Mutex<Option<mpsc::Sender<RecordEvent>>>>;
+
+pub fn new_tap() -> RecordTap {
+    Arc::new(Mutex::new(None))
+}
+
+#[derive(Debug)]
+pub enum RecordEvent {
+    /// First bytes of the current connection, used only for their header pages.
+    Head(Vec<u8>),
+    Data(Vec<u8>),
+    /// A new track started. Playback, and so this event, runs behind the stream bytes
+    /// by the buffered audio and any pause, so the cut goes back to where the track
+    /// started in the stream.
+    Track(TrackInfo),
+}
+
+/// Per-track recorder that stores the stream exactly as received.
+///
+/// Ogg streams are cut on page boundaries and every file starts with the codec
+/// headers, so Vorbis and Opus tracks are written without re-encoding. A chained
+/// stream starts a new logical stream with each track, which is where the cut goes.
+/// Without one, the cut goes to the first page received after the track's start time by
+/// the local clock. Other streams (MP3) are cut at chunk boundaries the same way;
+/// decoders resync on the next frame. Every cut lands on a boundary, so nothing is ever
+/// re-encoded; only audio of a logical stream whose headers were missed is skipped.
+#[derive(Debug)]
+pub struct Recorder {
+    tap: RecordTap,
+}
+
+impl Recorder {
+    pub fn start(tap: RecordTap, current: Option<TrackInfo>) -> io::Result<Self> {
+        let dir = output_dir().ok_or_else(|| io::Error::other("no music directory"))?;
+        fs::create_dir_all(&dir)?;
+        let (tx, rx) = mpsc::channel();
+        if let Some(info) = current {
+            let _ = tx.send(RecordEvent::Track(info));
+        }
+        *tap.lock().unwrap() = Some(tx);
+        thread::spawn(move || {
+            let mut writer = TrackWriter::new(dir);
+            for event in rx {
+                if let Err(err) = writer.handle(event) {
+                    eprintln!("recording error: {err}");
+                    writer.close();
+                }
+            }
+            writer.close();
+        });
+        Ok(Self { tap })
+    }
+
+    pub fn track_changed(&self, info: &TrackInfo) {
+        if let Some(tx) = self.tap.lock().unwrap().as_ref() {
+            let _ = tx.send(RecordEvent::Track(info.clone()));
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        // Dropping the sender ends the writer thread once it drained the queue.
+        self.tap.lock().unwrap().take();
+    }
+}
+
+/// Save `data[start..]` of the stream as a clip named after `info`, next to the
+/// recordings. `head` is the start of the connection and `data[..start]` what came before
+/// the clip; both are only scanned for codec headers. Returns the new file.
+pub fn save_clip(head: &[u8], data: &[u8], start: usize, info: &TrackInfo) -> io::Result<PathBuf> {
+    let dir = output_dir()
+        .ok_or_else(|| io::Error::other("no music directory"))?
+        .join("Clips");
+    fs::create_dir_all(&dir)?;
+    let mut writer = TrackWriter::new(dir);
+    writer.handle(RecordEvent::Head(head.to_vec()))?;
+    writer.handle(RecordEvent::Data(data[..start].to_vec()))?;
+    writer.handle(RecordEvent::Track(info.clone()))?;
+    writer.handle(RecordEvent::Data(data[start..].to_vec()))?;
+    let path = writer.open.as_ref().map(|open| open.path.clone());
+    writer.close();
+    path.ok_or_else(|| io::Error::other("no complete audio in the clip"))
+}
+
+pub fn output_dir() -> Option<PathBuf> {
+    dirs::audio_dir()
+        .or_else(dirs::home_dir)
+        .map(|dir| dir.join("LISTEN.moe"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Unknown,
+    Ogg,
+    Raw,
+}
+
+struct OpenFile {
+    file: File,
+    path: PathBuf,
+    track: TrackInfo,
+    /// Bytes written so far.
+    len: u64,
+    /// Places a later track may start, oldest first.
+    marks: Vec<Mark>,
+    sequence: u32,
+    serial: Option<u32>,
+}
+
+/// A page or chunk in an open file that a new file can start with.
+#[derive(Debug, Clone, Copy)]
+struct Mark {
+    offset: u64,
+    received: SystemTime,
+    /// Logical stream of an Ogg page.
+    serial: Option<u32>,
+    /// Starts a new logical stream.
+    bos: bool,
+}
+
+struct TrackWriter {
+    dir: PathBuf,
+    container: Container,
+    pages: ogg::PageReader,
+    /// Header pages of the current logical stream.
+    headers: Vec<ogg::Page>,
+    collecting_headers: bool,
+    pending: Option<TrackInfo>,
+    open: Option<OpenFile>,
+    /// When the bytes being written arrived.
+    received: SystemTime,
+    /// Head bytes came in; a logical stream starting on the next page starts the
+    /// connection, not a new track.
+    connected: bool,
+}
+
+impl TrackWriter {
+    fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            container: Container::Unknown,
+            pages: ogg::PageReader::default(),
+            headers: Vec::new(),
+            collecting_headers: false,
+            pending: None,
+            open: None,
+            received: SystemTime::UNIX_EPOCH,
+            connected: false,
+        }
+    }
+
+    fn handle(&mut self, event: RecordEvent) -> io::Result<()> {
+        match event {
+            RecordEvent::Track(info) => {
+                let current = self
+                    .pending
+                    .as_ref()
+                    .or(self.open.as_ref().map(|open| &open.track));
+                // The same track sent again, e.g. after a resume.
+                if current.is_some_and(|current| current.same_play(&info)) {
+                    return Ok(());
+                }
+                if !self.cut_back(&info)? {
+                    self.pending = Some(info);
+                }
+                Ok(())
+            }
+            RecordEvent::Head(bytes) => {
+                self.connected = true;
+                self.detect(&bytes);
+                if self.container == Container::Ogg {
+                    self.pages.clear();
+                    self.pages.push(&bytes);
+                    self.headers.clear();
+                    while let Some(page) = self.pages.next_page() {
+                        if !page.is_header() {
+                            break;
+                        }
+                        self.headers.push(page);
+                    }
+                    // The live bytes that follow are not contiguous with the head.
+                    self.pages.clear();
+                }
+                Ok(())
+            }
+            RecordEvent::Data(bytes) => {
+                self.received = SystemTime::now();
+                self.detect(&bytes);
+                match self.container {
+                    Container::Ogg => {
+                        self.pages.push(&bytes);
+                        while let Some(page) = self.pages.next_page() {
+                            self.write_page(page)?;
+                        }
+                        Ok(())
+                    }
+                    Container::Raw => {
+                        if let Some(info) = self.pending.take() {
+                            self.open_file(&info, "mp3")?;
+                        }
+                        self.mark(None, false);
+                        match self.open.as_mut() {
+                            Some(open) => open.write(&bytes),
+                            None => Ok(()),
+                        }
+                    }
+                    Container::Unknown => Ok(()),
+                }
+            }
+        }
+    }
+
+    fn detect(&mut self, bytes: &[u8]) {
+        if self.container == Container::Unknown && !bytes.is_empty() {
+            self.container = if ogg::looks_like_ogg(bytes) {
+                Container::Ogg
+            } else {
+                Container::Raw
+            };
+        }
+    }
+
+    fn write_page(&mut self, page: ogg::Page) -> io::Result<()> {
+        let connected = std::mem::take(&mut self.connected);
+        if page.is_bos() {
+            self.headers.clear();
+            self.collecting_headers = true;
+        }
+        if self.collecting_headers {
+            if page.is_header() {
+                self.headers.push(page.clone());
+                // A chained stream starts a new logical stream: the cleanest cut point.
+                if page.is_bos() {
+                    if let Some(info) = self.pending.take() {
+                        self.open_file(&info, "ogg")?;
+                    }
+                    self.mark(Some(page.serial()), !connected);
+                }
+                return self.append(&page);
+            }
+            self.collecting_headers = false;
+        }
+
+        if let Some(info) = self.pending.take() {
+            let aligned = self
+                .headers
+                .first()
+                .is_some_and(|h| h.serial() == page.serial());
+            if aligned {
+                self.open_file(&info, "ogg")?;
+                for header in self.headers.clone() {
+                    self.append(&header)?;
+                }
+            } else {
+                // Headers for this logical stream are unknown; wait for the next one.
+                self.close();
+                return Ok(());
+            }
+        }
+        self.mark(Some(page.serial()), false);
+        self.append(&page)
+    }
+
+    /// Note that the next bytes written can start a file.
+    fn mark(&mut self, serial: Option<u32>, bos: bool) {
+        if let Some(open) = self.open.as_mut() {
+            open.marks.push(Mark {
+                offset: open.len,
+                received: self.received,
+                serial,
+                bos,
+            });
+        }
+    }
+
+    /// Start the file for `info` where the track started in what was already written,
+    /// moving the rest out of the open file. False when the start is not in there yet.
+    fn cut_back(&mut self, info: &TrackInfo) -> io::Result<bool> {
+        let Some(open) = self.open.as_mut() else {
+            return Ok(false);
+        };
+        let serial = self.headers.first().map(ogg::Page::serial);
+        // The first new logical stream after the file's own start belongs to the next
+        // track, however far behind playback is. Without one, go by the clock; a start
+        // before everything written means the clock is off.
+        let cut = match open.marks.iter().skip(1).position(|mark| mark.bos) {
+            Some(index) => index + 1,
+            None => match open
+                .marks
+                .iter()
+                .position(|mark| mark.received >= info.start_time_utc && mark.serial == serial)
+            {
+                Some(index) if index > 0 => index,
+                _ => return Ok(false),
+            },
+        };
+        let mut marks = open.marks.split_off(cut);
+        let from = marks[0].offset;
+        let mut tail = Vec::new();
+        open.file.seek(SeekFrom::Start(from))?;
+        open.file.read_to_end(&mut tail)?;
+        open.file.set_len(from)?;
+        open.len = from;
+
+        let ext = match self.container {
+            Container::Ogg => "ogg",
+            _ => "mp3",
+        };
+        self.open_file(info, ext)?;
+        if self.container == Container::Ogg {
+            // A new logical stream brings its own headers.
+            if !marks[0].bos {
+                for header in self.headers.clone() {
+                    self.append(&header)?;
+                }
+            }
+        }
+        let Some(open) = self.open.as_mut() else {
+            return Ok(true);
+        };
+        let base = open.len;
+        if self.container == Container::Ogg {
+            // Pages keep their length when they are sequenced anew.
+            let mut pages = ogg::PageReader::default();
+            pages.push(&tail);
+            while let Some(page) = pages.next_page() {
+                self.append(&page)?;
+            }
+        } else {
+            open.write(&tail)?;
+        }
+        for mark in &mut marks {
+            mark.offset = mark.offset - from + base;
+        }
+        if let Some(open) = self.open.as_mut() {
+            open.marks = marks;
+        }
+        Ok(true)
+    }
+
+    fn append(&mut self, page: &ogg::Page) -> io::Result<()> {
+        let Some(open) = self.open.as_mut() else {
+            return Ok(());
+        };
+        if open.serial.is_some_and(|s| s != page.serial()) {
+            // New chained stream inside the same file: sequence numbers restart.
+            open.sequence = 0;
+        }
+        open.serial = Some(page.serial());
+        open.write(&page.with_sequence(open.sequence))?;
+        open.sequence += 1;
+        Ok(())
+    }
+
+    fn open_file(&mut self, info: &TrackInfo, ext: &str) -> io::Result<()> {
+        self.close();
+        let path = unique_path(&self.dir, &file_stem(info), ext);
+        #[cfg(debug_assertions)]
+        println!("[{}] Recording to {}", now_string(), path.display());
+        self.open = Some(OpenFile {
+            // Readable too, to move a late cut's audio into the next file.
+            file: File::options()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&path)?,
+            path,
+            track: info.clone(),
+            len: 0,
+            marks: Vec::new(),
+            sequence: 0,
+            serial: None,
+        });
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        if let Some(mut open) = self.open.take() {
+            let _ = open.file.flush();
+        }
+    }
+}
+
+impl OpenFile {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.file.write_all(bytes)?;
+        self.len += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+fn file_stem(info: &TrackInfo) -> String {
+    let name = if info.artist.is_empty() {
+        info.title.clone()
+    } else {
+        format!("{} - {}", info.artist, info.title)
+    };
+    let clean: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let clean = clean.trim().trim_start_matches('.').to_string();
+    if clean.is_empty() {
+        "Unknown".to_string()
+    } else {
+        clean
+    }
+}
+
+fn unique_path(dir: &Path, stem: &str, ext: &str) -> PathBuf {
+    let mut path = dir.join(format!("{stem}.{ext}"));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{stem} ({n}).{ext}"));
+        n += 1;
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn track(title: &str, start_secs: u64) -> TrackInfo {
+        TrackInfo {
+            song_id: None,
+            artist: String::new(),
+            title: title.to_string(),
+            album_cover: None,
+            artist_image: None,
+            start_time_utc: SystemTime::UNIX_EPOCH + Duration::from_secs(start_secs),
+            duration_secs: 200,
+            source: None,
+            requester: None,
+            event: None,
+            listeners: None,
+        }
+    }
+
+    fn pages(path: &Path) -> Vec<ogg::Page> {
+        let mut reader = ogg::PageReader::default();
+        reader.push(&fs::read(path).unwrap());
+        std::iter::from_fn(|| reader.next_page()).collect()
+    }
+
+    #[test]
+    fn cuts_late_tracks_back_at_the_next_logical_stream() {
+        let dir = std::env::temp_dir().join(format!("petal-record-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let first = [
+            ogg::test_page(1, 0, true, b"id"),
+            ogg::test_page(1, 0, false, b"setup"),
+        ]
+        .concat();
+        let mut writer = TrackWriter::new(dir.clone());
+        writer.handle(RecordEvent::Head(first.clone())).unwrap();
+        writer
+            .handle(RecordEvent::Track(track("One", 100)))
+            .unwrap();
+        writer.handle(RecordEvent::Data(first)).unwrap();
+        writer
+            .handle(RecordEvent::Data(ogg::test_page(1, 960, false, b"one")))
+            .unwrap();
+        // The stream is already into the next track when playback gets there.
+        for page in [
+            ogg::test_page(2, 0, true, b"id"),
+            ogg::test_page(2, 0, false, b"setup"),
+            ogg::test_page(2, 960, false, b"two"),
+        ] {
+            writer.handle(RecordEvent::Data(page)).unwrap();
+        }
+        writer
+            .handle(RecordEvent::Track(track("One", 100)))
+            .unwrap();
+        writer
+            .handle(RecordEvent::Track(track("Two", 300)))
+            .unwrap();
+        writer
+            .handle(RecordEvent::Data(ogg::test_page(2, 1920, false, b"more")))
+            .unwrap();
+        writer.close();
+
+        let one = pages(&dir.join("One.ogg"));
+        let two = pages(&dir.join("Two.ogg"));
+        assert!(!dir.join("One (2).ogg").exists());
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(one.len(), 3);
+        assert!(one.iter().all(|page| page.serial() == 1));
+        assert_eq!(two.len(), 4);
+        assert!(two[0].is_bos() && two.iter().all(|page| page.serial() == 2));
+    }
+}
@@ -0,0 +1,133 @@
+//! Minimal Ogg page handling for lossless passthrough cuts.
+//!
+//! Pages are never decoded: they are only split out of the byte stream, classified and
+//! re-sequenced so that every output file is a valid stream on its own.
+
+const CAPTURE_PATTERN: &[u8] = b"OggS";
+const HEADER_LEN: usize = 27;
+const FLAG_BOS: u8 = 0x02;
+
+#[derive(Debug, Clone)]
+pub struct Page {
+    bytes: Vec<u8>,
+}
+
+impl Page {
+    pub fn is_bos(&self) -> bool {
+        self.bytes[5] & FLAG_BOS != 0
+    }
+
+    pub fn granule(&self) -> u64 {
+        u64::from_le_bytes(self.bytes[6..14].try_into().unwrap())
+    }
+
+    pub fn serial(&self) -> u32 {
+        u32::from_le_bytes(self.bytes[14..18].try_into().unwrap())
+    }
+
+    /// Header pages (identification, comments, setup) carry granule position 0.
+    pub fn is_header(&self) -> bool {
+        self.granule() == 0
+    }
+
+    /// Page bytes with the sequence number replaced and the checksum recomputed.
+    pub fn with_sequence(&self, sequence: u32) -> Vec<u8> {
+        let mut bytes = self.bytes.clone();
+        bytes[18..22].copy_from_slice(&sequence.to_le_bytes());
+        bytes[22..26].fill(0);
+        let crc = crc32(&bytes);
+        bytes[22..26].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+}
+
+/// Incremental page splitter; resyncs on the capture pattern after garbage or gaps.
+#[derive(Debug, Default)]
+pub struct PageReader {
+    buf: Vec<u8>,
+}
+
+impl PageReader {
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    pub fn clear(&mut self) {
+        self.buf.clear();
+    }
+
+    pub fn next_page(&mut self) -> Option<Page> {
+        loop {
+            let start = find(&self.buf, CAPTURE_PATTERN)?;
+            self.buf.drain(..start);
+            if self.buf.len() < HEADER_LEN {
+                return None;
+            }
+            if self.buf[4] != 0 {
+                // Unsupported version: not a real page boundary.
+                self.buf.drain(..1);
+                continue;
+            }
+            let n_segments = self.buf[26] as usize;
+            if self.buf.len() < HEADER_LEN + n_segments {
+                return None;
+            }
+            let body: usize = self.buf[HEADER_LEN..HEADER_LEN + n_segments]
+                .iter()
+                .map(|&s| s as usize)
+                .sum();
+            let len = HEADER_LEN + n_segments + body;
+            if self.buf.len() < len {
+                return None;
+            }
+            let bytes: Vec<u8> = self.buf.drain(..len).collect();
+            let mut check = bytes.clone();
+            check[22..26].fill(0);
+            if crc32(&check).to_le_bytes() != bytes[22..26] {
+                // Corrupt or false match: skip past this capture pattern.
+                self.buf.splice(..0, bytes[1..].iter().copied());
+                continue;
+            }
+            return Some(Page { bytes });
+        }
+    }
+}
+
+/// A page of logical stream `serial` holding `body`, for tests.
+#[cfg(test)]
+pub fn test_page(serial: u32, granule: u64, bos: bool, body: &[u8]) -> Vec<u8> {
+    assert!(body.len() < 255);
+    let mut bytes = CAPTURE_PATTERN.to_vec();
+    bytes.push(0);
+    bytes.push(if bos { FLAG_BOS } else { 0 });
+    bytes.extend_from_slice(&granule.to_le_bytes());
+    bytes.extend_from_slice(&serial.to_le_bytes());
+    bytes.extend_from_slice(&[0; 8]);
+    bytes.push(1);
+    bytes.push(body.len() as u8);
+    bytes.extend_from_slice(body);
+    Page { bytes }.with_sequence(0)
+}
+
+pub fn looks_like_ogg(bytes: &[u8]) -> bool {
+    bytes.starts_with(CAPTURE_PATTERN)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// CRC-32 as used by Ogg: polynomial 0x04c11db7, no reflection, zero init and xor-out.
+fn crc32(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0u32, |crc, &b| {
+        let mut crc = crc ^ ((b as u32) << 24);
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04c1_1db7
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
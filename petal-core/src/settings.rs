@@ -0,0 +1,340 @@
+use dirs_next as dirs;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::listen::Gains;
+use crate::station::{Station, StreamFormat};
+
+/// Per-station behavior, keyed by `Station::name` in the settings file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StationPrefs {
+    /// Desktop notification when a new track starts while the window is in the background.
+    pub notify: bool,
+}
+
+impl Default for StationPrefs {
+    fn default() -> Self {
+        Self { notify: true }
+    }
+}
+
+/// Color scheme preference.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Appearance {
+    /// Light or dark depending on the cover art, system default without one.
+    #[default]
+    Cover,
+    System,
+    Light,
+    Dark,
+}
+
+impl Appearance {
+    pub const ALL: [Appearance; 4] = [
+        Appearance::Cover,
+        Appearance::System,
+        Appearance::Light,
+        Appearance::Dark,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Appearance::Cover => "cover",
+            Appearance::System => "system",
+            Appearance::Light => "light",
+            Appearance::Dark => "dark",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+}
+
+/// Network timeouts shared by the stream request and the websocket.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Timeouts {
+    pub connect_secs: u32,
+    /// Longest wait for the next bytes of an open connection.
+    pub read_secs: u32,
+    /// Reconnect after this much silence on a connected stream; 0 turns it off.
+    pub silence_secs: u32,
+    /// Keep the track info connection open this many minutes after Stop, so playing again
+    /// shows the current track at once; 0 disconnects right away.
+    pub linger_mins: u32,
+    /// Close the track info connection after this many minutes paused, and reconnect on
+    /// resume; 0 stays connected.
+    pub suspend_mins: u32,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect_secs: 5,
+            read_secs: 30,
+            silence_secs: 30,
+            linger_mins: 5,
+            suspend_mins: 0,
+        }
+    }
+}
+
+impl Timeouts {
+    pub fn connect(&self) -> Duration {
+        Duration::from_secs(self.connect_secs.max(1).into())
+    }
+
+    pub fn read(&self) -> Duration {
+        Duration::from_secs(self.read_secs.max(1).into())
+    }
+
+    pub fn silence(&self) -> Option<Duration> {
+        (self.silence_secs > 0).then(|| Duration::from_secs(self.silence_secs.into()))
+    }
+
+    pub fn linger(&self) -> Duration {
+        Duration::from_secs(u64::from(self.linger_mins) * 60)
+    }
+
+    pub fn suspend(&self) -> Option<Duration> {
+        (self.suspend_mins > 0).then(|| Duration::from_secs(u64::from(self.suspend_mins) * 60))
+    }
+}
+
+/// MQTT broker that receives track changes and the playback state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Mqtt {
+    pub host: String,
+    pub port: u16,
+    /// Prefix of the published topics.
+    pub topic: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Default for Mqtt {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: 1883,
+            topic: "listenmoe".to_string(),
+            username: None,
+            password: None,
+        }
+    }
+}
+
+/// A recording window that runs whether or not anything is playing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledRecording {
+    /// Station name, e.g. `kpop`.
+    pub station: String,
+    /// Unix time in seconds.
+    pub start: i64,
+    pub duration_mins: u32,
+}
+
+impl ScheduledRecording {
+    pub fn end(&self) -> i64 {
+        self.start + i64::from(self.duration_mins) * 60
+    }
+}
+
+/// User preferences, stored as JSON in the per-user config directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub stream_format: StreamFormat,
+    /// Read-only mode for shared computers: playback works, preferences are locked.
+    pub guest_mode: bool,
+    /// Salted hash of the optional PIN to leave guest mode. A convenience lock, not a
+    /// security boundary.
+    pub guest_pin: Option<String>,
+    /// Stronger contrast and no color-only state cues.
+    pub high_visibility: bool,
+    /// Volume keys adjust the player's own gain while the window is focused.
+    pub volume_keys: bool,
+    /// Bind the media keys through the GlobalShortcuts portal as well (Linux).
+    pub media_keys: bool,
+    pub stations: BTreeMap<String, StationPrefs>,
+    /// Seconds of decoded audio that may queue up ahead of the output device. Without
+    /// timeshift, pausing for longer than this stops reading the stream, so the server may
+    /// drop the connection.
+    pub buffer_secs: u32,
+    /// Minutes of the stream kept while paused, to resume where playback left off; 0 turns
+    /// timeshift off.
+    pub timeshift_mins: u32,
+    pub pause_on_unplug: bool,
+    /// Milliseconds to fade in when playback starts or resumes and out when it pauses,
+    /// stops or the app quits; 0 turns fading off.
+    pub fade_ms: u32,
+    /// Proxy URL for all connections; when unset, the proxy environment variables apply.
+    pub proxy: Option<String>,
+    pub timeouts: Timeouts,
+    /// Show a message when the stream goes silent and is reconnected.
+    pub notify_silence: bool,
+    /// Connect only while playing: Stop drops the track info connection right away and
+    /// the window shows no track until the next Play.
+    pub connect_on_play: bool,
+    pub scheduled_recording: Option<ScheduledRecording>,
+    /// Play to a Snapcast server or other reader instead of the output device:
+    /// `tcp://host:port` or the path of a FIFO.
+    pub audio_sink: Option<String>,
+    /// Equalizer gain per band in dB; off when unset.
+    pub equalizer: Option<Gains>,
+    /// Steer the volume toward a common loudness, so songs and stations sound alike.
+    pub normalize_loudness: bool,
+    /// Play both channels in both ears.
+    pub mono_audio: bool,
+    /// Left/right balance, -1.0 (left) to 1.0 (right).
+    pub balance: f32,
+    /// Compact window with only the header bar.
+    pub mini_player: bool,
+    /// Window width and height when it was last closed; the content decides when unset.
+    pub window_size: Option<(i32, i32)>,
+    pub window_maximized: bool,
+    /// Scroll a track title that does not fit back and forth instead of cutting it off.
+    pub marquee_title: bool,
+    pub appearance: Appearance,
+    /// Keep a desktop file and icons in the user's data directory (builds with the `setup`
+    /// feature outside a sandbox).
+    pub desktop_integration: bool,
+    /// Closing the window hides it and playback continues.
+    pub run_in_background: bool,
+    /// Start with the window hidden, as with `--hidden`, e.g. when started at login.
+    pub start_hidden: bool,
+    pub show_lyrics: bool,
+    /// Spectrum bars behind the header bar; computing them costs some CPU.
+    pub show_visualizer: bool,
+    /// Left/right level meter under the title bar.
+    pub show_levels: bool,
+    /// Text file that always holds the current track, for streaming overlays.
+    pub now_playing_file: Option<PathBuf>,
+    /// Also keep the cover art next to that file.
+    pub now_playing_cover: bool,
+    /// Shell command or webhook URL run on every track change.
+    pub track_hook: Option<String>,
+    /// Serve the HTTP remote control on `127.0.0.1:remote_port`.
+    pub remote_control: bool,
+    pub remote_port: u16,
+    /// Access token of paired phones; while set, the remote control is also served on the
+    /// local network.
+    pub remote_token: Option<String>,
+    /// Publish to this MQTT broker, for home automation dashboards.
+    pub mqtt: Option<Mqtt>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            stream_format: StreamFormat::default(),
+            guest_mode: false,
+            guest_pin: None,
+            high_visibility: false,
+            volume_keys: false,
+            media_keys: false,
+            stations: BTreeMap::new(),
+            buffer_secs: 60,
+            timeshift_mins: 10,
+            pause_on_unplug: true,
+            fade_ms: 500,
+            proxy: None,
+            timeouts: Timeouts::default(),
+            notify_silence: true,
+            connect_on_play: false,
+            scheduled_recording: None,
+            audio_sink: None,
+            equalizer: None,
+            normalize_loudness: false,
+            mono_audio: false,
+            balance: 0.0,
+            mini_player: false,
+            window_size: None,
+            window_maximized: false,
+            marquee_title: false,
+            appearance: Appearance::default(),
+            desktop_integration: true,
+            run_in_background: false,
+            start_hidden: false,
+            show_lyrics: false,
+            show_visualizer: true,
+            show_levels: false,
+            now_playing_file: None,
+            now_playing_cover: false,
+            track_hook: None,
+            remote_control: false,
+            remote_port: 8642,
+            remote_token: None,
+            mqtt: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn fade(&self) -> Duration {
+        Duration::from_millis(u64::from(self.fade_ms))
+    }
+
+    /// Timeouts for the track info connection, which never lingers with `connect_on_play`.
+    pub fn meta_timeouts(&self) -> Timeouts {
+        Timeouts {
+            linger_mins: if self.connect_on_play {
+                0
+            } else {
+                self.timeouts.linger_mins
+            },
+            ..self.timeouts
+        }
+    }
+
+    pub fn station(&self, station: Station) -> StationPrefs {
+        self.stations
+            .get(station.name())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn station_mut(&mut self, station: Station) -> &mut StationPrefs {
+        self.stations.entry(station.name().to_string()).or_default()
+    }
+
+    pub fn load() -> Self {
+        let Some(path) = settings_path() else {
+            return Self::default();
+        };
+        match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|err| {
+                eprintln!("Ignoring invalid settings in {}: {err}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = settings_path() else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| {
+                let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+                fs::write(&path, json)
+            });
+        if let Err(err) = result {
+            eprintln!("Failed to save settings to {}: {err}", path.display());
+        }
+    }
+}
+
+fn settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(crate::APP_ID).join("settings.json"))
+}
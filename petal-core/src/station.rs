@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Station {
+    Jpop,
+    Kpop,
+}
+
+/// Encodings LISTEN.moe serves for every station.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamFormat {
+    #[default]
+    Vorbis,
+    Opus,
+    Mp3,
+}
+
+impl StreamFormat {
+    pub const ALL: [StreamFormat; 3] =
+        [StreamFormat::Vorbis, StreamFormat::Opus, StreamFormat::Mp3];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            StreamFormat::Vorbis => "vorbis",
+            StreamFormat::Opus => "opus",
+            StreamFormat::Mp3 => "mp3",
+        }
+    }
+
+    pub const fn display_name(self) -> &'static str {
+        match self {
+            StreamFormat::Vorbis => "Ogg Vorbis",
+            StreamFormat::Opus => "Ogg Opus",
+            StreamFormat::Mp3 => "MP3",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    /// Symphonia ships no Opus decoder, so that endpoint cannot be played back.
+    pub const fn is_decodable(self) -> bool {
+        !matches!(self, StreamFormat::Opus)
+    }
+}
+
+impl Station {
+    /// In the order the station switcher shows them.
+    pub const ALL: [Station; 2] = [Station::Jpop, Station::Kpop];
+
+    pub fn stream_url(self, format: StreamFormat) -> &'static str {
+        match (self, format) {
+            (Station::Jpop, StreamFormat::Vorbis) => "https://listen.moe/stream",
+            (Station::Jpop, StreamFormat::Opus) => "https://listen.moe/opus",
+            (Station::Jpop, StreamFormat::Mp3) => "https://listen.moe/fallback",
+            (Station::Kpop, StreamFormat::Vorbis) => "https://listen.moe/kpop/stream",
+            (Station::Kpop, StreamFormat::Opus) => "https://listen.moe/kpop/opus",
+            (Station::Kpop, StreamFormat::Mp3) => "https://listen.moe/kpop/fallback",
+        }
+    }
+
+    /// Playable endpoints in failover order: the preferred format first, then the others.
+    pub fn stream_endpoints(self, preferred: StreamFormat) -> Vec<(StreamFormat, &'static str)> {
+        std::iter::once(preferred)
+            .chain(StreamFormat::ALL.into_iter().filter(|f| *f != preferred))
+            .filter(|f| f.is_decodable())
+            .map(|f| (f, self.stream_url(f)))
+            .collect()
+    }
+
+    pub fn ws_url(self) -> &'static str {
+        match self {
+            Station::Jpop => "wss://listen.moe/gateway_v2",
+            Station::Kpop => "wss://listen.moe/kpop/gateway_v2",
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Station::Jpop => "jpop",
+            Station::Kpop => "kpop",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.name() == name)
+    }
+
+    pub const fn display_name(self) -> &'static str {
+        match self {
+            Station::Jpop => "J-POP",
+            Station::Kpop => "K-POP",
+        }
+    }
+
+    /// Brand color, used as the accent when there is no cover to take it from.
+    pub const fn accent_rgb(self) -> (u8, u8, u8) {
+        match self {
+            Station::Jpop => (255, 1, 91),
+            Station::Kpop => (48, 169, 237),
+        }
+    }
+}
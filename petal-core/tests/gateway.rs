@@ -0,0 +1,203 @@
+//! The metadata loop against a scripted local gateway.
+
+mod support;
+
+use petal_core::events::{EventBus, GatewayState, PlayerEvent};
+use petal_core::meta::{Meta, TrackInfo};
+use petal_core::settings::Timeouts;
+use petal_core::station::Station;
+use std::rc::Rc;
+use std::sync::atomic::AtomicU64;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant, SystemTime};
+use support::{song, update, MockGateway, PATIENCE};
+
+/// Comfortably longer than it takes to deliver an update on a local socket.
+const QUIET: Duration = Duration::from_millis(500);
+
+fn meta(gateway: &MockGateway, lag_ms: u64) -> (Rc<Meta>, mpsc::Receiver<PlayerEvent>) {
+    let events = EventBus::new();
+    let rx = events.subscribe();
+    let meta = Meta::new(Station::Jpop, events, Arc::new(AtomicU64::new(lag_ms)));
+    meta.set_gateway_url(Some(gateway.url()));
+    meta.set_proxy(None);
+    meta.set_timeouts(Timeouts {
+        linger_mins: 0,
+        ..Timeouts::default()
+    });
+    (meta, rx)
+}
+
+/// The next track shown within `timeout`, skipping other events.
+fn next_track(rx: &mpsc::Receiver<PlayerEvent>, timeout: Duration) -> Option<TrackInfo> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(left).ok()? {
+            PlayerEvent::TrackChanged(info) | PlayerEvent::TrackResynced(info) => {
+                return Some(*info)
+            }
+            _ => continue,
+        }
+    }
+}
+
+fn next_gateway_state(rx: &mpsc::Receiver<PlayerEvent>) -> GatewayState {
+    let deadline = Instant::now() + PATIENCE;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(left).expect("no gateway state change") {
+            PlayerEvent::GatewayStateChanged(state) => return state,
+            _ => continue,
+        }
+    }
+}
+
+fn ago(secs: u64) -> SystemTime {
+    SystemTime::now() - Duration::from_secs(secs)
+}
+
+#[test]
+fn shows_the_current_track_and_the_next() {
+    let gateway = MockGateway::start();
+    let (meta, rx) = meta(&gateway, 0);
+    meta.start();
+
+    let mut session = gateway.accept();
+    session.hello(45_000);
+    // One heartbeat right after HELLO.
+    session.expect_heartbeat();
+    session.heartbeat_ack();
+
+    let first = song(1, "Renai Circulation", "Kana Hanazawa", 255);
+    session.track_update(update(first.clone(), ago(10), vec![]));
+    let shown = next_track(&rx, PATIENCE).expect("first track");
+    assert_eq!(shown.title, "Renai Circulation");
+    assert_eq!(shown.artist, "Kana Hanazawa");
+
+    session.track_update(update(
+        song(2, "Connect", "ClariS", 270),
+        ago(0),
+        vec![first],
+    ));
+    let shown = next_track(&rx, PATIENCE).expect("second track");
+    assert_eq!(shown.title, "Connect");
+}
+
+#[test]
+fn waits_for_buffered_playback() {
+    let gateway = MockGateway::start();
+    let (meta, rx) = meta(&gateway, 2_000);
+    meta.start();
+
+    let mut session = gateway.accept();
+    session.hello(45_000);
+    let first = song(1, "Earlier", "A", 200);
+    session.track_update(update(first.clone(), ago(30), vec![]));
+    assert_eq!(next_track(&rx, PATIENCE).unwrap().title, "Earlier");
+
+    // Starts now, but the speakers are two seconds behind the gateway.
+    session.track_update(update(song(2, "Later", "B", 200), ago(0), vec![first]));
+    assert!(next_track(&rx, QUIET).is_none(), "shown before it is heard");
+    assert_eq!(next_track(&rx, PATIENCE).unwrap().title, "Later");
+}
+
+#[test]
+fn catches_up_from_last_played() {
+    let gateway = MockGateway::start();
+    // Playback is still a minute behind, in the song before the current one.
+    let (meta, rx) = meta(&gateway, 60_000);
+    meta.start();
+
+    let mut session = gateway.accept();
+    session.hello(45_000);
+    session.track_update(update(
+        song(2, "Current", "B", 200),
+        ago(30),
+        vec![song(1, "Previous", "A", 200)],
+    ));
+    assert_eq!(next_track(&rx, PATIENCE).unwrap().title, "Previous");
+}
+
+#[test]
+fn pause_holds_updates_until_resume() {
+    let gateway = MockGateway::start();
+    let (meta, rx) = meta(&gateway, 0);
+    meta.start();
+
+    let mut session = gateway.accept();
+    session.hello(45_000);
+    let first = song(1, "Before pause", "A", 200);
+    session.track_update(update(first.clone(), ago(10), vec![]));
+    assert_eq!(next_track(&rx, PATIENCE).unwrap().title, "Before pause");
+
+    meta.pause();
+    session.track_update(update(
+        song(2, "While paused", "B", 200),
+        ago(0),
+        vec![first],
+    ));
+    assert!(next_track(&rx, QUIET).is_none(), "updated while paused");
+
+    meta.start();
+    assert_eq!(next_track(&rx, PATIENCE).unwrap().title, "While paused");
+}
+
+#[test]
+fn reconnects_when_the_server_closes() {
+    let gateway = MockGateway::start();
+    let (meta, rx) = meta(&gateway, 0);
+    meta.start();
+    assert_eq!(next_gateway_state(&rx), GatewayState::Connecting);
+
+    let mut session = gateway.accept();
+    session.hello(45_000);
+    assert_eq!(next_gateway_state(&rx), GatewayState::Connected);
+    let first = song(1, "First session", "A", 200);
+    session.track_update(update(first.clone(), ago(10), vec![]));
+    assert_eq!(next_track(&rx, PATIENCE).unwrap().title, "First session");
+    session.close();
+
+    assert_eq!(next_gateway_state(&rx), GatewayState::Reconnecting);
+    let mut session = gateway.accept();
+    session.hello(45_000);
+    session.track_update(update(
+        song(2, "Second session", "B", 200),
+        ago(2),
+        vec![first],
+    ));
+    assert_eq!(next_track(&rx, PATIENCE).unwrap().title, "Second session");
+}
+
+#[test]
+fn reconnects_without_heartbeat_acks() {
+    let gateway = MockGateway::start();
+    let (meta, _rx) = meta(&gateway, 0);
+    meta.start();
+
+    let mut session = gateway.accept();
+    session.hello(100);
+    // Heartbeats keep coming, but none is acknowledged.
+    assert!(session.closed_within(PATIENCE), "kept a dead connection");
+    assert!(gateway.try_accept(PATIENCE).is_some(), "did not reconnect");
+}
+
+#[test]
+fn stop_disconnects() {
+    let gateway = MockGateway::start();
+    let (meta, _rx) = meta(&gateway, 0);
+    meta.start();
+
+    let mut session = gateway.accept();
+    session.hello(45_000);
+    session.expect_heartbeat();
+    meta.stop();
+    assert!(
+        session.closed_within(PATIENCE),
+        "still connected after stop"
+    );
+    assert!(
+        gateway.try_accept(QUIET).is_none(),
+        "reconnected after stop"
+    );
+}
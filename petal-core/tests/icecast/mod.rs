@@ -0,0 +1,202 @@
+//! A local stand-in for an Icecast server and a network audio sink, so the audio pipeline
+//! runs end to end without the real stream or a sound device.
+//!
+//! The stream is mostly MPEG audio made of silent frames, which can be generated here: this
+//! build does not decode Opus. Vorbis comes from a recorded file in
+//! `fixtures/icecast`.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a test waits for something that should happen.
+pub const PATIENCE: Duration = Duration::from_secs(10);
+
+pub struct MockIcecast {
+    url: String,
+    requests: mpsc::Receiver<Request>,
+}
+
+impl MockIcecast {
+    /// Listen on a free local port; every request for the stream becomes a [`Request`].
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/stream", listener.local_addr().unwrap());
+        let (tx, requests) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                let Some(request) = Request::read(stream) else {
+                    continue;
+                };
+                if tx.send(request).is_err() {
+                    return;
+                }
+            }
+        });
+        Self { url, requests }
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    /// The next request for the stream.
+    pub fn accept(&self) -> Request {
+        self.requests
+            .recv_timeout(PATIENCE)
+            .expect("the player did not connect")
+    }
+}
+
+/// One request for the stream, answered by the test.
+pub struct Request {
+    stream: TcpStream,
+}
+
+impl Request {
+    /// Read up to the end of the request head.
+    fn read(stream: TcpStream) -> Option<Self> {
+        let mut reader = BufReader::new(stream.try_clone().ok()?);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).ok()? == 0 {
+                return None;
+            }
+            if line == "\r\n" {
+                return Some(Self { stream });
+            }
+        }
+    }
+
+    /// Answer with an error status and no stream.
+    pub fn refuse(mut self, status: &str) {
+        let _ = write!(
+            self.stream,
+            "HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        );
+    }
+
+    /// Start a chunked MPEG audio stream, as Icecast does.
+    pub fn start_stream(&mut self) {
+        self.start_stream_of("audio/mpeg");
+    }
+
+    /// Start a chunked stream of `content_type`.
+    pub fn start_stream_of(&mut self, content_type: &str) {
+        write!(
+            self.stream,
+            "HTTP/1.1 200 OK\r\n\
+             Content-Type: {content_type}\r\n\
+             Transfer-Encoding: chunked\r\n\
+             icy-name: Mock Radio\r\n\
+             Cache-Control: no-cache\r\n\r\n"
+        )
+        .unwrap();
+    }
+
+    /// Send `bytes` as one chunk; false once the player hung up.
+    pub fn send(&mut self, bytes: &[u8]) -> bool {
+        let mut chunk = format!("{:x}\r\n", bytes.len()).into_bytes();
+        chunk.extend_from_slice(bytes);
+        chunk.extend_from_slice(b"\r\n");
+        self.stream.write_all(&chunk).is_ok()
+    }
+
+    /// Stream `frames` in chunks of a few frames, at about real time, until all are sent
+    /// or the player hangs up.
+    pub fn play(&mut self, frames: &[Vec<u8>]) -> bool {
+        for batch in frames.chunks(4) {
+            if !self.send(&batch.concat()) {
+                return false;
+            }
+            thread::sleep(FRAME_DURATION * batch.len() as u32 / 2);
+        }
+        true
+    }
+
+    /// Drop the connection in the middle of a chunk, as when a relay restarts.
+    pub fn cut(mut self, frame: &[u8]) {
+        let _ = write!(self.stream, "{:x}\r\n", frame.len() * 2);
+        let _ = self.stream.write_all(&frame[..frame.len() / 2]);
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+    }
+
+    /// End the stream cleanly, after the last chunk.
+    pub fn finish(mut self) {
+        let _ = self.stream.write_all(b"0\r\n\r\n");
+    }
+}
+
+/// Roughly how much audio one frame holds: 1152 samples.
+pub const FRAME_DURATION: Duration = Duration::from_millis(24);
+
+/// `count` silent MPEG-1 Layer III frames, mono, 128 kbit/s at `sample_rate` (32, 44.1 or
+/// 48 kHz).
+pub fn mp3_frames(sample_rate: u32, count: usize) -> Vec<Vec<u8>> {
+    let rate_bits = match sample_rate {
+        44_100 => 0b00,
+        48_000 => 0b01,
+        32_000 => 0b10,
+        _ => panic!("no MPEG-1 sample rate {sample_rate}"),
+    };
+    let len = 144 * 128_000 / sample_rate as usize;
+    // Sync, MPEG-1, Layer III, no CRC; 128 kbit/s; mono. All-zero side info and main data
+    // decode to silence.
+    let mut frame = vec![0; len];
+    frame[..4].copy_from_slice(&[0xff, 0xfb, 0x90 | rate_bits << 2, 0xc0]);
+    vec![frame; count]
+}
+
+/// A network audio sink: takes the player's raw PCM and counts it.
+pub struct PcmSink {
+    target: String,
+    received: Arc<AtomicUsize>,
+}
+
+impl PcmSink {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let target = format!("tcp://{}", listener.local_addr().unwrap());
+        let received = Arc::new(AtomicUsize::new(0));
+        let counter = received.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let counter = counter.clone();
+                thread::spawn(move || {
+                    let mut buf = [0; 16 * 1024];
+                    while let Ok(n @ 1..) = stream.read(&mut buf) {
+                        counter.fetch_add(n, Ordering::Relaxed);
+                    }
+                });
+            }
+        });
+        Self { target, received }
+    }
+
+    /// `tcp://host:port`, for [`petal_core::listen::SinkTarget::parse`].
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn received(&self) -> usize {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    /// Whether more than `bytes` in total arrive within `timeout`.
+    pub fn wait_for(&self, bytes: usize, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if self.received() > bytes {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        false
+    }
+}
@@ -0,0 +1,197 @@
+//! The audio pipeline against a scripted local stream server.
+
+mod icecast;
+
+use icecast::{mp3_frames, MockIcecast, PcmSink, PATIENCE};
+use petal_core::events::{EventBus, PlaybackState, PlayerEvent};
+use petal_core::listen::{Listen, SinkTarget};
+use petal_core::settings::Timeouts;
+use petal_core::station::{Station, StreamFormat};
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn listen(server: &MockIcecast, sink: &PcmSink) -> (Rc<Listen>, mpsc::Receiver<PlayerEvent>) {
+    let events = EventBus::new();
+    let rx = events.subscribe();
+    let listen = Listen::new(Station::Jpop, StreamFormat::Mp3, 2, events);
+    listen.set_stream_url(Some(server.url()));
+    listen.set_audio_sink(SinkTarget::parse(sink.target()));
+    listen.set_visualizer(false);
+    listen.set_proxy(None);
+    // The test stream is nothing but silence.
+    listen.set_timeouts(Timeouts {
+        silence_secs: 0,
+        ..Timeouts::default()
+    });
+    (listen, rx)
+}
+
+/// Wait for the player to reach `state`; panics if it fails on the way.
+fn wait_for_state(rx: &mpsc::Receiver<PlayerEvent>, state: PlaybackState) {
+    let deadline = Instant::now() + PATIENCE;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(left) {
+            Ok(PlayerEvent::StateChanged(PlaybackState::Error)) => panic!("playback failed"),
+            Ok(PlayerEvent::StateChanged(reached)) if reached == state => return,
+            Ok(_) => continue,
+            Err(_) => panic!("never reached {state:?}"),
+        }
+    }
+}
+
+/// Whether the player reports an error within `timeout`.
+fn reports_error(rx: &mpsc::Receiver<PlayerEvent>, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(left) {
+            Ok(PlayerEvent::Error(_)) => return true,
+            Ok(_) => continue,
+            Err(_) => return false,
+        }
+    }
+}
+
+#[test]
+fn plays_a_chunked_stream() {
+    let server = MockIcecast::start();
+    let sink = PcmSink::start();
+    let (listen, rx) = listen(&server, &sink);
+    listen.start();
+
+    let mut request = server.accept();
+    request.start_stream();
+    request.play(&mp3_frames(44_100, 40));
+    wait_for_state(&rx, PlaybackState::Playing);
+    assert!(sink.wait_for(0, PATIENCE), "no audio reached the sink");
+
+    listen.stop();
+    wait_for_state(&rx, PlaybackState::Stopped);
+}
+
+#[test]
+fn reconnects_after_the_stream_ends() {
+    let server = MockIcecast::start();
+    let sink = PcmSink::start();
+    let (listen, rx) = listen(&server, &sink);
+    listen.start();
+
+    let mut request = server.accept();
+    request.start_stream();
+    request.play(&mp3_frames(44_100, 20));
+    wait_for_state(&rx, PlaybackState::Playing);
+    request.finish();
+
+    wait_for_state(&rx, PlaybackState::Reconnecting);
+    let mut request = server.accept();
+    request.start_stream();
+    request.play(&mp3_frames(44_100, 20));
+    wait_for_state(&rx, PlaybackState::Playing);
+}
+
+#[test]
+fn reconnects_after_a_cut_connection() {
+    let server = MockIcecast::start();
+    let sink = PcmSink::start();
+    let (listen, rx) = listen(&server, &sink);
+    listen.start();
+
+    let mut request = server.accept();
+    request.start_stream();
+    let frames = mp3_frames(44_100, 20);
+    request.play(&frames);
+    wait_for_state(&rx, PlaybackState::Playing);
+    request.cut(&frames[0]);
+
+    let mut request = server.accept();
+    request.start_stream();
+    request.play(&mp3_frames(44_100, 20));
+    wait_for_state(&rx, PlaybackState::Playing);
+}
+
+#[test]
+fn retries_after_an_error_status() {
+    let server = MockIcecast::start();
+    let sink = PcmSink::start();
+    let (listen, rx) = listen(&server, &sink);
+    listen.start();
+
+    server.accept().refuse("503 Service Unavailable");
+    assert!(reports_error(&rx, PATIENCE), "the failure went unreported");
+
+    server.accept().refuse("404 Not Found");
+    let mut request = server.accept();
+    request.start_stream();
+    request.play(&mp3_frames(44_100, 20));
+    wait_for_state(&rx, PlaybackState::Playing);
+}
+
+#[test]
+fn follows_a_sample_rate_change() {
+    let server = MockIcecast::start();
+    let sink = PcmSink::start();
+    let (listen, rx) = listen(&server, &sink);
+    listen.start();
+
+    let mut request = server.accept();
+    request.start_stream();
+    request.play(&mp3_frames(44_100, 20));
+    wait_for_state(&rx, PlaybackState::Playing);
+
+    // A new source on the mount, in another format.
+    let before = sink.received();
+    request.play(&mp3_frames(48_000, 40));
+    assert!(sink.wait_for(before, PATIENCE), "no audio after the change");
+    thread::sleep(Duration::from_millis(200));
+    assert!(!listen.is_paused());
+    while let Ok(event) = rx.try_recv() {
+        assert!(
+            !matches!(event, PlayerEvent::StateChanged(PlaybackState::Error)),
+            "playback failed"
+        );
+    }
+}
+
+/// Two chained Ogg Vorbis streams, as when a source hands over on the mount: 2.3 s of
+/// `beep3.ogg` from rodio 0.15 (MIT or Apache-2.0), then all 10 s of it under a new serial.
+const CHAINED_OGG: &[u8] = include_bytes!("fixtures/icecast/chained.ogg");
+/// Samples in the first stream, at 44.1 kHz: the granule position of its last page.
+const FIRST_STREAM_SAMPLES: usize = 103_104;
+
+#[test]
+fn plays_on_across_chained_ogg_streams() {
+    let server = MockIcecast::start();
+    let sink = PcmSink::start();
+    let (listen, rx) = listen(&server, &sink);
+    listen.start();
+
+    let mut request = server.accept();
+    request.start_stream_of("application/ogg");
+    for chunk in CHAINED_OGG.chunks(4096) {
+        assert!(request.send(chunk), "the player hung up");
+    }
+    wait_for_state(&rx, PlaybackState::Playing);
+
+    // The sink is unpaced, so everything decoded arrives; 16-bit stereo at 48 kHz. More than
+    // the first stream holds means the decoder was rebuilt for the second.
+    let first_stream = FIRST_STREAM_SAMPLES * 48_000 / 44_100 * 4;
+    assert!(
+        sink.wait_for(first_stream, PATIENCE),
+        "no audio after the first stream"
+    );
+    while let Ok(event) = rx.try_recv() {
+        assert!(
+            !matches!(
+                event,
+                PlayerEvent::StateChanged(PlaybackState::Error | PlaybackState::Reconnecting)
+            ),
+            "the player dropped the stream at the boundary"
+        );
+    }
+
+    listen.stop();
+    wait_for_state(&rx, PlaybackState::Stopped);
+}
@@ -0,0 +1,158 @@
+//! A local stand-in for the LISTEN.moe gateway: accepts websocket connections and lets a
+//! test script what the server says, so the metadata loop runs without the real service.
+
+use serde_json::{json, Value};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tungstenite::{Message, WebSocket};
+
+/// How long a test waits for something that should happen.
+pub const PATIENCE: Duration = Duration::from_secs(10);
+
+pub struct MockGateway {
+    url: String,
+    sessions: mpsc::Receiver<Session>,
+}
+
+impl MockGateway {
+    /// Listen on a free local port; every connection becomes a [`Session`].
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}/gateway_v2", listener.local_addr().unwrap());
+        let (tx, sessions) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                let Ok(ws) = tungstenite::accept(stream) else {
+                    continue;
+                };
+                if tx.send(Session { ws }).is_err() {
+                    return;
+                }
+            }
+        });
+        Self { url, sessions }
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    /// The next client connection, already past the websocket handshake.
+    pub fn accept(&self) -> Session {
+        self.sessions
+            .recv_timeout(PATIENCE)
+            .expect("the client did not connect")
+    }
+
+    /// Whether the client connects again within `timeout`.
+    pub fn try_accept(&self, timeout: Duration) -> Option<Session> {
+        self.sessions.recv_timeout(timeout).ok()
+    }
+}
+
+/// One connected client, seen from the server side.
+pub struct Session {
+    ws: WebSocket<TcpStream>,
+}
+
+impl Session {
+    pub fn send(&mut self, frame: Value) {
+        self.ws.send(Message::text(frame.to_string())).unwrap();
+    }
+
+    /// Greet the client, asking for a heartbeat every `heartbeat_ms`.
+    pub fn hello(&mut self, heartbeat_ms: u64) {
+        self.send(json!({
+            "op": 0,
+            "d": { "message": "Welcome to LISTEN.moe! Enjoy your stay!", "heartbeat": heartbeat_ms },
+        }));
+    }
+
+    pub fn heartbeat_ack(&mut self) {
+        self.send(json!({ "op": 10 }));
+    }
+
+    pub fn track_update(&mut self, update: Value) {
+        self.send(json!({ "op": 1, "t": "TRACK_UPDATE", "d": update }));
+    }
+
+    /// The next frame the client sends, or `None` if it sends nothing within `timeout` or
+    /// disconnected.
+    pub fn recv(&mut self, timeout: Duration) -> Option<Value> {
+        self.ws.get_mut().set_read_timeout(Some(timeout)).unwrap();
+        loop {
+            match self.ws.read() {
+                Ok(Message::Text(text)) => return serde_json::from_str(&text).ok(),
+                Ok(_) => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+
+    /// Wait for a heartbeat (`op` 9) from the client.
+    pub fn expect_heartbeat(&mut self) {
+        let frame = self.recv(PATIENCE).expect("no heartbeat");
+        assert_eq!(frame["op"], 9, "expected a heartbeat, got {frame}");
+    }
+
+    /// Whether the client closed the connection within `timeout`.
+    pub fn closed_within(&mut self, timeout: Duration) -> bool {
+        let deadline = std::time::Instant::now() + timeout;
+        self.ws.get_mut().set_read_timeout(Some(timeout)).unwrap();
+        loop {
+            match self.ws.read() {
+                Ok(_) if std::time::Instant::now() < deadline => continue,
+                Ok(_) => return false,
+                Err(tungstenite::Error::Io(err))
+                    if matches!(
+                        err.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return false
+                }
+                Err(_) => return true,
+            }
+        }
+    }
+
+    /// End the session from the server side, as after a deploy.
+    pub fn close(mut self) {
+        let _ = self.ws.close(None);
+        let _ = self.ws.flush();
+    }
+}
+
+/// A `song` object as the gateway sends it.
+pub fn song(id: u32, title: &str, artist: &str, duration_secs: u32) -> Value {
+    json!({
+        "id": id,
+        "title": title,
+        "sources": [],
+        "artists": [{ "id": id, "name": artist, "nameRomaji": null, "image": null }],
+        "albums": [],
+        "duration": duration_secs,
+    })
+}
+
+/// A `TRACK_UPDATE` payload for `song` starting at `start`, after `last_played`
+/// (newest first, as the gateway orders it).
+pub fn update(song: Value, start: SystemTime, last_played: Vec<Value>) -> Value {
+    json!({
+        "song": song,
+        "requester": null,
+        "event": null,
+        "startTime": rfc3339(start),
+        "lastPlayed": last_played,
+        "listeners": 100,
+    })
+}
+
+fn rfc3339(time: SystemTime) -> String {
+    OffsetDateTime::from(time).format(&Rfc3339).unwrap()
+}
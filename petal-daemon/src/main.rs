@@ -0,0 +1,258 @@
+//! Plays a station without any user interface: for jukeboxes and user services.
+//! Track changes go to stdout; on Linux the player is controlled over MPRIS. Built with
+//! the `tui` feature, `--tui` shows a terminal interface instead.
+
+#[cfg(target_os = "linux")]
+mod mpris;
+
+mod player;
+#[cfg(target_os = "linux")]
+mod service;
+#[cfg(all(target_os = "linux", feature = "setup"))]
+mod setup;
+#[cfg(feature = "tui")]
+mod tui;
+
+use petal_core::events::{EventBus, PlayerEvent};
+use petal_core::locale;
+use petal_core::log::now_string;
+use petal_core::meta::TrackInfo;
+use petal_core::settings::Settings;
+use petal_core::station::{Station, StreamFormat};
+use player::{Command, Player};
+use std::process::ExitCode;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[cfg(not(feature = "tui"))]
+const USAGE: &str = "Usage: petal-daemon [--station jpop|kpop] [--format vorbis|mp3] [--paused]";
+#[cfg(feature = "tui")]
+const USAGE: &str =
+    "Usage: petal-daemon [--station jpop|kpop] [--format vorbis|mp3] [--paused] [--tui]";
+#[cfg(all(target_os = "linux", feature = "setup"))]
+const SETUP_USAGE: &str =
+    "       petal-daemon --install-service [--station jpop|kpop] [--format vorbis|mp3]\n       petal-daemon --uninstall-service";
+
+#[derive(Debug)]
+struct Options {
+    station: Station,
+    format: Option<StreamFormat>,
+    paused: bool,
+    #[cfg(feature = "tui")]
+    tui: bool,
+    #[cfg(all(target_os = "linux", feature = "setup"))]
+    setup: Option<Setup>,
+}
+
+#[cfg(all(target_os = "linux", feature = "setup"))]
+#[derive(Debug, Clone, Copy)]
+enum Setup {
+    Install,
+    Uninstall,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options {
+            station: Station::Jpop,
+            format: None,
+            paused: false,
+            #[cfg(feature = "tui")]
+            tui: false,
+            #[cfg(all(target_os = "linux", feature = "setup"))]
+            setup: None,
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--station" => {
+                    let name = args.next().unwrap_or_default();
+                    options.station = Station::from_name(&name)
+                        .ok_or_else(|| format!("Unknown station: {name}"))?;
+                }
+                "--format" => {
+                    let name = args.next().unwrap_or_default();
+                    let format = StreamFormat::from_name(&name)
+                        .filter(|f| f.is_decodable())
+                        .ok_or_else(|| format!("Unsupported stream format: {name}"))?;
+                    options.format = Some(format);
+                }
+                "--paused" => options.paused = true,
+                #[cfg(feature = "tui")]
+                "--tui" => options.tui = true,
+                #[cfg(all(target_os = "linux", feature = "setup"))]
+                "--install-service" => options.setup = Some(Setup::Install),
+                #[cfg(all(target_os = "linux", feature = "setup"))]
+                "--uninstall-service" => options.setup = Some(Setup::Uninstall),
+                _ => return Err(format!("Unknown option: {arg}")),
+            }
+        }
+        Ok(options)
+    }
+
+    /// The options a service should be started with.
+    #[cfg(all(target_os = "linux", feature = "setup"))]
+    fn service_args(&self) -> Vec<String> {
+        let mut args = vec!["--station".to_string(), self.station.name().to_string()];
+        if let Some(format) = self.format {
+            args.extend(["--format".to_string(), format.name().to_string()]);
+        }
+        args
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}\n{USAGE}");
+            #[cfg(all(target_os = "linux", feature = "setup"))]
+            eprintln!("{SETUP_USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    #[cfg(all(target_os = "linux", feature = "setup"))]
+    if let Some(setup) = options.setup {
+        return run_setup(setup, &options);
+    }
+    locale::init_i18n();
+
+    let settings = Settings::load();
+    let events = EventBus::new();
+    let player_events = events.subscribe();
+    let player = Player::new(options.station, options.format, &settings, &events);
+
+    let (command_tx, commands) = mpsc::channel::<Command>();
+    #[cfg(target_os = "linux")]
+    let mpris = mpris::Mpris::spawn(command_tx.clone());
+    drop(command_tx);
+
+    #[cfg(feature = "tui")]
+    let mut screen = match options.tui.then(tui::Screen::open).transpose() {
+        Ok(screen) => screen,
+        Err(err) => {
+            eprintln!("Failed to open the terminal: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if !options.paused {
+        player.play();
+    }
+    #[cfg(target_os = "linux")]
+    {
+        service::handle_signals();
+        service::notify("READY=1");
+    }
+    loop {
+        #[cfg(target_os = "linux")]
+        if service::stop_requested() {
+            service::notify("STOPPING=1");
+            player.shutdown();
+            return ExitCode::SUCCESS;
+        }
+        for command in commands.try_iter() {
+            if !player.apply(command) {
+                return ExitCode::SUCCESS;
+            }
+        }
+        for event in player_events.try_iter() {
+            player.observe(&event);
+            #[cfg(target_os = "linux")]
+            {
+                mpris.observe(&event);
+                service::observe(&event);
+            }
+            #[cfg(feature = "tui")]
+            if let Some(screen) = screen.as_mut() {
+                screen.observe(event);
+                continue;
+            }
+            log(&event);
+        }
+        #[cfg(feature = "tui")]
+        if let Some(screen) = screen.as_mut() {
+            if let Err(err) = screen.draw(&player) {
+                screen.close();
+                eprintln!("Failed to draw: {err}");
+                return ExitCode::FAILURE;
+            }
+            if let Some(command) = screen.wait_for_key(POLL_INTERVAL) {
+                if !player.apply(command) {
+                    return ExitCode::SUCCESS;
+                }
+            }
+            continue;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "setup"))]
+fn run_setup(setup: Setup, options: &Options) -> ExitCode {
+    let result = match setup {
+        Setup::Install => setup::install(&options.service_args()).map(|path| {
+            println!("Installed {}", path.display());
+            println!("Start it now and at every login with:");
+            println!("  systemctl --user enable --now {}", setup::UNIT_NAME);
+        }),
+        Setup::Uninstall => setup::uninstall().map(|removed| match removed {
+            Some(path) => {
+                println!("Removed {}", path.display());
+                println!("Stop a running service with:");
+                println!("  systemctl --user disable --now {}", setup::UNIT_NAME);
+            }
+            None => println!("Nothing to remove"),
+        }),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Service setup failed: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn log(event: &PlayerEvent) {
+    match event {
+        PlayerEvent::TrackChanged(info) => println!("[{}] {}", now_string(), describe(info)),
+        PlayerEvent::StationChanged(station) => {
+            println!("[{}] {}", now_string(), station.display_name());
+        }
+        PlayerEvent::Error(message) => eprintln!("[{}] {message}", now_string()),
+        _ => {}
+    }
+}
+
+pub(crate) fn describe(info: &TrackInfo) -> String {
+    match info.source.as_deref() {
+        Some(source) => format!("{} - {} ({source})", info.artist, info.title),
+        None => format!("{} - {}", info.artist, info.title),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn parses_options() {
+        let options = parse(&[]).unwrap();
+        assert_eq!(options.station, Station::Jpop);
+        assert!(!options.paused);
+
+        let options = parse(&["--station", "kpop", "--paused"]).unwrap();
+        assert_eq!(options.station, Station::Kpop);
+        assert!(options.paused);
+
+        assert!(parse(&["--station", "polka"]).is_err());
+        assert!(parse(&["--volume"]).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+use async_executor::LocalExecutor;
+use mpris_server::{Metadata, PlaybackStatus, Player};
+use petal_core::events::{PlaybackState, PlayerEvent};
+use petal_core::meta::TrackInfo;
+use std::sync::mpsc;
+use std::thread;
+
+use crate::Command;
+
+const BUS_SUFFIX: &str = "listenmoe_daemon";
+const IDENTITY: &str = "Listen Moe";
+
+enum Update {
+    Track(Metadata),
+    Playback(PlaybackStatus),
+}
+
+/// The MPRIS player, served from its own thread: it needs an async executor, and the
+/// radio lives on the main thread.
+pub struct Mpris {
+    updates: async_channel::Sender<Update>,
+}
+
+impl Mpris {
+    pub fn spawn(commands: mpsc::Sender<Command>) -> Self {
+        let (updates, rx) = async_channel::unbounded();
+        thread::spawn(move || {
+            let executor = LocalExecutor::new();
+            futures_lite::future::block_on(executor.run(serve(&executor, commands, rx)));
+        });
+        Self { updates }
+    }
+
+    pub fn observe(&self, event: &PlayerEvent) {
+        match event {
+            PlayerEvent::TrackChanged(info) | PlayerEvent::TrackResynced(info) => {
+                self.track_changed(info)
+            }
+            PlayerEvent::StateChanged(state) => self.set_playback(*state),
+            _ => {}
+        }
+    }
+
+    fn track_changed(&self, info: &TrackInfo) {
+        let mut metadata = Metadata::builder()
+            .title(info.title.clone())
+            .artist([info.artist.clone()])
+            .album(info.source.clone().unwrap_or_default());
+        if let Some(url) = info.album_cover.as_ref().or(info.artist_image.as_ref()) {
+            metadata = metadata.art_url(url.clone());
+        }
+        let _ = self.updates.try_send(Update::Track(metadata.build()));
+    }
+
+    fn set_playback(&self, state: PlaybackState) {
+        let status = match state {
+            PlaybackState::Connecting
+            | PlaybackState::Buffering
+            | PlaybackState::Playing
+            | PlaybackState::Reconnecting => PlaybackStatus::Playing,
+            PlaybackState::Paused => PlaybackStatus::Paused,
+            PlaybackState::Stopped | PlaybackState::Error => PlaybackStatus::Stopped,
+        };
+        let _ = self.updates.try_send(Update::Playback(status));
+    }
+}
+
+async fn serve(
+    executor: &LocalExecutor<'_>,
+    commands: mpsc::Sender<Command>,
+    updates: async_channel::Receiver<Update>,
+) {
+    let player = Player::builder(BUS_SUFFIX)
+        .identity(IDENTITY)
+        .can_control(true)
+        .can_play(true)
+        .can_pause(true)
+        .can_go_next(true)
+        .can_go_previous(true)
+        .can_quit(true)
+        .build()
+        .await;
+    let player = match player {
+        Ok(player) => player,
+        Err(err) => {
+            eprintln!("Media control unavailable: {err}");
+            return;
+        }
+    };
+    let on = |command: Command| {
+        let commands = commands.clone();
+        move |_: &Player| {
+            let _ = commands.send(command);
+        }
+    };
+    player.connect_play(on(Command::Play));
+    player.connect_pause(on(Command::Pause));
+    player.connect_play_pause(on(Command::Toggle));
+    player.connect_stop(on(Command::Stop));
+    player.connect_next(on(Command::Next));
+    player.connect_previous(on(Command::Previous));
+    player.connect_quit(on(Command::Quit));
+    executor.spawn(player.run()).detach();
+
+    while let Ok(update) = updates.recv().await {
+        let _ = match update {
+            Update::Track(metadata) => player.set_metadata(metadata).await,
+            Update::Playback(status) => player.set_playback_status(status).await,
+        };
+    }
+}
@@ -0,0 +1,136 @@
+use petal_core::events::{EventBus, PlaybackState, PlayerEvent};
+use petal_core::listen::{Listen, SinkTarget};
+use petal_core::meta::Meta;
+use petal_core::settings::Settings;
+use petal_core::station::{Station, StreamFormat};
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// What a remote control or key asks of the player.
+#[derive(Debug, Clone, Copy)]
+pub enum Command {
+    Play,
+    Pause,
+    Toggle,
+    Stop,
+    /// Switch to the other station.
+    Next,
+    Previous,
+    Quit,
+}
+
+/// The stream and its track info, driven together.
+pub struct Player {
+    radio: Rc<Listen>,
+    meta: Rc<Meta>,
+    playback: Cell<PlaybackState>,
+}
+
+impl Player {
+    pub fn new(
+        station: Station,
+        format: Option<StreamFormat>,
+        settings: &Settings,
+        events: &EventBus,
+    ) -> Self {
+        let radio = Listen::new(
+            station,
+            format.unwrap_or(settings.stream_format),
+            settings.buffer_secs,
+            events.clone(),
+        );
+        radio.set_visualizer(false);
+        radio.set_proxy(settings.proxy.clone());
+        radio.set_timeouts(settings.timeouts);
+        radio.set_fade(settings.fade());
+        radio.set_equalizer(settings.equalizer);
+        radio.set_normalize(settings.normalize_loudness);
+        radio.set_timeshift(settings.timeshift_mins);
+        radio.set_audio_sink(settings.audio_sink.as_deref().and_then(SinkTarget::parse));
+        let meta = Meta::new(station, events.clone(), radio.lag_ms());
+        meta.set_proxy(settings.proxy.clone());
+        meta.set_timeouts(settings.meta_timeouts());
+        Self {
+            radio,
+            meta,
+            playback: Cell::new(PlaybackState::Stopped),
+        }
+    }
+
+    pub fn station(&self) -> Station {
+        self.radio.get_station()
+    }
+
+    pub fn playback(&self) -> PlaybackState {
+        self.playback.get()
+    }
+
+    pub fn play(&self) {
+        self.meta.start();
+        self.radio.start();
+    }
+
+    fn pause(&self) {
+        self.meta.pause();
+        self.radio.pause();
+    }
+
+    pub fn stop(&self) {
+        self.meta.stop();
+        self.radio.stop();
+    }
+
+    /// Stop and let the stream fade out before the process exits.
+    pub fn shutdown(&self) {
+        self.meta.stop();
+        self.radio.shutdown();
+    }
+
+    fn switch_station(&self) {
+        let station = match self.station() {
+            Station::Jpop => Station::Kpop,
+            Station::Kpop => Station::Jpop,
+        };
+        self.radio.set_station(station);
+        self.meta.set_station(station);
+    }
+
+    /// Carry out `command`; false once the player should quit.
+    pub fn apply(&self, command: Command) -> bool {
+        match command {
+            Command::Play => self.play(),
+            Command::Pause => self.pause(),
+            Command::Toggle => match self.playback() {
+                PlaybackState::Paused | PlaybackState::Stopped | PlaybackState::Error => {
+                    self.play()
+                }
+                _ => self.pause(),
+            },
+            Command::Stop => self.stop(),
+            Command::Next | Command::Previous => self.switch_station(),
+            Command::Quit => {
+                self.shutdown();
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Follow the player's own events: its state, and restarting after a sleep.
+    pub fn observe(&self, event: &PlayerEvent) {
+        match event {
+            PlayerEvent::StateChanged(state) => self.playback.set(*state),
+            // Both connections are likely dead after a sleep; start them over.
+            PlayerEvent::Resumed
+                if !matches!(
+                    self.playback(),
+                    PlaybackState::Paused | PlaybackState::Stopped
+                ) =>
+            {
+                self.stop();
+                self.play();
+            }
+            _ => {}
+        }
+    }
+}
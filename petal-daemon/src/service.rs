@@ -0,0 +1,89 @@
+//! Running as a systemd service: readiness and status notifications (`sd_notify`), and a
+//! clean stop on SIGTERM.
+
+use std::env;
+use std::ffi::OsStr;
+use std::io;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use petal_core::events::PlayerEvent;
+
+use crate::describe;
+
+static STOP: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn request_stop(_: libc::c_int) {
+    STOP.store(true, Ordering::Relaxed);
+}
+
+/// Turn SIGTERM and SIGINT into a stop request, so the stream is closed before exiting.
+pub fn handle_signals() {
+    let handler = request_stop as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe.
+    unsafe {
+        libc::signal(libc::SIGTERM, handler);
+        libc::signal(libc::SIGINT, handler);
+    }
+}
+
+pub fn stop_requested() -> bool {
+    STOP.load(Ordering::Relaxed)
+}
+
+/// Show the current track in `systemctl status`.
+pub fn observe(event: &PlayerEvent) {
+    if let PlayerEvent::TrackChanged(info) | PlayerEvent::TrackResynced(info) = event {
+        notify(&format!("STATUS={}", describe(info)));
+    }
+}
+
+/// Tell the service manager about a state change, e.g. `READY=1`; does nothing outside a
+/// `Type=notify` service.
+pub fn notify(state: &str) {
+    let Some(socket) = env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    if let Err(err) = send(&socket, state) {
+        eprintln!("Failed to notify the service manager: {err}");
+    }
+}
+
+/// Send `state` to the notification socket, which is a path or, starting with `@`, an
+/// abstract socket name.
+fn send(socket: &OsStr, state: &str) -> io::Result<()> {
+    let address = match socket.as_bytes().strip_prefix(b"@") {
+        Some(name) => SocketAddr::from_abstract_name(name)?,
+        None => SocketAddr::from_pathname(socket)?,
+    };
+    UnixDatagram::unbound()?.send_to_addr(state.as_bytes(), &address)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sends_notifications() {
+        let path = env::temp_dir().join(format!("petal-notify-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let manager = UnixDatagram::bind(&path).unwrap();
+        send(path.as_os_str(), "READY=1").unwrap();
+        let mut buf = [0; 64];
+        let len = manager.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"READY=1");
+        std::fs::remove_file(&path).unwrap();
+
+        let name = format!("@petal-notify-{}", std::process::id());
+        let manager = UnixDatagram::bind_addr(
+            &SocketAddr::from_abstract_name(&name.as_bytes()[1..]).unwrap(),
+        )
+        .unwrap();
+        send(OsStr::new(&name), "STOPPING=1").unwrap();
+        let len = manager.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"STOPPING=1");
+    }
+}
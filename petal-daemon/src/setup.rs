@@ -0,0 +1,89 @@
+//! Installs the daemon as a systemd user service, so playback starts at login, also on
+//! machines without a desktop.
+
+use dirs_next as dirs;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const UNIT_TEMPLATE: &str = include_str!("../../data/petal-daemon.service.in");
+pub const UNIT_NAME: &str = "petal-daemon.service";
+
+/// Write the user unit, starting this binary with `args`. Returns its path.
+pub fn install(args: &[String]) -> io::Result<PathBuf> {
+    let path = unit_path()?;
+    let contents = unit(&env::current_exe()?, args);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+/// Remove the user unit; `None` when it was not installed.
+pub fn uninstall() -> io::Result<Option<PathBuf>> {
+    let path = unit_path()?;
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(Some(path)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// `~/.config/systemd/user/petal-daemon.service`.
+fn unit_path() -> io::Result<PathBuf> {
+    let config = dirs::config_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no configuration directory"))?;
+    Ok(config.join("systemd").join("user").join(UNIT_NAME))
+}
+
+fn unit(exec: &Path, args: &[String]) -> String {
+    UNIT_TEMPLATE
+        .lines()
+        .map(|line| {
+            if line.starts_with("ExecStart=") {
+                let command: Vec<String> = std::iter::once(exec.to_string_lossy().into_owned())
+                    .chain(args.iter().cloned())
+                    .map(|word| quote(&word))
+                    .collect();
+                format!("ExecStart={}", command.join(" "))
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        + "\n"
+}
+
+/// Quote a word for a unit's command line, which expands `%` specifiers and `$` variables.
+fn quote(word: &str) -> String {
+    let word = word.replace('%', "%%").replace('$', "$$");
+    if !word.contains(|c: char| c.is_whitespace() || "\"'\\;".contains(c)) {
+        return word;
+    }
+    let mut quoted = String::from("\"");
+    for c in word.chars() {
+        if matches!(c, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_runs_this_binary() {
+        let args = ["--station".to_string(), "kpop".to_string()];
+        let unit = unit(Path::new("/home/me/My Apps/petal-daemon"), &args);
+        assert!(unit.contains("\nExecStart=\"/home/me/My Apps/petal-daemon\" --station kpop\n"));
+        assert!(unit.contains("\nType=notify\n"));
+        assert_eq!(quote("50%$"), "50%%$$");
+    }
+}
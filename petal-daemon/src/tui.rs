@@ -0,0 +1,174 @@
+use petal_core::events::{PlaybackState, PlayerEvent};
+use petal_core::meta::TrackInfo;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, List, ListItem, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::VecDeque;
+use std::io;
+use std::time::Duration;
+
+use crate::describe;
+use crate::player::{Command, Player};
+
+const HISTORY_LEN: usize = 50;
+
+const KEYS: &str = "space play/pause · s stop · tab station · q quit";
+
+/// The full-screen terminal interface; restores the terminal when dropped.
+pub struct Screen {
+    terminal: DefaultTerminal,
+    now: Option<Box<TrackInfo>>,
+    /// Earlier tracks, newest first.
+    history: VecDeque<Box<TrackInfo>>,
+    /// The latest failure, until the next track.
+    status: Option<String>,
+}
+
+impl Screen {
+    pub fn open() -> io::Result<Self> {
+        Ok(Self {
+            terminal: ratatui::try_init()?,
+            now: None,
+            history: VecDeque::new(),
+            status: None,
+        })
+    }
+
+    pub fn observe(&mut self, event: PlayerEvent) {
+        match event {
+            PlayerEvent::TrackChanged(info) => {
+                if let Some(previous) = self.now.replace(info) {
+                    self.history.push_front(previous);
+                    self.history.truncate(HISTORY_LEN);
+                }
+                self.status = None;
+            }
+            PlayerEvent::TrackResynced(info) => self.now = Some(info),
+            PlayerEvent::StationChanged(_) => {
+                self.now = None;
+                self.history.clear();
+            }
+            PlayerEvent::Error(message) => self.status = Some(message),
+            _ => {}
+        }
+    }
+
+    pub fn draw(&mut self, player: &Player) -> io::Result<()> {
+        self.terminal
+            .draw(|frame| {
+                render(
+                    frame,
+                    player,
+                    self.now.as_deref(),
+                    &self.history,
+                    &self.status,
+                )
+            })
+            .map(drop)
+    }
+
+    /// Wait up to `timeout` for a key press and map it to a command.
+    pub fn wait_for_key(&self, timeout: Duration) -> Option<Command> {
+        if !event::poll(timeout).ok()? {
+            return None;
+        }
+        let Event::Key(key) = event::read().ok()? else {
+            return None;
+        };
+        if key.kind != KeyEventKind::Press {
+            return None;
+        }
+        match key.code {
+            KeyCode::Char(' ') | KeyCode::Char('p') => Some(Command::Toggle),
+            KeyCode::Char('s') => Some(Command::Stop),
+            KeyCode::Tab | KeyCode::Right | KeyCode::Char('n') => Some(Command::Next),
+            KeyCode::BackTab | KeyCode::Left => Some(Command::Previous),
+            KeyCode::Char('q') | KeyCode::Esc => Some(Command::Quit),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Command::Quit)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Screen {
+    /// Give the terminal back, e.g. to print an error.
+    pub fn close(&mut self) {
+        ratatui::restore();
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+fn state_label(state: PlaybackState) -> &'static str {
+    match state {
+        PlaybackState::Stopped => "Stopped",
+        PlaybackState::Connecting => "Connecting…",
+        PlaybackState::Buffering => "Buffering…",
+        PlaybackState::Playing => "Playing",
+        PlaybackState::Paused => "Paused",
+        PlaybackState::Reconnecting => "Reconnecting…",
+        PlaybackState::Error => "Playback failed",
+    }
+}
+
+fn render(
+    frame: &mut Frame,
+    player: &Player,
+    now: Option<&TrackInfo>,
+    history: &VecDeque<Box<TrackInfo>>,
+    status: &Option<String>,
+) {
+    let [top, middle, bottom] = Layout::vertical([
+        Constraint::Length(6),
+        Constraint::Min(3),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let mut lines = match now {
+        Some(info) => {
+            let mut lines = vec![
+                Line::from(info.title.as_str()).bold(),
+                Line::from(info.artist.as_str()),
+            ];
+            if let Some(source) = info.source.as_deref() {
+                lines.push(Line::from(source).italic());
+            }
+            lines
+        }
+        None => vec![Line::from("Waiting for track info…").dim()],
+    };
+    lines.push(Line::default());
+    lines.push(match status {
+        Some(message) => Line::from(message.as_str()).red(),
+        None => Line::from(state_label(player.playback())).dim(),
+    });
+    let title = format!(" {} ", player.station().display_name());
+    frame.render_widget(
+        Paragraph::new(Text::from(lines)).block(Block::bordered().title(title)),
+        top,
+    );
+
+    let items: Vec<ListItem> = history
+        .iter()
+        .map(|info| ListItem::new(describe(info)))
+        .collect();
+    frame.render_widget(
+        List::new(items).block(Block::bordered().title(" History ")),
+        middle,
+    );
+
+    frame.render_widget(
+        Paragraph::new(KEYS).style(Style::default().add_modifier(Modifier::DIM)),
+        bottom,
+    );
+}
@@ -0,0 +1,252 @@
+//! "Export diagnostics": a zip with recent player events, the effective settings, a probe of
+//! every stream and gateway endpoint and version info, so "no audio" reports can be debugged
+//! remotely.
+
+mod zip;
+
+use reqwest::blocking::Client;
+use reqwest::Url;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::events::{GatewayState, PlaybackState, PlayerEvent};
+use crate::log::now_string;
+use crate::proxy;
+use crate::settings::{Settings, Timeouts};
+use crate::station::{Station, StreamFormat};
+use zip::ZipWriter;
+
+/// Event lines kept for the bundle.
+const MAX_LINES: usize = 300;
+
+/// Recent player events and the last reported connection states.
+#[derive(Debug, Default, Clone)]
+pub struct Diagnostics {
+    lines: VecDeque<String>,
+    playback: Option<PlaybackState>,
+    gateway: Option<GatewayState>,
+}
+
+impl Diagnostics {
+    pub fn record(&mut self, event: &PlayerEvent) {
+        let line = match event {
+            PlayerEvent::TrackChanged(info) => format!("track: {} - {}", info.artist, info.title),
+            PlayerEvent::TrackResynced(info) => {
+                format!("track again: {} - {}", info.artist, info.title)
+            }
+            PlayerEvent::StateChanged(state) => {
+                self.playback = Some(*state);
+                format!("playback: {state:?}")
+            }
+            PlayerEvent::GatewayStateChanged(state) => {
+                self.gateway = Some(*state);
+                format!("gateway: {state:?}")
+            }
+            PlayerEvent::StationChanged(station) => format!("station: {}", station.name()),
+            PlayerEvent::OutputLost => "output device lost".to_string(),
+            PlayerEvent::Silence => "dead air, reconnecting".to_string(),
+            PlayerEvent::BufferLow => "buffer low".to_string(),
+            PlayerEvent::Resumed => "resumed from sleep".to_string(),
+            PlayerEvent::Error(message) => format!("error: {message}"),
+        };
+        if self.lines.len() == MAX_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(format!("[{}] {line}", now_string()));
+    }
+
+    /// Probe the endpoints, write the bundle into `dir` and return its path. `toolkit`
+    /// describes the UI libraries in use. This blocks for up to the connect timeout per
+    /// endpoint.
+    pub fn export(
+        &self,
+        dir: &Path,
+        settings: &Settings,
+        station: Station,
+        toolkit: &str,
+    ) -> io::Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let stamp = now_string().replace([':', '.'], "");
+        let path = dir.join(format!("listenmoe-diagnostics-{stamp}.zip"));
+
+        let mut zip = ZipWriter::new(BufWriter::new(File::create(&path)?));
+        zip.add("player-events.log", self.events_log().as_bytes())?;
+        let settings_json = serde_json::to_string_pretty(&redacted(settings))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        zip.add("settings.json", settings_json.as_bytes())?;
+        zip.add(
+            "connection.txt",
+            self.connection_report(settings, station).as_bytes(),
+        )?;
+        zip.add("version.txt", version_report(toolkit).as_bytes())?;
+        zip.finish()?;
+        Ok(path)
+    }
+
+    fn events_log(&self) -> String {
+        let mut log =
+            "# Player events while the app ran; console output is not included.\n".to_string();
+        for line in &self.lines {
+            log.push_str(line);
+            log.push('\n');
+        }
+        log
+    }
+
+    fn connection_report(&self, settings: &Settings, station: Station) -> String {
+        let mut report = format!(
+            "station: {}\npreferred format: {}\nplayback: {}\ngateway: {}\n\nstream endpoints (GET):\n",
+            station.name(),
+            settings.stream_format.name(),
+            self.playback
+                .map_or("unknown".to_string(), |s| format!("{s:?}")),
+            self.gateway
+                .map_or("unknown".to_string(), |s| format!("{s:?}")),
+        );
+        let timeouts = settings.timeouts;
+        let client = Client::builder()
+            .pool_max_idle_per_host(0)
+            .connect_timeout(timeouts.connect())
+            .timeout(timeouts.read());
+        let client = proxy::apply(client, settings.proxy.as_deref()).and_then(|c| c.build());
+        for format in StreamFormat::ALL {
+            let url = station.stream_url(format);
+            let probe = match &client {
+                Ok(client) => probe_stream(client, url),
+                Err(err) => format!("not probed: {err}"),
+            };
+            report.push_str(&format!(
+                "  {} {url}{}: {probe}\n",
+                format.name(),
+                if format.is_decodable() {
+                    ""
+                } else {
+                    " (not playable)"
+                }
+            ));
+        }
+        let ws_proxy = Url::parse(station.ws_url())
+            .ok()
+            .and_then(|url| proxy::for_url(settings.proxy.as_deref(), &url));
+        report.push_str(&format!(
+            "\ngateway: {}\nwebsocket proxy: {}\ngateway connect: {}\n",
+            station.ws_url(),
+            if ws_proxy.is_some() { "yes" } else { "no" },
+            probe_gateway(station.ws_url(), ws_proxy.as_deref(), timeouts),
+        ));
+        report
+    }
+}
+
+/// The response status of a stream endpoint and how long its headers took.
+fn probe_stream(client: &Client, url: &str) -> String {
+    let started = Instant::now();
+    // Dropping the response closes the stream once the headers are in.
+    match client.get(url).send() {
+        Ok(response) => format!(
+            "HTTP {} in {} ms",
+            response.status().as_u16(),
+            started.elapsed().as_millis()
+        ),
+        Err(err) => format!("failed after {} ms: {err}", started.elapsed().as_millis()),
+    }
+}
+
+/// How long a TCP connection to the gateway takes, through `proxy` if one applies.
+fn probe_gateway(url: &str, proxy: Option<&str>, timeouts: Timeouts) -> String {
+    let Some((host, port)) = Url::parse(url)
+        .ok()
+        .and_then(|url| Some((url.host_str()?.to_string(), url.port_or_known_default()?)))
+    else {
+        return "not probed: bad URL".to_string();
+    };
+    let started = Instant::now();
+    let result = match proxy {
+        Some(proxy) => proxy::tunnel(proxy, &host, port, timeouts),
+        None => proxy::connect_tcp(&host, port, timeouts),
+    };
+    match result {
+        Ok(_) => format!("connected in {} ms", started.elapsed().as_millis()),
+        Err(err) => format!("failed after {} ms: {err}", started.elapsed().as_millis()),
+    }
+}
+
+/// Settings with the guest PIN, remote token, track hook and proxy and MQTT credentials
+/// taken out.
+fn redacted(settings: &Settings) -> Settings {
+    let mut settings = settings.clone();
+    if settings.guest_pin.is_some() {
+        settings.guest_pin = Some("<redacted>".to_string());
+    }
+    if settings.remote_token.is_some() {
+        settings.remote_token = Some("<redacted>".to_string());
+    }
+    // Webhook URLs and commands often carry tokens.
+    if settings.track_hook.is_some() {
+        settings.track_hook = Some("<redacted>".to_string());
+    }
+    if let Some(mqtt) = settings
+        .mqtt
+        .as_mut()
+        .filter(|mqtt| mqtt.password.is_some())
+    {
+        mqtt.password = Some("<redacted>".to_string());
+    }
+    settings.proxy = settings.proxy.map(|proxy| match Url::parse(&proxy) {
+        Ok(mut url) if !url.username().is_empty() || url.password().is_some() => {
+            let _ = url.set_username("redacted");
+            let _ = url.set_password(None);
+            url.to_string()
+        }
+        Ok(_) => proxy,
+        Err(_) => "<unparsable>".to_string(),
+    });
+    settings
+}
+
+fn version_report(toolkit: &str) -> String {
+    format!(
+        "{} {}\nos: {} ({})\n{toolkit}\n",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn probes_report_status_and_failures() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            stream
+                .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+        });
+        let client = Client::builder().build().unwrap();
+        let probe = probe_stream(&client, &format!("http://{addr}/stream"));
+        assert!(probe.starts_with("HTTP 404 in "), "{probe}");
+        server.join().unwrap();
+
+        let timeouts = Settings::default().timeouts;
+        let probe = probe_gateway(&format!("wss://{addr}/gateway"), None, timeouts);
+        assert!(probe.starts_with("failed after "), "{probe}");
+        assert_eq!(
+            probe_gateway("not a url", None, timeouts),
+            "not probed: bad URL"
+        );
+    }
+}
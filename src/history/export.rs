@@ -0,0 +1,150 @@
+//! CSV and JSON dumps of the play history, for spreadsheets or other tools.
+
+use serde::Serialize;
+use std::time::UNIX_EPOCH;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use super::Play;
+
+const CSV_HEADER: &str = "started_at,station,artist,title,duration_secs,listened_secs";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+impl Format {
+    pub const ALL: [Format; 2] = [Format::Csv, Format::Json];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name() == name)
+    }
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    started_at: String,
+    station: &'a str,
+    artist: &'a str,
+    title: &'a str,
+    duration_secs: u32,
+    listened_secs: u32,
+}
+
+impl<'a> Record<'a> {
+    fn new(play: &'a Play) -> Self {
+        Self {
+            started_at: rfc3339(play),
+            station: &play.station,
+            artist: &play.artist,
+            title: &play.title,
+            duration_secs: play.duration_secs,
+            listened_secs: play.listened_secs,
+        }
+    }
+}
+
+pub fn render(format: Format, plays: &[Play]) -> String {
+    match format {
+        Format::Csv => to_csv(plays),
+        Format::Json => to_json(plays),
+    }
+}
+
+/// RFC 4180 CSV with a header row and CRLF line ends.
+fn to_csv(plays: &[Play]) -> String {
+    let mut out = String::from(CSV_HEADER);
+    out.push_str("\r\n");
+    for play in plays {
+        let record = Record::new(play);
+        let fields = [
+            record.started_at.as_str(),
+            record.station,
+            record.artist,
+            record.title,
+        ];
+        for field in fields {
+            out.push_str(&csv_field(field));
+            out.push(',');
+        }
+        out.push_str(&format!(
+            "{},{}\r\n",
+            record.duration_secs, record.listened_secs
+        ));
+    }
+    out
+}
+
+fn to_json(plays: &[Play]) -> String {
+    let records: Vec<Record> = plays.iter().map(Record::new).collect();
+    // Plain strings and numbers always serialize.
+    serde_json::to_string_pretty(&records).unwrap_or_default()
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn rfc3339(play: &Play) -> String {
+    let secs = play
+        .started_at
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    OffsetDateTime::from_unix_timestamp(secs)
+        .ok()
+        .and_then(|t| t.format(&Rfc3339).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn play(artist: &str, title: &str) -> Play {
+        Play {
+            artist: artist.into(),
+            title: title.into(),
+            station: "jpop".into(),
+            started_at: UNIX_EPOCH + Duration::from_secs(1_735_732_800),
+            duration_secs: 255,
+            listened_secs: 120,
+        }
+    }
+
+    #[test]
+    fn csv_quotes_when_needed() {
+        let csv = to_csv(&[
+            play("Kana Hanazawa", "Renai Circulation"),
+            play("A, B", "Say \"hi\""),
+        ]);
+        assert_eq!(
+            csv,
+            "started_at,station,artist,title,duration_secs,listened_secs\r\n\
+             2025-01-01T12:00:00Z,jpop,Kana Hanazawa,Renai Circulation,255,120\r\n\
+             2025-01-01T12:00:00Z,jpop,\"A, B\",\"Say \"\"hi\"\"\",255,120\r\n"
+        );
+    }
+
+    #[test]
+    fn json_lists_records() {
+        let json: serde_json::Value =
+            serde_json::from_str(&to_json(&[play("Kana Hanazawa", "Renai Circulation")])).unwrap();
+        assert_eq!(json[0]["started_at"], "2025-01-01T12:00:00Z");
+        assert_eq!(json[0]["artist"], "Kana Hanazawa");
+        assert_eq!(json[0]["listened_secs"], 120);
+    }
+}
@@ -0,0 +1,143 @@
+//! Songs hearted in the app, stored next to the play history. They are keyed by LISTEN.moe
+//! song id, so they can be merged with the favorites of an account.
+
+use rusqlite::params;
+use std::collections::HashSet;
+use std::time::SystemTime;
+
+use super::{log_err, unix_secs, PlayHistory};
+
+pub(super) const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS favorites (
+    song_id INTEGER PRIMARY KEY,
+    artist TEXT NOT NULL,
+    title TEXT NOT NULL,
+    added_at INTEGER NOT NULL
+);
+";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Favorite {
+    pub song_id: u32,
+    pub artist: String,
+    pub title: String,
+}
+
+/// What merging local favorites with an account's takes.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Merge {
+    /// Local favorites the account does not have yet.
+    pub upload: Vec<u32>,
+    /// Account favorites missing locally.
+    pub download: Vec<Favorite>,
+}
+
+/// Favorites on either side end up on both; the same song id counts once.
+pub fn merge(local: &[Favorite], remote: &[Favorite]) -> Merge {
+    let local_ids: HashSet<u32> = local.iter().map(|f| f.song_id).collect();
+    let remote_ids: HashSet<u32> = remote.iter().map(|f| f.song_id).collect();
+
+    let mut uploaded = HashSet::new();
+    let upload = local
+        .iter()
+        .map(|f| f.song_id)
+        .filter(|id| !remote_ids.contains(id) && uploaded.insert(*id))
+        .collect();
+    let mut downloaded = HashSet::new();
+    let download = remote
+        .iter()
+        .filter(|f| !local_ids.contains(&f.song_id) && downloaded.insert(f.song_id))
+        .cloned()
+        .collect();
+    Merge { upload, download }
+}
+
+impl PlayHistory {
+    /// All favorites, most recently added first.
+    pub fn favorites(&self) -> Vec<Favorite> {
+        let result = self
+            .db
+            .prepare_cached(
+                "SELECT song_id, artist, title FROM favorites ORDER BY added_at DESC, song_id",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| {
+                    Ok(Favorite {
+                        song_id: row.get(0)?,
+                        artist: row.get(1)?,
+                        title: row.get(2)?,
+                    })
+                })?
+                .collect()
+            });
+        log_err(result).unwrap_or_default()
+    }
+
+    pub fn is_favorite(&self, song_id: u32) -> bool {
+        let result = self.db.query_row(
+            "SELECT EXISTS (SELECT 1 FROM favorites WHERE song_id = ?1)",
+            params![song_id],
+            |row| row.get(0),
+        );
+        log_err(result).unwrap_or(false)
+    }
+
+    /// Heart or unheart a song. Hearting one that already is keeps its original date.
+    pub fn set_favorite(&self, favorite: &Favorite, on: bool) {
+        let result = if on {
+            self.db.execute(
+                "INSERT OR IGNORE INTO favorites (song_id, artist, title, added_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    favorite.song_id,
+                    favorite.artist,
+                    favorite.title,
+                    unix_secs(SystemTime::now()),
+                ],
+            )
+        } else {
+            self.db.execute(
+                "DELETE FROM favorites WHERE song_id = ?1",
+                params![favorite.song_id],
+            )
+        };
+        log_err(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::tests::history;
+
+    fn fav(song_id: u32) -> Favorite {
+        Favorite {
+            song_id,
+            artist: format!("Artist {song_id}"),
+            title: format!("Song {song_id}"),
+        }
+    }
+
+    #[test]
+    fn merge_resolves_duplicates_by_song_id() {
+        let local = [fav(1), fav(2), fav(2)];
+        let remote = [fav(2), fav(3), fav(3)];
+        let merge = merge(&local, &remote);
+        assert_eq!(merge.upload, [1]);
+        assert_eq!(merge.download, [fav(3)]);
+    }
+
+    #[test]
+    fn favorites_round_trip() {
+        let history = history();
+        history.set_favorite(&fav(7), true);
+        history.set_favorite(&fav(7), true);
+        history.set_favorite(&fav(8), true);
+        assert!(history.is_favorite(7));
+        assert_eq!(history.favorites().len(), 2);
+
+        history.set_favorite(&fav(7), false);
+        assert!(!history.is_favorite(7));
+        assert_eq!(history.favorites(), [fav(8)]);
+    }
+}
@@ -0,0 +1,327 @@
+//! Play history kept in an SQLite database in the per-user data directory, so search and
+//! statistics cover earlier sessions too.
+//!
+//! A track is only recorded once audio for it is actually playing; the time spent listening
+//! is added up across pauses and written when the track ends.
+
+pub mod export;
+pub mod favorites;
+pub mod stats;
+
+use dirs_next as dirs;
+use rusqlite::{params, Connection};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::meta::TrackInfo;
+use crate::station::Station;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS plays (
+    id INTEGER PRIMARY KEY,
+    artist TEXT NOT NULL,
+    title TEXT NOT NULL,
+    station TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    duration_secs INTEGER NOT NULL,
+    listened_secs INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS plays_started_at ON plays (started_at);
+";
+
+/// One row of the history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Play {
+    pub artist: String,
+    pub title: String,
+    /// `Station::name` at the time; kept as text so old rows survive station changes.
+    pub station: String,
+    pub started_at: SystemTime,
+    /// Length of the track as announced by the gateway, 0 when unknown.
+    pub duration_secs: u32,
+    pub listened_secs: u32,
+}
+
+/// The track the gateway announced last and how long it has been heard.
+struct Current {
+    info: TrackInfo,
+    station: Station,
+    /// Row of the track, once playback of it started.
+    row: Option<i64>,
+    listened: Duration,
+    playing_since: Option<Instant>,
+}
+
+pub struct PlayHistory {
+    db: Connection,
+    current: Option<Current>,
+}
+
+impl PlayHistory {
+    /// Open (creating) the history database. Without a data directory, or when the file
+    /// cannot be opened, the history lives in memory for this session.
+    pub fn open() -> Self {
+        let opened = match dirs::data_dir() {
+            Some(dir) => {
+                let path = dir.join(crate::APP_ID).join("history.sqlite3");
+                Self::open_at(&path)
+                    .map_err(|err| {
+                        eprintln!("Failed to open play history {}: {err}", path.display())
+                    })
+                    .ok()
+            }
+            None => None,
+        };
+        opened.unwrap_or_else(|| {
+            Self::with_connection(
+                Connection::open_in_memory().expect("in-memory database without allocation"),
+            )
+            .expect("history schema is valid")
+        })
+    }
+
+    pub fn open_at(path: &Path) -> rusqlite::Result<Self> {
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    fn with_connection(db: Connection) -> rusqlite::Result<Self> {
+        db.execute_batch(SCHEMA)?;
+        db.execute_batch(favorites::SCHEMA)?;
+        Ok(Self { db, current: None })
+    }
+
+    /// A new track was announced on `station`; `playing` tells whether audio is running.
+    /// The track already current, sent again e.g. after a resume, stays one play.
+    pub fn track_changed(&mut self, info: &TrackInfo, station: Station, playing: bool) {
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|current| current.station == station && current.info.same_play(info))
+        {
+            if playing {
+                self.set_playing(true);
+            }
+            return;
+        }
+        self.finish();
+        self.current = Some(Current {
+            info: info.clone(),
+            station,
+            row: None,
+            listened: Duration::ZERO,
+            playing_since: None,
+        });
+        if playing {
+            self.set_playing(true);
+        }
+    }
+
+    /// Audio started or stopped for the current track.
+    pub fn set_playing(&mut self, playing: bool) {
+        let Some(current) = self.current.as_mut() else {
+            return;
+        };
+        match (playing, current.playing_since) {
+            (true, None) => {
+                current.playing_since = Some(Instant::now());
+                if current.row.is_none() {
+                    current.row = log_err(insert(&self.db, &current.info, current.station));
+                }
+            }
+            (false, Some(since)) => {
+                current.listened += since.elapsed();
+                current.playing_since = None;
+                if let Some(row) = current.row {
+                    log_err(update_listened(&self.db, row, current.listened));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Store the listening time of the current track; call before quitting.
+    pub fn finish(&mut self) {
+        self.set_playing(false);
+        self.current = None;
+    }
+
+    /// Most recent plays first, at most `limit`.
+    pub fn recent(&self, limit: usize) -> Vec<Play> {
+        self.query(
+            "SELECT artist, title, station, started_at, duration_secs, listened_secs
+             FROM plays ORDER BY started_at DESC, id DESC LIMIT ?1",
+            params![limit as i64],
+        )
+    }
+
+    /// The whole history, oldest first.
+    pub fn all(&self) -> Vec<Play> {
+        self.query(
+            "SELECT artist, title, station, started_at, duration_secs, listened_secs
+             FROM plays ORDER BY started_at, id",
+            [],
+        )
+    }
+
+    /// Distinct tracks whose artist or title contains `needle` (case-insensitive for
+    /// ASCII), most recently played first.
+    pub fn search(&self, needle: &str, limit: usize) -> Vec<(String, String)> {
+        let pattern = format!(
+            "%{}%",
+            needle
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        let result = self
+            .db
+            .prepare_cached(
+                "SELECT artist, title FROM plays
+                 WHERE artist LIKE ?1 ESCAPE '\\' OR title LIKE ?1 ESCAPE '\\'
+                 GROUP BY artist, title ORDER BY MAX(started_at) DESC LIMIT ?2",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(params![pattern, limit as i64], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })?
+                .collect()
+            });
+        log_err(result).unwrap_or_default()
+    }
+
+    fn query(&self, sql: &str, params: impl rusqlite::Params) -> Vec<Play> {
+        let result = self.db.prepare_cached(sql).and_then(|mut stmt| {
+            stmt.query_map(params, |row| {
+                Ok(Play {
+                    artist: row.get(0)?,
+                    title: row.get(1)?,
+                    station: row.get(2)?,
+                    started_at: from_unix_secs(row.get(3)?),
+                    duration_secs: row.get(4)?,
+                    listened_secs: row.get(5)?,
+                })
+            })?
+            .collect()
+        });
+        log_err(result).unwrap_or_default()
+    }
+}
+
+impl Drop for PlayHistory {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+fn insert(db: &Connection, info: &TrackInfo, station: Station) -> rusqlite::Result<i64> {
+    db.execute(
+        "INSERT INTO plays (artist, title, station, started_at, duration_secs)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            info.artist,
+            info.title,
+            station.name(),
+            unix_secs(info.start_time_utc),
+            info.duration_secs,
+        ],
+    )?;
+    Ok(db.last_insert_rowid())
+}
+
+fn update_listened(db: &Connection, row: i64, listened: Duration) -> rusqlite::Result<()> {
+    db.execute(
+        "UPDATE plays SET listened_secs = ?1 WHERE id = ?2",
+        params![listened.as_secs() as i64, row],
+    )?;
+    Ok(())
+}
+
+fn unix_secs(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+fn from_unix_secs(secs: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64)
+}
+
+fn log_err<T>(result: rusqlite::Result<T>) -> Option<T> {
+    result
+        .map_err(|err| eprintln!("Play history error: {err}"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(super) fn track(artist: &str, title: &str, start_secs: u64) -> TrackInfo {
+        TrackInfo {
+            song_id: None,
+            artist: artist.into(),
+            title: title.into(),
+            album_cover: None,
+            artist_image: None,
+            start_time_utc: UNIX_EPOCH + Duration::from_secs(start_secs),
+            duration_secs: 200,
+            source: None,
+            requester: None,
+            event: None,
+            listeners: None,
+        }
+    }
+
+    pub(super) fn history() -> PlayHistory {
+        PlayHistory::with_connection(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn records_only_tracks_that_played() {
+        let mut history = history();
+        history.track_changed(&track("A", "Stopped", 100), Station::Jpop, false);
+        history.track_changed(&track("B", "Heard", 200), Station::Kpop, false);
+        history.set_playing(true);
+        history.track_changed(&track("C", "Playing", 300), Station::Kpop, true);
+        history.finish();
+
+        let titles: Vec<_> = history.recent(10).into_iter().map(|p| p.title).collect();
+        assert_eq!(titles, ["Playing", "Heard"]);
+        assert_eq!(history.recent(10)[1].station, "kpop");
+    }
+
+    #[test]
+    fn same_track_again_is_one_play() {
+        let mut history = history();
+        history.track_changed(&track("A", "Song", 100), Station::Jpop, true);
+        history.set_playing(false);
+        history.track_changed(&track("A", "Song", 100), Station::Jpop, true);
+        history.finish();
+
+        assert_eq!(history.recent(10).len(), 1);
+    }
+
+    #[test]
+    fn search_is_distinct_and_escapes_wildcards() {
+        let mut history = history();
+        for (i, title) in ["Song", "100% Song", "Other", "Song"]
+            .into_iter()
+            .enumerate()
+        {
+            history.track_changed(&track("Artist", title, i as u64), Station::Jpop, true);
+        }
+        history.finish();
+
+        assert_eq!(
+            history.search("song", 10),
+            [
+                ("Artist".to_string(), "Song".to_string()),
+                ("Artist".to_string(), "100% Song".to_string())
+            ]
+        );
+        assert_eq!(history.search("%", 10).len(), 1);
+    }
+}
@@ -0,0 +1,114 @@
+//! Listening statistics over a time range of the history.
+
+use rusqlite::params;
+use std::time::SystemTime;
+
+use super::{log_err, unix_secs, PlayHistory};
+
+/// Totals for a time range. Rankings are by play count, then listening time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub plays: u32,
+    pub listened_secs: u64,
+    /// `Station::name` with its listening time, most listened first.
+    pub stations: Vec<(String, u64)>,
+    /// Artist with play count.
+    pub top_artists: Vec<(String, u32)>,
+    /// Artist and title with play count.
+    pub top_tracks: Vec<(String, String, u32)>,
+}
+
+impl PlayHistory {
+    /// Statistics for plays started at or after `since` (all of them for `None`), with at
+    /// most `top` entries per ranking.
+    pub fn stats(&self, since: Option<SystemTime>, top: usize) -> Stats {
+        let since = since.map_or(0, unix_secs);
+        let top = top as i64;
+        let totals = self.db.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(listened_secs), 0) FROM plays WHERE started_at >= ?1",
+            params![since],
+            |row| Ok((row.get::<_, u32>(0)?, row.get::<_, i64>(1)?)),
+        );
+        let (plays, listened_secs) = log_err(totals).unwrap_or_default();
+
+        let stations = self.ranking(
+            "SELECT station, SUM(listened_secs) AS secs FROM plays WHERE started_at >= ?1
+             GROUP BY station ORDER BY secs DESC LIMIT ?2",
+            since,
+            top,
+            |row| Ok((row.get(0)?, row.get::<_, i64>(1)?.max(0) as u64)),
+        );
+        let top_artists = self.ranking(
+            "SELECT artist, COUNT(*) AS n FROM plays WHERE started_at >= ?1
+             GROUP BY artist ORDER BY n DESC, SUM(listened_secs) DESC, artist LIMIT ?2",
+            since,
+            top,
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        );
+        let top_tracks = self.ranking(
+            "SELECT artist, title, COUNT(*) AS n FROM plays WHERE started_at >= ?1
+             GROUP BY artist, title ORDER BY n DESC, SUM(listened_secs) DESC, title LIMIT ?2",
+            since,
+            top,
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        );
+
+        Stats {
+            plays,
+            listened_secs: listened_secs.max(0) as u64,
+            stations,
+            top_artists,
+            top_tracks,
+        }
+    }
+
+    fn ranking<T>(
+        &self,
+        sql: &str,
+        since: i64,
+        top: i64,
+        map: impl FnMut(&rusqlite::Row) -> rusqlite::Result<T>,
+    ) -> Vec<T> {
+        let result = self
+            .db
+            .prepare_cached(sql)
+            .and_then(|mut stmt| stmt.query_map(params![since, top], map)?.collect());
+        log_err(result).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::history::tests::{history, track};
+    use crate::station::Station;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn ranks_and_filters_by_time() {
+        let mut history = history();
+        let plays = [
+            ("A", "One", 100, Station::Jpop),
+            ("A", "Two", 200, Station::Jpop),
+            ("B", "Three", 300, Station::Kpop),
+            ("A", "One", 400, Station::Kpop),
+        ];
+        for (artist, title, start, station) in plays {
+            history.track_changed(&track(artist, title, start), station, true);
+        }
+        history.finish();
+
+        let all = history.stats(None, 5);
+        assert_eq!(all.plays, 4);
+        assert_eq!(
+            all.top_artists,
+            [("A".to_string(), 3), ("B".to_string(), 1)]
+        );
+        assert_eq!(all.top_tracks[0], ("A".to_string(), "One".to_string(), 2));
+        assert_eq!(all.stations.len(), 2);
+
+        let recent = history.stats(Some(UNIX_EPOCH + Duration::from_secs(300)), 1);
+        assert_eq!(recent.plays, 2);
+        assert_eq!(recent.top_artists.len(), 1);
+        assert_eq!(recent.stations, [("kpop".to_string(), 0)]);
+    }
+}
@@ -0,0 +1,217 @@
+//! A user-configured action on every track change: either a shell command, which gets the
+//! artist, title and station in its environment, or a webhook, which gets them as a JSON
+//! POST.
+//!
+//! Hooks run on a worker thread fed by the player's events; failures are logged and
+//! otherwise ignored.
+
+use reqwest::blocking::Client;
+use reqwest::Url;
+use serde::Serialize;
+use std::error::Error;
+use std::process::Command;
+use std::sync::mpsc;
+use std::thread;
+
+use crate::events::PlayerEvent;
+use crate::meta::TrackInfo;
+use crate::settings::{Settings, Timeouts};
+use crate::station::Station;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Hook {
+    /// Run through the shell with the artist, title and station in `LISTENMOE_ARTIST`,
+    /// `LISTENMOE_TITLE` and `LISTENMOE_STATION`, and outside Windows also as `$1`, `$2`
+    /// and `$3`.
+    Command(String),
+    Webhook(Url),
+}
+
+impl Hook {
+    /// An `http(s)://` URL is a webhook, anything else a command. `None` when blank.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        match Url::parse(text) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => Some(Hook::Webhook(url)),
+            _ => Some(Hook::Command(text.to_string())),
+        }
+    }
+
+    /// Run the hook for `info` on a worker thread.
+    pub fn fire(
+        &self,
+        info: &TrackInfo,
+        station: Station,
+        proxy: Option<String>,
+        timeouts: Timeouts,
+    ) {
+        let hook = self.clone();
+        let payload = Payload::new(info, station);
+        thread::spawn(move || {
+            let result = match &hook {
+                Hook::Command(command) => run_command(command, &payload),
+                Hook::Webhook(url) => post(url, &payload, proxy.as_deref(), timeouts),
+            };
+            if let Err(err) = result {
+                eprintln!("Track change hook failed: {err}");
+            }
+        });
+    }
+}
+
+/// Run the configured hook for every new track in `events`, until the player goes away.
+/// Settings are read again for each track, so a changed hook applies right away.
+pub fn spawn(events: mpsc::Receiver<PlayerEvent>, mut station: Station) {
+    thread::spawn(move || {
+        let mut last: Option<Box<TrackInfo>> = None;
+        for event in events {
+            match event {
+                PlayerEvent::StationChanged(new) => station = new,
+                PlayerEvent::TrackChanged(info) => {
+                    if last.as_ref().is_some_and(|last| last.same_play(&info)) {
+                        continue;
+                    }
+                    last = Some(info.clone());
+                    let settings = Settings::load();
+                    if let Some(hook) = settings.track_hook.as_deref().and_then(Hook::parse) {
+                        hook.fire(&info, station, settings.proxy, settings.timeouts);
+                    }
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+/// What a hook learns about the new track.
+#[derive(Debug, Serialize)]
+struct Payload {
+    artist: String,
+    title: String,
+    station: String,
+    source: Option<String>,
+    requester: Option<String>,
+    cover: Option<String>,
+    duration_secs: u32,
+}
+
+impl Payload {
+    fn new(info: &TrackInfo, station: Station) -> Self {
+        Self {
+            artist: info.artist.clone(),
+            title: info.title.clone(),
+            station: station.display_name().to_string(),
+            source: info.source.clone(),
+            requester: info.requester.clone(),
+            cover: info
+                .album_cover
+                .clone()
+                .or_else(|| info.artist_image.clone()),
+            duration_secs: info.duration_secs,
+        }
+    }
+}
+
+fn shell(command: &str) -> Command {
+    #[cfg(windows)]
+    {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    }
+    #[cfg(not(windows))]
+    {
+        let mut shell = Command::new("sh");
+        // The first argument after the script becomes `$0`.
+        shell.args(["-c", command, env!("CARGO_PKG_NAME")]);
+        shell
+    }
+}
+
+fn run_command(command: &str, payload: &Payload) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut shell = shell(command);
+    // cmd parses its whole command line again, so quotes or `&` in a title would run as
+    // commands; there the values only go into the environment.
+    #[cfg(not(windows))]
+    shell.args([&payload.artist, &payload.title, &payload.station]);
+    let status = shell
+        .env("LISTENMOE_ARTIST", &payload.artist)
+        .env("LISTENMOE_TITLE", &payload.title)
+        .env("LISTENMOE_STATION", &payload.station)
+        .status()?;
+    if !status.success() {
+        return Err(format!("`{command}` exited with {status}").into());
+    }
+    Ok(())
+}
+
+fn post(
+    url: &Url,
+    payload: &Payload,
+    proxy: Option<&str>,
+    timeouts: Timeouts,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let builder = Client::builder()
+        .connect_timeout(timeouts.connect())
+        .timeout(timeouts.read())
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            " ",
+            env!("CARGO_PKG_VERSION"),
+            " (",
+            env!("CARGO_PKG_REPOSITORY"),
+            ")"
+        ));
+    let client = crate::proxy::apply(builder, proxy)?.build()?;
+    let resp = client
+        .post(url.clone())
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(payload)?)
+        .send()?;
+    if !resp.status().is_success() {
+        return Err(format!("Non-success status: {}", resp.status()).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_are_webhooks() {
+        assert!(matches!(
+            Hook::parse(" https://home.local/hook "),
+            Some(Hook::Webhook(url)) if url.as_str() == "https://home.local/hook"
+        ));
+        assert_eq!(
+            Hook::parse("notify-send \"$2\""),
+            Some(Hook::Command("notify-send \"$2\"".into()))
+        );
+        assert_eq!(
+            Hook::parse("file:///tmp/x"),
+            Some(Hook::Command("file:///tmp/x".into()))
+        );
+        assert_eq!(Hook::parse("   "), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_gets_positional_arguments() {
+        let payload = Payload {
+            artist: "A".into(),
+            title: "T".into(),
+            station: "S".into(),
+            source: None,
+            requester: None,
+            cover: None,
+            duration_secs: 0,
+        };
+        assert!(run_command(r#"test "$1|$2|$3" = "A|T|S""#, &payload).is_ok());
+        assert!(run_command(r#"test "$LISTENMOE_TITLE" = T"#, &payload).is_ok());
+        assert!(run_command("exit 3", &payload).is_err());
+    }
+}
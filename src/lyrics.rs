@@ -0,0 +1,243 @@
+//! Song lyrics from LRCLIB (<https://lrclib.net>), a free database without API keys.
+//!
+//! Responses are kept in the disk cache, including misses, so a track is looked up once.
+
+use reqwest::blocking::Client;
+use reqwest::Url;
+use serde::Deserialize;
+use std::error::Error;
+use std::time::Duration;
+
+use crate::cache::DiskCache;
+use crate::settings::Timeouts;
+
+const API_URL: &str = "https://lrclib.net/api/get";
+/// Upper bound for the on-disk lyrics cache; entries are a few kilobytes.
+const LYRICS_CACHE_MAX_BYTES: u64 = 4 * 1024 * 1024;
+
+/// One line of synced lyrics and when it starts, relative to the start of the track.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LyricLine {
+    pub at: Duration,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lyrics {
+    /// Timed lines, in order.
+    Synced(Vec<LyricLine>),
+    Plain(String),
+    Instrumental,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Record {
+    #[serde(default)]
+    instrumental: bool,
+    plain_lyrics: Option<String>,
+    synced_lyrics: Option<String>,
+}
+
+/// Look up the lyrics of a track, from the cache or LRCLIB. `Ok(None)` when there are none.
+/// Blocking; call from a worker thread.
+pub fn load_blocking(
+    artist: &str,
+    title: &str,
+    duration_secs: u32,
+    proxy: Option<&str>,
+    timeouts: Timeouts,
+) -> Result<Option<Lyrics>, Box<dyn Error + Send + Sync>> {
+    let mut params = vec![
+        ("artist_name", artist.to_string()),
+        ("track_name", title.to_string()),
+    ];
+    // LRCLIB matches the duration within a few seconds; without one it takes any.
+    if duration_secs > 0 {
+        params.push(("duration", duration_secs.to_string()));
+    }
+    let url = Url::parse_with_params(API_URL, &params)?;
+
+    let cache = DiskCache::open("lyrics", LYRICS_CACHE_MAX_BYTES);
+    let body = match cache.as_ref().and_then(|c| c.get(url.as_str())) {
+        Some(body) => body,
+        None => {
+            let body = fetch_blocking(&url, proxy, timeouts)?;
+            if let Some(cache) = cache {
+                if let Err(err) = cache.put(url.as_str(), &body) {
+                    eprintln!("Failed to cache lyrics for {artist} - {title}: {err}");
+                }
+            }
+            body
+        }
+    };
+    // An empty entry records a miss.
+    if body.is_empty() {
+        return Ok(None);
+    }
+    Ok(parse_record(&serde_json::from_slice(&body)?))
+}
+
+/// The response body, or an empty one when LRCLIB does not know the track.
+fn fetch_blocking(
+    url: &Url,
+    proxy: Option<&str>,
+    timeouts: Timeouts,
+) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let builder = Client::builder()
+        .connect_timeout(timeouts.connect())
+        .timeout(timeouts.read())
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            " ",
+            env!("CARGO_PKG_VERSION"),
+            " (",
+            env!("CARGO_PKG_REPOSITORY"),
+            ")"
+        ));
+    let client = crate::proxy::apply(builder, proxy)?.build()?;
+    let resp = client.get(url.clone()).send()?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(Vec::new());
+    }
+    if !resp.status().is_success() {
+        return Err(format!("Non-success status: {}", resp.status()).into());
+    }
+    Ok(resp.bytes()?.to_vec())
+}
+
+fn parse_record(record: &Record) -> Option<Lyrics> {
+    if record.instrumental {
+        return Some(Lyrics::Instrumental);
+    }
+    let synced = record.synced_lyrics.as_deref().map(parse_lrc);
+    match (synced, record.plain_lyrics.as_deref()) {
+        (Some(lines), _) if !lines.is_empty() => Some(Lyrics::Synced(lines)),
+        (_, Some(plain)) if !plain.trim().is_empty() => Some(Lyrics::Plain(plain.to_string())),
+        _ => None,
+    }
+}
+
+/// Parse LRC text: `[mm:ss.xx]text` lines, possibly with several time tags per line.
+/// Metadata tags (`[ar:...]`) and untimed lines are skipped.
+pub fn parse_lrc(text: &str) -> Vec<LyricLine> {
+    let mut lines = Vec::new();
+    for raw in text.lines() {
+        let mut rest = raw.trim();
+        let mut stamps = Vec::new();
+        while let Some(tag) = rest.strip_prefix('[') {
+            let Some(end) = tag.find(']') else {
+                break;
+            };
+            match parse_timestamp(&tag[..end]) {
+                Some(at) => stamps.push(at),
+                None => break,
+            }
+            rest = &tag[end + 1..];
+        }
+        let text = rest.trim();
+        lines.extend(stamps.into_iter().map(|at| LyricLine {
+            at,
+            text: text.to_string(),
+        }));
+    }
+    lines.sort_by_key(|line| line.at);
+    lines
+}
+
+/// `mm:ss`, `mm:ss.xx` or `mm:ss.xxx`.
+fn parse_timestamp(tag: &str) -> Option<Duration> {
+    let (minutes, seconds) = tag.split_once(':')?;
+    let minutes: u64 = minutes.parse().ok()?;
+    let (secs, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+    let secs: u64 = secs.parse().ok()?;
+    if secs >= 60 || !fraction.chars().all(|c| c.is_ascii_digit()) || fraction.len() > 3 {
+        return None;
+    }
+    let millis = match fraction.len() {
+        0 => 0,
+        len => fraction.parse::<u64>().ok()? * 10u64.pow(3 - len as u32),
+    };
+    Some(Duration::from_millis((minutes * 60 + secs) * 1000 + millis))
+}
+
+/// Index of the line being sung at `position`, `None` before the first one.
+pub fn line_at(lines: &[LyricLine], position: Duration) -> Option<usize> {
+    lines
+        .partition_point(|line| line.at <= position)
+        .checked_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn parses_lrc_lines() {
+        let lines =
+            parse_lrc("[ar:Someone]\n[00:01.50]First\n[00:12.345] Second \n\nno tag\n[01:02]Third");
+        let parsed: Vec<_> = lines.iter().map(|l| (l.at, l.text.as_str())).collect();
+        assert_eq!(
+            parsed,
+            [
+                (ms(1_500), "First"),
+                (ms(12_345), "Second"),
+                (ms(62_000), "Third")
+            ]
+        );
+    }
+
+    #[test]
+    fn repeated_tags_expand_in_order() {
+        let lines = parse_lrc("[00:20.00][00:05.00]Chorus\n[00:10.00]Verse");
+        let parsed: Vec<_> = lines.iter().map(|l| (l.at, l.text.as_str())).collect();
+        assert_eq!(
+            parsed,
+            [
+                (ms(5_000), "Chorus"),
+                (ms(10_000), "Verse"),
+                (ms(20_000), "Chorus")
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_timestamps() {
+        assert_eq!(parse_timestamp("00:61.00"), None);
+        assert_eq!(parse_timestamp("ab:10"), None);
+        assert_eq!(parse_timestamp("00:10.1234"), None);
+        assert_eq!(parse_timestamp("00:10.5"), Some(ms(10_500)));
+    }
+
+    #[test]
+    fn finds_current_line() {
+        let lines = parse_lrc("[00:02.00]a\n[00:04.00]b\n[00:06.00]c");
+        assert_eq!(line_at(&lines, ms(1_000)), None);
+        assert_eq!(line_at(&lines, ms(2_000)), Some(0));
+        assert_eq!(line_at(&lines, ms(5_999)), Some(1));
+        assert_eq!(line_at(&lines, ms(60_000)), Some(2));
+        assert_eq!(line_at(&[], ms(0)), None);
+    }
+
+    #[test]
+    fn prefers_synced_over_plain() {
+        let record: Record = serde_json::from_str(
+            r#"{"instrumental":false,"plainLyrics":"a","syncedLyrics":"[00:01.00]a"}"#,
+        )
+        .unwrap();
+        assert!(matches!(parse_record(&record), Some(Lyrics::Synced(_))));
+
+        let record: Record =
+            serde_json::from_str(r#"{"plainLyrics":"a\nb","syncedLyrics":null}"#).unwrap();
+        assert_eq!(parse_record(&record), Some(Lyrics::Plain("a\nb".into())));
+
+        let record: Record =
+            serde_json::from_str(r#"{"instrumental":true,"plainLyrics":null,"syncedLyrics":null}"#)
+                .unwrap();
+        assert_eq!(parse_record(&record), Some(Lyrics::Instrumental));
+    }
+}
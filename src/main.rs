@@ -1,30 +1,32 @@
 #![cfg_attr(all(not(debug_assertions), target_os = "windows"), windows_subsystem = "windows")]
 
-mod http_source;
-mod listen;
-mod locale;
-#[cfg(debug_assertions)]
-mod log;
-mod meta;
-mod station;
+mod diagnostics;
+mod history;
+mod hook;
+mod lyrics;
+mod mqtt;
+mod nowplaying;
+mod remote;
+#[cfg(all(target_os = "linux", feature = "setup"))]
+mod setup;
 mod ui;
 
-#[cfg(debug_assertions)]
-const APP_ID: &str = "io.github.noobping.listenmoe_beta";
-#[cfg(not(debug_assertions))]
-const APP_ID: &str = "io.github.noobping.listenmoe";
-#[cfg(target_os = "windows")]
+use petal_core::{
+    api, cache, events, listen, locale, log, meta, proxy, record, settings, station, APP_ID,
+};
+#[cfg(not(target_os = "linux"))]
 const RESOURCE_ID: &str = "/io/github/noobping/listenmoe";
-#[cfg(target_os = "windows")]
+#[cfg(not(target_os = "linux"))]
 use adw::gtk::{gdk::Display, IconTheme};
 use adw::prelude::*;
 use adw::Application;
 
 fn main() {
+    ui::install_panic_hook(); // Report crashes, also where there is no console.
     locale::init_i18n();
 
     // Register resources compiled into the binary. If this fails, the app cannot find its assets.
-    #[cfg(target_os = "windows")]
+    #[cfg(not(target_os = "linux"))]
     adw::gtk::gio::resources_register_include!("compiled.gresource")
         .expect("Failed to register resources");
 
@@ -32,14 +34,18 @@ fn main() {
     adw::init().expect("Failed to initialize libadwaita");
 
     // Load the icon theme from the embedded resources so that icons resolve correctly even outside a installed environment.
-    #[cfg(target_os = "windows")]
+    #[cfg(not(target_os = "linux"))]
     if let Some(display) = Display::default() {
         let theme = IconTheme::for_display(&display);
         theme.add_resource_path(RESOURCE_ID);
     }
 
     // Create the GTK application. The application ID must be unique and corresponds to the desktop file name.
-    let app = Application::builder().application_id(APP_ID).build();
+    let app = Application::builder()
+        .application_id(APP_ID)
+        .flags(adw::gtk::gio::ApplicationFlags::HANDLES_COMMAND_LINE)
+        .build();
     app.connect_activate(ui::build_ui); // Build the UI when the application is activated.
+    ui::install_command_line(&app); // Options also reach an already running instance.
     app.run(); // Run the application. This function does not return until the last window is closed.
 }
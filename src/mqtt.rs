@@ -0,0 +1,466 @@
+//! Publishes track changes and the playback state to an MQTT broker, for home automation
+//! dashboards like Home Assistant. Speaks just enough MQTT 3.1.1 over plain TCP to publish:
+//!
+//! - `{topic}/track`: the current track as JSON
+//! - `{topic}/state`: `playing`, `paused`, `stopped`, …
+//! - `{topic}/availability`: `online`, or `offline` (as the will) once the app is gone
+//!
+//! All messages are retained, so a dashboard that connects later still sees them. Home
+//! Assistant discovery configs under `homeassistant/` add both as sensors without any YAML.
+
+use serde::Serialize;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::log::now_string;
+use crate::meta::TrackInfo;
+use crate::settings::{Mqtt, Timeouts};
+use crate::station::Station;
+
+/// The broker drops the connection after 1.5 times this without a packet from us.
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+const PINGREQ: [u8; 2] = [0xc0, 0x00];
+const PINGRESP: u8 = 0xd0;
+const DISCONNECT: [u8; 2] = [0xe0, 0x00];
+
+/// Something to publish.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Update {
+    /// JSON from [`track_payload`].
+    Track(String),
+    State(&'static str),
+}
+
+#[derive(Serialize)]
+struct TrackPayload<'a> {
+    artist: &'a str,
+    title: &'a str,
+    station: &'static str,
+    duration_secs: Option<u32>,
+    listeners: Option<u32>,
+}
+
+pub fn track_payload(info: &TrackInfo, station: Station) -> String {
+    serde_json::to_string(&TrackPayload {
+        artist: &info.artist,
+        title: &info.title,
+        station: station.name(),
+        duration_secs: Some(info.duration_secs).filter(|secs| *secs > 0),
+        listeners: info.listeners,
+    })
+    .unwrap_or_default()
+}
+
+/// Publishes from a background thread, reconnecting as needed. Dropping it disconnects.
+pub struct MqttPublisher {
+    updates: mpsc::Sender<Update>,
+}
+
+impl MqttPublisher {
+    pub fn start(broker: Mqtt, timeouts: Timeouts) -> io::Result<Self> {
+        let (updates, rx) = mpsc::channel();
+        thread::Builder::new()
+            .name("mqtt".into())
+            .spawn(move || run(&broker, timeouts, &rx))?;
+        Ok(Self { updates })
+    }
+
+    pub fn publish(&self, update: Update) {
+        let _ = self.updates.send(update);
+    }
+}
+
+/// The latest of each message, published again after reconnecting.
+#[derive(Default)]
+struct Latest {
+    track: Option<String>,
+    state: Option<&'static str>,
+}
+
+impl Latest {
+    /// Whether `update` differs from what was published last; retained messages need
+    /// not be sent again.
+    fn changes(&self, update: &Update) -> bool {
+        match update {
+            Update::Track(track) => self.track.as_ref() != Some(track),
+            Update::State(state) => self.state != Some(*state),
+        }
+    }
+
+    fn store(&mut self, update: Update) {
+        match update {
+            Update::Track(track) => self.track = Some(track),
+            Update::State(state) => self.state = Some(state),
+        }
+    }
+}
+
+fn run(broker: &Mqtt, timeouts: Timeouts, updates: &mpsc::Receiver<Update>) {
+    let mut latest = Latest::default();
+    let mut failing = false;
+    loop {
+        let result = connect(broker, timeouts).and_then(|stream| {
+            failing = false;
+            publish_until_closed(stream, broker, updates, &mut latest)
+        });
+        match result {
+            Ok(()) => return,
+            // Only the first of a run of failures; the broker may be down for hours.
+            Err(err) if !failing => {
+                eprintln!(
+                    "[{}] MQTT connection to {} failed: {err}",
+                    now_string(),
+                    broker.host
+                );
+                failing = true;
+            }
+            Err(_err) => {
+                #[cfg(debug_assertions)]
+                println!(
+                    "[{}] MQTT connection to {} failed again: {_err}",
+                    now_string(),
+                    broker.host
+                );
+            }
+        }
+
+        // Keep collecting updates while waiting to retry.
+        let retry = Instant::now() + RETRY_DELAY;
+        loop {
+            match updates.recv_timeout(retry.saturating_duration_since(Instant::now())) {
+                Ok(update) => latest.store(update),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+}
+
+/// Publish updates until the sender is dropped (`Ok`) or the connection fails.
+fn publish_until_closed(
+    mut stream: TcpStream,
+    broker: &Mqtt,
+    updates: &mpsc::Receiver<Update>,
+    latest: &mut Latest,
+) -> io::Result<()> {
+    for (topic, payload) in discovery(&broker.topic) {
+        stream.write_all(&publish_packet(&topic, payload.as_bytes(), true)?)?;
+    }
+    let topic = &broker.topic;
+    stream.write_all(&publish_packet(
+        &format!("{topic}/availability"),
+        b"online",
+        true,
+    )?)?;
+    if let Some(track) = &latest.track {
+        stream.write_all(&publish_packet(
+            &format!("{topic}/track"),
+            track.as_bytes(),
+            true,
+        )?)?;
+    }
+    if let Some(state) = latest.state {
+        stream.write_all(&publish_packet(
+            &format!("{topic}/state"),
+            state.as_bytes(),
+            true,
+        )?)?;
+    }
+
+    loop {
+        match updates.recv_timeout(KEEP_ALIVE / 2) {
+            Ok(update) if !latest.changes(&update) => {}
+            Ok(update) => {
+                let packet = match &update {
+                    Update::Track(track) => {
+                        publish_packet(&format!("{topic}/track"), track.as_bytes(), true)?
+                    }
+                    Update::State(state) => {
+                        publish_packet(&format!("{topic}/state"), state.as_bytes(), true)?
+                    }
+                };
+                latest.store(update);
+                stream.write_all(&packet)?;
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                stream.write_all(&PINGREQ)?;
+                await_pingresp(&mut stream)?;
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                // A clean disconnect discards the will, so say so ourselves.
+                stream.write_all(&publish_packet(
+                    &format!("{topic}/availability"),
+                    b"offline",
+                    true,
+                )?)?;
+                return stream.write_all(&DISCONNECT);
+            }
+        }
+    }
+}
+
+/// Read packets until the reply to a ping, skipping anything else the broker sends.
+fn await_pingresp(stream: &mut impl Read) -> io::Result<()> {
+    loop {
+        let mut header = [0; 1];
+        stream.read_exact(&mut header)?;
+        let mut len = 0usize;
+        for shift in (0..4).map(|i| 7 * i) {
+            let mut byte = [0; 1];
+            stream.read_exact(&mut byte)?;
+            len |= usize::from(byte[0] & 0x7f) << shift;
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+        }
+        io::copy(&mut stream.take(len as u64), &mut io::sink())?;
+        if header[0] & 0xf0 == PINGRESP {
+            return Ok(());
+        }
+    }
+}
+
+fn connect(broker: &Mqtt, timeouts: Timeouts) -> io::Result<TcpStream> {
+    let mut last_err = io::Error::new(io::ErrorKind::NotFound, "no address for the host");
+    for addr in (broker.host.as_str(), broker.port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeouts.connect()) {
+            Ok(mut stream) => {
+                stream.set_read_timeout(Some(timeouts.read()))?;
+                stream.set_write_timeout(Some(timeouts.read()))?;
+                let client_id = format!("{}-{}", env!("CARGO_PKG_NAME"), std::process::id());
+                stream.write_all(&connect_packet(broker, &client_id)?)?;
+                let mut connack = [0; 4];
+                stream.read_exact(&mut connack)?;
+                check_connack(connack)?;
+                return Ok(stream);
+            }
+            Err(err) => last_err = err,
+        }
+    }
+    Err(last_err)
+}
+
+fn connect_packet(broker: &Mqtt, client_id: &str) -> io::Result<Vec<u8>> {
+    // Clean session, with a retained will on the availability topic.
+    let mut flags = 0x02 | 0x04 | 0x20;
+    let mut body = Vec::new();
+    put_str(&mut body, "MQTT")?;
+    body.push(4); // protocol level 3.1.1
+    let flags_at = body.len();
+    body.push(0);
+    body.extend_from_slice(&(KEEP_ALIVE.as_secs() as u16).to_be_bytes());
+    put_str(&mut body, client_id)?;
+    put_str(&mut body, &format!("{}/availability", broker.topic))?;
+    put_bytes(&mut body, b"offline")?;
+    if let Some(username) = broker.username.as_deref().filter(|u| !u.is_empty()) {
+        flags |= 0x80;
+        put_str(&mut body, username)?;
+        if let Some(password) = broker.password.as_deref() {
+            flags |= 0x40;
+            put_bytes(&mut body, password.as_bytes())?;
+        }
+    }
+    body[flags_at] = flags;
+    packet(0x10, &body)
+}
+
+fn check_connack(connack: [u8; 4]) -> io::Result<()> {
+    if connack[..2] != [0x20, 0x02] {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the server does not speak MQTT",
+        ));
+    }
+    let reason = match connack[3] {
+        0 => return Ok(()),
+        1 => "unsupported protocol version",
+        2 => "client identifier rejected",
+        3 => "server unavailable",
+        4 => "bad user name or password",
+        5 => "not authorized",
+        _ => "connection refused",
+    };
+    Err(io::Error::new(io::ErrorKind::ConnectionRefused, reason))
+}
+
+fn publish_packet(topic: &str, payload: &[u8], retain: bool) -> io::Result<Vec<u8>> {
+    let mut body = Vec::with_capacity(topic.len() + payload.len() + 2);
+    put_str(&mut body, topic)?;
+    body.extend_from_slice(payload);
+    packet(0x30 | u8::from(retain), &body)
+}
+
+/// Fixed header (type and flags, then the remaining length) followed by `body`.
+fn packet(header: u8, body: &[u8]) -> io::Result<Vec<u8>> {
+    // The remaining length takes at most four bytes of seven bits each.
+    if body.len() >= 1 << 28 {
+        return Err(too_large());
+    }
+    let mut packet = Vec::with_capacity(body.len() + 5);
+    packet.push(header);
+    let mut len = body.len();
+    loop {
+        let byte = (len % 128) as u8;
+        len /= 128;
+        if len == 0 {
+            packet.push(byte);
+            break;
+        }
+        packet.push(byte | 0x80);
+    }
+    packet.extend_from_slice(body);
+    Ok(packet)
+}
+
+fn put_str(out: &mut Vec<u8>, value: &str) -> io::Result<()> {
+    put_bytes(out, value.as_bytes())
+}
+
+fn put_bytes(out: &mut Vec<u8>, value: &[u8]) -> io::Result<()> {
+    let len = u16::try_from(value.len()).map_err(|_| too_large())?;
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(value);
+    Ok(())
+}
+
+fn too_large() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "too large for an MQTT packet")
+}
+
+/// Home Assistant discovery topics and configs for the track and state sensors.
+fn discovery(topic: &str) -> Vec<(String, String)> {
+    let node: String = topic
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let device = serde_json::json!({
+        "identifiers": [node],
+        "name": "LISTEN.moe",
+        "manufacturer": "petal",
+    });
+    let availability = format!("{topic}/availability");
+    let track = serde_json::json!({
+        "name": "Now playing",
+        "unique_id": format!("{node}_track"),
+        "state_topic": format!("{topic}/track"),
+        "value_template": "{{ value_json.artist }} – {{ value_json.title }}",
+        "json_attributes_topic": format!("{topic}/track"),
+        "availability_topic": availability,
+        "icon": "mdi:music",
+        "device": device,
+    });
+    let state = serde_json::json!({
+        "name": "Playback",
+        "unique_id": format!("{node}_state"),
+        "state_topic": format!("{topic}/state"),
+        "availability_topic": availability,
+        "icon": "mdi:radio",
+        "device": device,
+    });
+    vec![
+        (
+            format!("homeassistant/sensor/{node}/track/config"),
+            track.to_string(),
+        ),
+        (
+            format!("homeassistant/sensor/{node}/state/config"),
+            state.to_string(),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    fn broker(port: u16) -> Mqtt {
+        Mqtt {
+            host: "127.0.0.1".into(),
+            port,
+            ..Mqtt::default()
+        }
+    }
+
+    #[test]
+    fn encodes_remaining_length() {
+        assert_eq!(packet(0xc0, &[]).unwrap(), PINGREQ);
+        let long = packet(0x30, &[0; 321]).unwrap();
+        assert_eq!(&long[..3], &[0x30, 0xc1, 0x02]);
+        assert_eq!(long.len(), 3 + 321);
+    }
+
+    #[test]
+    fn encodes_publish() {
+        assert_eq!(
+            publish_packet("a/b", b"hi", true).unwrap(),
+            [0x31, 7, 0, 3, b'a', b'/', b'b', b'h', b'i']
+        );
+    }
+
+    #[test]
+    fn encodes_connect() {
+        let mut with_login = broker(1883);
+        with_login.username = Some("home".into());
+        with_login.password = Some("secret".into());
+        let plain = connect_packet(&broker(1883), "id").unwrap();
+        let login = connect_packet(&with_login, "id").unwrap();
+        assert_eq!(&plain[2..10], &[0, 4, b'M', b'Q', b'T', b'T', 4, 0x26]);
+        assert_eq!(login[9], 0xe6);
+        assert!(login.ends_with(b"\0\x04home\0\x06secret"));
+    }
+
+    #[test]
+    fn skips_to_the_ping_reply() {
+        let mut replies: &[u8] = &[0x90, 0x03, 0, 1, 0, 0xd0, 0x00, 0xff];
+        await_pingresp(&mut replies).unwrap();
+        assert_eq!(replies, [0xff]);
+    }
+
+    #[test]
+    fn skips_repeated_messages() {
+        let mut latest = Latest::default();
+        latest.store(Update::State("playing"));
+        assert!(!latest.changes(&Update::State("playing")));
+        assert!(latest.changes(&Update::State("paused")));
+        assert!(latest.changes(&Update::Track("{}".into())));
+    }
+
+    #[test]
+    fn reads_connack() {
+        assert!(check_connack([0x20, 2, 0, 0]).is_ok());
+        let err = check_connack([0x20, 2, 0, 4]).unwrap_err();
+        assert_eq!(err.to_string(), "bad user name or password");
+        assert!(check_connack(*b"HTTP").is_err());
+    }
+
+    #[test]
+    fn publishes_to_a_broker() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let publisher = MqttPublisher::start(broker(port), Timeouts::default()).unwrap();
+        publisher.publish(Update::State("playing"));
+
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut connect = [0; 2];
+        stream.read_exact(&mut connect).unwrap();
+        assert_eq!(connect[0], 0x10);
+        let mut rest = vec![0; usize::from(connect[1])];
+        stream.read_exact(&mut rest).unwrap();
+        stream.write_all(&[0x20, 2, 0, 0]).unwrap();
+
+        drop(publisher);
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).unwrap();
+        let state = publish_packet("listenmoe/state", b"playing", true).unwrap();
+        assert!(
+            received.windows(state.len()).any(|w| w == state),
+            "no state in {received:?}"
+        );
+        assert!(received.ends_with(&DISCONNECT));
+    }
+}
@@ -0,0 +1,90 @@
+//! The current track as a text file (and optionally its cover as an image next to it), for
+//! streaming software like OBS that shows the contents of a file in an overlay.
+//!
+//! Files are replaced atomically, so a reader never sees a half-written one.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::meta::TrackInfo;
+
+/// The line written for a track: "Artist – Title", or whichever of the two is known.
+pub fn line(info: &TrackInfo) -> String {
+    match (info.artist.trim(), info.title.trim()) {
+        ("", title) => title.to_string(),
+        (artist, "") => artist.to_string(),
+        (artist, title) => format!("{artist} – {title}"),
+    }
+}
+
+/// Where the cover goes for the text file at `text`: the same name with a `.png` extension.
+pub fn cover_path(text: &Path) -> PathBuf {
+    text.with_extension("png")
+}
+
+pub fn write_text(path: &Path, info: &TrackInfo) -> io::Result<()> {
+    write_atomic(path, line(info).as_bytes())
+}
+
+/// Write `contents` to a temporary file next to `path` and rename it into place.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".part");
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+/// Remove a stale cover; a missing one is fine.
+pub fn remove_cover(text: &Path) -> io::Result<()> {
+    match fs::remove_file(cover_path(text)) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    fn track(artist: &str, title: &str) -> TrackInfo {
+        TrackInfo {
+            song_id: None,
+            artist: artist.into(),
+            title: title.into(),
+            album_cover: None,
+            artist_image: None,
+            start_time_utc: UNIX_EPOCH,
+            duration_secs: 0,
+            source: None,
+            requester: None,
+            event: None,
+            listeners: None,
+        }
+    }
+
+    #[test]
+    fn formats_the_line() {
+        assert_eq!(line(&track("ClariS", "Connect")), "ClariS – Connect");
+        assert_eq!(line(&track("", "Connect")), "Connect");
+        assert_eq!(line(&track("ClariS ", " ")), "ClariS");
+    }
+
+    #[test]
+    fn replaces_the_file() {
+        let dir = std::env::temp_dir().join(format!("listenmoe-np-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("now-playing.txt");
+        write_text(&path, &track("A", "One")).unwrap();
+        write_text(&path, &track("B", "Two")).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "B – Two");
+        assert!(!dir.join("now-playing.txt.part").exists());
+        assert_eq!(cover_path(&path), dir.join("now-playing.png"));
+        remove_cover(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
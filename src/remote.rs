@@ -0,0 +1,531 @@
+//! Optional HTTP remote control on the loopback interface, for scripts and tools that cannot
+//! use D-Bus:
+//!
+//! - `GET /`: a small page with playback buttons, for phones
+//! - `GET /status`: the playback state, station and current track as JSON
+//! - `POST /play`, `POST /pause`
+//! - `POST /station?name=kpop` (or the name as the request body)
+//!
+//! Commands only take POST from the page itself or without an `Origin` header, so other web
+//! pages cannot trigger them. Requests for any host name but `localhost` are refused, which
+//! keeps out pages that rebind their own name to this machine. With an access token the server also listens on the local
+//! network; requests from other machines then need `?token=` or `Authorization: Bearer`.
+
+use serde::Serialize;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::events::PlaybackState;
+use crate::meta::TrackInfo;
+use crate::station::Station;
+
+/// Longest request head or body accepted.
+const MAX_REQUEST: usize = 8 * 1024;
+const IO_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The phone remote served at `/`; it reads the token from its own address.
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>LISTEN.moe</title>
+<style>
+body { font-family: sans-serif; margin: 2em auto; max-width: 24em; padding: 0 1em; text-align: center; }
+button { font-size: 1.2em; margin: 0.3em; padding: 0.6em 1em; }
+#track { min-height: 3em; }
+</style>
+</head>
+<body>
+<p id="track"></p>
+<button onclick="send('/play')">&#9654;</button>
+<button onclick="send('/pause')">&#10074;&#10074;</button>
+<br>
+<button onclick="send('/station?name=jpop')">J-pop</button>
+<button onclick="send('/station?name=kpop')">K-pop</button>
+<script>
+const token = new URLSearchParams(location.search).get("token") || "";
+const headers = { Authorization: "Bearer " + token };
+async function refresh() {
+  const response = await fetch("/status", { headers });
+  const status = await response.json();
+  document.getElementById("track").textContent = status.error
+    || [status.artist, status.title].filter(Boolean).join(" - ") + " (" + status.state + ")";
+}
+async function send(path) {
+  await fetch(path, { method: "POST", headers });
+  setTimeout(refresh, 500);
+}
+refresh();
+setInterval(refresh, 5000);
+</script>
+</body>
+</html>
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Play,
+    Pause,
+    Station(Station),
+}
+
+/// What `/status` reports; kept current by the UI.
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+    pub state: &'static str,
+    pub station: &'static str,
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub duration_secs: Option<u32>,
+    pub listeners: Option<u32>,
+}
+
+impl Status {
+    pub fn new(station: Station) -> Self {
+        Self {
+            state: PlaybackState::Stopped.name(),
+            station: station.name(),
+            artist: None,
+            title: None,
+            duration_secs: None,
+            listeners: None,
+        }
+    }
+
+    pub fn set_track(&mut self, info: &TrackInfo) {
+        self.artist = Some(info.artist.clone());
+        self.title = Some(info.title.clone());
+        self.duration_secs = Some(info.duration_secs).filter(|secs| *secs > 0);
+        self.listeners = info.listeners;
+    }
+
+    pub fn set_state(&mut self, state: PlaybackState) {
+        self.state = state.name();
+    }
+
+    pub fn set_station(&mut self, station: Station) {
+        self.station = station.name();
+    }
+}
+
+pub type SharedStatus = Arc<Mutex<Status>>;
+
+/// The listening server; it stops when dropped.
+pub struct RemoteServer {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RemoteServer {
+    /// Listen on `127.0.0.1:port`, or on every interface when there is a `token`, answering
+    /// from `status` and sending commands to `commands`.
+    pub fn start(
+        port: u16,
+        token: Option<String>,
+        status: SharedStatus,
+        commands: mpsc::Sender<Command>,
+    ) -> io::Result<Self> {
+        let ip = match token {
+            Some(_) => Ipv4Addr::UNSPECIFIED,
+            None => Ipv4Addr::LOCALHOST,
+        };
+        let listener = TcpListener::bind((ip, port))?;
+        let addr = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            thread::Builder::new()
+                .name("remote-control".into())
+                .spawn(move || {
+                    for stream in listener.incoming() {
+                        if stop.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        let result =
+                            stream.and_then(|s| serve(s, token.as_deref(), &status, &commands));
+                        if let Err(err) = result {
+                            eprintln!("Remote control request failed: {err}");
+                        }
+                    }
+                })?
+        };
+        Ok(Self {
+            addr,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for RemoteServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // Wake the blocking accept so the thread sees the flag.
+        let wake = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), self.addr.port());
+        let _ = TcpStream::connect_timeout(&wake, IO_TIMEOUT);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// The address other machines on the local network reach this one at, if any.
+pub fn lan_ip() -> Option<IpAddr> {
+    // Connecting a UDP socket only picks a route; nothing is sent.
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9)).ok()?;
+    Some(socket.local_addr().ok()?.ip()).filter(|ip| !ip.is_loopback() && !ip.is_unspecified())
+}
+
+/// The address a phone opens to pair, as encoded in the QR code.
+pub fn pairing_url(ip: IpAddr, port: u16, token: &str) -> String {
+    format!("http://{}/?token={token}", SocketAddr::new(ip, port))
+}
+
+/// One parsed request.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Request {
+    method: String,
+    target: String,
+    /// Sent by a browser from a page other than ours.
+    cross_origin: bool,
+    /// The `Authorization: Bearer` token.
+    bearer: Option<String>,
+    /// Came in over the loopback interface, which needs no token.
+    local: bool,
+    /// Addressed to a host name other than `localhost`, as a DNS-rebinding page would be.
+    foreign_host: bool,
+    body: String,
+}
+
+fn serve(
+    stream: TcpStream,
+    token: Option<&str>,
+    status: &SharedStatus,
+    commands: &mpsc::Sender<Command>,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let (code, content_type, body) = match read_request(&mut reader) {
+        Ok(request)
+            if !request.foreign_host
+                && request.method == "GET"
+                && request.target.split('?').next() == Some("/") =>
+        {
+            (200, "text/html; charset=utf-8", PAGE.to_string())
+        }
+        Ok(mut request) => {
+            request.local = stream.peer_addr()?.ip().is_loopback();
+            let status = status.lock().map(|s| s.clone()).ok();
+            let (code, body, command) = route(&request, token, status.as_ref());
+            if let Some(command) = command {
+                let _ = commands.send(command);
+            }
+            (code, "application/json", body)
+        }
+        Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+            (400, "application/json", error_body("Bad request"))
+        }
+        Err(err) => return Err(err),
+    };
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {code} {}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        reason(code),
+        body.len()
+    )?;
+    stream.flush()
+}
+
+fn read_request(reader: &mut impl BufRead) -> io::Result<Request> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed request");
+    let mut head = 0;
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+    let mut request = Request {
+        method: method.to_string(),
+        target: target.to_string(),
+        ..Request::default()
+    };
+
+    let mut content_length = 0;
+    let (mut origin, mut host) = (None, None);
+    loop {
+        line.clear();
+        let read = reader.read_line(&mut line)?;
+        head += read;
+        if read == 0 || head > MAX_REQUEST {
+            return Err(invalid());
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(invalid());
+        };
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.trim().parse().map_err(|_| invalid())?;
+        } else if name.eq_ignore_ascii_case("origin") {
+            origin = Some(value.trim().to_string());
+        } else if name.eq_ignore_ascii_case("host") {
+            host = Some(value.trim().to_string());
+        } else if name.eq_ignore_ascii_case("authorization") {
+            request.bearer = value.trim().strip_prefix("Bearer ").map(str::to_string);
+        }
+    }
+    request.foreign_host = host.as_deref().is_some_and(|host| !known_host(host));
+    request.cross_origin = origin.is_some_and(|origin| {
+        host.is_none_or(|host| origin.strip_prefix("http://") != Some(host.as_str()))
+    });
+    if content_length > MAX_REQUEST {
+        return Err(invalid());
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    request.body = String::from_utf8(body).map_err(|_| invalid())?;
+    Ok(request)
+}
+
+/// `localhost` or an IP address, such as the LAN address a phone was paired with. A page can
+/// only point its own DNS name at this machine, never an address literal.
+fn known_host(host: &str) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => host.rsplit_once(':').map_or(host, |(name, _)| name),
+    };
+    name.eq_ignore_ascii_case("localhost") || name.parse::<IpAddr>().is_ok()
+}
+
+/// Status code, JSON body and the command to run, if any.
+fn route(
+    request: &Request,
+    token: Option<&str>,
+    status: Option<&Status>,
+) -> (u16, String, Option<Command>) {
+    let (path, query) = request
+        .target
+        .split_once('?')
+        .unwrap_or((&request.target, ""));
+    if request.foreign_host {
+        return (403, error_body("Unknown host"), None);
+    }
+    let authorized = request.local
+        || token.is_some_and(|token| {
+            [request.bearer.as_deref(), query_param(query, "token")].contains(&Some(token))
+        });
+    if !authorized {
+        return (401, error_body("Missing or wrong token"), None);
+    }
+    match (request.method.as_str(), path) {
+        ("GET", "/status") => match status.and_then(|s| serde_json::to_string(s).ok()) {
+            Some(body) => (200, body, None),
+            None => (500, error_body("Status unavailable"), None),
+        },
+        (_, "/status") => (405, error_body("Use GET"), None),
+        (method, "/play" | "/pause" | "/station") if method != "POST" => {
+            (405, error_body("Use POST"), None)
+        }
+        (_, "/play" | "/pause" | "/station") if request.cross_origin => (
+            403,
+            error_body("Cross-origin requests are not allowed"),
+            None,
+        ),
+        (_, "/play") => (202, ok_body(), Some(Command::Play)),
+        (_, "/pause") => (202, ok_body(), Some(Command::Pause)),
+        (_, "/station") => {
+            let name = query_param(query, "name").unwrap_or(request.body.trim());
+            match Station::from_name(name) {
+                Some(station) => (202, ok_body(), Some(Command::Station(station))),
+                None => (
+                    400,
+                    error_body("Unknown station; expected jpop or kpop"),
+                    None,
+                ),
+            }
+        }
+        _ => (404, error_body("Not found"), None),
+    }
+}
+
+fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query.split('&').find_map(|pair| {
+        pair.strip_prefix(name)
+            .and_then(|rest| rest.strip_prefix('='))
+    })
+}
+
+fn ok_body() -> String {
+    r#"{"ok":true}"#.to_string()
+}
+
+fn error_body(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+fn reason(code: u16) -> &'static str {
+    match code {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn request(method: &str, target: &str, body: &str) -> Request {
+        Request {
+            method: method.into(),
+            target: target.into(),
+            local: true,
+            body: body.into(),
+            ..Request::default()
+        }
+    }
+
+    #[test]
+    fn parses_a_request() {
+        let raw = "POST /station HTTP/1.1\r\nHost: localhost\r\nContent-Length: 4\r\n\r\nkpop";
+        let mut parsed = read_request(&mut raw.as_bytes()).unwrap();
+        parsed.local = true;
+        assert_eq!(parsed, request("POST", "/station", "kpop"));
+
+        let raw = "GET /status HTTP/1.1\r\nOrigin: https://example.com\r\n\r\n";
+        assert!(read_request(&mut raw.as_bytes()).unwrap().cross_origin);
+        let raw = "POST /play HTTP/1.1\r\nHost: 10.0.0.2:8642\r\nOrigin: http://10.0.0.2:8642\r\n\
+                   Authorization: Bearer abc\r\n\r\n";
+        let parsed = read_request(&mut raw.as_bytes()).unwrap();
+        assert!(!parsed.cross_origin);
+        assert_eq!(parsed.bearer.as_deref(), Some("abc"));
+        assert!(read_request(&mut "GET /status HTTP/1.1\r\nbroken\r\n\r\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn routes_commands() {
+        assert_eq!(
+            route(&request("POST", "/play", ""), None, None).2,
+            Some(Command::Play)
+        );
+        assert_eq!(
+            route(&request("POST", "/station?name=kpop", ""), None, None).2,
+            Some(Command::Station(Station::Kpop))
+        );
+        assert_eq!(
+            route(&request("POST", "/station", "jpop\n"), None, None).2,
+            Some(Command::Station(Station::Jpop))
+        );
+        assert_eq!(
+            route(&request("POST", "/station", "rock"), None, None).0,
+            400
+        );
+        assert_eq!(route(&request("GET", "/pause", ""), None, None).0, 405);
+        assert_eq!(route(&request("GET", "/nope", ""), None, None).0, 404);
+
+        let mut cross_origin = request("POST", "/pause", "");
+        cross_origin.cross_origin = true;
+        assert_eq!(
+            route(&cross_origin, None, None),
+            (
+                403,
+                error_body("Cross-origin requests are not allowed"),
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn refuses_foreign_hosts() {
+        let parse = |host: &str| {
+            let raw = format!("POST /play HTTP/1.1\r\nHost: {host}\r\n\r\n");
+            read_request(&mut raw.as_bytes()).unwrap().foreign_host
+        };
+        assert!(!parse("localhost:8642"));
+        assert!(!parse("127.0.0.1:8642"));
+        assert!(!parse("[::1]:8642"));
+        assert!(!parse("192.168.1.20:8642"));
+        assert!(parse("rebind.example.com:8642"));
+        assert!(parse("127.0.0.1.example.com"));
+
+        let mut rebound = request("POST", "/play", "");
+        rebound.foreign_host = true;
+        assert_eq!(
+            route(&rebound, None, None),
+            (403, error_body("Unknown host"), None)
+        );
+        rebound.target = "/status".into();
+        rebound.method = "GET".into();
+        assert_eq!(route(&rebound, None, None).0, 403);
+    }
+
+    #[test]
+    fn needs_the_token_from_other_machines() {
+        let mut remote = request("POST", "/play", "");
+        remote.local = false;
+        assert_eq!(route(&remote, None, None).0, 401);
+        assert_eq!(route(&remote, Some("abc"), None).0, 401);
+
+        remote.bearer = Some("abc".into());
+        assert_eq!(route(&remote, Some("abc"), None).2, Some(Command::Play));
+        assert_eq!(route(&remote, Some("new"), None).0, 401);
+
+        let remote = Request {
+            target: "/play?token=abc".into(),
+            ..remote
+        };
+        assert_eq!(route(&remote, Some("abc"), None).2, Some(Command::Play));
+    }
+
+    #[test]
+    fn reports_status() {
+        let mut status = Status::new(Station::Jpop);
+        status.set_state(PlaybackState::Playing);
+        let (code, body, command) = route(&request("GET", "/status", ""), None, Some(&status));
+        assert_eq!((code, command), (200, None));
+        assert!(body.contains(r#""state":"playing""#), "{body}");
+        assert!(body.contains(r#""station":"jpop""#), "{body}");
+    }
+
+    #[test]
+    fn serves_over_tcp() {
+        let status = Arc::new(Mutex::new(Status::new(Station::Kpop)));
+        let (tx, rx) = mpsc::channel();
+        let server = RemoteServer::start(0, None, status, tx).unwrap();
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        stream
+            .write_all(b"POST /pause HTTP/1.1\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(
+            response.starts_with("HTTP/1.1 202 Accepted\r\n"),
+            "{response}"
+        );
+        assert_eq!(rx.recv_timeout(IO_TIMEOUT), Ok(Command::Pause));
+        drop(server);
+    }
+}
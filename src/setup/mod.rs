@@ -0,0 +1,152 @@
+//! Desktop integration for builds that are not installed by a package: cargo installs and
+//! AppImages. Writes the desktop file and icons into the per-user data directory.
+//!
+//! The app keeps its preferences in a JSON file (see `settings`), so there is no GSettings
+//! schema to install.
+
+use dirs_next as dirs;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::APP_ID;
+
+const DESKTOP_TEMPLATE: &str = include_str!("../../data/desktop.in");
+const SYMBOLIC_ICON: &str = "io.github.noobping.listenmoe-symbolic";
+const SYMBOLIC_SVG: &[u8] =
+    include_bytes!("../../data/symbolic/apps/io.github.noobping.listenmoe-symbolic.svg");
+#[cfg(debug_assertions)]
+const SCALABLE_SVG: &[u8] =
+    include_bytes!("../../data/scalable/apps/io.github.noobping.listenmoe_beta.svg");
+#[cfg(not(debug_assertions))]
+const SCALABLE_SVG: &[u8] =
+    include_bytes!("../../data/scalable/apps/io.github.noobping.listenmoe.svg");
+#[cfg(debug_assertions)]
+const PNG_128: &[u8] =
+    include_bytes!("../../data/128x128/apps/io.github.noobping.listenmoe_beta.png");
+#[cfg(not(debug_assertions))]
+const PNG_128: &[u8] = include_bytes!("../../data/128x128/apps/io.github.noobping.listenmoe.png");
+
+/// Whether desktop integration applies to this process: a sandbox installs its own files.
+pub fn is_needed() -> bool {
+    env::var_os("FLATPAK_ID").is_none()
+}
+
+/// Install or refresh the desktop file and icons. Files that are already up to date are left
+/// alone; returns the ones that were written.
+pub fn install() -> io::Result<Vec<PathBuf>> {
+    let Some(share) = dirs::data_dir() else {
+        return Ok(Vec::new());
+    };
+    let exec = launcher()?;
+    let mut written = Vec::new();
+    for (path, contents) in files(&share, &exec) {
+        if fs::read(&path).is_ok_and(|existing| existing == contents) {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Remove the installed desktop file and icons, and the cache directory (covers, crash
+/// report). Returns what was deleted; missing files are skipped.
+pub fn uninstall() -> io::Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    if let Some(share) = dirs::data_dir() {
+        for path in paths(&share) {
+            if remove(fs::remove_file(&path))? {
+                removed.push(path);
+            }
+        }
+    }
+    if let Some(cache) = dirs::cache_dir().map(|dir| dir.join(APP_ID)) {
+        if remove(fs::remove_dir_all(&cache))? {
+            removed.push(cache);
+        }
+    }
+    Ok(removed)
+}
+
+/// Whether a removal deleted something; a missing file is not an error.
+fn remove(result: io::Result<()>) -> io::Result<bool> {
+    match result {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// The installed files below `share` (usually `~/.local/share`): the desktop file and the
+/// scalable, 128px and symbolic icons.
+fn paths(share: &Path) -> [PathBuf; 4] {
+    let icons = share.join("icons").join("hicolor");
+    [
+        share.join("applications").join(format!("{APP_ID}.desktop")),
+        icons.join("scalable/apps").join(format!("{APP_ID}.svg")),
+        icons.join("128x128/apps").join(format!("{APP_ID}.png")),
+        icons
+            .join("symbolic/apps")
+            .join(format!("{SYMBOLIC_ICON}.svg")),
+    ]
+}
+
+/// Every installed file with its contents.
+fn files(share: &Path, exec: &Path) -> Vec<(PathBuf, Vec<u8>)> {
+    let [desktop, scalable, png, symbolic] = paths(share);
+    vec![
+        (desktop, desktop_entry(exec).into_bytes()),
+        (scalable, SCALABLE_SVG.to_vec()),
+        (png, PNG_128.to_vec()),
+        (symbolic, SYMBOLIC_SVG.to_vec()),
+    ]
+}
+
+/// The executable the desktop file should start: the AppImage itself rather than its
+/// temporary mount point, else the running binary.
+fn launcher() -> io::Result<PathBuf> {
+    match env::var_os("APPIMAGE") {
+        Some(appimage) => Ok(PathBuf::from(appimage)),
+        None => env::current_exe(),
+    }
+}
+
+fn desktop_entry(exec: &Path) -> String {
+    DESKTOP_TEMPLATE
+        .lines()
+        .map(|line| {
+            if line.starts_with("Exec=") {
+                format!("Exec={}", quote_exec(exec))
+            } else if line.starts_with("Icon=") {
+                format!("Icon={APP_ID}")
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        + "\n"
+}
+
+/// Quote a path for the `Exec` key, per the desktop entry specification.
+fn quote_exec(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if !path.contains(|c: char| c.is_whitespace() || "\"'\\><~|&;$*?#()`".contains(c)) {
+        return path.into_owned();
+    }
+    let mut quoted = String::from("\"");
+    for c in path.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    // The value is also a desktop entry string, which escapes backslashes once more.
+    quoted.replace('\\', "\\\\")
+}
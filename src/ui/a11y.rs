@@ -0,0 +1,75 @@
+use adw::gtk::{
+    self,
+    accessible::Property,
+    gdk::Display,
+    gio::{Menu, SimpleAction},
+    ApplicationWindow,
+};
+use adw::prelude::*;
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::settings::Settings;
+
+/// Rules applied while the high-visibility preference is on: state must never be
+/// conveyed by tint alone, and decorative layers must not reduce text contrast.
+const HIGH_VISIBILITY_CSS: &str = r#"
+window.high-visibility .header-viz { opacity: 0; }
+window.high-visibility .cover-backdrop { opacity: 0; }
+window.high-visibility .titlebar-tint { background: @window_bg_color; color: @window_fg_color; }
+window.high-visibility headerbar button { outline: 2px solid currentColor; outline-offset: -4px; }
+window.high-visibility .track-title .title { font-weight: 800; }
+window.high-visibility .track-title .subtitle { opacity: 1; font-weight: 600; }
+"#;
+
+/// Give an icon-only control a tooltip and an accessible name.
+pub fn label_icon_button(widget: &(impl IsA<gtk::Widget> + IsA<gtk::Accessible>), label: &str) {
+    widget.set_tooltip_text(Some(label));
+    widget.update_property(&[Property::Label(label)]);
+}
+
+fn apply_high_visibility(window: &ApplicationWindow, enabled: bool) {
+    if enabled {
+        window.add_css_class("high-visibility");
+    } else {
+        window.remove_css_class("high-visibility");
+    }
+}
+
+/// Install the high-visibility stylesheet and the `win.high_visibility` toggle.
+pub fn install(window: &ApplicationWindow, menu: &Menu, settings: &Rc<RefCell<Settings>>) {
+    let provider = gtk::CssProvider::new();
+    provider.load_from_data(HIGH_VISIBILITY_CSS);
+    if let Some(display) = Display::default() {
+        gtk::style_context_add_provider_for_display(
+            &display,
+            &provider,
+            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION + 1,
+        );
+    }
+
+    let enabled = settings.borrow().high_visibility;
+    apply_high_visibility(window, enabled);
+
+    let action = SimpleAction::new_stateful("high_visibility", None, &enabled.to_variant());
+    {
+        let window = window.clone();
+        let settings = settings.clone();
+        action.connect_activate(move |action, _| {
+            let enabled = !settings.borrow().high_visibility;
+            action.set_state(&enabled.to_variant());
+            apply_high_visibility(&window, enabled);
+            let mut settings = settings.borrow_mut();
+            settings.high_visibility = enabled;
+            settings.save();
+        });
+    }
+    window.add_action(&action);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("High visibility")),
+        Some("win.high_visibility"),
+    );
+}
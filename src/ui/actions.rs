@@ -8,8 +8,7 @@ use adw::gtk::{
 };
 use adw::{prelude::*, Application, WindowTitle};
 use gettextrs::gettext;
-#[cfg(target_os = "linux")]
-use mpris_server::PlaybackStatus;
+use std::cell::RefCell;
 use std::rc::Rc;
 #[cfg(target_os = "linux")]
 use std::sync::mpsc;
@@ -18,7 +17,8 @@ use std::sync::mpsc;
 use super::controls::{build_controls, MediaControlEvent, MediaControls};
 use crate::listen::Listen;
 use crate::meta::Meta;
-use crate::station::Station;
+use crate::settings::Settings;
+use crate::station::{Station, StreamFormat};
 
 const APP_NAME: &str = "Listen Moe";
 #[cfg(debug_assertions)]
@@ -57,21 +57,12 @@ pub fn build_actions(
             }
         }
     };
-    let set_playback = {
-        let controls = controls.clone();
-        move |status| {
-            if let Some(c) = controls.as_ref() {
-                c.set_playback(status);
-            }
-        }
-    };
     window.add_action(&{
         let radio = radio.clone();
         let meta = meta.clone();
         let win = win_title.clone();
         let play = play_button.clone();
         let pause = pause_button.clone();
-        let set_playback = set_playback.clone();
         make_action("play", move || {
             win.set_title(APP_NAME);
             win.set_subtitle("Connecting...");
@@ -79,7 +70,6 @@ pub fn build_actions(
             radio.start();
             play.set_visible(false);
             pause.set_visible(true);
-            set_playback(PlaybackStatus::Playing);
         })
     });
     window.add_action(&{
@@ -88,7 +78,6 @@ pub fn build_actions(
         let win = win_title.clone();
         let play = play_button.clone();
         let pause = pause_button.clone();
-        let set_playback = set_playback.clone();
         make_action("pause", move || {
             meta.pause();
             radio.pause();
@@ -96,7 +85,6 @@ pub fn build_actions(
             play.set_visible(true);
             win.set_title(APP_NAME);
             win.set_subtitle(&gettext("J-POP and K-POP radio"));
-            set_playback(PlaybackStatus::Paused);
         })
     });
     window.add_action(&{
@@ -105,7 +93,6 @@ pub fn build_actions(
         let win = win_title.clone();
         let play = play_button.clone();
         let pause = pause_button.clone();
-        let set_playback = set_playback.clone();
         make_action("stop", move || {
             meta.stop();
             radio.stop();
@@ -113,11 +100,10 @@ pub fn build_actions(
             play.set_visible(true);
             win.set_title(APP_NAME);
             win.set_subtitle(&gettext("J-POP and K-POP radio"));
-            set_playback(PlaybackStatus::Stopped);
         })
     });
     add_actions(window, win_title, play_button, pause_button, radio, meta);
-    add_accels(app);
+    add_accels(app, window);
 
     (controls, ctrl_rx)
 }
@@ -178,7 +164,7 @@ pub fn build_actions(
         })
     });
     add_actions(window, win_title, play_button, pause_button, radio, meta);
-    add_accels(app);
+    add_accels(app, window);
 }
 
 fn add_actions(
@@ -191,7 +177,10 @@ fn add_actions(
 ) {
     window.add_action(&{
         let win = window.clone();
-        make_action("quit", move || win.close())
+        make_action("quit", move || match win.application() {
+            Some(app) => app.quit(),
+            None => win.close(),
+        })
     });
     window.add_action(&{
         let win_clone = window.clone();
@@ -297,24 +286,36 @@ fn add_actions(
     });
 }
 
-fn add_accels(app: &Application) {
+fn add_accels(app: &Application, window: &ApplicationWindow) {
     app.set_accels_for_action("win.about", &["F1"]);
     app.set_accels_for_action("win.copy", &["<primary>c"]);
+    app.set_accels_for_action("win.search", &["<primary>f"]);
     app.set_accels_for_action("win.jpop", &["<primary>j"]);
     app.set_accels_for_action("win.kpop", &["<primary>k"]);
-    app.set_accels_for_action("win.quit", &["<primary>q", "Escape"]);
+    app.set_accels_for_action("win.quit", &["<primary>q"]);
     app.set_accels_for_action("win.prev_station", &["<primary>z", "XF86AudioPrev"]);
     app.set_accels_for_action(
         "win.next_station",
         &["<primary>y", "<primary><shift>z", "XF86AudioNext"],
     );
-    app.set_accels_for_action(
-        "win.toggle",
-        &["<primary>p", "space", "Return", "<primary>s"],
-    );
+    app.set_accels_for_action("win.toggle", &["<primary>p", "<primary>s"]);
     app.set_accels_for_action("win.play", &["XF86AudioPlay"]);
     app.set_accels_for_action("win.stop", &["XF86AudioStop"]);
     app.set_accels_for_action("win.pause", &["XF86AudioPause"]);
+
+    // Application accels come before the focused widget, so keys that also press buttons and
+    // type in entries only apply when nothing focused uses them.
+    let keys = gtk::ShortcutController::new();
+    for (trigger, action) in [
+        ("space|Return|KP_Enter", "win.toggle"),
+        ("Escape", "win.quit"),
+    ] {
+        keys.add_shortcut(gtk::Shortcut::new(
+            gtk::ShortcutTrigger::parse_string(trigger),
+            Some(gtk::NamedAction::new(action)),
+        ));
+    }
+    window.add_controller(keys);
 }
 
 pub fn populate_menu(
@@ -325,22 +326,61 @@ pub fn populate_menu(
     meta: &Rc<Meta>,
 ) {
     menu.append(Some(&gettext("Copy title & artist")), Some("win.copy"));
-    for station in [Station::Jpop, Station::Kpop] {
+    // Shown by the station switcher rather than in the menu.
+    for station in Station::ALL {
         let action = create_station_action(station, &play_button, &window, &radio, &meta);
         window.add_action(&action);
-        menu.append(
-            Some(
-                gettext("Play %s")
-                    .replace("%s", station.display_name())
-                    .as_str(),
-            ),
-            Some(&format!("win.{}", station.name())),
-        );
     }
     menu.append(Some(&gettext("About")), Some("win.about"));
     menu.append(Some(&gettext("Quit")), Some("win.quit"));
 }
 
+/// Add a "Stream quality" submenu backed by a stateful `win.stream_format` action.
+pub fn add_stream_format_menu(
+    window: &ApplicationWindow,
+    menu: &gtk::gio::Menu,
+    radio: &Rc<Listen>,
+    settings: &Rc<RefCell<Settings>>,
+) {
+    let current = settings.borrow().stream_format;
+    let action = SimpleAction::new_stateful(
+        "stream_format",
+        Some(glib::VariantTy::STRING),
+        &current.name().to_variant(),
+    );
+    {
+        let radio = radio.clone();
+        let settings = settings.clone();
+        action.connect_activate(move |action, param| {
+            let Some(format) = param
+                .and_then(|p| p.str())
+                .and_then(StreamFormat::from_name)
+            else {
+                return;
+            };
+            action.set_state(&format.name().to_variant());
+            radio.set_stream_format(format);
+            let mut settings = settings.borrow_mut();
+            settings.stream_format = format;
+            settings.save();
+        });
+    }
+    window.add_action(&action);
+
+    let submenu = gtk::gio::Menu::new();
+    for format in StreamFormat::ALL.into_iter().filter(|f| f.is_decodable()) {
+        submenu.append(
+            Some(format.display_name()),
+            Some(&format!("win.stream_format::{}", format.name())),
+        );
+    }
+    menu.insert_submenu(
+        menu.n_items() - 2,
+        Some(&gettext("Stream quality")),
+        &submenu,
+    );
+}
+
 fn create_station_action(
     station: Station,
     play_button: &Button,
@@ -0,0 +1,58 @@
+use adw::gtk::{self, Orientation};
+use adw::{
+    prelude::*, Breakpoint, BreakpointBin, BreakpointCondition, BreakpointConditionLengthType,
+    LengthUnit,
+};
+
+/// From this width on, the station switcher moves beside the track info. Narrower windows,
+/// such as on 360 px phones, keep everything stacked.
+const WIDE_WIDTH: f64 = 560.0;
+
+/// Put `body` in an overlay whose width decides the layout: wide windows show `content` as a
+/// row, with the `switcher` stacked vertically next to `details`. Returns the overlay to use
+/// in place of `body`.
+pub fn install(
+    body: &impl IsA<gtk::Widget>,
+    content: &gtk::Box,
+    switcher: &gtk::Box,
+    details: &impl IsA<gtk::Widget>,
+) -> gtk::Overlay {
+    // A breakpoint bin does not pass on its child's minimum size, so it only watches from an
+    // overlay; `body` is measured as before and the window still follows its height.
+    let bin = BreakpointBin::builder()
+        .width_request(1)
+        .height_request(1)
+        .can_target(false)
+        .build();
+    bin.set_child(Some(&gtk::Box::new(Orientation::Vertical, 0)));
+
+    let wide = Breakpoint::new(BreakpointCondition::new_length(
+        BreakpointConditionLengthType::MinWidth,
+        WIDE_WIDTH,
+        LengthUnit::Sp,
+    ));
+    wide.add_setters(&[
+        (
+            content.upcast_ref::<gtk::Widget>(),
+            "orientation",
+            Orientation::Horizontal.to_value(),
+        ),
+        (
+            switcher.upcast_ref(),
+            "orientation",
+            Orientation::Vertical.to_value(),
+        ),
+        (
+            switcher.upcast_ref(),
+            "valign",
+            gtk::Align::Start.to_value(),
+        ),
+        (details.upcast_ref(), "hexpand", true.to_value()),
+    ]);
+    bin.add_breakpoint(wide);
+
+    let overlay = gtk::Overlay::new();
+    overlay.set_child(Some(body));
+    overlay.add_overlay(&bin);
+    overlay
+}
@@ -0,0 +1,93 @@
+use adw::gtk::{
+    self,
+    gio::{Menu, SimpleAction},
+    glib, ApplicationWindow,
+};
+use adw::{prelude::*, ColorScheme, StyleManager};
+use gettextrs::gettext;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::settings::{Appearance, Settings};
+
+/// Applies the color scheme preference; by default the scheme follows the cover art.
+pub struct AppearanceManager {
+    style_manager: StyleManager,
+    settings: Rc<RefCell<Settings>>,
+    /// Lightness of the current cover, `None` while there is none.
+    cover_is_light: Cell<Option<bool>>,
+}
+
+impl AppearanceManager {
+    /// Add the `win.appearance` submenu and apply the persisted preference.
+    pub fn install(
+        window: &ApplicationWindow,
+        menu: &Menu,
+        settings: &Rc<RefCell<Settings>>,
+    ) -> Rc<Self> {
+        let manager = Rc::new(Self {
+            style_manager: StyleManager::default(),
+            settings: settings.clone(),
+            cover_is_light: Cell::new(None),
+        });
+        manager.apply();
+
+        let current = settings.borrow().appearance;
+        let action = SimpleAction::new_stateful(
+            "appearance",
+            Some(glib::VariantTy::STRING),
+            &current.name().to_variant(),
+        );
+        {
+            let manager = manager.clone();
+            action.connect_activate(move |action, param| {
+                let Some(appearance) = param.and_then(|p| p.str()).and_then(Appearance::from_name)
+                else {
+                    return;
+                };
+                action.set_state(&appearance.name().to_variant());
+                {
+                    let mut settings = manager.settings.borrow_mut();
+                    settings.appearance = appearance;
+                    settings.save();
+                }
+                manager.apply();
+            });
+        }
+        window.add_action(&action);
+
+        let submenu = gtk::gio::Menu::new();
+        for appearance in Appearance::ALL {
+            submenu.append(
+                Some(&label(appearance)),
+                Some(&format!("win.appearance::{}", appearance.name())),
+            );
+        }
+        menu.insert_submenu(menu.n_items() - 2, Some(&gettext("Appearance")), &submenu);
+        manager
+    }
+
+    /// A new cover was shown (`Some(is_light)`) or cleared (`None`).
+    pub fn set_cover(&self, cover_is_light: Option<bool>) {
+        self.cover_is_light.set(cover_is_light);
+        self.apply();
+    }
+
+    fn apply(&self) {
+        let scheme = match (self.settings.borrow().appearance, self.cover_is_light.get()) {
+            (Appearance::Cover, Some(true)) | (Appearance::Light, _) => ColorScheme::ForceLight,
+            (Appearance::Cover, Some(false)) | (Appearance::Dark, _) => ColorScheme::ForceDark,
+            (Appearance::Cover, None) | (Appearance::System, _) => ColorScheme::Default,
+        };
+        self.style_manager.set_color_scheme(scheme);
+    }
+}
+
+fn label(appearance: Appearance) -> String {
+    match appearance {
+        Appearance::Cover => gettext("Match cover"),
+        Appearance::System => gettext("Follow system"),
+        Appearance::Light => gettext("Light"),
+        Appearance::Dark => gettext("Dark"),
+    }
+}
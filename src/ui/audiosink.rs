@@ -0,0 +1,82 @@
+use adw::gtk::{
+    gio::{Menu, SimpleAction},
+    ApplicationWindow, Entry,
+};
+use adw::{prelude::*, AlertDialog, ResponseAppearance};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::listen::{Listen, SinkTarget};
+use crate::settings::Settings;
+
+/// Add the `win.audio_sink` action, which asks for a Snapcast server or other network sink
+/// to play to instead of this device.
+pub fn install(
+    window: &ApplicationWindow,
+    menu: &Menu,
+    radio: &Rc<Listen>,
+    settings: &Rc<RefCell<Settings>>,
+) {
+    let action = SimpleAction::new("audio_sink", None);
+    {
+        let window = window.clone();
+        let radio = radio.clone();
+        let settings = settings.clone();
+        action.connect_activate(move |_, _| request_sink(&window, &radio, &settings));
+    }
+    window.add_action(&action);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Audio output…")),
+        Some("win.audio_sink"),
+    );
+}
+
+fn request_sink(window: &ApplicationWindow, radio: &Rc<Listen>, settings: &Rc<RefCell<Settings>>) {
+    let entry = Entry::builder()
+        .placeholder_text("tcp://snapserver:4953")
+        .activates_default(true)
+        .build();
+    if let Some(sink) = settings.borrow().audio_sink.as_deref() {
+        entry.set_text(sink);
+    }
+    let dialog = AlertDialog::new(
+        Some(&gettext("Audio output")),
+        Some(&gettext(
+            "Send the audio as 48 kHz 16-bit stereo to a Snapcast server or another program, as tcp://host:port or the path of a FIFO like /tmp/snapfifo. Leave empty to play on this device. Applies the next time playback starts.",
+        )),
+    );
+    dialog.set_extra_child(Some(&entry));
+    dialog.add_response("cancel", &gettext("_Cancel"));
+    dialog.add_response("save", &gettext("_Save"));
+    dialog.set_response_appearance("save", ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("save"));
+    dialog.set_close_response("cancel");
+
+    let parent = window.clone();
+    let radio = radio.clone();
+    let settings = settings.clone();
+    dialog.connect_response(None, move |_, response| {
+        if response != "save" {
+            return;
+        }
+        let sink = entry.text().trim().to_string();
+        let target = SinkTarget::parse(&sink);
+        if !sink.is_empty() && target.is_none() {
+            let error = AlertDialog::new(
+                Some(&gettext("Invalid audio output")),
+                Some(&gettext("Use tcp://host:port or the path of a FIFO.")),
+            );
+            error.add_response("close", &gettext("_Close"));
+            error.present(Some(&parent));
+            return;
+        }
+        radio.set_audio_sink(target);
+        let mut settings = settings.borrow_mut();
+        settings.audio_sink = (!sink.is_empty()).then_some(sink);
+        settings.save();
+    });
+    dialog.present(Some(window));
+}
@@ -0,0 +1,178 @@
+use adw::gtk::{
+    self,
+    gdk::{
+        gdk_pixbuf::{InterpType::Bilinear, Pixbuf},
+        MemoryFormat, MemoryTexture,
+    },
+    gio::{Cancellable, MemoryInputStream},
+    Picture, Stack, StackTransitionType,
+};
+use adw::{glib, prelude::*};
+use std::cell::Cell;
+use std::sync::mpsc;
+use std::thread;
+
+/// Width the cover is reduced to before blurring; the result is scaled up by GTK.
+const BLUR_WIDTH: i32 = 96;
+const BLUR_RADIUS: usize = 5;
+const BLUR_PASSES: usize = 3;
+const DARKEN: f32 = 0.7;
+const CROSSFADE_MS: u32 = 600;
+
+/// A blurred cover as tightly packed RGBA pixels.
+#[derive(Debug)]
+pub struct BlurredImage {
+    width: i32,
+    height: i32,
+    rgba: Vec<u8>,
+}
+
+/// Blur and darken a cover on a worker thread and deliver the result through `tx`.
+pub fn blur_async(bytes: Vec<u8>, tx: mpsc::Sender<Result<BlurredImage, String>>) {
+    thread::spawn(move || {
+        let _ = tx.send(blur_cover_blocking(&bytes));
+    });
+}
+
+fn blur_cover_blocking(bytes: &[u8]) -> Result<BlurredImage, String> {
+    let stream = MemoryInputStream::from_bytes(&glib::Bytes::from(bytes));
+    let pixbuf = Pixbuf::from_stream(&stream, None::<&Cancellable>).map_err(|e| e.to_string())?;
+
+    let height = (BLUR_WIDTH * pixbuf.height() / pixbuf.width().max(1)).max(1);
+    let small = pixbuf
+        .scale_simple(BLUR_WIDTH, height, Bilinear)
+        .ok_or("failed to scale cover")?;
+
+    let (width, height) = (small.width() as usize, small.height() as usize);
+    let n_channels = small.n_channels() as usize;
+    let rowstride = small.rowstride() as usize;
+    let pixels = small.read_pixel_bytes();
+
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let i = y * rowstride + x * n_channels;
+            rgba.extend_from_slice(&pixels[i..i + 3]);
+            rgba.push(if n_channels == 4 { pixels[i + 3] } else { 255 });
+        }
+    }
+
+    for _ in 0..BLUR_PASSES {
+        box_blur(&mut rgba, width, height, BLUR_RADIUS, true);
+        box_blur(&mut rgba, width, height, BLUR_RADIUS, false);
+    }
+    for px in rgba.chunks_exact_mut(4) {
+        for c in &mut px[..3] {
+            *c = (*c as f32 * DARKEN) as u8;
+        }
+    }
+
+    Ok(BlurredImage {
+        width: width as i32,
+        height: height as i32,
+        rgba,
+    })
+}
+
+/// One separable box-blur pass; repeated passes approximate a gaussian.
+fn box_blur(rgba: &mut [u8], width: usize, height: usize, radius: usize, horizontal: bool) {
+    let (lines, len) = if horizontal {
+        (height, width)
+    } else {
+        (width, height)
+    };
+    let index = |line: usize, pos: usize| {
+        if horizontal {
+            (line * width + pos) * 4
+        } else {
+            (pos * width + line) * 4
+        }
+    };
+
+    let window = (2 * radius + 1) as u32;
+    let mut out = vec![[0u8; 4]; len];
+    for line in 0..lines {
+        let mut sum = [0u32; 4];
+        // Prime the window with edge-clamped samples.
+        for k in 0..window as usize {
+            let pos = (k as isize - radius as isize).clamp(0, len as isize - 1) as usize;
+            let i = index(line, pos);
+            for c in 0..4 {
+                sum[c] += rgba[i + c] as u32;
+            }
+        }
+        for (pos, px) in out.iter_mut().enumerate() {
+            for c in 0..4 {
+                px[c] = (sum[c] / window) as u8;
+            }
+            let add = index(line, (pos + radius + 1).min(len - 1));
+            let sub = index(line, pos.saturating_sub(radius));
+            for c in 0..4 {
+                sum[c] = sum[c] + rgba[add + c] as u32 - rgba[sub + c] as u32;
+            }
+        }
+        for (pos, px) in out.iter().enumerate() {
+            let i = index(line, pos);
+            rgba[i..i + 4].copy_from_slice(px);
+        }
+    }
+}
+
+/// Background layer that crossfades between blurred covers.
+pub struct Backdrop {
+    stack: Stack,
+    pictures: [Picture; 2],
+    front: Cell<usize>,
+}
+
+impl Backdrop {
+    pub fn new() -> Self {
+        let stack = Stack::builder()
+            .transition_type(StackTransitionType::Crossfade)
+            .transition_duration(CROSSFADE_MS)
+            .hexpand(true)
+            .vexpand(true)
+            .can_target(false)
+            .build();
+        stack.add_css_class("cover-backdrop");
+        stack.add_named(&gtk::Box::new(gtk::Orientation::Vertical, 0), Some("empty"));
+
+        let pictures = [(); 2].map(|_| {
+            // A blurred image has no detail to distort, so stretching to fill is fine.
+            let picture = Picture::builder()
+                .keep_aspect_ratio(false)
+                .can_shrink(true)
+                .build();
+            stack.add_child(&picture);
+            picture
+        });
+
+        Self {
+            stack,
+            pictures,
+            front: Cell::new(0),
+        }
+    }
+
+    pub fn widget(&self) -> &Stack {
+        &self.stack
+    }
+
+    pub fn show(&self, image: BlurredImage) {
+        let texture = MemoryTexture::new(
+            image.width,
+            image.height,
+            MemoryFormat::R8g8b8a8,
+            &glib::Bytes::from_owned(image.rgba),
+            image.width as usize * 4,
+        );
+        let next = 1 - self.front.get();
+        self.pictures[next].set_paintable(Some(&texture));
+        self.stack.set_visible_child(&self.pictures[next]);
+        self.front.set(next);
+    }
+
+    pub fn clear(&self) {
+        self.stack.set_visible_child_name("empty");
+    }
+}
@@ -0,0 +1,162 @@
+use adw::gtk::{
+    gio::{self, DBusCallFlags, DBusSignalFlags, Menu, SignalSubscription, SimpleAction},
+    ApplicationWindow,
+};
+use adw::{glib, prelude::*, Application};
+use gettextrs::gettext;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::env;
+use std::rc::Rc;
+
+use crate::settings::Settings;
+
+const PORTAL_BUS: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const REQUEST_TOKEN: &str = "listenmoe_background";
+
+/// Add the `win.run_in_background` toggle: while it is on, closing the window hides it and the
+/// stream keeps playing; starting the app again brings the window back. The
+/// `win.start_hidden` toggle starts the app that way. Inside Flatpak the toggles only show
+/// up once the Background portal allows it.
+pub fn install(
+    app: &Application,
+    window: &ApplicationWindow,
+    menu: &Menu,
+    settings: &Rc<RefCell<Settings>>,
+) {
+    let sandboxed = env::var_os("FLATPAK_ID").is_some();
+    let allowed = Rc::new(Cell::new(!sandboxed));
+    {
+        let allowed = allowed.clone();
+        let settings = settings.clone();
+        window.connect_close_request(move |window| {
+            if allowed.get() && settings.borrow().run_in_background {
+                window.set_visible(false);
+                glib::Propagation::Stop
+            } else {
+                glib::Propagation::Proceed
+            }
+        });
+    }
+
+    let enabled = settings.borrow().run_in_background;
+    let action = SimpleAction::new_stateful("run_in_background", None, &enabled.to_variant());
+    {
+        let settings = settings.clone();
+        action.connect_activate(move |action, _| {
+            let mut settings = settings.borrow_mut();
+            settings.run_in_background = !settings.run_in_background;
+            action.set_state(&settings.run_in_background.to_variant());
+            settings.save();
+        });
+    }
+    window.add_action(&action);
+
+    let enabled = settings.borrow().start_hidden;
+    let action = SimpleAction::new_stateful("start_hidden", None, &enabled.to_variant());
+    {
+        let settings = settings.clone();
+        action.connect_activate(move |action, _| {
+            let mut settings = settings.borrow_mut();
+            settings.start_hidden = !settings.start_hidden;
+            action.set_state(&settings.start_hidden.to_variant());
+            settings.save();
+        });
+    }
+    window.add_action(&action);
+
+    if !sandboxed {
+        add_menu_item(menu);
+        return;
+    }
+    let menu = menu.clone();
+    request_background(app, move |granted| {
+        allowed.set(granted);
+        if granted {
+            add_menu_item(&menu);
+        }
+    });
+}
+
+fn add_menu_item(menu: &Menu) {
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Keep playing when closed")),
+        Some("win.run_in_background"),
+    );
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Start in the background")),
+        Some("win.start_hidden"),
+    );
+}
+
+/// Ask the Background portal whether the app may keep running without a window, and call
+/// `on_result` with the answer. Nothing is called without a session bus.
+fn request_background(app: &Application, on_result: impl Fn(bool) + 'static) {
+    let Some(connection) = app.dbus_connection() else {
+        return;
+    };
+    let Some(sender) = connection.unique_name() else {
+        return;
+    };
+    // The portal answers on a request object whose path is derived from our bus name and
+    // token, so subscribe before calling to not miss a quick reply.
+    let handle = format!(
+        "{PORTAL_PATH}/request/{}/{REQUEST_TOKEN}",
+        sender.trim_start_matches(':').replace('.', "_")
+    );
+    let subscription: Rc<RefCell<Option<SignalSubscription>>> = Rc::new(RefCell::new(None));
+    let on_result = Rc::new(on_result);
+    {
+        let subscription_slot = subscription.clone();
+        let on_result = on_result.clone();
+        *subscription.borrow_mut() = Some(connection.subscribe_to_signal(
+            Some(PORTAL_BUS),
+            Some("org.freedesktop.portal.Request"),
+            Some("Response"),
+            Some(&handle),
+            None,
+            DBusSignalFlags::NONE,
+            move |signal| {
+                let granted = signal
+                    .parameters
+                    .get::<(u32, HashMap<String, glib::Variant>)>()
+                    .is_some_and(|(response, results)| {
+                        response == 0
+                            && results.get("background").and_then(|v| v.get::<bool>()) == Some(true)
+                    });
+                on_result(granted);
+                subscription_slot.borrow_mut().take();
+            },
+        ));
+    }
+
+    let options = glib::VariantDict::new(None);
+    options.insert("handle_token", REQUEST_TOKEN);
+    options.insert(
+        "reason",
+        gettext("Keep playing the radio with the window closed"),
+    );
+    options.insert("autostart", false);
+    let parameters = glib::Variant::tuple_from_iter(["".to_variant(), options.end()]);
+    connection.call(
+        Some(PORTAL_BUS),
+        PORTAL_PATH,
+        "org.freedesktop.portal.Background",
+        "RequestBackground",
+        Some(&parameters),
+        None,
+        DBusCallFlags::NONE,
+        -1,
+        None::<&gio::Cancellable>,
+        move |result| {
+            if let Err(err) = result {
+                eprintln!("Background portal request failed: {err}");
+                subscription.borrow_mut().take();
+                on_result(false);
+            }
+        },
+    );
+}
@@ -0,0 +1,112 @@
+use adw::gtk::{
+    self,
+    gio::{Menu, SimpleAction},
+    ApplicationWindow, Orientation, Scale,
+};
+use adw::{prelude::*, AlertDialog};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::listen::{ChannelMix, Listen};
+use crate::settings::Settings;
+
+/// Add the `win.mono_audio` toggle and the `win.balance` action, which opens a balance
+/// slider.
+pub fn install(
+    window: &ApplicationWindow,
+    menu: &Menu,
+    radio: &Rc<Listen>,
+    settings: &Rc<RefCell<Settings>>,
+) {
+    {
+        let settings = settings.borrow();
+        radio.set_channel_mix(ChannelMix {
+            mono: settings.mono_audio,
+            balance: settings.balance,
+        });
+    }
+
+    let mono = SimpleAction::new_stateful(
+        "mono_audio",
+        None,
+        &settings.borrow().mono_audio.to_variant(),
+    );
+    {
+        let radio = radio.clone();
+        let settings = settings.clone();
+        mono.connect_activate(move |action, _| {
+            let mut settings = settings.borrow_mut();
+            settings.mono_audio = !settings.mono_audio;
+            action.set_state(&settings.mono_audio.to_variant());
+            radio.set_channel_mix(ChannelMix {
+                mono: settings.mono_audio,
+                ..radio.channel_mix()
+            });
+            settings.save();
+        });
+    }
+    window.add_action(&mono);
+
+    let balance = SimpleAction::new("balance", None);
+    {
+        let window = window.clone();
+        let radio = radio.clone();
+        let settings = settings.clone();
+        balance.connect_activate(move |_, _| show_balance(&window, &radio, &settings));
+    }
+    window.add_action(&balance);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Mono audio")),
+        Some("win.mono_audio"),
+    );
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Balance…")),
+        Some("win.balance"),
+    );
+}
+
+/// The balance applies while sliding and is saved when the dialog closes.
+fn show_balance(window: &ApplicationWindow, radio: &Rc<Listen>, settings: &Rc<RefCell<Settings>>) {
+    let scale = Scale::with_range(Orientation::Horizontal, -1.0, 1.0, 0.05);
+    scale.set_value(settings.borrow().balance.into());
+    scale.set_width_request(260);
+    scale.add_mark(-1.0, gtk::PositionType::Bottom, Some(&gettext("Left")));
+    scale.add_mark(0.0, gtk::PositionType::Bottom, Some(&gettext("Center")));
+    scale.add_mark(1.0, gtk::PositionType::Bottom, Some(&gettext("Right")));
+    scale.update_property(&[gtk::accessible::Property::Label(&gettext("Balance"))]);
+    {
+        let radio = radio.clone();
+        scale.connect_value_changed(move |scale| {
+            radio.set_channel_mix(ChannelMix {
+                balance: scale.value() as f32,
+                ..radio.channel_mix()
+            });
+        });
+    }
+
+    let dialog = AlertDialog::new(Some(&gettext("Balance")), None);
+    dialog.set_extra_child(Some(&scale));
+    dialog.add_response("center", &gettext("C_enter"));
+    dialog.add_response("close", &gettext("_Close"));
+    dialog.set_default_response(Some("close"));
+    dialog.set_close_response("close");
+
+    let radio = radio.clone();
+    let settings = settings.clone();
+    dialog.connect_response(None, move |_, response| {
+        if response == "center" {
+            radio.set_channel_mix(ChannelMix {
+                balance: 0.0,
+                ..radio.channel_mix()
+            });
+        }
+        let mut settings = settings.borrow_mut();
+        settings.balance = radio.channel_mix().balance;
+        settings.save();
+    });
+    dialog.present(Some(window));
+}
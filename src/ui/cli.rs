@@ -0,0 +1,169 @@
+use adw::gtk::{gio::ApplicationCommandLine, ApplicationWindow};
+use adw::{glib, prelude::*, Application};
+use gettextrs::gettext;
+
+use crate::locale::t;
+#[cfg(all(target_os = "linux", feature = "setup"))]
+use crate::settings::Settings;
+#[cfg(all(target_os = "linux", feature = "setup"))]
+use crate::setup;
+use crate::station::Station;
+
+/// Register the command-line options. The primary instance handles them, also when
+/// they are passed to a second invocation, so they act on the running window.
+pub fn install_command_line(app: &Application) {
+    app.add_main_option(
+        "station",
+        glib::Char::from(b's'),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::String,
+        &gettext("Switch to a station (jpop or kpop)"),
+        Some("STATION"),
+    );
+    app.add_main_option(
+        "play",
+        glib::Char::from(b'p'),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::None,
+        &gettext("Start playback"),
+        None,
+    );
+    app.add_main_option(
+        "paused",
+        glib::Char::from(0),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::None,
+        &gettext("Start without audio, or pause a running instance"),
+        None,
+    );
+    app.add_main_option(
+        "hidden",
+        glib::Char::from(0),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::None,
+        &gettext("Start without showing the window"),
+        None,
+    );
+    app.add_main_option(
+        "quit",
+        glib::Char::from(b'q'),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::None,
+        &gettext("Quit the running instance"),
+        None,
+    );
+    #[cfg(all(target_os = "linux", feature = "setup"))]
+    app.add_main_option(
+        "uninstall",
+        glib::Char::from(0),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::None,
+        &gettext("Remove the desktop file, icons and cached data, then exit"),
+        None,
+    );
+    app.connect_command_line(handle_command_line);
+}
+
+fn handle_command_line(app: &Application, cmdline: &ApplicationCommandLine) -> glib::ExitCode {
+    let options = cmdline.options_dict();
+    let running = app.active_window();
+
+    #[cfg(all(target_os = "linux", feature = "setup"))]
+    if options.contains("uninstall") {
+        return uninstall(app);
+    }
+    if options.contains("quit") {
+        match running {
+            Some(window) => activate(&window, "win.quit"),
+            None => app.quit(),
+        }
+        return glib::ExitCode::SUCCESS;
+    }
+    if options.contains("play") && options.contains("paused") {
+        eprintln!("{}", gettext("--play and --paused cannot be combined"));
+        return glib::ExitCode::FAILURE;
+    }
+    let station = match options.lookup::<String>("station") {
+        Ok(Some(name)) => match Station::from_name(&name) {
+            Some(station) => Some(station),
+            None => {
+                eprintln!("{}", t!("Unknown station: {}", name));
+                return glib::ExitCode::FAILURE;
+            }
+        },
+        _ => None,
+    };
+
+    let was_running = running.is_some();
+    let hidden = options.contains("hidden");
+    let window = match running {
+        Some(window) => {
+            if !hidden {
+                window.present();
+            }
+            window
+        }
+        None => {
+            super::window::build(app, hidden);
+            match app.active_window() {
+                Some(window) => window,
+                None => return glib::ExitCode::FAILURE,
+            }
+        }
+    };
+    let Ok(window) = window.downcast::<ApplicationWindow>() else {
+        return glib::ExitCode::FAILURE;
+    };
+
+    if let Some(station) = station {
+        activate(&window, &format!("win.{}", station.name()));
+    }
+    if options.contains("play") {
+        activate(&window, "win.play");
+    } else if options.contains("paused") && was_running {
+        // A fresh window starts without audio anyway.
+        activate(&window, "win.pause");
+    }
+    glib::ExitCode::SUCCESS
+}
+
+/// Turn the desktop integration off, so the next start does not bring it back, and remove
+/// the installed files.
+#[cfg(all(target_os = "linux", feature = "setup"))]
+fn uninstall(app: &Application) -> glib::ExitCode {
+    let toggle = app
+        .active_window()
+        .and_downcast::<ApplicationWindow>()
+        .and_then(|window| window.lookup_action("desktop_integration"));
+    match toggle {
+        // The running window owns the settings and would save over a change made here.
+        Some(action) => {
+            if action.state().and_then(|state| state.get::<bool>()) == Some(true) {
+                action.activate(None);
+            }
+        }
+        None => {
+            let mut settings = Settings::load();
+            settings.desktop_integration = false;
+            settings.save();
+        }
+    }
+
+    match setup::uninstall() {
+        Ok(removed) if removed.is_empty() => println!("{}", gettext("Nothing to remove")),
+        Ok(removed) => {
+            for path in removed {
+                println!("{}", t!("Removed {}", path.display()));
+            }
+        }
+        Err(err) => {
+            eprintln!("{}", t!("Uninstall failed: {}", err));
+            return glib::ExitCode::FAILURE;
+        }
+    }
+    glib::ExitCode::SUCCESS
+}
+
+fn activate(window: &impl IsA<adw::gtk::Widget>, action: &str) {
+    let _ = WidgetExt::activate_action(window, action, None::<&glib::Variant>);
+}
@@ -2,9 +2,20 @@ use adw::gtk;
 use adw::gtk::gdk::gdk_pixbuf::{InterpType::Bilinear, Pixbuf};
 use adw::gtk::gdk::Display;
 use std::error::Error;
+use std::sync::mpsc;
+use std::thread;
 
-pub fn fetch_cover_bytes_blocking(url: &str) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
-    let resp = reqwest::blocking::get(url)?;
+use crate::cache::DiskCache;
+
+/// Upper bound for the on-disk cover cache.
+const COVER_CACHE_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+pub fn fetch_cover_bytes_blocking(
+    url: &str,
+    proxy: Option<&str>,
+) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let client = crate::proxy::apply(reqwest::blocking::Client::builder(), proxy)?.build()?;
+    let resp = client.get(url).send()?;
     if !resp.status().is_success() {
         return Err(format!("Non-success status: {}", resp.status()).into());
     }
@@ -12,6 +23,38 @@ pub fn fetch_cover_bytes_blocking(url: &str) -> Result<Vec<u8>, Box<dyn Error +
     Ok(body.to_vec())
 }
 
+/// Load cover bytes from the disk cache, downloading (and caching) them on a miss.
+/// Blocking; call from a worker thread.
+pub fn load_cover_bytes_blocking(
+    url: &str,
+    proxy: Option<&str>,
+) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let cache = DiskCache::open("covers", COVER_CACHE_MAX_BYTES);
+    if let Some(bytes) = cache.as_ref().and_then(|c| c.get(url)) {
+        return Ok(bytes);
+    }
+    let bytes = fetch_cover_bytes_blocking(url, proxy)?;
+    if let Some(cache) = cache {
+        if let Err(err) = cache.put(url, &bytes) {
+            eprintln!("Failed to cache cover {url}: {err}");
+        }
+    }
+    Ok(bytes)
+}
+
+/// Load a cover on a worker thread and deliver the result through `tx`,
+/// so the GTK main loop never waits on disk or network.
+pub fn load_cover_async(
+    url: String,
+    proxy: Option<String>,
+    tx: mpsc::Sender<Result<Vec<u8>, String>>,
+) {
+    thread::spawn(move || {
+        let result = load_cover_bytes_blocking(&url, proxy.as_deref()).map_err(|e| e.to_string());
+        let _ = tx.send(result);
+    });
+}
+
 pub fn install_css_provider() -> gtk::CssProvider {
     let provider = gtk::CssProvider::new();
     if let Some(display) = Display::default() {
@@ -73,7 +116,93 @@ pub fn avg_rgb_from_pixbuf(pixbuf: &Pixbuf) -> (u8, u8, u8) {
     )
 }
 
-pub fn apply_color(provider: &gtk::CssProvider, tint: (u8, u8, u8), tint_is_light: bool) {
+/// The most common color of a cover, ignoring near-gray pixels when there are enough
+/// colorful ones. Better suited as an accent than the average, which tends towards mud.
+pub fn dominant_rgb_from_pixbuf(pixbuf: &Pixbuf) -> (u8, u8, u8) {
+    let small = pixbuf
+        .scale_simple(48, 48, Bilinear)
+        .unwrap_or_else(|| pixbuf.clone());
+
+    let w = small.width() as usize;
+    let h = small.height() as usize;
+    let n_channels = small.n_channels() as usize;
+    let rowstride = small.rowstride() as usize;
+    let pixels = small.read_pixel_bytes();
+
+    // 4 bits per channel: 4096 buckets of (count, r, g, b) sums.
+    let mut colorful = vec![(0u32, 0u32, 0u32, 0u32); 4096];
+    let mut all = vec![(0u32, 0u32, 0u32, 0u32); 4096];
+    for y in 0..h {
+        for x in 0..w {
+            let i = y * rowstride + x * n_channels;
+            if n_channels == 4 && pixels[i + 3] < 20 {
+                continue;
+            }
+            let (r, g, b) = (pixels[i], pixels[i + 1], pixels[i + 2]);
+            let bucket = (r as usize >> 4) << 8 | (g as usize >> 4) << 4 | (b as usize >> 4);
+
+            let max = r.max(g).max(b);
+            let min = r.min(g).min(b);
+            let is_colorful = max - min > 40 && max > 40;
+
+            let add = |e: &mut (u32, u32, u32, u32)| {
+                e.0 += 1;
+                e.1 += r as u32;
+                e.2 += g as u32;
+                e.3 += b as u32;
+            };
+            add(&mut all[bucket]);
+            if is_colorful {
+                add(&mut colorful[bucket]);
+            }
+        }
+    }
+
+    let total: u32 = all.iter().map(|e| e.0).sum();
+    let colorful_total: u32 = colorful.iter().map(|e| e.0).sum();
+    let hist = if colorful_total * 10 >= total {
+        &colorful
+    } else {
+        &all
+    };
+
+    match hist.iter().max_by_key(|e| e.0) {
+        Some(&(n, r, g, b)) if n > 0 => ((r / n) as u8, (g / n) as u8, (b / n) as u8),
+        _ => (128, 128, 128),
+    }
+}
+
+/// CSS for widgets that follow the accent: progress bars, toggles and indicators.
+fn accent_css(accent: (u8, u8, u8)) -> String {
+    let (r, g, b) = accent;
+    let (fr, fg, fb) = if is_light_color(r, g, b) {
+        (0, 0, 0)
+    } else {
+        (255, 255, 255)
+    };
+    format!(
+        r#"
+        @define-color cover_accent rgb({r} {g} {b});
+        .accent-tint {{
+            color: rgb({r} {g} {b});
+        }}
+        progressbar.accent-tint > trough > progress {{
+            background-color: rgb({r} {g} {b});
+        }}
+        button.accent-tint:checked {{
+            background: rgb({r} {g} {b});
+            color: rgb({fr} {fg} {fb});
+        }}
+        "#
+    )
+}
+
+pub fn apply_color(
+    provider: &gtk::CssProvider,
+    tint: (u8, u8, u8),
+    tint_is_light: bool,
+    accent: (u8, u8, u8),
+) {
     let (r, g, b) = tint;
 
     // Foreground for buttons/text
@@ -115,6 +244,10 @@ pub fn apply_color(provider: &gtk::CssProvider, tint: (u8, u8, u8), tint_is_ligh
             color: rgb({vr} {vg} {vb});
         }}
 
+        .cover-backdrop {{
+            opacity: 0.45;
+        }}
+
         headerbar.viz-transparent {{
             background: transparent;
             box-shadow: none;
@@ -144,21 +277,24 @@ pub fn apply_color(provider: &gtk::CssProvider, tint: (u8, u8, u8), tint_is_ligh
             background: rgb({r} {g} {b});
         }}
         "#
-    );
+    ) + &accent_css(accent);
 
     provider.load_from_data(&css);
 }
 
-pub fn apply_cover_tint_css_clear(provider: &gtk::CssProvider) {
-    provider.load_from_data(
+/// Reset the cover tint; `accent` is the station color used while there is no cover.
+pub fn apply_cover_tint_css_clear(provider: &gtk::CssProvider, accent: (u8, u8, u8)) {
+    let (r, g, b) = accent;
+    let css = format!(
         r#"
-        .titlebar-tint { background: transparent; }
-        .header-viz { color: @accent_color; }
-        .header-viz:backdrop { color: @accent_color; }
-        headerbar.viz-transparent { background: transparent; box-shadow: none; }
-        headerbar.viz-transparent:backdrop { background: transparent; box-shadow: none; }
+        .titlebar-tint {{ background: transparent; }}
+        .header-viz {{ color: rgb({r} {g} {b}); }}
+        .header-viz:backdrop {{ color: rgb({r} {g} {b}); }}
+        headerbar.viz-transparent {{ background: transparent; box-shadow: none; }}
+        headerbar.viz-transparent:backdrop {{ background: transparent; box-shadow: none; }}
         "#
-    );
+    ) + &accent_css(accent);
+    provider.load_from_data(&css);
 }
 
 pub fn is_light_color(r: u8, g: u8, b: u8) -> bool {
@@ -0,0 +1,151 @@
+//! Panic hook that reports crashes instead of letting the app silently vanish.
+//!
+//! Release builds on Windows have no console, so the report is also written to the cache
+//! directory and, while GTK is still usable, shown in a dialog that can open its folder or
+//! copy it.
+
+use adw::gtk::{self, gio, glib};
+use adw::{prelude::*, AlertDialog, ResponseAppearance};
+use dirs_next as dirs;
+use gettextrs::gettext;
+use std::backtrace::Backtrace;
+use std::cell::Cell;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::PathBuf;
+use std::thread;
+
+use crate::locale::t;
+
+thread_local! {
+    /// Set while the dialog is up, so a panic inside it does not open another one.
+    static REPORTING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Replace the default panic hook. Call once, before the UI is built.
+pub fn install_panic_hook() {
+    panic::set_hook(Box::new(|info| {
+        let report = report(info);
+        eprintln!("{report}");
+        let saved = save(&report);
+        if REPORTING.with(|reporting| reporting.replace(true)) {
+            return;
+        }
+        if gtk::is_initialized_main_thread() {
+            // The panic unwinds into GTK once we return, which ends the process, so wait here
+            // until the user is done with the report. The clipboard is gone with the process,
+            // so copying keeps the dialog open.
+            let main_loop = glib::MainLoop::new(None, false);
+            let dialog = dialog(report, saved);
+            {
+                let main_loop = main_loop.clone();
+                dialog.connect_response(None, move |_, response| {
+                    if response != "copy" {
+                        main_loop.quit();
+                    }
+                });
+            }
+            main_loop.run();
+        } else if gtk::is_initialized() {
+            // A worker thread died; the window keeps running and can show the report.
+            glib::MainContext::default().invoke(move || {
+                dialog(report, saved);
+            });
+        }
+        REPORTING.with(|reporting| reporting.set(false));
+    }));
+}
+
+fn report(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    let location = info
+        .location()
+        .map(|location| location.to_string())
+        .unwrap_or_default();
+    format!(
+        "{} {} crashed\nthread '{}' panicked at {location}:\n{message}\n\n{}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        thread::current().name().unwrap_or("<unnamed>"),
+        Backtrace::force_capture(),
+    )
+}
+
+/// Write `report` to `crash.log` in the cache directory, overwriting the previous one.
+fn save(report: &str) -> Option<PathBuf> {
+    let path = dirs::cache_dir()?.join(crate::APP_ID).join("crash.log");
+    let written = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, report));
+    match written {
+        Ok(()) => {
+            eprintln!("Crash report written to {}", path.display());
+            Some(path)
+        }
+        Err(err) => {
+            eprintln!("Failed to write crash report: {err}");
+            None
+        }
+    }
+}
+
+fn dialog(report: String, saved: Option<PathBuf>) -> AlertDialog {
+    let body = match &saved {
+        Some(path) => t!("Something went wrong. A report was saved to {}.", path.display()),
+        None => gettext("Something went wrong."),
+    };
+    let text = gtk::TextView::new();
+    text.set_editable(false);
+    text.set_monospace(true);
+    text.set_wrap_mode(gtk::WrapMode::WordChar);
+    text.buffer().set_text(&report);
+    let scroller = gtk::ScrolledWindow::new();
+    scroller.set_min_content_height(200);
+    scroller.set_child(Some(&text));
+
+    let dialog = AlertDialog::new(Some(&gettext("LISTEN.moe ran into a problem")), Some(&body));
+    dialog.set_extra_child(Some(&scroller));
+    dialog.add_response("close", &gettext("_Close"));
+    if let Some(folder) = saved.as_ref().and_then(|path| path.parent()) {
+        dialog.add_response("folder", &gettext("_Open folder"));
+        let uri = gio::File::for_path(folder).uri();
+        dialog.connect_response(Some("folder"), move |_, _| {
+            if let Err(err) =
+                gio::AppInfo::launch_default_for_uri(&uri, None::<&gio::AppLaunchContext>)
+            {
+                eprintln!("Failed to open {uri}: {err}");
+            }
+        });
+    }
+    dialog.add_response("copy", &gettext("_Copy report"));
+    dialog.set_response_appearance("copy", ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("copy"));
+    dialog.set_close_response("close");
+
+    let parent = gio::Application::default()
+        .and_downcast::<gtk::Application>()
+        .and_then(|app| app.active_window());
+    dialog.connect_response(Some("copy"), {
+        let parent = parent.clone();
+        move |dialog, _| {
+            dialog.clipboard().set_text(&report);
+            // Responses close the dialog; bring it back so the report can still be pasted.
+            dialog.set_body(&gettext(
+                "The report is on the clipboard. Keep this open until you have pasted it.",
+            ));
+            glib::idle_add_local_once({
+                let dialog = dialog.clone();
+                let parent = parent.clone();
+                move || dialog.present(parent.as_ref())
+            });
+        }
+    });
+    dialog.present(parent.as_ref());
+    dialog
+}
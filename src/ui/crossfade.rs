@@ -0,0 +1,80 @@
+use adw::gtk;
+use adw::prelude::*;
+use adw::{CallbackAnimationTarget, TimedAnimation};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const FADE_OUT_MS: u32 = 120;
+const FADE_IN_MS: u32 = 200;
+
+type Change = Box<dyn FnOnce()>;
+
+/// Fades widgets out, applies a change and fades them back in, so new track info does not
+/// jump in. Adwaita animations finish at once when the system asks for reduced motion, and
+/// then the change is applied straight away.
+pub struct Crossfade {
+    widgets: Vec<gtk::Widget>,
+    fade_out: TimedAnimation,
+    fade_in: TimedAnimation,
+    pending: Rc<RefCell<Option<Change>>>,
+}
+
+impl Crossfade {
+    /// `widgets` fade together, timed by `clock`: animations are skipped on a widget that
+    /// is not shown, such as the cover in a closed popover, so pick one that always is.
+    pub fn new(clock: &impl IsA<gtk::Widget>, widgets: &[gtk::Widget]) -> Self {
+        let set_opacity = {
+            let widgets = widgets.to_vec();
+            move |value: f64| widgets.iter().for_each(|w| w.set_opacity(value))
+        };
+        let fade_out = TimedAnimation::new(
+            clock,
+            1.0,
+            0.0,
+            FADE_OUT_MS,
+            CallbackAnimationTarget::new(set_opacity.clone()),
+        );
+        let fade_in = TimedAnimation::new(
+            clock,
+            0.0,
+            1.0,
+            FADE_IN_MS,
+            CallbackAnimationTarget::new(set_opacity),
+        );
+
+        let pending: Rc<RefCell<Option<Change>>> = Rc::new(RefCell::new(None));
+        {
+            let pending = pending.clone();
+            let fade_in = fade_in.clone();
+            fade_out.connect_done(move |_| {
+                let change = pending.borrow_mut().take();
+                if let Some(change) = change {
+                    change();
+                }
+                fade_in.play();
+            });
+        }
+        Self {
+            widgets: widgets.to_vec(),
+            fade_out,
+            fade_in,
+            pending,
+        }
+    }
+
+    /// Swap in new content; while a swap is still fading out, only the latest one applies.
+    pub fn swap(&self, change: impl FnOnce() + 'static) {
+        let fading = self
+            .pending
+            .borrow_mut()
+            .replace(Box::new(change))
+            .is_some();
+        if !fading {
+            // Out from wherever a previous fade-in got to.
+            self.fade_in.pause();
+            let opacity = self.widgets.first().map_or(1.0, |w| w.opacity());
+            self.fade_out.set_value_from(opacity);
+            self.fade_out.play();
+        }
+    }
+}
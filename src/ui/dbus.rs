@@ -0,0 +1,153 @@
+use adw::gtk::{
+    gio::{self, DBusConnection, DBusMethodInvocation},
+    ApplicationWindow,
+};
+use adw::{glib, prelude::*, Application};
+use std::rc::Rc;
+
+use super::search::SessionHistory;
+use crate::listen::Listen;
+use crate::meta::TrackInfo;
+use crate::station::Station;
+
+const INTERFACE: &str = "dev.noobping.petal.Control";
+const INTROSPECTION: &str = r#"
+<node>
+  <interface name="dev.noobping.petal.Control">
+    <method name="Play"/>
+    <method name="Pause"/>
+    <method name="SetStation">
+      <arg name="station" type="s" direction="in"/>
+    </method>
+    <method name="GetCurrentTrack">
+      <arg name="artist" type="s" direction="out"/>
+      <arg name="title" type="s" direction="out"/>
+      <arg name="station" type="s" direction="out"/>
+      <arg name="duration" type="u" direction="out"/>
+    </method>
+    <signal name="TrackChanged">
+      <arg name="artist" type="s"/>
+      <arg name="title" type="s"/>
+      <arg name="station" type="s"/>
+      <arg name="duration" type="u"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+/// A small scripting interface on the application's object path, simpler to use from
+/// status bars than MPRIS. Stations are named as on the command line (`jpop`, `kpop`).
+pub struct ControlInterface {
+    connection: DBusConnection,
+    path: String,
+    radio: Rc<Listen>,
+}
+
+impl ControlInterface {
+    /// Export the interface; `None` when the application has no session bus connection.
+    pub fn register(
+        app: &Application,
+        window: &ApplicationWindow,
+        radio: &Rc<Listen>,
+        history: &SessionHistory,
+    ) -> Option<Self> {
+        let connection = app.dbus_connection()?;
+        let path = app.dbus_object_path()?.to_string();
+        let info = gio::DBusNodeInfo::for_xml(INTROSPECTION)
+            .ok()?
+            .lookup_interface(INTERFACE)?;
+
+        let window = window.clone();
+        let handler_radio = radio.clone();
+        let history = history.clone();
+        let registered = connection
+            .register_object(&path, &info)
+            .method_call(move |_, _, _, _, method, params, invocation| {
+                handle_call(
+                    &window,
+                    &handler_radio,
+                    &history,
+                    method,
+                    &params,
+                    invocation,
+                );
+            })
+            .build();
+        if let Err(err) = registered {
+            eprintln!("Failed to export {INTERFACE}: {err}");
+            return None;
+        }
+
+        Some(Self {
+            connection,
+            path,
+            radio: radio.clone(),
+        })
+    }
+
+    pub fn track_changed(&self, info: &TrackInfo) {
+        let params = track_tuple(Some(info), self.radio.get_station()).to_variant();
+        if let Err(err) =
+            self.connection
+                .emit_signal(None, &self.path, INTERFACE, "TrackChanged", Some(&params))
+        {
+            eprintln!("Failed to emit TrackChanged: {err}");
+        }
+    }
+}
+
+fn handle_call(
+    window: &ApplicationWindow,
+    radio: &Listen,
+    history: &SessionHistory,
+    method: &str,
+    params: &glib::Variant,
+    invocation: DBusMethodInvocation,
+) {
+    match method {
+        "Play" => activate(window, "win.play"),
+        "Pause" => activate(window, "win.pause"),
+        "SetStation" => {
+            let station = params
+                .get::<(String,)>()
+                .and_then(|(name,)| Station::from_name(&name));
+            match station {
+                Some(station) => activate(window, &format!("win.{}", station.name())),
+                None => {
+                    invocation.return_dbus_error(
+                        "org.freedesktop.DBus.Error.InvalidArgs",
+                        "Unknown station; expected jpop or kpop",
+                    );
+                    return;
+                }
+            }
+        }
+        "GetCurrentTrack" => {
+            let track = track_tuple(history.borrow().back(), radio.get_station());
+            invocation.return_value(Some(&track.to_variant()));
+            return;
+        }
+        _ => {
+            invocation.return_dbus_error(
+                "org.freedesktop.DBus.Error.UnknownMethod",
+                &format!("Unknown method {method}"),
+            );
+            return;
+        }
+    }
+    invocation.return_value(None);
+}
+
+/// Empty strings and a zero duration before the first track arrives.
+fn track_tuple(info: Option<&TrackInfo>, station: Station) -> (String, String, String, u32) {
+    (
+        info.map(|i| i.artist.clone()).unwrap_or_default(),
+        info.map(|i| i.title.clone()).unwrap_or_default(),
+        station.name().to_string(),
+        info.map_or(0, |i| i.duration_secs),
+    )
+}
+
+fn activate(window: &ApplicationWindow, action: &str) {
+    let _ = WidgetExt::activate_action(window, action, None::<&glib::Variant>);
+}
@@ -0,0 +1,82 @@
+use adw::gtk::{
+    self,
+    gio::{self, Menu, SimpleAction},
+    glib, ApplicationWindow,
+};
+use adw::{prelude::*, AlertDialog};
+use dirs_next as dirs;
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::diagnostics::Diagnostics;
+use crate::listen::Listen;
+use crate::locale::t;
+use crate::settings::Settings;
+
+/// Add the `win.export_diagnostics` action, which saves a diagnostics zip to the
+/// downloads folder. The endpoints are probed off the main thread.
+pub fn install(
+    window: &ApplicationWindow,
+    menu: &Menu,
+    radio: &Rc<Listen>,
+    settings: &Rc<RefCell<Settings>>,
+    diagnostics: &Rc<RefCell<Diagnostics>>,
+) {
+    let action = SimpleAction::new("export_diagnostics", None);
+    {
+        let window = window.clone();
+        let radio = radio.clone();
+        let settings = settings.clone();
+        let diagnostics = diagnostics.clone();
+        action.connect_activate(move |_, _| {
+            let Some(dir) = dirs::download_dir().or_else(dirs::home_dir) else {
+                return;
+            };
+            let diagnostics = diagnostics.borrow().clone();
+            let settings = settings.borrow().clone();
+            let station = radio.get_station();
+            let toolkit = toolkit_versions();
+            let window = window.clone();
+            glib::MainContext::default().spawn_local(async move {
+                let result = gio::spawn_blocking(move || {
+                    diagnostics.export(&dir, &settings, station, &toolkit)
+                })
+                .await
+                .unwrap_or_else(|_| Err(std::io::Error::other("diagnostics export panicked")));
+                let (heading, body) = match result {
+                    Ok(path) => (gettext("Diagnostics exported"), path.display().to_string()),
+                    Err(err) => {
+                        eprintln!("Failed to export diagnostics: {err}");
+                        (
+                            gettext("Export failed"),
+                            t!("Could not write the diagnostics file: {}", err),
+                        )
+                    }
+                };
+                let dialog = AlertDialog::new(Some(&heading), Some(&body));
+                dialog.add_response("close", &gettext("_Close"));
+                dialog.present(Some(&window));
+            });
+        });
+    }
+    window.add_action(&action);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Export diagnostics")),
+        Some("win.export_diagnostics"),
+    );
+}
+
+fn toolkit_versions() -> String {
+    format!(
+        "gtk {}.{}.{}\nlibadwaita {}.{}.{}",
+        gtk::major_version(),
+        gtk::minor_version(),
+        gtk::micro_version(),
+        adw::major_version(),
+        adw::minor_version(),
+        adw::micro_version(),
+    )
+}
@@ -0,0 +1,180 @@
+use adw::gtk::{
+    self,
+    gio::{Menu, SimpleAction},
+    ApplicationWindow, DropDown, Orientation, Scale,
+};
+use adw::{prelude::*, AlertDialog};
+use gettextrs::gettext;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::listen::{Gains, Listen, Preset, BANDS, MAX_GAIN_DB};
+use crate::settings::Settings;
+
+/// Add the `win.equalizer` action, which opens the ten-band equalizer, and the
+/// `win.normalize_loudness` toggle.
+pub fn install(
+    window: &ApplicationWindow,
+    menu: &Menu,
+    radio: &Rc<Listen>,
+    settings: &Rc<RefCell<Settings>>,
+) {
+    let action = SimpleAction::new("equalizer", None);
+    {
+        let window = window.clone();
+        let radio = radio.clone();
+        let settings = settings.clone();
+        action.connect_activate(move |_, _| show_equalizer(&window, &radio, &settings));
+    }
+    window.add_action(&action);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Equalizer…")),
+        Some("win.equalizer"),
+    );
+
+    let enabled = settings.borrow().normalize_loudness;
+    let action = SimpleAction::new_stateful("normalize_loudness", None, &enabled.to_variant());
+    {
+        let radio = radio.clone();
+        let settings = settings.clone();
+        action.connect_activate(move |action, _| {
+            let mut settings = settings.borrow_mut();
+            settings.normalize_loudness = !settings.normalize_loudness;
+            action.set_state(&settings.normalize_loudness.to_variant());
+            radio.set_normalize(settings.normalize_loudness);
+            settings.save();
+        });
+    }
+    window.add_action(&action);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Even out loudness")),
+        Some("win.normalize_loudness"),
+    );
+}
+
+fn preset_name(preset: Preset) -> String {
+    match preset {
+        Preset::Flat => gettext("Flat"),
+        Preset::BassBoost => gettext("Bass boost"),
+        Preset::Vocal => gettext("Vocal"),
+    }
+}
+
+/// Position in the preset list; "Custom" comes last.
+fn preset_index(gains: &Gains) -> u32 {
+    Preset::matching(gains)
+        .and_then(|preset| Preset::ALL.iter().position(|p| *p == preset))
+        .unwrap_or(Preset::ALL.len()) as u32
+}
+
+fn band_label(freq: f32) -> String {
+    if freq >= 1000.0 {
+        format!("{}k", freq / 1000.0)
+    } else {
+        format!("{freq}")
+    }
+}
+
+/// Changes apply while the dialog is open and are saved when it closes.
+fn show_equalizer(
+    window: &ApplicationWindow,
+    radio: &Rc<Listen>,
+    settings: &Rc<RefCell<Settings>>,
+) {
+    let gains = Rc::new(Cell::new(
+        settings.borrow().equalizer.unwrap_or(Preset::Flat.gains()),
+    ));
+
+    let mut names: Vec<String> = Preset::ALL.into_iter().map(preset_name).collect();
+    names.push(gettext("Custom"));
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let presets = DropDown::from_strings(&names);
+    presets.set_selected(preset_index(&gains.get()));
+    presets.set_halign(gtk::Align::Center);
+
+    let bands = gtk::Box::new(Orientation::Horizontal, 6);
+    bands.set_halign(gtk::Align::Center);
+    let mut sliders = Vec::with_capacity(BANDS.len());
+    for (i, freq) in BANDS.iter().enumerate() {
+        let scale = Scale::with_range(
+            Orientation::Vertical,
+            (-MAX_GAIN_DB).into(),
+            MAX_GAIN_DB.into(),
+            0.5,
+        );
+        scale.set_inverted(true);
+        scale.set_height_request(160);
+        scale.set_value(gains.get()[i].into());
+        scale.add_mark(0.0, gtk::PositionType::Right, None);
+        scale.update_property(&[gtk::accessible::Property::Label(&format!(
+            "{} Hz",
+            band_label(*freq)
+        ))]);
+        let label = gtk::Label::new(Some(&band_label(*freq)));
+        label.add_css_class("caption");
+        let column = gtk::Box::new(Orientation::Vertical, 4);
+        column.append(&scale);
+        column.append(&label);
+        bands.append(&column);
+        sliders.push(scale);
+    }
+
+    // Moving the sliders from a preset must not count as a custom change.
+    let syncing = Rc::new(Cell::new(false));
+    for (i, scale) in sliders.iter().enumerate() {
+        let gains = gains.clone();
+        let presets = presets.clone();
+        let radio = radio.clone();
+        let syncing = syncing.clone();
+        scale.connect_value_changed(move |scale| {
+            let mut current = gains.get();
+            current[i] = scale.value() as f32;
+            gains.set(current);
+            radio.set_equalizer(Some(current));
+            if !syncing.get() {
+                syncing.set(true);
+                presets.set_selected(preset_index(&current));
+                syncing.set(false);
+            }
+        });
+    }
+    {
+        let sliders = sliders.clone();
+        let syncing = syncing.clone();
+        presets.connect_selected_notify(move |presets| {
+            let Some(preset) = Preset::ALL.get(presets.selected() as usize) else {
+                return;
+            };
+            if syncing.get() {
+                return;
+            }
+            syncing.set(true);
+            for (scale, gain) in sliders.iter().zip(preset.gains()) {
+                scale.set_value(gain.into());
+            }
+            syncing.set(false);
+        });
+    }
+
+    let content = gtk::Box::new(Orientation::Vertical, 12);
+    content.append(&presets);
+    content.append(&bands);
+
+    let dialog = AlertDialog::new(Some(&gettext("Equalizer")), None);
+    dialog.set_extra_child(Some(&content));
+    dialog.add_response("close", &gettext("_Close"));
+    dialog.set_close_response("close");
+
+    let settings = settings.clone();
+    dialog.connect_response(None, move |_, _| {
+        let gains = gains.get();
+        let mut settings = settings.borrow_mut();
+        settings.equalizer = (Preset::matching(&gains) != Some(Preset::Flat)).then_some(gains);
+        settings.save();
+    });
+    dialog.present(Some(window));
+}
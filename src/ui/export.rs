@@ -0,0 +1,86 @@
+use adw::gtk::{
+    gio::{Menu, SimpleAction},
+    glib, ApplicationWindow, FileChooserAction, FileChooserNative, ResponseType,
+};
+use adw::{prelude::*, AlertDialog};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+
+use crate::history::export::{self, Format};
+use crate::history::PlayHistory;
+use crate::locale::t;
+
+/// Add the `win.export_history` submenu, which saves the play history as CSV or JSON to
+/// a file picked by the user.
+pub fn install(window: &ApplicationWindow, menu: &Menu, history: &Rc<RefCell<PlayHistory>>) {
+    let action = SimpleAction::new("export_history", Some(glib::VariantTy::STRING));
+    {
+        let window = window.clone();
+        let history = history.clone();
+        action.connect_activate(move |_, param| {
+            if let Some(format) = param.and_then(|p| p.str()).and_then(Format::from_name) {
+                choose_file(&window, &history, format);
+            }
+        });
+    }
+    window.add_action(&action);
+
+    let submenu = Menu::new();
+    for format in Format::ALL {
+        submenu.append(
+            Some(&label(format)),
+            Some(&format!("win.export_history::{}", format.name())),
+        );
+    }
+    menu.insert_submenu(
+        menu.n_items() - 2,
+        Some(&gettext("Export history")),
+        &submenu,
+    );
+}
+
+fn label(format: Format) -> String {
+    match format {
+        Format::Csv => gettext("CSV…"),
+        Format::Json => gettext("JSON…"),
+    }
+}
+
+fn choose_file(window: &ApplicationWindow, history: &Rc<RefCell<PlayHistory>>, format: Format) {
+    let chooser = FileChooserNative::new(
+        Some(&gettext("Export history")),
+        Some(window),
+        FileChooserAction::Save,
+        Some(&gettext("_Export")),
+        Some(&gettext("_Cancel")),
+    );
+    chooser.set_modal(true);
+    chooser.set_current_name(&format!("listening-history.{}", format.name()));
+
+    // The native dialog is not owned by the window; keep it alive until it answers.
+    let keep_alive = RefCell::new(Some(chooser.clone()));
+    let window = window.clone();
+    let history = history.clone();
+    chooser.connect_response(move |chooser, response| {
+        keep_alive.borrow_mut().take();
+        if response != ResponseType::Accept {
+            return;
+        }
+        let Some(path) = chooser.file().and_then(|file| file.path()) else {
+            return;
+        };
+        let contents = export::render(format, &history.borrow().all());
+        if let Err(err) = fs::write(&path, contents) {
+            eprintln!("Failed to export history to {}: {err}", path.display());
+            let dialog = AlertDialog::new(
+                Some(&gettext("Export failed")),
+                Some(&t!("Could not write {}: {}", path.display(), err)),
+            );
+            dialog.add_response("close", &gettext("_Close"));
+            dialog.present(Some(&window));
+        }
+    });
+    chooser.show();
+}
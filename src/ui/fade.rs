@@ -0,0 +1,67 @@
+use adw::gtk::{
+    gio::{Menu, SimpleAction},
+    glib, ApplicationWindow,
+};
+use adw::{prelude::*, Application};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::listen::Listen;
+use crate::settings::Settings;
+
+/// Fade lengths offered in the menu, in milliseconds.
+const LENGTHS: [u32; 4] = [0, 500, 1500, 3000];
+
+/// Add a "Fade in and out" submenu backed by a stateful `win.fade` action, and fade the
+/// stream out before the app exits.
+pub fn install(
+    app: &Application,
+    window: &ApplicationWindow,
+    menu: &Menu,
+    radio: &Rc<Listen>,
+    settings: &Rc<RefCell<Settings>>,
+) {
+    radio.set_fade(settings.borrow().fade());
+    {
+        let radio = radio.clone();
+        app.connect_shutdown(move |_| radio.shutdown());
+    }
+
+    let action = SimpleAction::new_stateful(
+        "fade",
+        Some(glib::VariantTy::UINT32),
+        &settings.borrow().fade_ms.to_variant(),
+    );
+    {
+        let radio = radio.clone();
+        let settings = settings.clone();
+        action.connect_activate(move |action, param| {
+            let Some(fade_ms) = param.and_then(|p| p.get::<u32>()) else {
+                return;
+            };
+            action.set_state(&fade_ms.to_variant());
+            let mut settings = settings.borrow_mut();
+            settings.fade_ms = fade_ms;
+            radio.set_fade(settings.fade());
+            settings.save();
+        });
+    }
+    window.add_action(&action);
+
+    let submenu = Menu::new();
+    for fade_ms in LENGTHS {
+        let label = match fade_ms {
+            0 => gettext("Off"),
+            500 => gettext("Short"),
+            1500 => gettext("Medium"),
+            _ => gettext("Long"),
+        };
+        submenu.append(Some(&label), Some(&format!("win.fade(uint32 {fade_ms})")));
+    }
+    menu.insert_submenu(
+        menu.n_items() - 2,
+        Some(&gettext("Fade in and out")),
+        &submenu,
+    );
+}
@@ -0,0 +1,147 @@
+use adw::gtk::{
+    gio::{self, Menu, SimpleAction},
+    ApplicationWindow,
+};
+use adw::prelude::*;
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::api::{Client, Song};
+use crate::history::favorites::{self, Favorite};
+use crate::history::PlayHistory;
+use crate::meta::TrackInfo;
+use crate::settings::Settings;
+
+/// Outcome of merging with an account: favorites uploaded and downloaded.
+pub struct Synced {
+    pub uploaded: usize,
+    pub downloaded: usize,
+    /// Uploads the API refused; those stay local-only until the next sync.
+    pub failed: usize,
+}
+
+/// The `win.favorite` toggle for the current track, backed by the favorites table of the
+/// play history.
+pub struct Favorites {
+    action: SimpleAction,
+    history: Rc<RefCell<PlayHistory>>,
+    settings: Rc<RefCell<Settings>>,
+    track: RefCell<Option<Favorite>>,
+}
+
+impl Favorites {
+    /// Add the toggle right after "Copy title & artist". It is disabled while the current
+    /// track has no LISTEN.moe song id, and in guest mode.
+    pub fn install(
+        window: &ApplicationWindow,
+        menu: &Menu,
+        history: &Rc<RefCell<PlayHistory>>,
+        settings: &Rc<RefCell<Settings>>,
+    ) -> Rc<Self> {
+        let action = SimpleAction::new_stateful("favorite", None, &false.to_variant());
+        action.set_enabled(false);
+        let favorites = Rc::new(Self {
+            action: action.clone(),
+            history: history.clone(),
+            settings: settings.clone(),
+            track: RefCell::new(None),
+        });
+        {
+            let favorites = favorites.clone();
+            action.connect_activate(move |action, _| {
+                let Some(track) = favorites.track.borrow().clone() else {
+                    return;
+                };
+                let on = !action
+                    .state()
+                    .and_then(|s| s.get::<bool>())
+                    .unwrap_or(false);
+                favorites.history.borrow().set_favorite(&track, on);
+                action.set_state(&on.to_variant());
+            });
+        }
+        window.add_action(&action);
+        menu.insert(1, Some(&gettext("Favorite")), Some("win.favorite"));
+        favorites
+    }
+
+    pub fn track_changed(&self, info: &TrackInfo) {
+        let track = info.song_id.map(|song_id| Favorite {
+            song_id,
+            artist: info.artist.clone(),
+            title: info.title.clone(),
+        });
+        self.action
+            .set_enabled(track.is_some() && !self.settings.borrow().guest_mode);
+        *self.track.borrow_mut() = track;
+        self.refresh();
+    }
+
+    fn refresh(&self) {
+        let on = self
+            .track
+            .borrow()
+            .as_ref()
+            .is_some_and(|track| self.history.borrow().is_favorite(track.song_id));
+        self.action.set_state(&on.to_variant());
+    }
+
+    /// Merge the local favorites with those of `username`'s account: local ones the account
+    /// lacks are uploaded and the account's are added locally. `progress` gets the fraction
+    /// done after every step.
+    pub async fn sync(
+        &self,
+        client: Client,
+        username: String,
+        progress: impl Fn(f64),
+    ) -> Result<Synced, String> {
+        progress(0.0);
+        let remote = {
+            let client = client.clone();
+            gio::spawn_blocking(move || client.favorites(&username).map_err(|e| e.to_string()))
+                .await
+                .map_err(|_| "Favorites worker panicked".to_string())??
+        };
+        let remote: Vec<Favorite> = remote.iter().map(favorite_from).collect();
+        let merge = favorites::merge(&self.history.borrow().favorites(), &remote);
+
+        {
+            let history = self.history.borrow();
+            for favorite in &merge.download {
+                history.set_favorite(favorite, true);
+            }
+        }
+        self.refresh();
+
+        let steps = merge.upload.len() + 1;
+        progress(1.0 / steps as f64);
+        let mut failed = 0;
+        for (done, song_id) in merge.upload.iter().copied().enumerate() {
+            let client = client.clone();
+            let result = gio::spawn_blocking(move || client.favorite_song(song_id)).await;
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => {
+                    eprintln!("Failed to upload favorite {song_id}: {err}");
+                    failed += 1;
+                }
+                Err(_) => failed += 1,
+            }
+            progress((done + 2) as f64 / steps as f64);
+        }
+        Ok(Synced {
+            uploaded: merge.upload.len() - failed,
+            downloaded: merge.download.len(),
+            failed,
+        })
+    }
+}
+
+fn favorite_from(song: &Song) -> Favorite {
+    Favorite {
+        song_id: song.id,
+        artist: song.artists(),
+        title: song.title().to_string(),
+    }
+}
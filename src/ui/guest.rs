@@ -0,0 +1,217 @@
+use adw::gtk::{
+    gio::{Menu, SimpleAction},
+    ApplicationWindow, PasswordEntry,
+};
+use adw::{glib, prelude::*, AlertDialog, ResponseAppearance};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::settings::Settings;
+
+/// Window actions that change preferences or touch accounts/recordings.
+/// They are disabled while guest mode is on; playback and station actions are not listed.
+const PROTECTED_ACTIONS: &[&str] = &[
+    "stream_format",
+    "high_visibility",
+    "favorite",
+    "export_history",
+    "export_diagnostics",
+    "record",
+    "save_clip",
+    "schedule_recording",
+    "notify",
+    "volume_keys",
+    "media_keys",
+    "pause_on_unplug",
+    "proxy",
+    "timeouts",
+    "connect_on_play",
+    "audio_sink",
+    "equalizer",
+    "normalize_loudness",
+    "mono_audio",
+    "balance",
+    "fade",
+    "appearance",
+    "show_visualizer",
+    "show_levels",
+    "marquee",
+    "lyrics",
+    "mini_player",
+    "desktop_integration",
+    "run_in_background",
+    "start_hidden",
+    "request_song",
+    "now_playing_file",
+    "now_playing_cover",
+    "now_playing_off",
+    "track_hook",
+    "remote_control",
+    "remote_pair",
+    "mqtt",
+];
+
+/// Marks a stored PIN as `sha256:{salt}:{hash}` rather than the PIN itself.
+const HASHED: &str = "sha256:";
+
+/// Add the `win.guest_mode` toggle and apply the persisted lock state.
+/// Call after all protected actions have been added to the window.
+pub fn install(window: &ApplicationWindow, menu: &Menu, settings: &Rc<RefCell<Settings>>) {
+    {
+        // Settings from before PINs were hashed hold the PIN itself.
+        let mut settings = settings.borrow_mut();
+        if let Some(pin) = settings.guest_pin.take() {
+            let hashed = pin.starts_with(HASHED);
+            settings.guest_pin = Some(if hashed { pin } else { hash_pin(&pin) });
+            if !hashed {
+                settings.save();
+            }
+        }
+    }
+    let locked = settings.borrow().guest_mode;
+    let action = SimpleAction::new_stateful("guest_mode", None, &locked.to_variant());
+    {
+        let window = window.clone();
+        let settings = settings.clone();
+        action.connect_activate(move |action, _| {
+            if settings.borrow().guest_mode {
+                request_unlock(&window, action, &settings);
+            } else {
+                request_lock(&window, action, &settings);
+            }
+        });
+    }
+    window.add_action(&action);
+    set_locked(window, locked);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Guest mode")),
+        Some("win.guest_mode"),
+    );
+}
+
+fn set_locked(window: &ApplicationWindow, locked: bool) {
+    for name in PROTECTED_ACTIONS {
+        if let Some(action) = window
+            .lookup_action(name)
+            .and_then(|a| a.downcast::<SimpleAction>().ok())
+        {
+            action.set_enabled(!locked);
+        }
+    }
+}
+
+fn apply(
+    window: &ApplicationWindow,
+    action: &SimpleAction,
+    settings: &Rc<RefCell<Settings>>,
+    locked: bool,
+    pin: Option<String>,
+) {
+    {
+        let mut settings = settings.borrow_mut();
+        settings.guest_mode = locked;
+        settings.guest_pin = pin;
+        settings.save();
+    }
+    action.set_state(&locked.to_variant());
+    set_locked(window, locked);
+}
+
+fn request_lock(
+    window: &ApplicationWindow,
+    action: &SimpleAction,
+    settings: &Rc<RefCell<Settings>>,
+) {
+    let entry = PasswordEntry::builder()
+        .show_peek_icon(true)
+        .placeholder_text(gettext("PIN (optional)"))
+        .build();
+    let dialog = AlertDialog::new(
+        Some(&gettext("Enable guest mode?")),
+        Some(&gettext(
+            "Settings are locked while playback and station switching keep working.",
+        )),
+    );
+    dialog.set_extra_child(Some(&entry));
+    dialog.add_response("cancel", &gettext("_Cancel"));
+    dialog.add_response("lock", &gettext("_Lock"));
+    dialog.set_response_appearance("lock", ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("lock"));
+    dialog.set_close_response("cancel");
+
+    let window_clone = window.clone();
+    let action = action.clone();
+    let settings = settings.clone();
+    dialog.connect_response(None, move |_, response| {
+        if response == "lock" {
+            let pin = entry.text().trim().to_string();
+            let pin = (!pin.is_empty()).then(|| hash_pin(&pin));
+            apply(&window_clone, &action, &settings, true, pin);
+        }
+    });
+    dialog.present(Some(window));
+}
+
+fn request_unlock(
+    window: &ApplicationWindow,
+    action: &SimpleAction,
+    settings: &Rc<RefCell<Settings>>,
+) {
+    let Some(expected) = settings.borrow().guest_pin.clone() else {
+        apply(window, action, settings, false, None);
+        return;
+    };
+
+    let entry = PasswordEntry::builder()
+        .placeholder_text(gettext("PIN"))
+        .activates_default(true)
+        .build();
+    let dialog = AlertDialog::new(Some(&gettext("Leave guest mode")), None);
+    dialog.set_extra_child(Some(&entry));
+    dialog.add_response("cancel", &gettext("_Cancel"));
+    dialog.add_response("unlock", &gettext("_Unlock"));
+    dialog.set_default_response(Some("unlock"));
+    dialog.set_close_response("cancel");
+
+    let window_clone = window.clone();
+    let action = action.clone();
+    let settings = settings.clone();
+    dialog.connect_response(None, move |dialog, response| {
+        if response != "unlock" {
+            return;
+        }
+        if pin_matches(&expected, entry.text().trim()) {
+            apply(&window_clone, &action, &settings, false, None);
+        } else {
+            dialog.set_body(&gettext("Wrong PIN."));
+            glib::idle_add_local_once({
+                let dialog = dialog.clone();
+                let window = window_clone.clone();
+                move || dialog.present(Some(&window))
+            });
+        }
+    });
+    dialog.present(Some(window));
+}
+
+/// A salted hash of `pin`, to store in the settings instead of the PIN.
+fn hash_pin(pin: &str) -> String {
+    let salt = glib::uuid_string_random();
+    format!("{HASHED}{salt}:{}", digest(&salt, pin))
+}
+
+fn pin_matches(stored: &str, pin: &str) -> bool {
+    stored
+        .strip_prefix(HASHED)
+        .and_then(|rest| rest.split_once(':'))
+        .is_some_and(|(salt, hash)| digest(salt, pin) == hash)
+}
+
+fn digest(salt: &str, pin: &str) -> String {
+    glib::compute_checksum_for_string(glib::ChecksumType::Sha256, format!("{salt}{pin}"))
+        .map(String::from)
+        .unwrap_or_default()
+}
@@ -0,0 +1,73 @@
+use adw::gtk::{
+    gio::{Menu, SimpleAction},
+    ApplicationWindow, Entry,
+};
+use adw::{prelude::*, AlertDialog, ResponseAppearance};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::events::EventBus;
+use crate::hook;
+use crate::settings::Settings;
+use crate::station::Station;
+
+/// Add the `win.track_hook` action, which asks for a command or webhook to run on every
+/// track change, and start running it for the tracks on `events`.
+pub fn install(
+    window: &ApplicationWindow,
+    menu: &Menu,
+    events: &EventBus,
+    station: Station,
+    settings: &Rc<RefCell<Settings>>,
+) {
+    hook::spawn(events.subscribe(), station);
+
+    let action = SimpleAction::new("track_hook", None);
+    {
+        let window = window.clone();
+        let settings = settings.clone();
+        action.connect_activate(move |_, _| request_hook(&window, &settings));
+    }
+    window.add_action(&action);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("On track change…")),
+        Some("win.track_hook"),
+    );
+}
+
+fn request_hook(window: &ApplicationWindow, settings: &Rc<RefCell<Settings>>) {
+    let entry = Entry::builder()
+        .placeholder_text("https://example.com/webhook")
+        .activates_default(true)
+        .build();
+    if let Some(hook) = settings.borrow().track_hook.as_deref() {
+        entry.set_text(hook);
+    }
+    let dialog = AlertDialog::new(
+        Some(&gettext("On track change")),
+        Some(&gettext(
+            "A webhook URL receives the artist, title and station as JSON. A command gets them in the LISTENMOE_ARTIST, LISTENMOE_TITLE and LISTENMOE_STATION environment variables. Leave empty to turn this off.",
+        )),
+    );
+    dialog.set_extra_child(Some(&entry));
+    dialog.add_response("cancel", &gettext("_Cancel"));
+    dialog.add_response("save", &gettext("_Save"));
+    dialog.set_response_appearance("save", ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("save"));
+    dialog.set_close_response("cancel");
+
+    let settings = settings.clone();
+    dialog.connect_response(None, move |_, response| {
+        if response != "save" {
+            return;
+        }
+        let hook = entry.text().trim().to_string();
+        let mut settings = settings.borrow_mut();
+        settings.track_hook = (!hook.is_empty()).then_some(hook);
+        settings.save();
+    });
+    dialog.present(Some(window));
+}
@@ -0,0 +1,51 @@
+use adw::gtk::{ApplicationInhibitFlags, ApplicationWindow};
+use adw::prelude::*;
+use adw::Application;
+use gettextrs::gettext;
+use std::cell::Cell;
+
+use crate::events::PlaybackState;
+
+/// Keeps the session from suspending or going idle while the stream is running.
+pub struct Inhibitor {
+    app: Application,
+    window: ApplicationWindow,
+    cookie: Cell<Option<u32>>,
+}
+
+impl Inhibitor {
+    pub fn new(app: &Application, window: &ApplicationWindow) -> Self {
+        Self {
+            app: app.clone(),
+            window: window.clone(),
+            cookie: Cell::new(None),
+        }
+    }
+
+    /// Hold the inhibitor while audio is (about to be) playing; release it otherwise.
+    pub fn set_playback(&self, state: PlaybackState) {
+        let active = matches!(
+            state,
+            PlaybackState::Connecting
+                | PlaybackState::Buffering
+                | PlaybackState::Playing
+                | PlaybackState::Reconnecting
+        );
+        match (active, self.cookie.get()) {
+            (true, None) => {
+                let cookie = self.app.inhibit(
+                    Some(&self.window),
+                    ApplicationInhibitFlags::SUSPEND | ApplicationInhibitFlags::IDLE,
+                    Some(&gettext("Playing radio")),
+                );
+                // 0 means the request was refused (e.g. no session manager).
+                self.cookie.set((cookie != 0).then_some(cookie));
+            }
+            (false, Some(cookie)) => {
+                self.app.uninhibit(cookie);
+                self.cookie.set(None);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use adw::gtk::{
+    self,
+    gdk::{gdk_pixbuf::Pixbuf, Display, Texture},
+    gio::{self, Cancellable, MemoryInputStream, Menu, SimpleAction},
+    glib, ApplicationWindow, FileChooserAction, FileChooserNative, ResponseType,
+};
+use adw::{prelude::*, AlertDialog, Toast, ToastOverlay};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+
+use super::{a11y, cover};
+use crate::locale::t;
+use crate::settings::Settings;
+
+/// Largest size the dialog opens at; it shrinks the cover to fit.
+const MAX_SIZE: i32 = 640;
+
+/// The current cover at full resolution in a dialog, with save and copy-link actions.
+pub struct CoverLightbox {
+    action: SimpleAction,
+    url: Rc<RefCell<Option<String>>>,
+}
+
+impl CoverLightbox {
+    /// Add the `win.view_cover` action and its menu item, which is how the keyboard gets to
+    /// the cover; it is disabled while there is no cover.
+    pub fn install(
+        window: &ApplicationWindow,
+        menu: &Menu,
+        settings: &Rc<RefCell<Settings>>,
+    ) -> Self {
+        let url: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+        let action = SimpleAction::new("view_cover", None);
+        action.set_enabled(false);
+        {
+            let window = window.clone();
+            let settings = settings.clone();
+            let url = url.clone();
+            action.connect_activate(move |_, _| {
+                if let Some(url) = url.borrow().clone() {
+                    let proxy = settings.borrow().proxy.clone();
+                    present(&window, url, proxy);
+                }
+            });
+        }
+        window.add_action(&action);
+        menu.insert(1, Some(&gettext("View cover")), Some("win.view_cover"));
+        Self { action, url }
+    }
+
+    /// The cover of the current track, as shown in the popover.
+    pub fn set_url(&self, url: Option<&str>) {
+        self.action.set_enabled(url.is_some());
+        *self.url.borrow_mut() = url.map(str::to_owned);
+    }
+}
+
+fn present(window: &ApplicationWindow, url: String, proxy: Option<String>) {
+    let bytes: Rc<RefCell<Option<glib::Bytes>>> = Rc::new(RefCell::new(None));
+
+    let save = gtk::Button::from_icon_name("document-save-symbolic");
+    save.set_sensitive(false);
+    a11y::label_icon_button(&save, &gettext("Save image…"));
+    let copy = gtk::Button::from_icon_name("edit-copy-symbolic");
+    a11y::label_icon_button(&copy, &gettext("Copy link"));
+    let header = adw::HeaderBar::new();
+    header.pack_start(&save);
+    header.pack_start(&copy);
+
+    let spinner = gtk::Spinner::builder()
+        .spinning(true)
+        .width_request(32)
+        .height_request(32)
+        .halign(gtk::Align::Center)
+        .valign(gtk::Align::Center)
+        .build();
+    let picture = gtk::Picture::builder().can_shrink(true).build();
+    let stack = gtk::Stack::new();
+    stack.add_child(&spinner);
+    stack.add_child(&picture);
+    let toasts = ToastOverlay::new();
+    toasts.set_child(Some(&stack));
+
+    let toolbar = adw::ToolbarView::new();
+    toolbar.add_top_bar(&header);
+    toolbar.set_content(Some(&toasts));
+    let dialog = adw::Dialog::builder()
+        .title(gettext("Cover"))
+        .content_width(MAX_SIZE)
+        .content_height(MAX_SIZE)
+        .child(&toolbar)
+        .build();
+
+    {
+        let url = url.clone();
+        let toasts = toasts.clone();
+        copy.connect_clicked(move |_| {
+            if let Some(display) = Display::default() {
+                display.clipboard().set_text(&url);
+                toasts.add_toast(Toast::new(&gettext("Link copied")));
+            }
+        });
+    }
+    {
+        let window = window.clone();
+        let bytes = bytes.clone();
+        let url = url.clone();
+        save.connect_clicked(move |_| {
+            if let Some(bytes) = bytes.borrow().clone() {
+                choose_file(&window, &url, bytes);
+            }
+        });
+    }
+
+    dialog.present(Some(window));
+
+    // Usually a disk cache hit: the same image was just shown in the popover.
+    glib::MainContext::default().spawn_local(async move {
+        let result = gio::spawn_blocking(move || {
+            cover::load_cover_bytes_blocking(&url, proxy.as_deref()).map_err(|e| e.to_string())
+        })
+        .await;
+        let loaded = match result {
+            Ok(Ok(data)) => {
+                let data = glib::Bytes::from_owned(data);
+                let stream = MemoryInputStream::from_bytes(&data);
+                Pixbuf::from_stream(&stream, None::<&Cancellable>)
+                    .map(|pixbuf| (Texture::for_pixbuf(&pixbuf), data))
+                    .map_err(|e| e.to_string())
+            }
+            Ok(Err(err)) => Err(err),
+            Err(_) => Err("cover loader panicked".to_string()),
+        };
+        match loaded {
+            Ok((texture, data)) => {
+                picture.set_paintable(Some(&texture));
+                stack.set_visible_child(&picture);
+                *bytes.borrow_mut() = Some(data);
+                save.set_sensitive(true);
+            }
+            Err(err) => {
+                eprintln!("Failed to load full-size cover: {err}");
+                let status = adw::StatusPage::builder()
+                    .icon_name("image-missing-symbolic")
+                    .title(gettext("Cover unavailable"))
+                    .build();
+                stack.add_child(&status);
+                stack.set_visible_child(&status);
+            }
+        }
+    });
+}
+
+fn choose_file(window: &ApplicationWindow, url: &str, bytes: glib::Bytes) {
+    let chooser = FileChooserNative::new(
+        Some(&gettext("Save image")),
+        Some(window),
+        FileChooserAction::Save,
+        Some(&gettext("_Save")),
+        Some(&gettext("_Cancel")),
+    );
+    chooser.set_modal(true);
+    chooser.set_current_name(file_name(url));
+
+    // The native dialog is not owned by the window; keep it alive until it answers.
+    let keep_alive = RefCell::new(Some(chooser.clone()));
+    let window = window.clone();
+    chooser.connect_response(move |chooser, response| {
+        keep_alive.borrow_mut().take();
+        if response != ResponseType::Accept {
+            return;
+        }
+        let Some(path) = chooser.file().and_then(|file| file.path()) else {
+            return;
+        };
+        if let Err(err) = fs::write(&path, &bytes) {
+            eprintln!("Failed to save cover to {}: {err}", path.display());
+            let dialog = AlertDialog::new(
+                Some(&gettext("Save failed")),
+                Some(&t!("Could not write {}: {}", path.display(), err)),
+            );
+            dialog.add_response("close", &gettext("_Close"));
+            dialog.present(Some(&window));
+        }
+    });
+    chooser.show();
+}
+
+/// The image's name on the CDN, e.g. "cover.jpg" for ".../covers/cover.jpg?v=2".
+fn file_name(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    match path.rsplit('/').next() {
+        Some(name) if !name.is_empty() => name,
+        _ => "cover.jpg",
+    }
+}
@@ -0,0 +1,255 @@
+use adw::gtk::{
+    self,
+    gio::{Menu, SimpleAction},
+    glib, graphene, ApplicationWindow, Label, Orientation,
+};
+use adw::prelude::*;
+use gettextrs::gettext;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    mpsc, Arc,
+};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::lyrics::{self, LyricLine, Lyrics};
+use crate::meta::TrackInfo;
+use crate::settings::Settings;
+
+/// How often the highlighted line of synced lyrics is updated.
+const SYNC_INTERVAL: Duration = Duration::from_millis(250);
+
+type LyricsResult = Result<Option<Lyrics>, String>;
+
+/// Optional lyrics pane under the progress row. Lyrics are only looked up while it is shown.
+pub struct LyricsPane {
+    revealer: gtk::Revealer,
+    scroller: gtk::ScrolledWindow,
+    lines_box: gtk::Box,
+    settings: Rc<RefCell<Settings>>,
+    lag_ms: Arc<AtomicU64>,
+    track: RefCell<Option<TrackInfo>>,
+    /// Bumped per lookup, so a late answer for an earlier track is dropped.
+    generation: Cell<u64>,
+    /// Generation the shown lyrics belong to; 0 before the first lookup.
+    loaded: Cell<u64>,
+    synced: RefCell<Vec<LyricLine>>,
+    labels: RefCell<Vec<Label>>,
+    current: Cell<Option<usize>>,
+    tx: mpsc::Sender<(u64, LyricsResult)>,
+    rx: mpsc::Receiver<(u64, LyricsResult)>,
+}
+
+impl LyricsPane {
+    /// Add the `win.lyrics` toggle and restore the persisted visibility.
+    pub fn install(
+        window: &ApplicationWindow,
+        menu: &Menu,
+        settings: &Rc<RefCell<Settings>>,
+        lag_ms: Arc<AtomicU64>,
+    ) -> Rc<Self> {
+        let lines_box = gtk::Box::new(Orientation::Vertical, 4);
+        lines_box.set_margin_top(8);
+        lines_box.set_margin_bottom(8);
+        lines_box.set_margin_start(8);
+        lines_box.set_margin_end(8);
+        let scroller = gtk::ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .min_content_height(160)
+            .child(&lines_box)
+            .build();
+        let revealer = gtk::Revealer::builder()
+            .transition_type(gtk::RevealerTransitionType::SlideDown)
+            .child(&scroller)
+            .build();
+
+        let (tx, rx) = mpsc::channel();
+        let pane = Rc::new(Self {
+            revealer,
+            scroller,
+            lines_box,
+            settings: settings.clone(),
+            lag_ms,
+            track: RefCell::new(None),
+            generation: Cell::new(0),
+            loaded: Cell::new(0),
+            synced: RefCell::new(Vec::new()),
+            labels: RefCell::new(Vec::new()),
+            current: Cell::new(None),
+            tx,
+            rx,
+        });
+        pane.revealer
+            .set_reveal_child(settings.borrow().show_lyrics);
+
+        let action =
+            SimpleAction::new_stateful("lyrics", None, &settings.borrow().show_lyrics.to_variant());
+        {
+            let pane = pane.clone();
+            action.connect_activate(move |action, _| {
+                let shown = {
+                    let mut settings = pane.settings.borrow_mut();
+                    settings.show_lyrics = !settings.show_lyrics;
+                    settings.save();
+                    settings.show_lyrics
+                };
+                action.set_state(&shown.to_variant());
+                pane.revealer.set_reveal_child(shown);
+                if shown && pane.loaded.get() != pane.generation.get() {
+                    pane.load();
+                }
+            });
+        }
+        window.add_action(&action);
+        menu.insert(
+            menu.n_items() - 2,
+            Some(&gettext("Lyrics")),
+            Some("win.lyrics"),
+        );
+
+        let weak = Rc::downgrade(&pane);
+        glib::timeout_add_local(SYNC_INTERVAL, move || match weak.upgrade() {
+            Some(pane) => {
+                pane.poll();
+                glib::ControlFlow::Continue
+            }
+            None => glib::ControlFlow::Break,
+        });
+        pane
+    }
+
+    pub fn widget(&self) -> &gtk::Revealer {
+        &self.revealer
+    }
+
+    pub fn track_changed(&self, info: &TrackInfo) {
+        *self.track.borrow_mut() = Some(info.clone());
+        self.generation.set(self.generation.get() + 1);
+        if self.settings.borrow().show_lyrics {
+            self.load();
+        }
+    }
+
+    /// Look up the lyrics of the current track on a worker thread.
+    fn load(&self) {
+        let Some(track) = self.track.borrow().clone() else {
+            return;
+        };
+        let generation = self.generation.get();
+        self.loaded.set(generation);
+        self.show_message(&gettext("Looking for lyrics…"));
+
+        let (proxy, timeouts) = {
+            let settings = self.settings.borrow();
+            (settings.proxy.clone(), settings.timeouts)
+        };
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let result = lyrics::load_blocking(
+                &track.artist,
+                &track.title,
+                track.duration_secs,
+                proxy.as_deref(),
+                timeouts,
+            )
+            .map_err(|e| e.to_string());
+            let _ = tx.send((generation, result));
+        });
+    }
+
+    /// Apply finished lookups and move the highlight along.
+    fn poll(&self) {
+        for (generation, result) in self.rx.try_iter() {
+            if generation != self.generation.get() {
+                continue;
+            }
+            match result {
+                Ok(Some(Lyrics::Synced(lines))) => self.show_synced(lines),
+                Ok(Some(Lyrics::Plain(text))) => self.show_message(&text),
+                Ok(Some(Lyrics::Instrumental)) => self.show_message(&gettext("Instrumental")),
+                Ok(None) => self.show_message(&gettext("No lyrics found")),
+                Err(err) => {
+                    eprintln!("Failed to load lyrics: {err}");
+                    self.show_message(&gettext("Could not load lyrics"));
+                }
+            }
+        }
+        self.highlight();
+    }
+
+    fn clear(&self) {
+        while let Some(child) = self.lines_box.first_child() {
+            self.lines_box.remove(&child);
+        }
+        self.synced.borrow_mut().clear();
+        self.labels.borrow_mut().clear();
+        self.current.set(None);
+        self.scroller.vadjustment().set_value(0.0);
+    }
+
+    fn show_message(&self, text: &str) {
+        self.clear();
+        let label = line_label(text);
+        self.lines_box.append(&label);
+    }
+
+    fn show_synced(&self, lines: Vec<LyricLine>) {
+        self.clear();
+        let labels: Vec<Label> = lines.iter().map(|line| line_label(&line.text)).collect();
+        for label in &labels {
+            label.add_css_class("dim-label");
+            self.lines_box.append(label);
+        }
+        *self.labels.borrow_mut() = labels;
+        *self.synced.borrow_mut() = lines;
+    }
+
+    /// Emphasize the line at the current position and scroll it into the middle.
+    fn highlight(&self) {
+        let synced = self.synced.borrow();
+        if synced.is_empty() || !self.revealer.reveals_child() {
+            return;
+        }
+        let Some(start) = self.track.borrow().as_ref().map(|t| t.start_time_utc) else {
+            return;
+        };
+        // Playback runs behind the live stream by the time spent paused.
+        let lag = Duration::from_millis(self.lag_ms.load(Ordering::Relaxed));
+        let position = SystemTime::now()
+            .checked_sub(lag)
+            .and_then(|now| now.duration_since(start).ok())
+            .unwrap_or_default();
+        let index = lyrics::line_at(&synced, position);
+        if index == self.current.get() {
+            return;
+        }
+
+        let labels = self.labels.borrow();
+        if let Some(previous) = self.current.get().and_then(|i| labels.get(i)) {
+            previous.remove_css_class("heading");
+            previous.add_css_class("dim-label");
+        }
+        self.current.set(index);
+        let Some(label) = index.and_then(|i| labels.get(i)) else {
+            return;
+        };
+        label.remove_css_class("dim-label");
+        label.add_css_class("heading");
+        if let Some(point) = label.compute_point(&self.lines_box, &graphene::Point::new(0.0, 0.0)) {
+            let adjustment = self.scroller.vadjustment();
+            let centered =
+                f64::from(point.y()) - (adjustment.page_size() - f64::from(label.height())) / 2.0;
+            adjustment.set_value(centered);
+        }
+    }
+}
+
+fn line_label(text: &str) -> Label {
+    let label = Label::new(Some(text));
+    label.set_wrap(true);
+    label.set_justify(gtk::Justification::Center);
+    label.set_selectable(false);
+    label
+}
@@ -0,0 +1,121 @@
+use adw::gtk::{gio::Menu, ApplicationWindow};
+use adw::{glib, prelude::*, Application};
+use block2::RcBlock;
+use objc2::rc::Retained;
+use objc2::runtime::AnyObject;
+use objc2::{class, msg_send, sel};
+use objc2_foundation::{NSMutableDictionary, NSNumber, NSString};
+use std::ptr::NonNull;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::events::PlaybackState;
+use crate::meta::TrackInfo;
+use crate::station::Station;
+
+const APP_NAME: &str = "Listen Moe";
+
+// `MPNowPlayingPlaybackState`
+const STATE_PLAYING: usize = 1;
+const STATE_PAUSED: usize = 2;
+const STATE_STOPPED: usize = 3;
+const STATE_INTERRUPTED: usize = 4;
+// `MPRemoteCommandHandlerStatusSuccess`
+const HANDLED: isize = 0;
+
+#[link(name = "MediaPlayer", kind = "framework")]
+extern "C" {
+    static MPMediaItemPropertyTitle: &'static NSString;
+    static MPMediaItemPropertyArtist: &'static NSString;
+    static MPMediaItemPropertyAlbumTitle: &'static NSString;
+    static MPNowPlayingInfoPropertyIsLiveStream: &'static NSString;
+}
+
+/// The Now Playing info center on macOS: Control Center and the menu bar show the current
+/// track, and the media keys and the buttons there run the window actions, like the MPRIS
+/// controls on Linux. There is no cover, which the system would need as an image up front.
+pub struct NowPlaying {
+    center: Retained<AnyObject>,
+}
+
+impl NowPlaying {
+    pub fn install(window: &ApplicationWindow) -> Self {
+        let (tx, rx) = mpsc::channel::<&'static str>();
+        let commands: Retained<AnyObject> =
+            unsafe { msg_send![class!(MPRemoteCommandCenter), sharedCommandCenter] };
+        for (command, action) in [
+            (sel!(togglePlayPauseCommand), "win.toggle"),
+            (sel!(playCommand), "win.play"),
+            (sel!(pauseCommand), "win.pause"),
+            (sel!(stopCommand), "win.stop"),
+            (sel!(nextTrackCommand), "win.next_station"),
+            (sel!(previousTrackCommand), "win.prev_station"),
+        ] {
+            let tx = tx.clone();
+            let handler = RcBlock::new(move |_event: NonNull<AnyObject>| -> isize {
+                let _ = tx.send(action);
+                HANDLED
+            });
+            unsafe {
+                let command: Retained<AnyObject> =
+                    msg_send![&*commands, performSelector: command];
+                let _: () = msg_send![&*command, setEnabled: true];
+                // The command keeps the handler; the returned target is only for removing it.
+                let _: Retained<AnyObject> =
+                    msg_send![&*command, addTargetWithHandler: &*handler];
+            }
+        }
+
+        // Commands may be handled off the main thread; run their actions on the main one.
+        let window = window.downgrade();
+        glib::timeout_add_local(Duration::from_millis(100), move || {
+            let Some(window) = window.upgrade() else {
+                return glib::ControlFlow::Break;
+            };
+            for action in rx.try_iter() {
+                let _ = WidgetExt::activate_action(&window, action, None::<&glib::Variant>);
+            }
+            glib::ControlFlow::Continue
+        });
+
+        let center = unsafe { msg_send![class!(MPNowPlayingInfoCenter), defaultCenter] };
+        Self { center }
+    }
+
+    pub fn set_playback(&self, state: PlaybackState) {
+        let state = match state {
+            PlaybackState::Connecting | PlaybackState::Buffering | PlaybackState::Playing => {
+                STATE_PLAYING
+            }
+            PlaybackState::Reconnecting => STATE_INTERRUPTED,
+            PlaybackState::Paused => STATE_PAUSED,
+            PlaybackState::Stopped | PlaybackState::Error => STATE_STOPPED,
+        };
+        let _: () = unsafe { msg_send![&*self.center, setPlaybackState: state] };
+    }
+
+    pub fn track_changed(&self, info: &TrackInfo, station: Station) {
+        let title = NSString::from_str(&info.title);
+        let artist = NSString::from_str(&info.artist);
+        let album = NSString::from_str(station.display_name());
+        let live = NSNumber::new_bool(true);
+        let track = NSMutableDictionary::<NSString, AnyObject>::new();
+        unsafe {
+            track.insert(MPMediaItemPropertyTitle, &title);
+            track.insert(MPMediaItemPropertyArtist, &artist);
+            track.insert(MPMediaItemPropertyAlbumTitle, &album);
+            track.insert(MPNowPlayingInfoPropertyIsLiveStream, &live);
+            let _: () = msg_send![&*self.center, setNowPlayingInfo: &*track];
+        }
+    }
+}
+
+/// Show `menu` in the global menu bar, under the app name, and add the usual window keys:
+/// ⌘W closes the window and ⌘M minimizes it.
+pub fn install_menubar(app: &Application, menu: &Menu) {
+    let menubar = Menu::new();
+    menubar.append_submenu(Some(APP_NAME), menu);
+    app.set_menubar(Some(&menubar));
+    app.set_accels_for_action("window.close", &["<primary>w"]);
+    app.set_accels_for_action("window.minimize", &["<primary>m"]);
+}
@@ -0,0 +1,165 @@
+use adw::gtk::{
+    self,
+    gio::{Menu, SimpleAction},
+    pango::EllipsizeMode,
+    ApplicationWindow, Orientation, PolicyType,
+};
+use adw::{
+    prelude::*, AnimationState, CallbackAnimationTarget, Easing, TimedAnimation, WindowTitle,
+};
+use gettextrs::gettext;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::settings::Settings;
+
+/// Marquee speed in pixels per second; slow enough to read along.
+const SPEED: f64 = 25.0;
+
+/// Add the header bar title: the artist and track title set on `win_title`, which is not
+/// shown itself. Long text is ellipsized with the full text in a tooltip, or with the
+/// `win.marquee` toggle the track title scrolls back and forth. It never scrolls while the
+/// system asks for reduced motion.
+pub fn install(
+    window: &ApplicationWindow,
+    menu: &Menu,
+    win_title: &WindowTitle,
+    settings: &Rc<RefCell<Settings>>,
+) -> gtk::Box {
+    let artist = label("title");
+    let title = label("subtitle");
+    win_title
+        .bind_property("title", &artist, "label")
+        .sync_create()
+        .build();
+    win_title
+        .bind_property("subtitle", &title, "label")
+        .sync_create()
+        .build();
+
+    let scroller = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(PolicyType::External)
+        .vscrollbar_policy(PolicyType::Never)
+        .propagate_natural_width(true)
+        .propagate_natural_height(true)
+        .child(&title)
+        .build();
+    let root = gtk::Box::new(Orientation::Vertical, 0);
+    root.set_valign(gtk::Align::Center);
+    root.add_css_class("track-title");
+    root.append(&artist);
+    root.append(&scroller);
+
+    let hadj = scroller.hadjustment();
+    let animation = TimedAnimation::new(
+        &scroller,
+        0.0,
+        0.0,
+        1,
+        CallbackAnimationTarget::new({
+            let hadj = hadj.clone();
+            move |value| hadj.set_value(value)
+        }),
+    );
+    animation.set_alternate(true);
+    animation.set_repeat_count(0);
+    animation.set_easing(Easing::EaseInOutSine);
+
+    let enabled = Rc::new(Cell::new(settings.borrow().marquee_title));
+    let update = {
+        let enabled = enabled.clone();
+        let title = title.clone();
+        let scroller = scroller.clone();
+        let hadj = hadj.clone();
+        move || {
+            let scroll = enabled.get() && scroller.settings().is_gtk_enable_animations();
+            title.set_ellipsize(if scroll {
+                EllipsizeMode::None
+            } else {
+                EllipsizeMode::End
+            });
+            let distance = hadj.upper() - hadj.page_size();
+            if scroll && distance >= 1.0 {
+                if animation.state() != AnimationState::Playing
+                    || (animation.value_to() - distance).abs() >= 1.0
+                {
+                    animation.set_value_to(distance);
+                    animation.set_duration((distance / SPEED * 1000.0) as u32);
+                    animation.reset();
+                    animation.play();
+                }
+            } else {
+                animation.reset();
+                hadj.set_value(0.0);
+            }
+        }
+    };
+    let update = Rc::new(update);
+    {
+        let update = update.clone();
+        hadj.connect_changed(move |_| update());
+    }
+    {
+        // Animations skip to the end while unmapped; start over once shown.
+        let update = update.clone();
+        scroller.connect_map(move |_| update());
+    }
+    {
+        let update = update.clone();
+        scroller
+            .settings()
+            .connect_gtk_enable_animations_notify(move |_| update());
+    }
+
+    root.set_has_tooltip(true);
+    {
+        let (artist, title) = (artist.clone(), title.clone());
+        root.connect_query_tooltip(move |_, _, _, _, tooltip| {
+            let cut = artist.layout().is_ellipsized()
+                || title.layout().is_ellipsized()
+                || hadj.upper() - hadj.page_size() >= 1.0;
+            if !cut {
+                return false;
+            }
+            let text: Vec<_> = [artist.label(), title.label()]
+                .into_iter()
+                .filter(|text| !text.is_empty())
+                .collect();
+            tooltip.set_text(Some(&text.join("\n")));
+            true
+        });
+    }
+
+    let action = SimpleAction::new_stateful("marquee", None, &enabled.get().to_variant());
+    {
+        let settings = settings.clone();
+        action.connect_activate(move |action, _| {
+            let mut settings = settings.borrow_mut();
+            settings.marquee_title = !settings.marquee_title;
+            action.set_state(&settings.marquee_title.to_variant());
+            enabled.set(settings.marquee_title);
+            update();
+            settings.save();
+        });
+    }
+    window.add_action(&action);
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Scroll long titles")),
+        Some("win.marquee"),
+    );
+
+    root
+}
+
+/// A single line styled like the labels of `WindowTitle`, hidden while empty.
+fn label(class: &str) -> gtk::Label {
+    let label = gtk::Label::builder()
+        .single_line_mode(true)
+        .ellipsize(EllipsizeMode::End)
+        .width_chars(5)
+        .build();
+    label.add_css_class(class);
+    label.connect_label_notify(|label| label.set_visible(!label.label().is_empty()));
+    label
+}
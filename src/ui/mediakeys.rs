@@ -0,0 +1,279 @@
+use adw::gtk::{
+    gio::{
+        self, DBusCallFlags, DBusConnection, DBusSignalFlags, Menu, SignalSubscription,
+        SimpleAction,
+    },
+    ApplicationWindow,
+};
+use adw::{glib, prelude::*, Application};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::settings::Settings;
+
+const PORTAL_BUS: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const PORTAL_INTERFACE: &str = "org.freedesktop.portal.GlobalShortcuts";
+const TOKEN: &str = "listenmoe_media_keys";
+
+type Results = HashMap<String, glib::Variant>;
+
+/// A bound GlobalShortcuts session; its shortcuts stay bound until it is closed.
+struct Session {
+    connection: DBusConnection,
+    handle: String,
+    _activated: SignalSubscription,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.connection.call(
+            Some(PORTAL_BUS),
+            &self.handle,
+            "org.freedesktop.portal.Session",
+            "Close",
+            None,
+            None,
+            DBusCallFlags::NONE,
+            -1,
+            None::<&gio::Cancellable>,
+            |_| {},
+        );
+    }
+}
+
+/// Add the `win.media_keys` toggle: while it is on, the media keys are bound through the
+/// GlobalShortcuts portal, for desktops that do not pass them to the media controls. It is
+/// off by default, as where both work every key press would count twice.
+pub fn install(
+    app: &Application,
+    window: &ApplicationWindow,
+    menu: &Menu,
+    settings: &Rc<RefCell<Settings>>,
+) {
+    let session: Rc<RefCell<Option<Session>>> = Rc::new(RefCell::new(None));
+    let connection = app.dbus_connection();
+    let enabled = settings.borrow().media_keys;
+    if enabled {
+        if let Some(connection) = connection.as_ref() {
+            bind(connection, window, settings, &session);
+        }
+    }
+
+    let action = SimpleAction::new_stateful("media_keys", None, &enabled.to_variant());
+    {
+        let window = window.clone();
+        let settings = settings.clone();
+        action.connect_activate(move |action, _| {
+            let enabled = {
+                let mut settings = settings.borrow_mut();
+                settings.media_keys = !settings.media_keys;
+                settings.save();
+                settings.media_keys
+            };
+            action.set_state(&enabled.to_variant());
+            match connection.as_ref() {
+                Some(connection) if enabled => bind(connection, &window, &settings, &session),
+                _ => drop(session.borrow_mut().take()),
+            }
+        });
+    }
+    window.add_action(&action);
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Media keys through the desktop")),
+        Some("win.media_keys"),
+    );
+}
+
+/// Shortcut id, description, preferred key and the window action it runs.
+fn shortcuts() -> [(&'static str, String, &'static str, &'static str); 5] {
+    [
+        (
+            "toggle",
+            gettext("Play or pause"),
+            "XF86AudioPlay",
+            "win.toggle",
+        ),
+        ("pause", gettext("Pause"), "XF86AudioPause", "win.pause"),
+        ("stop", gettext("Stop"), "XF86AudioStop", "win.stop"),
+        (
+            "next",
+            gettext("Next station"),
+            "XF86AudioNext",
+            "win.next_station",
+        ),
+        (
+            "previous",
+            gettext("Previous station"),
+            "XF86AudioPrev",
+            "win.prev_station",
+        ),
+    ]
+}
+
+/// Create a session and bind the media keys to it; they run their actions on `window`.
+fn bind(
+    connection: &DBusConnection,
+    window: &ApplicationWindow,
+    settings: &Rc<RefCell<Settings>>,
+    session: &Rc<RefCell<Option<Session>>>,
+) {
+    let options = glib::VariantDict::new(None);
+    options.insert("session_handle_token", TOKEN);
+    let connection_ = connection.clone();
+    let window = window.clone();
+    let settings = settings.clone();
+    let session = session.clone();
+    request(
+        connection,
+        "CreateSession",
+        Vec::new(),
+        options,
+        move |results| {
+            let Some(handle) = results
+                .as_ref()
+                .and_then(|results| results.get("session_handle"))
+                .and_then(|handle| handle.str().map(str::to_owned))
+            else {
+                eprintln!("GlobalShortcuts portal did not create a session");
+                return;
+            };
+            let Ok(path) = glib::variant::ObjectPath::try_from(handle.clone()) else {
+                return;
+            };
+
+            let activated = {
+                let window = window.clone();
+                connection_.subscribe_to_signal(
+                    Some(PORTAL_BUS),
+                    Some(PORTAL_INTERFACE),
+                    Some("Activated"),
+                    Some(PORTAL_PATH),
+                    Some(&handle),
+                    DBusSignalFlags::NONE,
+                    move |signal| {
+                        let Some((_, id, _, _)) =
+                            signal
+                                .parameters
+                                .get::<(glib::variant::ObjectPath, String, u64, Results)>()
+                        else {
+                            return;
+                        };
+                        if let Some((.., action)) = shortcuts().into_iter().find(|s| s.0 == id) {
+                            let _ =
+                                WidgetExt::activate_action(&window, action, None::<&glib::Variant>);
+                        }
+                    },
+                )
+            };
+            let bound = Session {
+                connection: connection_.clone(),
+                handle,
+                _activated: activated,
+            };
+            // Turned off while the session was being created: dropping it closes it.
+            if !settings.borrow().media_keys {
+                return;
+            }
+            // Kept before binding, so turning the toggle off meanwhile closes it.
+            *session.borrow_mut() = Some(bound);
+
+            let list: Vec<(String, Results)> = shortcuts()
+                .into_iter()
+                .map(|(id, description, trigger, _)| {
+                    let properties = HashMap::from([
+                        ("description".to_string(), description.to_variant()),
+                        ("preferred_trigger".to_string(), trigger.to_variant()),
+                    ]);
+                    (id.to_string(), properties)
+                })
+                .collect();
+            let args = vec![path.to_variant(), list.to_variant(), "".to_variant()];
+            request(
+                &connection_,
+                "BindShortcuts",
+                args,
+                glib::VariantDict::new(None),
+                |results| {
+                    if results.is_none() {
+                        eprintln!("GlobalShortcuts portal did not bind the media keys");
+                    }
+                },
+            );
+        },
+    );
+}
+
+/// Call a GlobalShortcuts `method` with `args` followed by `options`, and `on_response` with
+/// the results the portal answers with, or `None` when it failed or was cancelled.
+fn request(
+    connection: &DBusConnection,
+    method: &str,
+    args: Vec<glib::Variant>,
+    options: glib::VariantDict,
+    on_response: impl FnOnce(Option<Results>) + 'static,
+) {
+    let Some(sender) = connection.unique_name() else {
+        return;
+    };
+    let token = format!("{TOKEN}_{}", method.to_lowercase());
+    // The portal answers on a request object whose path is derived from our bus name and
+    // token, so subscribe before calling to not miss a quick reply.
+    let handle = format!(
+        "{PORTAL_PATH}/request/{}/{token}",
+        sender.trim_start_matches(':').replace('.', "_")
+    );
+    let on_response = Rc::new(RefCell::new(Some(on_response)));
+    let respond = move |results| {
+        let callback = on_response.borrow_mut().take();
+        if let Some(callback) = callback {
+            callback(results);
+        }
+    };
+    let subscription: Rc<RefCell<Option<SignalSubscription>>> = Rc::new(RefCell::new(None));
+    {
+        let subscription_slot = subscription.clone();
+        let respond = respond.clone();
+        *subscription.borrow_mut() = Some(connection.subscribe_to_signal(
+            Some(PORTAL_BUS),
+            Some("org.freedesktop.portal.Request"),
+            Some("Response"),
+            Some(&handle),
+            None,
+            DBusSignalFlags::NONE,
+            move |signal| {
+                let results = signal
+                    .parameters
+                    .get::<(u32, Results)>()
+                    .and_then(|(response, results)| (response == 0).then_some(results));
+                respond(results);
+                subscription_slot.borrow_mut().take();
+            },
+        ));
+    }
+
+    options.insert("handle_token", token.as_str());
+    let mut parameters = args;
+    parameters.push(options.end());
+    connection.call(
+        Some(PORTAL_BUS),
+        PORTAL_PATH,
+        PORTAL_INTERFACE,
+        method,
+        Some(&glib::Variant::tuple_from_iter(parameters)),
+        None,
+        DBusCallFlags::NONE,
+        -1,
+        None::<&gio::Cancellable>,
+        move |result| {
+            if let Err(err) = result {
+                eprintln!("GlobalShortcuts portal request failed: {err}");
+                subscription.borrow_mut().take();
+                respond(None);
+            }
+        },
+    );
+}
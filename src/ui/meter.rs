@@ -0,0 +1,168 @@
+use adw::gtk::{
+    self,
+    gio::{Menu, SimpleAction},
+    glib, ApplicationWindow,
+};
+use adw::prelude::*;
+use gettextrs::gettext;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::events::PlaybackState;
+use crate::listen::{Level, Levels, Listen};
+use crate::settings::Settings;
+
+use super::viz::widget_css_color;
+
+const REFRESH: Duration = Duration::from_millis(50);
+/// The bottom of the scale; anything quieter shows as empty.
+const FLOOR_DB: f32 = -60.0;
+/// Share of the previous value kept per refresh while the level falls.
+const FALLOFF: f32 = 0.8;
+/// How long a peak mark holds before it falls, in refreshes.
+const PEAK_HOLD: u32 = 20;
+
+/// 0.0 to 1.0 on a decibel scale.
+fn position(linear: f32) -> f32 {
+    if linear <= 0.0 {
+        return 0.0;
+    }
+    ((20.0 * linear.log10() - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0)
+}
+
+/// What one channel shows: the bar follows the RMS level, the mark the recent peak.
+#[derive(Debug, Clone, Copy, Default)]
+struct Shown {
+    rms: f32,
+    peak: f32,
+    hold: u32,
+}
+
+impl Shown {
+    fn update(&mut self, level: Level) {
+        let rms = position(level.rms);
+        self.rms = rms.max(self.rms * FALLOFF);
+        let peak = position(level.peak);
+        if peak >= self.peak {
+            self.peak = peak;
+            self.hold = PEAK_HOLD;
+        } else if self.hold > 0 {
+            self.hold -= 1;
+        } else {
+            self.peak *= FALLOFF;
+        }
+    }
+}
+
+/// Compact left/right level meter under the title bar, so it is visible that audio is
+/// flowing even when the system volume is muted.
+pub struct LevelMeter {
+    area: gtk::DrawingArea,
+    levels: Arc<Levels>,
+    shown: Rc<Cell<[Shown; 2]>>,
+    playing: Cell<bool>,
+}
+
+impl LevelMeter {
+    /// Add the meter to `content` and the `win.show_levels` toggle.
+    pub fn install(
+        window: &ApplicationWindow,
+        menu: &Menu,
+        content: &gtk::Box,
+        radio: &Rc<Listen>,
+        settings: &Rc<RefCell<Settings>>,
+    ) -> Rc<Self> {
+        let shown = Rc::new(Cell::new([Shown::default(); 2]));
+        let area = gtk::DrawingArea::new();
+        area.set_content_height(10);
+        area.set_margin_start(8);
+        area.set_margin_end(8);
+        area.set_margin_top(4);
+        area.set_hexpand(true);
+        area.set_accessible_role(gtk::AccessibleRole::Meter);
+        area.update_property(&[gtk::accessible::Property::Label(&gettext("Audio level"))]);
+        {
+            let shown = shown.clone();
+            area.set_draw_func(move |area, cr, w, h| {
+                let (r, g, b) = widget_css_color(area.upcast_ref());
+                let (w, h) = (f64::from(w), f64::from(h));
+                let row = (h - 2.0) / 2.0;
+                for (i, channel) in shown.get().iter().enumerate() {
+                    let y = i as f64 * (row + 2.0);
+                    cr.set_source_rgba(r, g, b, 0.15);
+                    cr.rectangle(0.0, y, w, row);
+                    let _ = cr.fill();
+                    cr.set_source_rgba(r, g, b, 0.8);
+                    cr.rectangle(0.0, y, w * f64::from(channel.rms), row);
+                    let _ = cr.fill();
+                    if channel.peak > 0.0 {
+                        let x = (w * f64::from(channel.peak) - 2.0).max(0.0);
+                        cr.rectangle(x, y, 2.0, row);
+                        let _ = cr.fill();
+                    }
+                }
+            });
+        }
+        content.append(&area);
+
+        let meter = Rc::new(Self {
+            area,
+            levels: radio.levels(),
+            shown,
+            playing: Cell::new(false),
+        });
+        let enabled = settings.borrow().show_levels;
+        meter.area.set_visible(enabled);
+
+        let action = SimpleAction::new_stateful("show_levels", None, &enabled.to_variant());
+        {
+            let meter = meter.clone();
+            let settings = settings.clone();
+            action.connect_activate(move |action, _| {
+                let mut settings = settings.borrow_mut();
+                settings.show_levels = !settings.show_levels;
+                action.set_state(&settings.show_levels.to_variant());
+                meter.area.set_visible(settings.show_levels);
+                settings.save();
+            });
+        }
+        window.add_action(&action);
+        menu.insert(
+            menu.n_items() - 2,
+            Some(&gettext("Show level meter")),
+            Some("win.show_levels"),
+        );
+
+        let weak = Rc::downgrade(&meter);
+        glib::timeout_add_local(REFRESH, move || {
+            let Some(meter) = weak.upgrade() else {
+                return glib::ControlFlow::Break;
+            };
+            if meter.area.is_visible() {
+                meter.refresh();
+            }
+            glib::ControlFlow::Continue
+        });
+        meter
+    }
+
+    fn refresh(&self) {
+        let levels = if self.playing.get() {
+            self.levels.get()
+        } else {
+            [Level::default(); 2]
+        };
+        let mut shown = self.shown.get();
+        for (shown, level) in shown.iter_mut().zip(levels) {
+            shown.update(level);
+        }
+        self.shown.set(shown);
+        self.area.queue_draw();
+    }
+
+    pub fn set_playback(&self, state: PlaybackState) {
+        self.playing.set(state == PlaybackState::Playing);
+    }
+}
@@ -0,0 +1,102 @@
+use adw::gtk::{self, gio::SimpleAction, ApplicationWindow, HeaderBar, Picture, ToggleButton};
+use adw::prelude::*;
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::a11y;
+use crate::settings::Settings;
+
+const THUMB_SIZE: i32 = 32;
+
+/// Add the `win.mini_player` toggle with its header bar button. Mini mode hides everything
+/// below the header bar and shows a cover thumbnail next to the playback buttons, which is
+/// returned. The window size is restored from the settings and saved when it hides.
+pub fn install(
+    window: &ApplicationWindow,
+    header: &HeaderBar,
+    buttons: &gtk::Box,
+    content: &impl IsA<gtk::Widget>,
+    art_picture: &Picture,
+    settings: &Rc<RefCell<Settings>>,
+) -> Picture {
+    let thumb = Picture::builder()
+        .can_shrink(true)
+        .width_request(THUMB_SIZE)
+        .height_request(THUMB_SIZE)
+        .valign(gtk::Align::Center)
+        .margin_start(4)
+        .focusable(false)
+        .build();
+    thumb.add_css_class("card");
+    art_picture
+        .bind_property("paintable", &thumb, "paintable")
+        .sync_create()
+        .build();
+    let click = gtk::GestureClick::new();
+    {
+        let thumb = thumb.clone();
+        click.connect_released(move |_, _, _, _| {
+            let _ = thumb.activate_action("win.view_cover", None);
+        });
+    }
+    thumb.add_controller(click);
+    buttons.append(&thumb);
+
+    {
+        let settings = settings.borrow();
+        if let Some((width, height)) = settings.window_size {
+            window.set_default_size(width, height);
+        }
+        if settings.window_maximized {
+            window.maximize();
+        }
+    }
+    {
+        // Hiding covers both closing and running in the background.
+        let settings = settings.clone();
+        window.connect_hide(move |window| {
+            let mut settings = settings.borrow_mut();
+            settings.window_size = Some(window.default_size());
+            settings.window_maximized = window.is_maximized();
+            settings.save();
+        });
+    }
+
+    let content = content.clone().upcast::<gtk::Widget>();
+    let apply = {
+        let thumb = thumb.clone();
+        let window = window.clone();
+        move |mini: bool| {
+            content.set_visible(!mini);
+            thumb.set_visible(mini);
+            if mini {
+                // Drop the height the content had; an unset height follows the header bar.
+                window.set_default_size(window.default_width(), -1);
+            }
+        }
+    };
+    let enabled = settings.borrow().mini_player;
+    apply(enabled);
+
+    let action = SimpleAction::new_stateful("mini_player", None, &enabled.to_variant());
+    {
+        let settings = settings.clone();
+        action.connect_activate(move |action, _| {
+            let mut settings = settings.borrow_mut();
+            settings.mini_player = !settings.mini_player;
+            action.set_state(&settings.mini_player.to_variant());
+            apply(settings.mini_player);
+            settings.save();
+        });
+    }
+    window.add_action(&action);
+
+    let toggle = ToggleButton::builder()
+        .icon_name("view-restore-symbolic")
+        .action_name("win.mini_player")
+        .build();
+    a11y::label_icon_button(&toggle, &gettext("Mini player"));
+    header.pack_end(&toggle);
+    thumb
+}
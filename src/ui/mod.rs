@@ -1,7 +1,65 @@
+mod a11y;
 mod actions;
+mod adaptive;
+mod appearance;
+mod audiosink;
+mod backdrop;
+mod background;
+mod channels;
+mod cli;
 #[cfg(target_os = "linux")]
 mod controls;
 mod cover;
+mod crash;
+mod crossfade;
+mod diagnostics;
+mod equalizer;
+mod export;
+mod fade;
+mod favorites;
+#[cfg(target_os = "linux")]
+mod dbus;
+mod guest;
+mod hook;
+mod inhibit;
+mod lightbox;
+mod network;
+mod lyrics;
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "linux")]
+mod mediakeys;
+mod marquee;
+mod meter;
+mod mini;
+mod mqtt;
+mod notify;
+mod nowplaying;
+mod placeholder;
+mod progress;
+mod proxy;
+mod recording;
+mod remote;
+mod requests;
+mod scheduled;
+mod search;
+#[cfg(all(target_os = "linux", feature = "setup"))]
+mod setup;
+#[cfg(target_os = "windows")]
+mod smtc;
+mod stations;
+mod stats;
+mod status;
+#[cfg(target_os = "windows")]
+mod thumbbar;
+mod timeouts;
+mod timeshift;
+mod toasts;
+mod unplug;
 mod viz;
+mod volume;
+mod websearch;
 mod window;
+pub use cli::install_command_line;
+pub use crash::install_panic_hook;
 pub use window::build_ui;
@@ -0,0 +1,158 @@
+use adw::gtk::{
+    self,
+    gio::{Menu, SimpleAction},
+    ApplicationWindow,
+};
+use adw::{prelude::*, AlertDialog, EntryRow, PasswordEntryRow, ResponseAppearance, SpinRow};
+use gettextrs::gettext;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::events::PlaybackState;
+use crate::meta::TrackInfo;
+use crate::mqtt::{self, MqttPublisher, Update};
+use crate::settings::{Mqtt, Settings};
+use crate::station::Station;
+
+/// Publishes to the MQTT broker configured with `win.mqtt`.
+pub struct MqttBridge {
+    settings: Rc<RefCell<Settings>>,
+    publisher: RefCell<Option<MqttPublisher>>,
+    track: RefCell<Option<String>>,
+    state: Cell<PlaybackState>,
+}
+
+impl MqttBridge {
+    pub fn install(
+        window: &ApplicationWindow,
+        menu: &Menu,
+        settings: &Rc<RefCell<Settings>>,
+    ) -> Rc<Self> {
+        let bridge = Rc::new(Self {
+            settings: settings.clone(),
+            publisher: RefCell::new(None),
+            track: RefCell::new(None),
+            state: Cell::new(PlaybackState::Stopped),
+        });
+        bridge.restart();
+
+        let action = SimpleAction::new("mqtt", None);
+        {
+            let window = window.clone();
+            let bridge = bridge.clone();
+            action.connect_activate(move |_, _| bridge.request_broker(&window));
+        }
+        window.add_action(&action);
+        menu.insert(
+            menu.n_items() - 2,
+            Some(&gettext("MQTT…")),
+            Some("win.mqtt"),
+        );
+        bridge
+    }
+
+    pub fn track_changed(&self, info: &TrackInfo, station: Station) {
+        let track = mqtt::track_payload(info, station);
+        *self.track.borrow_mut() = Some(track.clone());
+        self.publish(Update::Track(track));
+    }
+
+    pub fn set_playback(&self, state: PlaybackState) {
+        self.state.set(state);
+        self.publish(Update::State(state.name()));
+    }
+
+    fn publish(&self, update: Update) {
+        if let Some(publisher) = self.publisher.borrow().as_ref() {
+            publisher.publish(update);
+        }
+    }
+
+    /// Connect to the configured broker, if any, and publish the current state.
+    fn restart(&self) {
+        // Drop the old connection first; it says goodbye on its own thread.
+        self.publisher.borrow_mut().take();
+        let settings = self.settings.borrow();
+        let Some(broker) = settings.mqtt.clone().filter(|b| !b.host.is_empty()) else {
+            return;
+        };
+        let host = broker.host.clone();
+        match MqttPublisher::start(broker, settings.timeouts) {
+            Ok(publisher) => {
+                if let Some(track) = self.track.borrow().clone() {
+                    publisher.publish(Update::Track(track));
+                }
+                publisher.publish(Update::State(self.state.get().name()));
+                *self.publisher.borrow_mut() = Some(publisher);
+            }
+            Err(err) => eprintln!("Failed to start publishing to {host}: {err}"),
+        }
+    }
+
+    fn request_broker(self: &Rc<Self>, window: &ApplicationWindow) {
+        let current = self.settings.borrow().mqtt.clone().unwrap_or_default();
+        let host = EntryRow::new();
+        host.set_title(&gettext("Host"));
+        host.set_text(&current.host);
+        let port = SpinRow::with_range(1.0, 65535.0, 1.0);
+        port.set_title(&gettext("Port"));
+        port.set_value(current.port.into());
+        let topic = EntryRow::new();
+        topic.set_title(&gettext("Topic"));
+        topic.set_text(&current.topic);
+        let username = EntryRow::new();
+        username.set_title(&gettext("User name"));
+        username.set_text(current.username.as_deref().unwrap_or_default());
+        let password = PasswordEntryRow::new();
+        password.set_title(&gettext("Password"));
+        password.set_text(current.password.as_deref().unwrap_or_default());
+
+        let rows = gtk::ListBox::new();
+        rows.set_selection_mode(gtk::SelectionMode::None);
+        rows.add_css_class("boxed-list");
+        rows.append(&host);
+        rows.append(&port);
+        rows.append(&topic);
+        rows.append(&username);
+        rows.append(&password);
+
+        let dialog = AlertDialog::new(
+            Some(&gettext("MQTT")),
+            Some(&gettext(
+                "Publish the current track and playback state to a broker, for example for Home Assistant. Leave the host empty to turn this off.",
+            )),
+        );
+        dialog.set_extra_child(Some(&rows));
+        dialog.add_response("cancel", &gettext("_Cancel"));
+        dialog.add_response("save", &gettext("_Save"));
+        dialog.set_response_appearance("save", ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("save"));
+        dialog.set_close_response("cancel");
+
+        let bridge = self.clone();
+        dialog.connect_response(None, move |_, response| {
+            if response != "save" {
+                return;
+            }
+            let text = |row: &EntryRow| {
+                let text = row.text().trim().to_string();
+                (!text.is_empty()).then_some(text)
+            };
+            let topic = text(&topic).map(|t| t.trim_end_matches('/').to_string());
+            let broker = Mqtt {
+                host: host.text().trim().to_string(),
+                port: port.value() as u16,
+                topic: topic.unwrap_or_else(|| Mqtt::default().topic),
+                username: text(&username),
+                password: Some(password.text().to_string()).filter(|p| !p.is_empty()),
+            };
+            {
+                let mut settings = bridge.settings.borrow_mut();
+                settings.mqtt = (!broker.host.is_empty()).then_some(broker);
+                settings.save();
+            }
+            bridge.restart();
+        });
+        dialog.present(Some(window));
+    }
+}
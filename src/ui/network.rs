@@ -0,0 +1,106 @@
+use adw::gtk::{self, gio, ApplicationWindow};
+use adw::{glib, prelude::*, StatusPage};
+use gettextrs::gettext;
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::events::PlaybackState;
+
+/// Stops playback while the machine is offline, instead of letting the stream and the
+/// gateway retry in a loop, and starts it again once the network is back.
+pub struct Network {
+    window: ApplicationWindow,
+    stack: gtk::Stack,
+    monitor: gio::NetworkMonitor,
+    playback: Cell<PlaybackState>,
+    /// Playback was stopped for being offline and should come back with the network.
+    resume: Cell<bool>,
+    /// A stop we requested ourselves is on its way.
+    stopping: Cell<bool>,
+}
+
+impl Network {
+    /// Put `content` in a stack with an offline page that replaces it while offline.
+    pub fn new(window: &ApplicationWindow, content: &impl IsA<gtk::Widget>) -> Rc<Self> {
+        let offline = StatusPage::builder()
+            .icon_name("network-offline-symbolic")
+            .title(gettext("Offline"))
+            .description(gettext("Playback continues when the network is back"))
+            .build();
+        offline.add_css_class("compact");
+
+        let stack = gtk::Stack::new();
+        stack.set_vhomogeneous(false);
+        stack.add_named(content, Some("content"));
+        stack.add_named(&offline, Some("offline"));
+
+        let network = Rc::new(Self {
+            window: window.clone(),
+            stack,
+            monitor: gio::NetworkMonitor::default(),
+            playback: Cell::new(PlaybackState::Stopped),
+            resume: Cell::new(false),
+            stopping: Cell::new(false),
+        });
+        network.show_page();
+        let weak = Rc::downgrade(&network);
+        network
+            .monitor
+            .connect_network_changed(move |_, available| {
+                if let Some(network) = weak.upgrade() {
+                    network.availability_changed(available);
+                }
+            });
+        network
+    }
+
+    pub fn widget(&self) -> &gtk::Stack {
+        &self.stack
+    }
+
+    pub fn set_playback(&self, state: PlaybackState) {
+        self.playback.set(state);
+        match state {
+            PlaybackState::Stopped if self.stopping.get() => self.stopping.set(false),
+            // Stopped or paused by the user: stay that way when the network returns.
+            PlaybackState::Stopped | PlaybackState::Paused => self.resume.set(false),
+            PlaybackState::Connecting if !self.is_online() => self.stop_until_online(),
+            _ => {}
+        }
+    }
+
+    fn is_online(&self) -> bool {
+        self.monitor.is_network_available()
+    }
+
+    fn availability_changed(&self, available: bool) {
+        self.show_page();
+        let active = !matches!(
+            self.playback.get(),
+            PlaybackState::Stopped | PlaybackState::Paused
+        );
+        if !available && active {
+            self.stop_until_online();
+        } else if available && self.resume.replace(false) {
+            self.activate("win.play");
+        }
+    }
+
+    fn stop_until_online(&self) {
+        self.resume.set(true);
+        self.stopping.set(true);
+        self.activate("win.stop");
+    }
+
+    fn show_page(&self) {
+        self.stack.set_visible_child_name(if self.is_online() {
+            "content"
+        } else {
+            "offline"
+        });
+    }
+
+    fn activate(&self, action: &str) {
+        let _ = WidgetExt::activate_action(&self.window, action, None::<&glib::Variant>);
+    }
+}
@@ -0,0 +1,88 @@
+use adw::gtk::{
+    gio::{self, Menu, SimpleAction},
+    ApplicationWindow,
+};
+use adw::prelude::*;
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::listen::Listen;
+use crate::meta::TrackInfo;
+use crate::settings::Settings;
+
+/// Track-change notifications, honoring the per-station preference.
+pub struct Notifier {
+    window: ApplicationWindow,
+    action: SimpleAction,
+    radio: Rc<Listen>,
+    settings: Rc<RefCell<Settings>>,
+}
+
+impl Notifier {
+    /// Add the `win.notify` toggle, which applies to the current station only.
+    pub fn install(
+        window: &ApplicationWindow,
+        menu: &Menu,
+        radio: &Rc<Listen>,
+        settings: &Rc<RefCell<Settings>>,
+    ) -> Rc<Self> {
+        let enabled = settings.borrow().station(radio.get_station()).notify;
+        let action = SimpleAction::new_stateful("notify", None, &enabled.to_variant());
+        {
+            let radio = radio.clone();
+            let settings = settings.clone();
+            action.connect_activate(move |action, _| {
+                let mut settings = settings.borrow_mut();
+                let prefs = settings.station_mut(radio.get_station());
+                prefs.notify = !prefs.notify;
+                action.set_state(&prefs.notify.to_variant());
+                settings.save();
+            });
+        }
+        window.add_action(&action);
+
+        menu.insert(
+            menu.n_items() - 2,
+            Some(&gettext("Notify on new track")),
+            Some("win.notify"),
+        );
+
+        Rc::new(Self {
+            window: window.clone(),
+            action,
+            radio: radio.clone(),
+            settings: settings.clone(),
+        })
+    }
+
+    /// Reflect the current station's preference in the menu toggle.
+    pub fn sync_station(&self) {
+        let enabled = self
+            .settings
+            .borrow()
+            .station(self.radio.get_station())
+            .notify;
+        self.action.set_state(&enabled.to_variant());
+    }
+
+    pub fn track_changed(&self, info: &TrackInfo) {
+        self.sync_station();
+        let station = self.radio.get_station();
+        if self.window.is_active() || !self.settings.borrow().station(station).notify {
+            return;
+        }
+        let Some(app) = self.window.application() else {
+            return;
+        };
+        let notification = gio::Notification::new(&info.title);
+        let body = if info.artist.is_empty() {
+            station.display_name().to_string()
+        } else {
+            format!("{} · {}", info.artist, station.display_name())
+        };
+        notification.set_body(Some(&body));
+        // Reuse one id so a newer track replaces the previous notification.
+        app.send_notification(Some("track-change"), &notification);
+    }
+}
@@ -0,0 +1,174 @@
+use adw::gtk::{
+    self,
+    gdk_pixbuf::Pixbuf,
+    gio::{Menu, SimpleAction},
+    ApplicationWindow, FileChooserAction, FileChooserNative, ResponseType,
+};
+use adw::prelude::*;
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::meta::TrackInfo;
+use crate::nowplaying;
+use crate::settings::Settings;
+
+/// Keeps the now-playing file (and cover) configured in the settings up to date.
+pub struct NowPlayingFile {
+    settings: Rc<RefCell<Settings>>,
+    track: RefCell<Option<TrackInfo>>,
+    off: SimpleAction,
+}
+
+impl NowPlayingFile {
+    /// Add the "Now playing file" submenu: `win.now_playing_file` picks the file,
+    /// `win.now_playing_cover` toggles the cover image and `win.now_playing_off` stops.
+    pub fn install(
+        window: &ApplicationWindow,
+        menu: &Menu,
+        settings: &Rc<RefCell<Settings>>,
+    ) -> Rc<Self> {
+        let off = SimpleAction::new("now_playing_off", None);
+        off.set_enabled(settings.borrow().now_playing_file.is_some());
+        let file = Rc::new(Self {
+            settings: settings.clone(),
+            track: RefCell::new(None),
+            off: off.clone(),
+        });
+
+        let choose = SimpleAction::new("now_playing_file", None);
+        {
+            let window = window.clone();
+            let file = file.clone();
+            choose.connect_activate(move |_, _| file.choose(&window));
+        }
+        window.add_action(&choose);
+
+        let cover = SimpleAction::new_stateful(
+            "now_playing_cover",
+            None,
+            &settings.borrow().now_playing_cover.to_variant(),
+        );
+        {
+            let settings = settings.clone();
+            cover.connect_activate(move |action, _| {
+                let mut settings = settings.borrow_mut();
+                settings.now_playing_cover = !settings.now_playing_cover;
+                action.set_state(&settings.now_playing_cover.to_variant());
+                settings.save();
+            });
+        }
+        window.add_action(&cover);
+
+        {
+            let file = file.clone();
+            off.connect_activate(move |_, _| file.set_path(None));
+        }
+        window.add_action(&off);
+
+        let submenu = Menu::new();
+        submenu.append(Some(&gettext("Choose file…")), Some("win.now_playing_file"));
+        submenu.append(
+            Some(&gettext("Include cover art")),
+            Some("win.now_playing_cover"),
+        );
+        submenu.append(Some(&gettext("Stop writing")), Some("win.now_playing_off"));
+        menu.insert_submenu(
+            menu.n_items() - 2,
+            Some(&gettext("Now playing file")),
+            &submenu,
+        );
+        file
+    }
+
+    pub fn track_changed(&self, info: &TrackInfo) {
+        *self.track.borrow_mut() = Some(info.clone());
+        self.write_text();
+        // The cover follows once it is loaded; without one, drop the previous track's.
+        if info.album_cover.is_none() && info.artist_image.is_none() {
+            if let Some(path) = self.cover_target() {
+                if let Err(err) = nowplaying::remove_cover(&path) {
+                    eprintln!("Failed to remove now playing cover: {err}");
+                }
+            }
+        }
+    }
+
+    /// The cover of the current track was decoded.
+    pub fn cover_loaded(&self, pixbuf: &Pixbuf) {
+        let Some(path) = self.cover_target() else {
+            return;
+        };
+        let cover = nowplaying::cover_path(&path);
+        let result = pixbuf
+            .save_to_bufferv("png", &[])
+            .map_err(std::io::Error::other)
+            .and_then(|png| nowplaying::write_atomic(&cover, &png));
+        if let Err(err) = result {
+            eprintln!("Failed to write {}: {err}", cover.display());
+        }
+    }
+
+    /// The text file, when covers should be written next to it.
+    fn cover_target(&self) -> Option<PathBuf> {
+        let settings = self.settings.borrow();
+        settings
+            .now_playing_file
+            .clone()
+            .filter(|_| settings.now_playing_cover)
+    }
+
+    fn write_text(&self) {
+        let Some(path) = self.settings.borrow().now_playing_file.clone() else {
+            return;
+        };
+        let Some(info) = self.track.borrow().clone() else {
+            return;
+        };
+        if let Err(err) = nowplaying::write_text(&path, &info) {
+            eprintln!("Failed to write {}: {err}", path.display());
+        }
+    }
+
+    fn set_path(&self, path: Option<PathBuf>) {
+        self.off.set_enabled(path.is_some());
+        {
+            let mut settings = self.settings.borrow_mut();
+            settings.now_playing_file = path;
+            settings.save();
+        }
+        self.write_text();
+    }
+
+    fn choose(self: &Rc<Self>, window: &ApplicationWindow) {
+        let chooser = FileChooserNative::new(
+            Some(&gettext("Now playing file")),
+            Some(window),
+            FileChooserAction::Save,
+            Some(&gettext("_Choose")),
+            Some(&gettext("_Cancel")),
+        );
+        chooser.set_modal(true);
+        match self.settings.borrow().now_playing_file.as_deref() {
+            Some(path) => {
+                let _ = chooser.set_file(&gtk::gio::File::for_path(path));
+            }
+            None => chooser.set_current_name("now-playing.txt"),
+        }
+
+        // The native dialog is not owned by the window; keep it alive until it answers.
+        let keep_alive = RefCell::new(Some(chooser.clone()));
+        let file = self.clone();
+        chooser.connect_response(move |chooser, response| {
+            keep_alive.borrow_mut().take();
+            if response != ResponseType::Accept {
+                return;
+            }
+            if let Some(path) = chooser.file().and_then(|file| file.path()) {
+                file.set_path(Some(path));
+            }
+        });
+        chooser.show();
+    }
+}
@@ -0,0 +1,141 @@
+use adw::gtk;
+use adw::{prelude::*, StatusPage};
+use gettextrs::gettext;
+use std::cell::{Cell, RefCell};
+
+use crate::events::{GatewayState, PlaybackState};
+use crate::station::Station;
+
+/// Status pages in place of the track info while there is none yet: the station with a
+/// spinner while connecting or waiting for the first track, and the error with a retry
+/// button once the stream stopped.
+pub struct Placeholder {
+    stack: gtk::Stack,
+    waiting: StatusPage,
+    spinner: gtk::Spinner,
+    error: StatusPage,
+    station: Cell<Station>,
+    playback: Cell<PlaybackState>,
+    /// `None` while there is no track info connection, until playback starts one.
+    gateway: Cell<Option<GatewayState>>,
+    has_track: Cell<bool>,
+    message: RefCell<Option<String>>,
+}
+
+impl Placeholder {
+    /// Put `content` in a stack with the status pages that stand in for it.
+    pub fn new(
+        content: &impl IsA<gtk::Widget>,
+        station: Station,
+        gateway: Option<GatewayState>,
+    ) -> Self {
+        let spinner = gtk::Spinner::builder()
+            .width_request(32)
+            .height_request(32)
+            .build();
+        let waiting = StatusPage::builder().child(&spinner).build();
+        waiting.add_css_class("compact");
+
+        let retry = gtk::Button::builder()
+            .label(gettext("_Retry"))
+            .use_underline(true)
+            .action_name("win.retry")
+            .halign(gtk::Align::Center)
+            .build();
+        retry.add_css_class("pill");
+        let error = StatusPage::builder()
+            .icon_name("dialog-error-symbolic")
+            .title(gettext("Stream stopped"))
+            .child(&retry)
+            .build();
+        error.add_css_class("compact");
+
+        let stack = gtk::Stack::new();
+        stack.set_vhomogeneous(false);
+        stack.set_transition_type(gtk::StackTransitionType::Crossfade);
+        stack.add_named(content, Some("content"));
+        stack.add_named(&waiting, Some("waiting"));
+        stack.add_named(&error, Some("error"));
+
+        let placeholder = Self {
+            stack,
+            waiting,
+            spinner,
+            error,
+            station: Cell::new(station),
+            playback: Cell::new(PlaybackState::Stopped),
+            gateway: Cell::new(gateway),
+            has_track: Cell::new(false),
+            message: RefCell::new(None),
+        };
+        placeholder.refresh();
+        placeholder
+    }
+
+    pub fn widget(&self) -> &gtk::Stack {
+        &self.stack
+    }
+
+    pub fn track_changed(&self) {
+        self.has_track.set(true);
+        self.refresh();
+    }
+
+    /// The track info belongs to the previous station until the next update.
+    pub fn station_changed(&self, station: Station) {
+        self.station.set(station);
+        self.has_track.set(false);
+        self.refresh();
+    }
+
+    pub fn set_playback(&self, state: PlaybackState) {
+        self.playback.set(state);
+        if state != PlaybackState::Error {
+            self.message.borrow_mut().take();
+        }
+        self.refresh();
+    }
+
+    pub fn set_gateway(&self, state: GatewayState) {
+        self.gateway.set(Some(state));
+        self.refresh();
+    }
+
+    /// The track info connection closed and the track info was cleared with it.
+    pub fn disconnected(&self) {
+        self.gateway.set(None);
+        self.has_track.set(false);
+        self.refresh();
+    }
+
+    /// The reason shown on the error page.
+    pub fn error(&self, message: &str) {
+        *self.message.borrow_mut() = Some(message.to_string());
+        self.refresh();
+    }
+
+    fn refresh(&self) {
+        if self.playback.get() == PlaybackState::Error {
+            self.error.set_description(self.message.borrow().as_deref());
+            self.stack.set_visible_child_name("error");
+            return;
+        }
+        if self.has_track.get() {
+            self.stack.set_visible_child_name("content");
+            return;
+        }
+
+        let description = match self.gateway.get() {
+            None => gettext("Press play to start listening"),
+            Some(GatewayState::Connecting) => gettext("Connecting…"),
+            Some(GatewayState::Connected) => gettext("Waiting for the first track…"),
+            Some(GatewayState::Reconnecting) => gettext("Track info unavailable, retrying…"),
+        };
+        let busy = self.gateway.get().is_some();
+        self.spinner.set_spinning(busy);
+        self.spinner.set_visible(busy);
+        self.waiting.set_title(self.station.get().display_name());
+        self.waiting.set_description(Some(&description));
+        self.stack.set_visible_child_name("waiting");
+    }
+}
@@ -0,0 +1,106 @@
+use adw::gtk::{self, Align, Label, Orientation, ProgressBar};
+use adw::prelude::*;
+use std::cell::Cell;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use std::time::{Duration, SystemTime};
+
+/// Position of the current track, derived from the gateway's start time and duration.
+pub struct Progress {
+    row: gtk::Box,
+    bar: ProgressBar,
+    elapsed: Label,
+    remaining: Label,
+    start: Cell<Option<SystemTime>>,
+    duration_secs: Cell<u32>,
+    lag_ms: Arc<AtomicU64>,
+}
+
+impl Progress {
+    pub fn new(lag_ms: Arc<AtomicU64>) -> Self {
+        let elapsed = time_label();
+        let remaining = time_label();
+        let bar = ProgressBar::builder()
+            .hexpand(true)
+            .valign(Align::Center)
+            .build();
+        bar.add_css_class("accent-tint");
+
+        let row = gtk::Box::new(Orientation::Horizontal, 6);
+        row.set_margin_start(8);
+        row.set_margin_end(8);
+        row.set_margin_top(2);
+        row.set_margin_bottom(4);
+        row.append(&elapsed);
+        row.append(&bar);
+        row.append(&remaining);
+
+        let progress = Self {
+            row,
+            bar,
+            elapsed,
+            remaining,
+            start: Cell::new(None),
+            duration_secs: Cell::new(0),
+            lag_ms,
+        };
+        progress.tick();
+        progress
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.row
+    }
+
+    /// Show no track.
+    pub fn clear(&self) {
+        self.start.set(None);
+        self.tick();
+    }
+
+    pub fn set_track(&self, start: SystemTime, duration_secs: u32) {
+        self.start.set(Some(start));
+        self.duration_secs.set(duration_secs);
+        self.tick();
+    }
+
+    /// Recompute the position; call about once per second.
+    pub fn tick(&self) {
+        let duration = self.duration_secs.get();
+        let Some(start) = self.start.get().filter(|_| duration > 0) else {
+            self.row.set_sensitive(false);
+            self.bar.set_fraction(0.0);
+            self.elapsed.set_text("--:--");
+            self.remaining.set_text("--:--");
+            return;
+        };
+
+        // Playback runs behind the live stream by the time spent paused.
+        let lag = Duration::from_millis(self.lag_ms.load(Ordering::Relaxed));
+        let elapsed = SystemTime::now()
+            .checked_sub(lag)
+            .and_then(|now| now.duration_since(start).ok())
+            .map_or(0, |d| d.as_secs())
+            .min(duration as u64);
+
+        self.row.set_sensitive(true);
+        self.bar.set_fraction(elapsed as f64 / duration as f64);
+        self.elapsed.set_text(&format_time(elapsed));
+        self.remaining
+            .set_text(&format!("-{}", format_time(duration as u64 - elapsed)));
+    }
+}
+
+fn time_label() -> Label {
+    let label = Label::new(None);
+    label.add_css_class("caption");
+    label.add_css_class("numeric");
+    label.add_css_class("dim-label");
+    label
+}
+
+fn format_time(secs: u64) -> String {
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
@@ -0,0 +1,83 @@
+use adw::gtk::{
+    gio::{Menu, SimpleAction},
+    ApplicationWindow, Entry,
+};
+use adw::{prelude::*, AlertDialog, ResponseAppearance};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::listen::Listen;
+use crate::meta::Meta;
+use crate::settings::Settings;
+
+/// Add the `win.proxy` action, which asks for a proxy URL for all connections.
+pub fn install(
+    window: &ApplicationWindow,
+    menu: &Menu,
+    radio: &Rc<Listen>,
+    meta: &Rc<Meta>,
+    settings: &Rc<RefCell<Settings>>,
+) {
+    let action = SimpleAction::new("proxy", None);
+    {
+        let window = window.clone();
+        let radio = radio.clone();
+        let meta = meta.clone();
+        let settings = settings.clone();
+        action.connect_activate(move |_, _| {
+            request_proxy(&window, &radio, &meta, &settings);
+        });
+    }
+    window.add_action(&action);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Proxy…")),
+        Some("win.proxy"),
+    );
+}
+
+fn request_proxy(
+    window: &ApplicationWindow,
+    radio: &Rc<Listen>,
+    meta: &Rc<Meta>,
+    settings: &Rc<RefCell<Settings>>,
+) {
+    let entry = Entry::builder()
+        .placeholder_text("http://proxy.example:8080")
+        .activates_default(true)
+        .build();
+    if let Some(proxy) = settings.borrow().proxy.as_deref() {
+        entry.set_text(proxy);
+    }
+    let dialog = AlertDialog::new(
+        Some(&gettext("Proxy")),
+        Some(&gettext(
+            "Leave empty to use the system proxy settings. Applies from the next connection.",
+        )),
+    );
+    dialog.set_extra_child(Some(&entry));
+    dialog.add_response("cancel", &gettext("_Cancel"));
+    dialog.add_response("save", &gettext("_Save"));
+    dialog.set_response_appearance("save", ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("save"));
+    dialog.set_close_response("cancel");
+
+    let radio = radio.clone();
+    let meta = meta.clone();
+    let settings = settings.clone();
+    dialog.connect_response(None, move |_, response| {
+        if response != "save" {
+            return;
+        }
+        let proxy = entry.text().trim().to_string();
+        let proxy = (!proxy.is_empty()).then_some(proxy);
+        radio.set_proxy(proxy.clone());
+        meta.set_proxy(proxy.clone());
+        let mut settings = settings.borrow_mut();
+        settings.proxy = proxy;
+        settings.save();
+    });
+    dialog.present(Some(window));
+}
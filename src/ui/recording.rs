@@ -0,0 +1,97 @@
+use adw::gtk::{
+    gio::{Menu, SimpleAction},
+    ApplicationWindow,
+};
+use adw::{prelude::*, AlertDialog};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use super::search::SessionHistory;
+use crate::listen::Listen;
+use crate::locale::t;
+use crate::record::{self, Recorder};
+
+/// Length of a saved clip.
+const CLIP: Duration = Duration::from_secs(60);
+
+pub type ActiveRecorder = Rc<RefCell<Option<Recorder>>>;
+
+/// Add the `win.record` toggle, which saves each track as received from the stream, and
+/// `win.save_clip`, which saves the last minute from the timeshift buffer.
+pub fn install(
+    window: &ApplicationWindow,
+    menu: &Menu,
+    radio: &Rc<Listen>,
+    history: &SessionHistory,
+) -> ActiveRecorder {
+    let recorder: ActiveRecorder = Rc::new(RefCell::new(None));
+
+    let action = SimpleAction::new_stateful("record", None, &false.to_variant());
+    {
+        let radio = radio.clone();
+        let history = history.clone();
+        let recorder = recorder.clone();
+        action.connect_activate(move |action, _| {
+            let mut active = recorder.borrow_mut();
+            if active.take().is_none() {
+                let current = history.borrow().back().cloned();
+                match Recorder::start(radio.record_tap(), current) {
+                    Ok(r) => *active = Some(r),
+                    Err(err) => eprintln!("Failed to start recording: {err}"),
+                }
+            }
+            action.set_state(&active.is_some().to_variant());
+        });
+    }
+    window.add_action(&action);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Record tracks")),
+        Some("win.record"),
+    );
+
+    let action = SimpleAction::new("save_clip", None);
+    {
+        let window = window.clone();
+        let radio = radio.clone();
+        let history = history.clone();
+        action.connect_activate(move |_, _| save_clip(&window, &radio, &history));
+    }
+    window.add_action(&action);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Save the last minute")),
+        Some("win.save_clip"),
+    );
+    recorder
+}
+
+fn save_clip(window: &ApplicationWindow, radio: &Listen, history: &SessionHistory) {
+    let clip = radio.clip(CLIP);
+    let current = history.borrow().back().cloned();
+    let (heading, body) = match (clip, current) {
+        (Some(clip), Some(info)) => {
+            match record::save_clip(&clip.head, &clip.data, clip.start, &info) {
+                Ok(path) => (gettext("Clip saved"), path.display().to_string()),
+                Err(err) => {
+                    eprintln!("Failed to save clip: {err}");
+                    (
+                        gettext("Saving failed"),
+                        t!("Could not write the clip: {}", err),
+                    )
+                }
+            }
+        }
+        _ => (
+            gettext("Nothing to save yet"),
+            gettext("Clips come from the timeshift buffer, which fills while a track is playing."),
+        ),
+    };
+    let dialog = AlertDialog::new(Some(&heading), Some(&body));
+    dialog.add_response("close", &gettext("_Close"));
+    dialog.present(Some(window));
+}
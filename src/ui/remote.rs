@@ -0,0 +1,280 @@
+use adw::gtk::{
+    self,
+    gio::{Menu, SimpleAction},
+    ApplicationWindow,
+};
+use adw::{glib, prelude::*, AlertDialog, ResponseAppearance};
+use gettextrs::gettext;
+use qrcodegen::{QrCode, QrCodeEcc};
+use std::cell::RefCell;
+use std::mem;
+use std::rc::Rc;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+use crate::events::PlaybackState;
+use crate::meta::TrackInfo;
+use crate::remote::{self, Command, RemoteServer, SharedStatus, Status};
+use crate::settings::Settings;
+use crate::station::Station;
+
+/// How often commands from the server thread are picked up.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const TOKEN_BYTES: usize = 16;
+/// Width and height of the pairing code.
+const QR_SIZE: i32 = 240;
+/// Light modules scanners expect around the code.
+const QR_BORDER: i32 = 4;
+
+/// The `win.remote_control` toggle, the HTTP server behind it and `win.remote_pair`, which
+/// shares it with phones on the local network.
+pub struct RemoteControl {
+    window: ApplicationWindow,
+    settings: Rc<RefCell<Settings>>,
+    status: SharedStatus,
+    server: RefCell<Option<RemoteServer>>,
+}
+
+impl RemoteControl {
+    pub fn install(
+        window: &ApplicationWindow,
+        menu: &Menu,
+        settings: &Rc<RefCell<Settings>>,
+        station: Station,
+    ) -> Rc<Self> {
+        let remote = Rc::new(Self {
+            window: window.clone(),
+            settings: settings.clone(),
+            status: Arc::new(Mutex::new(Status::new(station))),
+            server: RefCell::new(None),
+        });
+        let enabled = settings.borrow().remote_control && remote.start();
+
+        let action = SimpleAction::new_stateful("remote_control", None, &enabled.to_variant());
+        {
+            let remote = remote.clone();
+            action.connect_activate(move |action, _| {
+                let running = if remote.server.borrow_mut().take().is_some() {
+                    false
+                } else {
+                    remote.start()
+                };
+                action.set_state(&running.to_variant());
+                let mut settings = remote.settings.borrow_mut();
+                settings.remote_control = running;
+                settings.save();
+            });
+        }
+        window.add_action(&action);
+        menu.insert(
+            menu.n_items() - 2,
+            Some(&gettext("Remote control over HTTP")),
+            Some("win.remote_control"),
+        );
+
+        let pair = SimpleAction::new("remote_pair", None);
+        {
+            let remote = remote.clone();
+            pair.connect_activate(move |_, _| remote.pair());
+        }
+        window.add_action(&pair);
+        menu.insert(
+            menu.n_items() - 2,
+            Some(&gettext("Pair a phone…")),
+            Some("win.remote_pair"),
+        );
+        remote
+    }
+
+    /// Start listening on the configured port; `false` when it is taken.
+    fn start(self: &Rc<Self>) -> bool {
+        let (port, token) = {
+            let settings = self.settings.borrow();
+            (settings.remote_port, settings.remote_token.clone())
+        };
+        let (tx, rx) = mpsc::channel();
+        match RemoteServer::start(port, token, self.status.clone(), tx) {
+            Ok(server) => {
+                #[cfg(debug_assertions)]
+                println!("Remote control listening on http://{}", server.addr());
+                *self.server.borrow_mut() = Some(server);
+            }
+            Err(err) => {
+                eprintln!("Failed to start remote control on port {port}: {err}");
+                return false;
+            }
+        }
+
+        let weak = Rc::downgrade(self);
+        glib::timeout_add_local(POLL_INTERVAL, move || {
+            let Some(remote) = weak.upgrade() else {
+                return glib::ControlFlow::Break;
+            };
+            // The sender lives in the server thread; once it is gone, so is this poll.
+            loop {
+                match rx.try_recv() {
+                    Ok(command) => remote.run(command),
+                    Err(mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                    Err(mpsc::TryRecvError::Disconnected) => return glib::ControlFlow::Break,
+                }
+            }
+        });
+        true
+    }
+
+    /// Serve again with `token`, turning the remote control on; the previous token is kept
+    /// when that fails.
+    fn restart(self: &Rc<Self>, token: Option<String>) -> bool {
+        let previous = mem::replace(&mut self.settings.borrow_mut().remote_token, token);
+        self.server.borrow_mut().take();
+        let started = self.start();
+        if !started {
+            self.settings.borrow_mut().remote_token = previous;
+            self.start();
+        }
+
+        let running = self.server.borrow().is_some();
+        if let Some(action) = self.window.lookup_action("remote_control") {
+            action.change_state(&running.to_variant());
+        }
+        let mut settings = self.settings.borrow_mut();
+        settings.remote_control = running;
+        settings.save();
+        started
+    }
+
+    /// Show the code a phone scans to open the remote, sharing it on the network first.
+    fn pair(self: &Rc<Self>) {
+        let Some(ip) = remote::lan_ip() else {
+            let dialog = AlertDialog::new(
+                Some(&gettext("Pair a phone")),
+                Some(&gettext(
+                    "This computer is not connected to a local network.",
+                )),
+            );
+            dialog.add_response("close", &gettext("_Close"));
+            dialog.present(Some(&self.window));
+            return;
+        };
+        let shared =
+            self.server.borrow().is_some() && self.settings.borrow().remote_token.is_some();
+        if !shared && !self.restart(Some(new_token())) {
+            return;
+        }
+        let url = {
+            let settings = self.settings.borrow();
+            let token = settings.remote_token.as_deref().unwrap_or_default();
+            remote::pairing_url(ip, settings.remote_port, token)
+        };
+        let code = match QrCode::encode_text(&url, QrCodeEcc::Medium) {
+            Ok(code) => code,
+            Err(err) => {
+                eprintln!("Failed to encode {url}: {err}");
+                return;
+            }
+        };
+
+        let link = gtk::Label::new(Some(&url));
+        link.set_selectable(true);
+        link.set_wrap(true);
+        link.set_wrap_mode(gtk::pango::WrapMode::Char);
+        link.add_css_class("caption");
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
+        content.append(&qr_area(code));
+        content.append(&link);
+
+        let dialog = AlertDialog::new(
+            Some(&gettext("Pair a phone")),
+            Some(&gettext(
+                "Scan this code with a phone on the same network to control playback from its browser.",
+            )),
+        );
+        dialog.set_extra_child(Some(&content));
+        dialog.add_response("stop", &gettext("_Stop sharing"));
+        dialog.add_response("rotate", &gettext("_New code"));
+        dialog.add_response("close", &gettext("_Close"));
+        dialog.set_response_appearance("stop", ResponseAppearance::Destructive);
+        dialog.set_default_response(Some("close"));
+        dialog.set_close_response("close");
+        let remote = self.clone();
+        dialog.connect_response(None, move |_, response| match response {
+            // Phones paired with the old code lose access.
+            "rotate" if remote.restart(Some(new_token())) => {
+                let remote = remote.clone();
+                glib::idle_add_local_once(move || remote.pair());
+            }
+            "stop" => {
+                remote.restart(None);
+            }
+            _ => {}
+        });
+        dialog.present(Some(&self.window));
+    }
+
+    fn run(&self, command: Command) {
+        let action = match command {
+            Command::Play => "win.play".to_string(),
+            Command::Pause => "win.pause".to_string(),
+            Command::Station(station) => format!("win.{}", station.name()),
+        };
+        let _ = WidgetExt::activate_action(&self.window, &action, None::<&glib::Variant>);
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut Status)) {
+        if let Ok(mut status) = self.status.lock() {
+            apply(&mut status);
+        }
+    }
+
+    pub fn track_changed(&self, info: &TrackInfo) {
+        self.update(|status| status.set_track(info));
+    }
+
+    pub fn set_playback(&self, state: PlaybackState) {
+        self.update(|status| status.set_state(state));
+    }
+
+    pub fn set_station(&self, station: Station) {
+        self.update(|status| status.set_station(station));
+    }
+}
+
+/// 128 bits from the OS random source, as hex; it is all that guards the network remote.
+fn new_token() -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    getrandom::fill(&mut bytes).expect("OS random source unavailable");
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Black modules on white whatever the theme, in whole pixels so they stay sharp.
+fn qr_area(code: QrCode) -> gtk::DrawingArea {
+    let area = gtk::DrawingArea::new();
+    area.set_content_width(QR_SIZE);
+    area.set_content_height(QR_SIZE);
+    area.set_halign(gtk::Align::Center);
+    area.set_draw_func(move |_, cr, w, h| {
+        let modules = code.size() + 2 * QR_BORDER;
+        let scale = (w.min(h) / modules).max(1);
+        let x0 = f64::from((w - scale * modules) / 2 + scale * QR_BORDER);
+        let y0 = f64::from((h - scale * modules) / 2 + scale * QR_BORDER);
+        let scale = f64::from(scale);
+
+        cr.set_source_rgb(1.0, 1.0, 1.0);
+        let _ = cr.paint();
+        cr.set_source_rgb(0.0, 0.0, 0.0);
+        for y in 0..code.size() {
+            for x in 0..code.size() {
+                if code.get_module(x, y) {
+                    cr.rectangle(
+                        x0 + f64::from(x) * scale,
+                        y0 + f64::from(y) * scale,
+                        scale,
+                        scale,
+                    );
+                }
+            }
+        }
+        let _ = cr.fill();
+    });
+    area
+}
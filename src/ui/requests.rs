@@ -0,0 +1,563 @@
+use adw::gtk::{
+    self,
+    gio::{self, Menu, SimpleAction},
+    ApplicationWindow, Orientation,
+};
+use adw::{glib, prelude::*, ActionRow, EntryRow, PasswordEntryRow, PreferencesGroup, Toast};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::favorites::Favorites;
+use crate::api::{ApiResult, Client, Login, Session, Song, User};
+use crate::locale::{n, t};
+use crate::meta::TrackInfo;
+use crate::settings::Settings;
+
+/// Search results shown at once.
+const RESULTS: u32 = 30;
+
+/// Where a request made in this session stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Sending,
+    Queued,
+    Playing,
+}
+
+/// Song requests for logged-in LISTEN.moe supporters: the `win.request_song` dialog logs in,
+/// searches the library and sends requests, and follows them until they are played.
+pub struct Requests {
+    window: ApplicationWindow,
+    settings: Rc<RefCell<Settings>>,
+    session: RefCell<Option<Session>>,
+    /// Requests of this session, by song id, with the title to spot them on air.
+    requested: RefCell<HashMap<u32, (Status, String)>>,
+    /// Result rows of the open dialog, to update their status in place.
+    rows: RefCell<Vec<(u32, gtk::Button)>>,
+    toasts: RefCell<Option<adw::ToastOverlay>>,
+    favorites: Rc<Favorites>,
+    /// Favorite sync progress in the open dialog.
+    progress: RefCell<Option<gtk::ProgressBar>>,
+}
+
+impl Requests {
+    pub fn install(
+        window: &ApplicationWindow,
+        menu: &Menu,
+        settings: &Rc<RefCell<Settings>>,
+        favorites: &Rc<Favorites>,
+    ) -> Rc<Self> {
+        let requests = Rc::new(Self {
+            window: window.clone(),
+            settings: settings.clone(),
+            session: RefCell::new(Session::load()),
+            requested: RefCell::new(HashMap::new()),
+            rows: RefCell::new(Vec::new()),
+            toasts: RefCell::new(None),
+            favorites: favorites.clone(),
+            progress: RefCell::new(None),
+        });
+
+        let action = SimpleAction::new("request_song", None);
+        {
+            let requests = requests.clone();
+            action.connect_activate(move |_, _| requests.present());
+        }
+        window.add_action(&action);
+        menu.insert(
+            menu.n_items() - 2,
+            Some(&gettext("Request a song…")),
+            Some("win.request_song"),
+        );
+        requests
+    }
+
+    /// Mark a request as playing once its song comes on; requests played before are done.
+    pub fn track_changed(&self, info: &TrackInfo) {
+        let mut started = false;
+        {
+            let mut requested = self.requested.borrow_mut();
+            requested.retain(|_, (status, _)| *status != Status::Playing);
+            for (status, title) in requested.values_mut() {
+                if *status == Status::Queued && *title == info.title {
+                    *status = Status::Playing;
+                    started = true;
+                }
+            }
+        }
+        self.refresh_rows();
+        if started {
+            self.toast(&t!("Your request is playing: {}", info.title));
+        }
+    }
+
+    fn client(&self) -> ApiResult<Client> {
+        let (proxy, timeouts) = {
+            let settings = self.settings.borrow();
+            (settings.proxy.clone(), settings.timeouts)
+        };
+        let client = Client::new(proxy.as_deref(), timeouts)?;
+        Ok(match self.session.borrow().as_ref() {
+            Some(session) => client.with_session(session),
+            None => client,
+        })
+    }
+
+    fn present(self: &Rc<Self>) {
+        let stack = gtk::Stack::new();
+        stack.set_transition_type(gtk::StackTransitionType::Crossfade);
+        let header = adw::HeaderBar::new();
+        let logout = gtk::Button::with_mnemonic(&gettext("Log _out"));
+        header.pack_end(&logout);
+
+        let progress = gtk::ProgressBar::builder()
+            .show_text(true)
+            .text(gettext("Syncing favorites…"))
+            .visible(false)
+            .build();
+        *self.progress.borrow_mut() = Some(progress.clone());
+
+        let toolbar = adw::ToolbarView::new();
+        toolbar.add_top_bar(&header);
+        toolbar.add_top_bar(&progress);
+        toolbar.set_content(Some(&stack));
+        let overlay = adw::ToastOverlay::new();
+        overlay.set_child(Some(&toolbar));
+        *self.toasts.borrow_mut() = Some(overlay.clone());
+
+        let dialog = adw::Dialog::builder()
+            .title(gettext("Request a song"))
+            .content_width(420)
+            .content_height(560)
+            .child(&overlay)
+            .build();
+
+        let show: Rc<dyn Fn()> = {
+            let requests = Rc::downgrade(self);
+            let stack = stack.clone();
+            let logout = logout.clone();
+            Rc::new(move || {
+                let Some(requests) = requests.upgrade() else {
+                    return;
+                };
+                let logged_in = requests.session.borrow().is_some();
+                logout.set_visible(logged_in);
+                stack.set_visible_child_name(if logged_in { "search" } else { "login" });
+            })
+        };
+        stack.add_named(&self.login_page(show.clone()), Some("login"));
+        stack.add_named(&self.search_page(), Some("search"));
+        {
+            let requests = self.clone();
+            let show = show.clone();
+            logout.connect_clicked(move |_| {
+                Session::clear();
+                requests.session.borrow_mut().take();
+                show();
+            });
+        }
+        show();
+
+        {
+            let requests = self.clone();
+            dialog.connect_closed(move |_| {
+                requests.rows.borrow_mut().clear();
+                requests.toasts.borrow_mut().take();
+                requests.progress.borrow_mut().take();
+            });
+        }
+        dialog.present(Some(&self.window));
+    }
+
+    fn login_page(self: &Rc<Self>, on_login: Rc<dyn Fn()>) -> gtk::Widget {
+        let username = EntryRow::builder().title(gettext("Username")).build();
+        let password = PasswordEntryRow::builder()
+            .title(gettext("Password"))
+            .build();
+        let code = EntryRow::builder()
+            .title(gettext("Two-factor code"))
+            .visible(false)
+            .build();
+        let group = PreferencesGroup::builder()
+            .title(gettext("LISTEN.moe account"))
+            .description(gettext(
+                "Log in to request songs. Requests are a supporter perk.",
+            ))
+            .build();
+        group.add(&username);
+        group.add(&password);
+        group.add(&code);
+
+        let button = gtk::Button::with_mnemonic(&gettext("_Log in"));
+        button.add_css_class("suggested-action");
+        button.add_css_class("pill");
+        button.set_halign(gtk::Align::Center);
+        button.set_margin_top(12);
+        group.add(&button);
+
+        let pending: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+        let submit = {
+            let requests = Rc::downgrade(self);
+            let button = button.clone();
+            let (username, password, code) = (username.clone(), password.clone(), code.clone());
+            move || {
+                let Some(requests) = requests.upgrade() else {
+                    return;
+                };
+                let client = match requests.client() {
+                    Ok(client) => client,
+                    Err(err) => {
+                        requests.toast(&err.to_string());
+                        return;
+                    }
+                };
+                let pending_token = pending.borrow().clone();
+                let (name, secret, otp) = (username.text(), password.text(), code.text());
+                button.set_sensitive(false);
+
+                let button = button.clone();
+                let code = code.clone();
+                let pending = pending.clone();
+                let on_login = on_login.clone();
+                run(
+                    move || match pending_token {
+                        Some(token) => client.login_mfa(&token, &otp).map(Login::Done),
+                        None => client.login(&name, &secret),
+                    },
+                    move |result| {
+                        button.set_sensitive(true);
+                        match result {
+                            Ok(Login::Done(session)) => {
+                                session.save();
+                                requests.toast(&t!("Logged in as {}", session.name()));
+                                *requests.session.borrow_mut() = Some(session);
+                                pending.borrow_mut().take();
+                                code.set_visible(false);
+                                on_login();
+                                requests.sync_favorites();
+                            }
+                            Ok(Login::NeedsCode(token)) => {
+                                *pending.borrow_mut() = Some(token);
+                                code.set_visible(true);
+                                code.grab_focus();
+                            }
+                            Err(err) => {
+                                eprintln!("Login failed: {err}");
+                                requests.toast(&t!("Login failed: {}", err));
+                            }
+                        }
+                    },
+                );
+            }
+        };
+        let submit = Rc::new(submit);
+        for entry in [&username, &code] {
+            let submit = submit.clone();
+            entry.connect_entry_activated(move |_| submit());
+        }
+        {
+            let submit = submit.clone();
+            password.connect_entry_activated(move |_| submit());
+        }
+        button.connect_clicked(move |_| submit());
+
+        let page = adw::PreferencesPage::new();
+        page.add(&group);
+        page.upcast()
+    }
+
+    fn search_page(self: &Rc<Self>) -> gtk::Widget {
+        let entry = gtk::SearchEntry::new();
+        entry.set_placeholder_text(Some(&gettext("Song, artist or anime")));
+        let list = gtk::ListBox::new();
+        list.set_selection_mode(gtk::SelectionMode::None);
+        list.add_css_class("boxed-list");
+        list.set_visible(false);
+        let status = adw::StatusPage::builder()
+            .icon_name("system-search-symbolic")
+            .description(gettext("Find a song to request"))
+            .vexpand(true)
+            .build();
+
+        let heading = gtk::Label::builder()
+            .label(gettext("Your favorites"))
+            .halign(gtk::Align::Start)
+            .visible(false)
+            .build();
+        heading.add_css_class("heading");
+
+        let content = gtk::Box::new(Orientation::Vertical, 12);
+        content.set_margin_top(12);
+        content.set_margin_bottom(12);
+        content.set_margin_start(12);
+        content.set_margin_end(12);
+        content.append(&entry);
+        content.append(&heading);
+        content.append(&list);
+        content.append(&status);
+        let scroller = gtk::ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .vexpand(true)
+            .child(&content)
+            .build();
+
+        // Until something is searched for, offer the account's favorites.
+        let show_favorites = {
+            let requests = Rc::downgrade(self);
+            let (list, status, heading) = (list.clone(), status.clone(), heading.clone());
+            move || {
+                let Some(requests) = requests.upgrade() else {
+                    return;
+                };
+                let Some(username) = requests
+                    .session
+                    .borrow()
+                    .as_ref()
+                    .map(|s| s.username.clone())
+                else {
+                    return;
+                };
+                let Ok(client) = requests.client() else {
+                    return;
+                };
+                let (list, status, heading) = (list.clone(), status.clone(), heading.clone());
+                run(
+                    move || client.user(&username, RESULTS),
+                    move |result| match result {
+                        Ok(user) => {
+                            requests.account_loaded(&user);
+                            if user.favorites.count == 0 {
+                                return;
+                            }
+                            status.set_visible(false);
+                            heading.set_visible(true);
+                            requests.show_results(&list, user.favorites.songs().cloned().collect());
+                        }
+                        Err(err) => eprintln!("Failed to load favorites: {err}"),
+                    },
+                );
+            }
+        };
+        let show_favorites = Rc::new(show_favorites);
+        {
+            let (entry, show_favorites) = (entry.clone(), show_favorites.clone());
+            scroller.connect_map(move |_| {
+                if entry.text().trim().is_empty() {
+                    show_favorites();
+                }
+            });
+        }
+
+        let requests = Rc::downgrade(self);
+        entry.connect_activate(move |entry| {
+            let query = entry.text().trim().to_string();
+            let Some(requests) = requests.upgrade() else {
+                return;
+            };
+            if query.is_empty() {
+                show_favorites();
+                return;
+            }
+            let client = match requests.client() {
+                Ok(client) => client,
+                Err(err) => {
+                    requests.toast(&err.to_string());
+                    return;
+                }
+            };
+            status.set_description(Some(&gettext("Searching…")));
+            status.set_visible(true);
+            heading.set_visible(false);
+            list.set_visible(false);
+
+            let list = list.clone();
+            let status = status.clone();
+            run(
+                move || client.search(&query, RESULTS),
+                move |result| match result {
+                    Ok(songs) if songs.is_empty() => {
+                        status.set_description(Some(&gettext("No songs found")));
+                    }
+                    Ok(songs) => {
+                        status.set_visible(false);
+                        requests.show_results(&list, songs);
+                    }
+                    Err(err) => {
+                        eprintln!("Song search failed: {err}");
+                        status.set_description(Some(&gettext("Could not search the library")));
+                    }
+                },
+            );
+        });
+        scroller.upcast()
+    }
+
+    /// Merge local favorites with the account, showing progress while the dialog is open.
+    fn sync_favorites(self: &Rc<Self>) {
+        let Some(username) = self.session.borrow().as_ref().map(|s| s.username.clone()) else {
+            return;
+        };
+        let client = match self.client() {
+            Ok(client) => client,
+            Err(err) => {
+                eprintln!("Favorite sync failed: {err}");
+                return;
+            }
+        };
+        let requests = self.clone();
+        glib::MainContext::default().spawn_local(async move {
+            let progress = requests.progress.borrow().clone();
+            if let Some(bar) = &progress {
+                bar.set_visible(true);
+            }
+            let result = requests
+                .favorites
+                .sync(client, username, |fraction| {
+                    if let Some(bar) = &progress {
+                        bar.set_fraction(fraction);
+                    }
+                })
+                .await;
+            if let Some(bar) = &progress {
+                bar.set_visible(false);
+            }
+            match result {
+                Ok(synced) => {
+                    requests.toast(&t!(
+                        "Favorites synced: {} added here, {} added to your account",
+                        synced.downloaded,
+                        synced.uploaded
+                    ));
+                    if synced.failed > 0 {
+                        requests.toast(&n!(
+                            "{} favorite could not be added to your account",
+                            "{} favorites could not be added to your account",
+                            synced.failed as u32
+                        ));
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Favorite sync failed: {err}");
+                    requests.toast(&t!("Could not sync favorites: {}", err));
+                }
+            }
+        });
+    }
+
+    /// Keep the saved display name current with the profile.
+    fn account_loaded(&self, user: &User) {
+        let mut session = self.session.borrow_mut();
+        let Some(session) = session.as_mut() else {
+            return;
+        };
+        if session.username == user.username && session.display_name != user.display_name {
+            session.display_name = user.display_name.clone();
+            session.save();
+        }
+    }
+
+    fn show_results(self: &Rc<Self>, list: &gtk::ListBox, songs: Vec<Song>) {
+        while let Some(child) = list.first_child() {
+            list.remove(&child);
+        }
+        let mut rows = self.rows.borrow_mut();
+        rows.clear();
+        for song in songs {
+            let subtitle = match song.sources() {
+                sources if sources.is_empty() => song.artists(),
+                sources => format!("{} · {sources}", song.artists()),
+            };
+            let row = ActionRow::builder()
+                .title(glib::markup_escape_text(song.title()))
+                .subtitle(glib::markup_escape_text(&subtitle))
+                .build();
+            let button = gtk::Button::builder().valign(gtk::Align::Center).build();
+            row.add_suffix(&button);
+            list.append(&row);
+
+            let id = song.id;
+            let requests = Rc::downgrade(self);
+            button.connect_clicked(move |_| {
+                if let Some(requests) = requests.upgrade() {
+                    requests.request(&song);
+                }
+            });
+            rows.push((id, button));
+        }
+        list.set_visible(true);
+        drop(rows);
+        self.refresh_rows();
+    }
+
+    fn request(self: &Rc<Self>, song: &Song) {
+        let client = match self.client() {
+            Ok(client) => client,
+            Err(err) => {
+                self.toast(&err.to_string());
+                return;
+            }
+        };
+        self.requested
+            .borrow_mut()
+            .insert(song.id, (Status::Sending, song.title().to_string()));
+        self.refresh_rows();
+
+        let requests = self.clone();
+        let (id, title) = (song.id, song.title().to_string());
+        run(
+            move || client.request_song(id),
+            move |result| {
+                match result {
+                    Ok(()) => {
+                        if let Some(entry) = requests.requested.borrow_mut().get_mut(&id) {
+                            entry.0 = Status::Queued;
+                        }
+                        requests.toast(&t!("Requested {}", title));
+                    }
+                    Err(err) => {
+                        eprintln!("Song request failed: {err}");
+                        requests.requested.borrow_mut().remove(&id);
+                        requests.toast(&err.to_string());
+                    }
+                }
+                requests.refresh_rows();
+            },
+        );
+    }
+
+    /// Label each result button with the status of its request.
+    fn refresh_rows(&self) {
+        let requested = self.requested.borrow();
+        for (id, button) in self.rows.borrow().iter() {
+            let (label, enabled) = match requested.get(id).map(|entry| entry.0) {
+                None => (gettext("Request"), true),
+                Some(Status::Sending) => (gettext("Sending…"), false),
+                Some(Status::Queued) => (gettext("Requested"), false),
+                Some(Status::Playing) => (gettext("Playing"), false),
+            };
+            button.set_label(&label);
+            button.set_sensitive(enabled);
+        }
+    }
+
+    fn toast(&self, message: &str) {
+        if let Some(overlay) = self.toasts.borrow().as_ref() {
+            overlay.add_toast(Toast::new(message));
+        }
+    }
+}
+
+/// Run `work` on a worker thread and hand its result to `done` on the main loop.
+fn run<T: Send + 'static>(
+    work: impl FnOnce() -> T + Send + 'static,
+    done: impl FnOnce(T) + 'static,
+) {
+    glib::MainContext::default().spawn_local(async move {
+        match gio::spawn_blocking(work).await {
+            Ok(result) => done(result),
+            Err(_) => eprintln!("API worker panicked"),
+        }
+    });
+}
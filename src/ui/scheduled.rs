@@ -0,0 +1,246 @@
+use adw::gtk::{
+    self,
+    gio::{Menu, SimpleAction},
+    glib, ApplicationWindow,
+};
+use adw::{prelude::*, AlertDialog, ComboRow, ResponseAppearance, SpinRow};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::AtomicU64;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::events::{EventBus, PlayerEvent};
+use crate::listen::Capture;
+use crate::locale::t;
+use crate::meta::Meta;
+use crate::record::{self, Recorder};
+use crate::settings::{ScheduledRecording, Settings};
+use crate::station::Station;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A scheduled recording in progress: its own stream and metadata connections, so it
+/// does not depend on what is playing.
+struct Active {
+    end: i64,
+    meta: Rc<Meta>,
+    events: mpsc::Receiver<PlayerEvent>,
+    recorder: Recorder,
+    _capture: Capture,
+}
+
+impl Drop for Active {
+    fn drop(&mut self) {
+        self.meta.stop();
+    }
+}
+
+/// Add the `win.schedule_recording` dialog and run the scheduled recording when its time
+/// comes.
+pub fn install(window: &ApplicationWindow, menu: &Menu, settings: &Rc<RefCell<Settings>>) {
+    let action = SimpleAction::new("schedule_recording", None);
+    {
+        let window = window.clone();
+        let settings = settings.clone();
+        action.connect_activate(move |_, _| request_schedule(&window, &settings));
+    }
+    window.add_action(&action);
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Schedule recording…")),
+        Some("win.schedule_recording"),
+    );
+
+    let settings = settings.clone();
+    let mut active: Option<Active> = None;
+    glib::timeout_add_local(POLL_INTERVAL, move || {
+        let now = unix_now();
+        if let Some(recording) = active.as_ref() {
+            for event in recording.events.try_iter() {
+                if let PlayerEvent::TrackChanged(info) = event {
+                    recording.recorder.track_changed(&info);
+                }
+            }
+        }
+        let scheduled = settings.borrow().scheduled_recording.clone();
+        match (&active, scheduled) {
+            (Some(recording), scheduled) if now >= recording.end || scheduled.is_none() => {
+                active = None;
+                let mut settings = settings.borrow_mut();
+                if settings
+                    .scheduled_recording
+                    .as_ref()
+                    .is_some_and(|s| s.end() <= now)
+                {
+                    settings.scheduled_recording = None;
+                    settings.save();
+                }
+            }
+            (None, Some(scheduled)) if now >= scheduled.end() => {
+                eprintln!("Missed the scheduled recording of {}", scheduled.station);
+                let mut settings = settings.borrow_mut();
+                settings.scheduled_recording = None;
+                settings.save();
+            }
+            (None, Some(scheduled)) if now >= scheduled.start => {
+                active = begin(&scheduled, &settings.borrow());
+            }
+            _ => {}
+        }
+        glib::ControlFlow::Continue
+    });
+}
+
+fn begin(scheduled: &ScheduledRecording, settings: &Settings) -> Option<Active> {
+    let station = Station::from_name(&scheduled.station)?;
+    let tap = record::new_tap();
+    let recorder = match Recorder::start(tap.clone(), None) {
+        Ok(recorder) => recorder,
+        Err(err) => {
+            eprintln!("Failed to start the scheduled recording: {err}");
+            return None;
+        }
+    };
+    let bus = EventBus::new();
+    let events = bus.subscribe();
+    // Nothing is played, so the metadata is not held back for buffered audio.
+    let meta = Meta::new(station, bus, Arc::new(AtomicU64::new(0)));
+    meta.set_proxy(settings.proxy.clone());
+    meta.set_timeouts(settings.timeouts);
+    meta.start();
+    let capture = Capture::start(
+        station,
+        settings.stream_format,
+        settings.proxy.clone(),
+        settings.timeouts,
+        tap,
+    );
+    Some(Active {
+        end: scheduled.end(),
+        meta,
+        events,
+        recorder,
+        _capture: capture,
+    })
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// The next time the local clock shows `hour:minute`, as Unix time.
+fn next_occurrence(hour: i32, minute: i32) -> Option<i64> {
+    let now = glib::DateTime::now_local().ok()?;
+    let today = glib::DateTime::from_local(
+        now.year(),
+        now.month(),
+        now.day_of_month(),
+        hour,
+        minute,
+        0.0,
+    )
+    .ok()?;
+    let next = if today.to_unix() <= now.to_unix() {
+        today.add_days(1).ok()?
+    } else {
+        today
+    };
+    Some(next.to_unix())
+}
+
+fn describe(scheduled: &ScheduledRecording) -> Option<String> {
+    let station = Station::from_name(&scheduled.station)?;
+    let start = glib::DateTime::from_unix_local(scheduled.start)
+        .ok()?
+        .format("%a %H:%M")
+        .ok()?;
+    Some(t!(
+        "{} on {} for {} min",
+        station.display_name(),
+        start,
+        scheduled.duration_mins
+    ))
+}
+
+fn request_schedule(window: &ApplicationWindow, settings: &Rc<RefCell<Settings>>) {
+    let current = settings.borrow().scheduled_recording.clone();
+
+    let names: Vec<&str> = Station::ALL.iter().map(|s| s.display_name()).collect();
+    let station = ComboRow::new();
+    station.set_title(&gettext("Station"));
+    station.set_model(Some(&gtk::StringList::new(&names)));
+    let start = current
+        .as_ref()
+        .and_then(|c| glib::DateTime::from_unix_local(c.start).ok());
+    if let Some(index) = current
+        .as_ref()
+        .and_then(|c| Station::ALL.iter().position(|s| s.name() == c.station))
+    {
+        station.set_selected(index as u32);
+    }
+    let hour = SpinRow::with_range(0.0, 23.0, 1.0);
+    hour.set_title(&gettext("Hour"));
+    hour.set_value(start.as_ref().map_or(20, |s| s.hour()).into());
+    let minute = SpinRow::with_range(0.0, 59.0, 1.0);
+    minute.set_title(&gettext("Minute"));
+    minute.set_value(start.as_ref().map_or(0, |s| s.minute()).into());
+    let duration = SpinRow::with_range(1.0, 24.0 * 60.0, 5.0);
+    duration.set_title(&gettext("Duration"));
+    duration.set_subtitle(&gettext("Minutes"));
+    duration.set_value(current.as_ref().map_or(60, |c| c.duration_mins).into());
+
+    let rows = gtk::ListBox::new();
+    rows.set_selection_mode(gtk::SelectionMode::None);
+    rows.add_css_class("boxed-list");
+    rows.append(&station);
+    rows.append(&hour);
+    rows.append(&minute);
+    rows.append(&duration);
+
+    let body = match current.as_ref().and_then(describe) {
+        Some(scheduled) => t!("Scheduled: {}", scheduled),
+        None => gettext(
+            "Record a station at a set time, even while nothing is playing. Tracks are saved as separate files.",
+        ),
+    };
+    let dialog = AlertDialog::new(Some(&gettext("Schedule recording")), Some(&body));
+    dialog.set_extra_child(Some(&rows));
+    dialog.add_response("cancel", &gettext("_Cancel"));
+    if current.is_some() {
+        dialog.add_response("clear", &gettext("Cancel _recording"));
+        dialog.set_response_appearance("clear", ResponseAppearance::Destructive);
+    }
+    dialog.add_response("save", &gettext("S_chedule"));
+    dialog.set_response_appearance("save", ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("save"));
+    dialog.set_close_response("cancel");
+
+    let settings = settings.clone();
+    dialog.connect_response(None, move |_, response| {
+        let scheduled = match response {
+            "clear" => None,
+            "save" => {
+                let Some(start) = next_occurrence(hour.value() as i32, minute.value() as i32)
+                else {
+                    return;
+                };
+                let station =
+                    Station::ALL[(station.selected() as usize).min(Station::ALL.len() - 1)];
+                Some(ScheduledRecording {
+                    station: station.name().to_string(),
+                    start,
+                    duration_mins: duration.value() as u32,
+                })
+            }
+            _ => return,
+        };
+        let mut settings = settings.borrow_mut();
+        settings.scheduled_recording = scheduled;
+        settings.save();
+    });
+    dialog.present(Some(window));
+}
@@ -0,0 +1,254 @@
+use adw::gtk::{
+    self, gdk::Display, gio::SimpleAction, Align, ApplicationWindow, Label, ListBox, ListBoxRow,
+    Orientation, PolicyType, Popover, ScrolledWindow, SearchEntry, SelectionMode,
+};
+use adw::{glib, prelude::*, ActionRow};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use crate::history::PlayHistory;
+use crate::meta::TrackInfo;
+use crate::station::Station;
+
+/// Tracks seen this session, newest last.
+pub type SessionHistory = Rc<RefCell<VecDeque<TrackInfo>>>;
+
+const MAX_RESULTS_PER_CATEGORY: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Category {
+    Stations,
+    History,
+}
+
+impl Category {
+    fn label(self) -> String {
+        match self {
+            Category::Stations => gettext("Stations"),
+            Category::History => gettext("History"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Target {
+    Station(Station),
+    Track { artist: String, title: String },
+}
+
+#[derive(Debug, Clone)]
+struct Hit {
+    category: Category,
+    title: String,
+    subtitle: String,
+    target: Target,
+}
+
+fn matches(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(needle)
+}
+
+/// Search every content source, grouped by category in display order. Without a query,
+/// the most recent plays are listed.
+fn search(query: &str, history: &PlayHistory) -> Vec<Hit> {
+    let needle = query.trim().to_lowercase();
+    if needle.is_empty() {
+        return history
+            .recent(MAX_RESULTS_PER_CATEGORY)
+            .into_iter()
+            .map(|play| {
+                let subtitle = match Station::from_name(&play.station) {
+                    Some(station) => format!("{} · {}", play.artist, station.display_name()),
+                    None => play.artist.clone(),
+                };
+                track_hit(subtitle, play.artist, play.title)
+            })
+            .collect();
+    }
+
+    let stations = [Station::Jpop, Station::Kpop]
+        .into_iter()
+        .filter(|s| matches(s.display_name(), &needle) || matches(s.name(), &needle))
+        .map(|s| Hit {
+            category: Category::Stations,
+            title: s.display_name().to_string(),
+            subtitle: gettext("Play %s").replace("%s", s.display_name()),
+            target: Target::Station(s),
+        });
+
+    let tracks = history
+        .search(&needle, MAX_RESULTS_PER_CATEGORY)
+        .into_iter()
+        .map(|(artist, title)| track_hit(artist.clone(), artist, title));
+
+    stations.chain(tracks).collect()
+}
+
+fn track_hit(subtitle: String, artist: String, title: String) -> Hit {
+    Hit {
+        category: Category::History,
+        title: title.clone(),
+        subtitle,
+        target: Target::Track { artist, title },
+    }
+}
+
+fn activate(window: &ApplicationWindow, target: &Target) {
+    match target {
+        Target::Station(station) => {
+            let _ = adw::prelude::WidgetExt::activate_action(
+                window,
+                &format!("win.{}", station.name()),
+                None::<&glib::Variant>,
+            );
+        }
+        Target::Track { artist, title } => {
+            if let Some(display) = Display::default() {
+                display.clipboard().set_text(&format!("{artist}, {title}"));
+            }
+        }
+    }
+}
+
+fn header_row(category: Category) -> ListBoxRow {
+    let label = Label::builder()
+        .label(category.label())
+        .halign(Align::Start)
+        .margin_top(6)
+        .margin_start(6)
+        .build();
+    label.add_css_class("heading");
+    ListBoxRow::builder()
+        .child(&label)
+        .activatable(false)
+        .selectable(false)
+        .build()
+}
+
+/// Build the search popover and register `win.search` (Ctrl+F) to open it.
+pub fn build_search(
+    window: &ApplicationWindow,
+    parent: &impl IsA<gtk::Widget>,
+    history: &Rc<RefCell<PlayHistory>>,
+) -> Popover {
+    let entry = SearchEntry::builder()
+        .placeholder_text(gettext("Search stations and history"))
+        .build();
+    let list = ListBox::builder()
+        .selection_mode(SelectionMode::None)
+        .build();
+    list.add_css_class("boxed-list");
+    let scroller = ScrolledWindow::builder()
+        .hscrollbar_policy(PolicyType::Never)
+        .propagate_natural_height(true)
+        .max_content_height(320)
+        .child(&list)
+        .build();
+    let content = gtk::Box::new(Orientation::Vertical, 6);
+    content.append(&entry);
+    content.append(&scroller);
+
+    let popover = Popover::builder()
+        .has_arrow(true)
+        .position(gtk::PositionType::Bottom)
+        .autohide(true)
+        .child(&content)
+        .build();
+    popover.set_parent(parent);
+    popover.set_size_request(300, -1);
+
+    // Targets of the rows currently shown; header rows have none.
+    let row_targets: Rc<RefCell<Vec<Option<Target>>>> = Rc::new(RefCell::new(Vec::new()));
+
+    let refresh = {
+        let list = list.clone();
+        let history = history.clone();
+        let row_targets = row_targets.clone();
+        Rc::new(move |text: &str| {
+            while let Some(child) = list.first_child() {
+                list.remove(&child);
+            }
+            let mut targets = row_targets.borrow_mut();
+            targets.clear();
+
+            let hits = search(text, &history.borrow());
+            let mut category = None;
+            for hit in hits {
+                if category != Some(hit.category) {
+                    category = Some(hit.category);
+                    list.append(&header_row(hit.category));
+                    targets.push(None);
+                }
+                let row = ActionRow::builder()
+                    .title(glib::markup_escape_text(&hit.title))
+                    .subtitle(glib::markup_escape_text(&hit.subtitle))
+                    .activatable(true)
+                    .build();
+                list.append(&row);
+                targets.push(Some(hit.target));
+            }
+            if targets.is_empty() && !text.trim().is_empty() {
+                let label = Label::new(Some(&gettext("No results")));
+                label.add_css_class("dim-label");
+                list.append(
+                    &ListBoxRow::builder()
+                        .child(&label)
+                        .activatable(false)
+                        .selectable(false)
+                        .build(),
+                );
+            }
+        })
+    };
+    {
+        let refresh = refresh.clone();
+        entry.connect_search_changed(move |entry| refresh(&entry.text()));
+    }
+    {
+        let window = window.clone();
+        let popover = popover.clone();
+        let row_targets = row_targets.clone();
+        list.connect_row_activated(move |_, row| {
+            let target = row_targets
+                .borrow()
+                .get(row.index() as usize)
+                .cloned()
+                .flatten();
+            if let Some(target) = target {
+                popover.popdown();
+                activate(&window, &target);
+            }
+        });
+    }
+    {
+        let window = window.clone();
+        let popover = popover.clone();
+        entry.connect_activate(move |_| {
+            let first = row_targets.borrow().iter().flatten().next().cloned();
+            if let Some(target) = first {
+                popover.popdown();
+                activate(&window, &target);
+            }
+        });
+    }
+    {
+        let popover = popover.clone();
+        entry.connect_stop_search(move |_| popover.popdown());
+    }
+
+    let action = SimpleAction::new("search", None);
+    {
+        let popover = popover.clone();
+        action.connect_activate(move |_, _| {
+            entry.set_text("");
+            refresh("");
+            popover.popup();
+            entry.grab_focus();
+        });
+    }
+    window.add_action(&action);
+
+    popover
+}
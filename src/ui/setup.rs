@@ -0,0 +1,57 @@
+use adw::gtk::{
+    gio::{Menu, SimpleAction},
+    ApplicationWindow,
+};
+use adw::prelude::*;
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::settings::Settings;
+use crate::setup;
+
+/// Refresh the desktop integration unless opted out, and add the `win.desktop_integration`
+/// toggle. Nothing is added inside a sandbox.
+pub fn install(window: &ApplicationWindow, menu: &Menu, settings: &Rc<RefCell<Settings>>) {
+    if !setup::is_needed() {
+        return;
+    }
+    let enabled = settings.borrow().desktop_integration;
+    if enabled {
+        refresh();
+    }
+    let action = SimpleAction::new_stateful("desktop_integration", None, &enabled.to_variant());
+    {
+        let settings = settings.clone();
+        action.connect_activate(move |action, _| {
+            let mut settings = settings.borrow_mut();
+            settings.desktop_integration = !settings.desktop_integration;
+            action.set_state(&settings.desktop_integration.to_variant());
+            settings.save();
+            if settings.desktop_integration {
+                refresh();
+            }
+        });
+    }
+    window.add_action(&action);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Add to applications menu")),
+        Some("win.desktop_integration"),
+    );
+}
+
+fn refresh() {
+    match setup::install() {
+        #[cfg(debug_assertions)]
+        Ok(written) => {
+            for path in written {
+                println!("Installed {}", path.display());
+            }
+        }
+        #[cfg(not(debug_assertions))]
+        Ok(_) => {}
+        Err(err) => eprintln!("Failed to install desktop integration: {err}"),
+    }
+}
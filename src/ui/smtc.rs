@@ -0,0 +1,124 @@
+use adw::gtk::ApplicationWindow;
+use adw::{glib, prelude::*};
+use std::sync::mpsc;
+use std::time::Duration;
+use windows::core::{Result, HSTRING};
+use windows::Foundation::{TypedEventHandler, Uri};
+use windows::Media::Playback::MediaPlayer;
+use windows::Media::{
+    MediaPlaybackStatus, MediaPlaybackType, SystemMediaTransportControls,
+    SystemMediaTransportControlsButton, SystemMediaTransportControlsButtonPressedEventArgs,
+};
+use windows::Storage::Streams::RandomAccessStreamReference;
+
+use crate::events::PlaybackState;
+use crate::meta::TrackInfo;
+use crate::station::Station;
+
+/// The System Media Transport Controls on Windows: the media overlay and the volume flyout
+/// show the current track and its cover, and the media keys and buttons there run the
+/// window actions, like the MPRIS controls on Linux.
+pub struct Smtc {
+    // Owns the controls; its own command handling is turned off.
+    _player: MediaPlayer,
+    controls: SystemMediaTransportControls,
+}
+
+impl Smtc {
+    /// `None`, after logging why, where the controls are unavailable.
+    pub fn install(window: &ApplicationWindow) -> Option<Self> {
+        let (tx, rx) = mpsc::channel::<&'static str>();
+        let smtc = match Self::new(tx) {
+            Ok(smtc) => smtc,
+            Err(err) => {
+                eprintln!("Media transport controls unavailable: {err}");
+                return None;
+            }
+        };
+
+        // Buttons are reported on a thread pool thread; run their actions on the main one.
+        let window = window.downgrade();
+        glib::timeout_add_local(Duration::from_millis(100), move || {
+            let Some(window) = window.upgrade() else {
+                return glib::ControlFlow::Break;
+            };
+            for action in rx.try_iter() {
+                let _ = WidgetExt::activate_action(&window, action, None::<&glib::Variant>);
+            }
+            glib::ControlFlow::Continue
+        });
+        Some(smtc)
+    }
+
+    fn new(tx: mpsc::Sender<&'static str>) -> Result<Self> {
+        let player = MediaPlayer::new()?;
+        player.CommandManager()?.SetIsEnabled(false)?;
+        let controls = player.SystemMediaTransportControls()?;
+        controls.SetIsEnabled(true)?;
+        controls.SetIsPlayEnabled(true)?;
+        controls.SetIsPauseEnabled(true)?;
+        controls.SetIsStopEnabled(true)?;
+        controls.SetIsNextEnabled(true)?;
+        controls.SetIsPreviousEnabled(true)?;
+        controls.SetPlaybackStatus(MediaPlaybackStatus::Closed)?;
+        controls.ButtonPressed(&TypedEventHandler::new(
+            move |_: &Option<SystemMediaTransportControls>,
+                  args: &Option<SystemMediaTransportControlsButtonPressedEventArgs>| {
+                let Some(args) = args else {
+                    return Ok(());
+                };
+                let action = match args.Button()? {
+                    SystemMediaTransportControlsButton::Play => "win.play",
+                    SystemMediaTransportControlsButton::Pause => "win.pause",
+                    SystemMediaTransportControlsButton::Stop => "win.stop",
+                    SystemMediaTransportControlsButton::Next => "win.next_station",
+                    SystemMediaTransportControlsButton::Previous => "win.prev_station",
+                    _ => return Ok(()),
+                };
+                let _ = tx.send(action);
+                Ok(())
+            },
+        ))?;
+
+        Ok(Self {
+            _player: player,
+            controls,
+        })
+    }
+
+    pub fn set_playback(&self, state: PlaybackState) {
+        let status = match state {
+            PlaybackState::Connecting | PlaybackState::Buffering | PlaybackState::Reconnecting => {
+                MediaPlaybackStatus::Changing
+            }
+            PlaybackState::Playing => MediaPlaybackStatus::Playing,
+            PlaybackState::Paused => MediaPlaybackStatus::Paused,
+            PlaybackState::Stopped | PlaybackState::Error => MediaPlaybackStatus::Stopped,
+        };
+        if let Err(err) = self.controls.SetPlaybackStatus(status) {
+            eprintln!("Failed to update media transport controls: {err}");
+        }
+    }
+
+    pub fn track_changed(&self, info: &TrackInfo, station: Station) {
+        if let Err(err) = self.show_track(info, station) {
+            eprintln!("Failed to update media transport controls: {err}");
+        }
+    }
+
+    fn show_track(&self, info: &TrackInfo, station: Station) -> Result<()> {
+        let updater = self.controls.DisplayUpdater()?;
+        updater.ClearAll()?;
+        updater.SetType(MediaPlaybackType::Music)?;
+        let music = updater.MusicProperties()?;
+        music.SetTitle(&HSTRING::from(info.title.as_str()))?;
+        music.SetArtist(&HSTRING::from(info.artist.as_str()))?;
+        music.SetAlbumTitle(&HSTRING::from(station.display_name()))?;
+        // The shell fetches the cover itself.
+        if let Some(url) = info.album_cover.as_ref().or(info.artist_image.as_ref()) {
+            let uri = Uri::CreateUri(&HSTRING::from(url.as_str()))?;
+            updater.SetThumbnail(&RandomAccessStreamReference::CreateFromUri(&uri)?)?;
+        }
+        updater.Update()
+    }
+}
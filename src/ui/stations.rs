@@ -0,0 +1,135 @@
+use adw::gtk::{
+    self,
+    gdk::Display,
+    gio::{BytesIcon, SimpleAction},
+    glib, ApplicationWindow, Orientation,
+};
+use adw::{prelude::*, PropertyAnimationTarget, TimedAnimation};
+use gettextrs::gettext;
+
+use crate::station::Station;
+
+/// Fade-in of the track info after switching, in milliseconds.
+const FADE_MS: u32 = 250;
+
+/// Station switcher under the titlebar: one linked toggle per station with its icon, tinted
+/// in its color while selected. Alt and the first letter of the station switch as well.
+pub struct StationSwitcher {
+    root: gtk::Box,
+    action: SimpleAction,
+    fade: TimedAnimation,
+}
+
+impl StationSwitcher {
+    /// Add the stateful `win.station` action; it plays through the `win.jpop`/`win.kpop`
+    /// actions, so switching behaves the same as from the shortcuts. `content` fades in
+    /// whenever the station changes.
+    pub fn install(window: &ApplicationWindow, current: Station, content: &gtk::Box) -> Self {
+        install_css();
+
+        let action = SimpleAction::new_stateful(
+            "station",
+            Some(glib::VariantTy::STRING),
+            &current.name().to_variant(),
+        );
+        {
+            let window = window.clone();
+            action.connect_activate(move |action, param| {
+                let Some(station) = param.and_then(|p| p.str()).and_then(Station::from_name) else {
+                    return;
+                };
+                action.set_state(&station.name().to_variant());
+                let _ = WidgetExt::activate_action(
+                    &window,
+                    &format!("win.{}", station.name()),
+                    None::<&glib::Variant>,
+                );
+            });
+        }
+        window.add_action(&action);
+
+        let root = gtk::Box::new(Orientation::Horizontal, 0);
+        root.add_css_class("linked");
+        root.add_css_class("station-switcher");
+        root.set_homogeneous(true);
+        root.set_margin_start(8);
+        root.set_margin_end(8);
+        root.set_margin_top(6);
+        for station in Station::ALL {
+            root.append(&station_button(station));
+        }
+
+        let fade = TimedAnimation::new(
+            content,
+            0.0,
+            1.0,
+            FADE_MS,
+            PropertyAnimationTarget::new(content, "opacity"),
+        );
+
+        Self { root, action, fade }
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.root
+    }
+
+    /// Select `station`, however it was switched to, and fade in its track info.
+    pub fn station_changed(&self, station: Station) {
+        self.action.set_state(&station.name().to_variant());
+        self.fade.play();
+    }
+}
+
+fn station_button(station: Station) -> gtk::ToggleButton {
+    let icon = gtk::Image::from_gicon(&BytesIcon::new(&glib::Bytes::from_static(icon_svg(
+        station,
+    ))));
+    let label = gtk::Label::with_mnemonic(&format!("_{}", station.display_name()));
+    let child = gtk::Box::new(Orientation::Horizontal, 6);
+    child.set_halign(gtk::Align::Center);
+    child.append(&icon);
+    child.append(&label);
+
+    let button = gtk::ToggleButton::builder()
+        .child(&child)
+        .action_name("win.station")
+        .action_target(&station.name().to_variant())
+        .tooltip_text(gettext("Play %s").replace("%s", station.display_name()))
+        .build();
+    button.add_css_class(station.name());
+    label.set_mnemonic_widget(Some(&button));
+    button
+}
+
+/// Drawn in the station's color, so they are not recolored like symbolic icons.
+fn icon_svg(station: Station) -> &'static [u8] {
+    match station {
+        Station::Jpop => include_bytes!("../../data/stations/jpop.svg"),
+        Station::Kpop => include_bytes!("../../data/stations/kpop.svg"),
+    }
+}
+
+fn install_css() {
+    let css: String = Station::ALL
+        .into_iter()
+        .map(|station| {
+            let (r, g, b) = station.accent_rgb();
+            format!(
+                ".station-switcher button.{name}:checked {{ \
+                     background-color: rgba({r}, {g}, {b}, 0.25); \
+                     box-shadow: inset 0 -2px rgb({r}, {g}, {b}); }}\n",
+                name = station.name()
+            )
+        })
+        .collect();
+    let provider = gtk::CssProvider::new();
+    provider.load_from_data(&css);
+    if let Some(display) = Display::default() {
+        gtk::style_context_add_provider_for_display(
+            &display,
+            &provider,
+            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use adw::gtk::{
+    self,
+    gio::{Menu, SimpleAction},
+    ApplicationWindow, Label,
+};
+use adw::{glib, prelude::*, ActionRow, PreferencesGroup, PreferencesPage};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+use crate::history::stats::Stats;
+use crate::history::PlayHistory;
+use crate::locale::{n, t};
+use crate::station::Station;
+
+/// Entries per ranking.
+const TOP: usize = 10;
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Copy)]
+enum Range {
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+impl Range {
+    const ALL: [Range; 4] = [Range::Week, Range::Month, Range::Year, Range::All];
+
+    fn label(self) -> String {
+        match self {
+            Range::Week => gettext("Last 7 days"),
+            Range::Month => gettext("Last 30 days"),
+            Range::Year => gettext("Last year"),
+            Range::All => gettext("All time"),
+        }
+    }
+
+    fn since(self) -> Option<SystemTime> {
+        let days = match self {
+            Range::Week => 7,
+            Range::Month => 30,
+            Range::Year => 365,
+            Range::All => return None,
+        };
+        SystemTime::now().checked_sub(DAY * days)
+    }
+}
+
+/// Add the `win.statistics` action, which opens listening statistics from the play history.
+pub fn install(window: &ApplicationWindow, menu: &Menu, history: &Rc<RefCell<PlayHistory>>) {
+    let action = SimpleAction::new("statistics", None);
+    {
+        let window = window.clone();
+        let history = history.clone();
+        action.connect_activate(move |_, _| present(&window, &history));
+    }
+    window.add_action(&action);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Statistics")),
+        Some("win.statistics"),
+    );
+}
+
+fn present(window: &ApplicationWindow, history: &Rc<RefCell<PlayHistory>>) {
+    let labels: Vec<String> = Range::ALL.into_iter().map(Range::label).collect();
+    let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+    let range = gtk::DropDown::from_strings(&labels);
+    let header = adw::HeaderBar::new();
+    header.pack_start(&range);
+
+    let page = PreferencesPage::new();
+    let toolbar = adw::ToolbarView::new();
+    toolbar.add_top_bar(&header);
+    toolbar.set_content(Some(&page));
+
+    let groups: Rc<RefCell<Vec<PreferencesGroup>>> = Rc::new(RefCell::new(Vec::new()));
+    let refresh = {
+        let history = history.clone();
+        move |selected: u32| {
+            let range = Range::ALL[(selected as usize).min(Range::ALL.len() - 1)];
+            let stats = history.borrow().stats(range.since(), TOP);
+            let mut groups = groups.borrow_mut();
+            for group in groups.drain(..) {
+                page.remove(&group);
+            }
+            for group in build_groups(&stats) {
+                page.add(&group);
+                groups.push(group);
+            }
+        }
+    };
+    refresh(range.selected());
+    range.connect_selected_notify(move |range| refresh(range.selected()));
+
+    let dialog = adw::Dialog::builder()
+        .title(gettext("Statistics"))
+        .content_width(380)
+        .content_height(560)
+        .child(&toolbar)
+        .build();
+    dialog.present(Some(window));
+}
+
+fn build_groups(stats: &Stats) -> Vec<PreferencesGroup> {
+    let overview = PreferencesGroup::new();
+    overview.add(&row(
+        &gettext("Time listened"),
+        None,
+        &listened(stats.listened_secs),
+    ));
+    overview.add(&row(
+        &gettext("Tracks played"),
+        None,
+        &stats.plays.to_string(),
+    ));
+    if stats.plays == 0 {
+        overview.set_description(Some(&gettext("Nothing was played in this period.")));
+        return vec![overview];
+    }
+    let mut groups = vec![overview];
+
+    let stations = PreferencesGroup::builder()
+        .title(gettext("Stations"))
+        .build();
+    for (name, secs) in &stats.stations {
+        let name = Station::from_name(name).map_or(name.as_str(), |s| s.display_name());
+        stations.add(&row(name, None, &listened(*secs)));
+    }
+    groups.push(stations);
+
+    let artists = PreferencesGroup::builder()
+        .title(gettext("Top artists"))
+        .build();
+    for (artist, count) in &stats.top_artists {
+        artists.add(&row(artist, None, &plays(*count)));
+    }
+    groups.push(artists);
+
+    let tracks = PreferencesGroup::builder()
+        .title(gettext("Most heard"))
+        .build();
+    for (artist, title, count) in &stats.top_tracks {
+        tracks.add(&row(title, Some(artist), &plays(*count)));
+    }
+    groups.push(tracks);
+    groups
+}
+
+fn row(title: &str, subtitle: Option<&str>, value: &str) -> ActionRow {
+    let row = ActionRow::builder()
+        .title(glib::markup_escape_text(title))
+        .build();
+    if let Some(subtitle) = subtitle {
+        row.set_subtitle(&glib::markup_escape_text(subtitle));
+    }
+    let value = Label::new(Some(value));
+    value.add_css_class("dim-label");
+    value.add_css_class("numeric");
+    row.add_suffix(&value);
+    row
+}
+
+fn listened(secs: u64) -> String {
+    let minutes = secs / 60;
+    let (hours, minutes) = (minutes / 60, minutes % 60);
+    if hours > 0 {
+        t!("{} h {} min", hours, minutes)
+    } else {
+        t!("{} min", minutes)
+    }
+}
+
+fn plays(count: u32) -> String {
+    n!("{} play", "{} plays", count)
+}
@@ -0,0 +1,83 @@
+use adw::gtk::{self, Orientation};
+use adw::prelude::*;
+use gettextrs::gettext;
+use std::cell::Cell;
+
+use super::a11y;
+use crate::events::{GatewayState, PlaybackState};
+
+/// Header bar indicator that tells buffering apart from a dead stream.
+pub struct Status {
+    root: gtk::Box,
+    spinner: gtk::Spinner,
+    icon: gtk::Image,
+    playback: Cell<PlaybackState>,
+    gateway: Cell<GatewayState>,
+}
+
+impl Status {
+    pub fn new() -> Self {
+        let spinner = gtk::Spinner::new();
+        let icon = gtk::Image::new();
+        let root = gtk::Box::new(Orientation::Horizontal, 0);
+        root.set_valign(gtk::Align::Center);
+        root.append(&spinner);
+        root.append(&icon);
+
+        let status = Self {
+            root,
+            spinner,
+            icon,
+            playback: Cell::new(PlaybackState::Stopped),
+            gateway: Cell::new(GatewayState::Connecting),
+        };
+        status.refresh();
+        status
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.root
+    }
+
+    pub fn set_playback(&self, state: PlaybackState) {
+        self.playback.set(state);
+        self.refresh();
+    }
+
+    pub fn set_gateway(&self, state: GatewayState) {
+        self.gateway.set(state);
+        self.refresh();
+    }
+
+    fn refresh(&self) {
+        let busy = match self.playback.get() {
+            PlaybackState::Connecting => Some(gettext("Connecting…")),
+            PlaybackState::Buffering => Some(gettext("Buffering…")),
+            PlaybackState::Reconnecting => Some(gettext("Reconnecting…")),
+            _ => None,
+        };
+        let problem = match (self.playback.get(), self.gateway.get()) {
+            (PlaybackState::Error, _) => Some(gettext("Stream stopped")),
+            (PlaybackState::Stopped | PlaybackState::Paused, _) => None,
+            (_, GatewayState::Reconnecting) => Some(gettext("Track info unavailable")),
+            _ => None,
+        };
+
+        self.spinner.set_spinning(busy.is_some());
+        self.spinner.set_visible(busy.is_some());
+        self.icon.set_visible(busy.is_none() && problem.is_some());
+        if let Some(label) = busy.as_ref() {
+            a11y::label_icon_button(&self.spinner, label);
+        }
+        if let Some(label) = problem.as_ref() {
+            let icon = if self.playback.get() == PlaybackState::Error {
+                "dialog-error-symbolic"
+            } else {
+                "network-offline-symbolic"
+            };
+            self.icon.set_icon_name(Some(icon));
+            a11y::label_icon_button(&self.icon, label);
+        }
+        self.root.set_visible(busy.is_some() || problem.is_some());
+    }
+}
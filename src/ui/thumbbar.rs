@@ -0,0 +1,238 @@
+use adw::gtk::{gdk, ApplicationWindow};
+use adw::{glib, glib::translate::ToGlibPtr, prelude::*};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use windows::core::{w, Result};
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::Graphics::Gdi::{CreateBitmap, DeleteObject};
+use windows::Win32::System::Com::{
+    CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED,
+};
+use windows::Win32::UI::Shell::{
+    DefSubclassProc, ITaskbarList3, SetWindowSubclass, TaskbarList, THBF_ENABLED, THBF_HIDDEN,
+    THBN_CLICKED, THB_FLAGS, THB_ICON, THB_TOOLTIP, THUMBBUTTON,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateIconIndirect, RegisterWindowMessageW, HICON, ICONINFO, WM_COMMAND,
+};
+
+use crate::events::PlaybackState;
+
+/// Icon size in pixels; the taskbar scales it down to fit.
+const SIZE: i32 = 32;
+
+#[derive(Clone, Copy)]
+enum Glyph {
+    Previous,
+    Play,
+    Pause,
+    Next,
+}
+
+/// Buttons by id, in the order they are shown.
+const BUTTONS: [(Glyph, &str); 4] = [
+    (Glyph::Previous, "win.prev_station"),
+    (Glyph::Play, "win.play"),
+    (Glyph::Pause, "win.pause"),
+    (Glyph::Next, "win.next_station"),
+];
+
+struct ThumbBar {
+    window: glib::WeakRef<ApplicationWindow>,
+    hwnd: HWND,
+    taskbar: ITaskbarList3,
+    /// Sent once the taskbar button exists, again after Explorer restarts.
+    created: u32,
+    icons: Vec<HICON>,
+    playing: bool,
+}
+
+thread_local! {
+    // The window procedure has no other way back to the window.
+    static THUMBBAR: RefCell<Option<ThumbBar>> = const { RefCell::new(None) };
+}
+
+/// Add previous station, play or pause, and next station buttons to the taskbar thumbnail
+/// of `window`, which run the same actions as the header bar buttons.
+pub fn install(window: &ApplicationWindow) {
+    window.connect_realize(|window| {
+        if let Err(err) = attach(window) {
+            eprintln!("Taskbar thumbnail buttons unavailable: {err}");
+        }
+    });
+}
+
+/// Show the pause button while playing and the play button otherwise.
+pub fn set_playback(state: PlaybackState) {
+    let playing = !matches!(
+        state,
+        PlaybackState::Paused | PlaybackState::Stopped | PlaybackState::Error
+    );
+    THUMBBAR.with_borrow_mut(|thumbbar| {
+        let Some(thumbbar) = thumbbar.as_mut() else {
+            return;
+        };
+        if thumbbar.playing != playing {
+            thumbbar.playing = playing;
+            // Fails until the taskbar button exists; it is shown as it is by then.
+            let _ = unsafe {
+                thumbbar
+                    .taskbar
+                    .ThumbBarUpdateButtons(thumbbar.hwnd, &thumbbar.buttons())
+            };
+        }
+    });
+}
+
+fn attach(window: &ApplicationWindow) -> Result<()> {
+    let Some(surface) = window.surface() else {
+        return Ok(());
+    };
+    let hwnd = unsafe { gdk_win32_surface_get_handle(surface.to_glib_none().0) };
+    let taskbar: ITaskbarList3 = unsafe {
+        // Already initialized by GTK or the media controls is fine too.
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+        CoCreateInstance(&TaskbarList, None, CLSCTX_INPROC_SERVER)?
+    };
+    unsafe { taskbar.HrInit()? };
+    let icons = BUTTONS
+        .iter()
+        .map(|(glyph, _)| icon(*glyph))
+        .collect::<Result<_>>()?;
+    let created = unsafe { RegisterWindowMessageW(w!("TaskbarButtonCreated")) };
+    THUMBBAR.set(Some(ThumbBar {
+        window: window.downgrade(),
+        hwnd,
+        taskbar,
+        created,
+        icons,
+        playing: false,
+    }));
+    unsafe { SetWindowSubclass(hwnd, Some(subclass), 1, 0) }.ok()
+}
+
+impl ThumbBar {
+    fn buttons(&self) -> Vec<THUMBBUTTON> {
+        BUTTONS
+            .iter()
+            .zip(&self.icons)
+            .enumerate()
+            .map(|(id, ((glyph, _), icon))| {
+                let (tip, hidden) = match glyph {
+                    Glyph::Previous => (gettext("Previous station"), false),
+                    Glyph::Play => (gettext("Play"), self.playing),
+                    Glyph::Pause => (gettext("Pause"), !self.playing),
+                    Glyph::Next => (gettext("Next station"), false),
+                };
+                let mut button = THUMBBUTTON {
+                    dwMask: THB_ICON | THB_TOOLTIP | THB_FLAGS,
+                    iId: id as u32,
+                    hIcon: *icon,
+                    dwFlags: if hidden { THBF_HIDDEN } else { THBF_ENABLED },
+                    ..Default::default()
+                };
+                let tip: Vec<u16> = tip.encode_utf16().collect();
+                let len = tip.len().min(button.szTip.len() - 1);
+                button.szTip[..len].copy_from_slice(&tip[..len]);
+                button
+            })
+            .collect()
+    }
+}
+
+unsafe extern "system" fn subclass(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+    _id: usize,
+    _data: usize,
+) -> LRESULT {
+    THUMBBAR.with_borrow(|thumbbar| {
+        let Some(thumbbar) = thumbbar.as_ref() else {
+            return;
+        };
+        if msg == thumbbar.created {
+            if let Err(err) = thumbbar
+                .taskbar
+                .ThumbBarAddButtons(hwnd, &thumbbar.buttons())
+            {
+                eprintln!("Failed to add taskbar thumbnail buttons: {err}");
+            }
+        } else if msg == WM_COMMAND && (wparam.0 >> 16) as u32 & 0xffff == THBN_CLICKED {
+            let Some(&(_, action)) = BUTTONS.get(wparam.0 & 0xffff) else {
+                return;
+            };
+            let window = thumbbar.window.clone();
+            // Not from inside the window procedure, where GTK is still handling the message.
+            glib::idle_add_local_once(move || {
+                if let Some(window) = window.upgrade() {
+                    let _ = WidgetExt::activate_action(&window, action, None::<&glib::Variant>);
+                }
+            });
+        }
+    });
+    DefSubclassProc(hwnd, msg, wparam, lparam)
+}
+
+/// A white `glyph` on a transparent background, for the dark taskbar previews.
+fn icon(glyph: Glyph) -> Result<HICON> {
+    let mut surface = cairo::ImageSurface::create(cairo::Format::ARgb32, SIZE, SIZE)
+        .expect("Failed to create icon surface");
+    {
+        let cr = cairo::Context::new(&surface).expect("Failed to draw icon");
+        cr.set_source_rgb(1.0, 1.0, 1.0);
+        cr.scale(f64::from(SIZE) / 16.0, f64::from(SIZE) / 16.0);
+        match glyph {
+            Glyph::Play => triangle(&cr, 4.0, 12.0),
+            Glyph::Pause => {
+                cr.rectangle(4.0, 3.0, 3.0, 10.0);
+                cr.rectangle(9.0, 3.0, 3.0, 10.0);
+            }
+            Glyph::Previous => {
+                cr.rectangle(3.0, 3.0, 2.0, 10.0);
+                triangle(&cr, 13.0, 5.0);
+            }
+            Glyph::Next => {
+                triangle(&cr, 3.0, 11.0);
+                cr.rectangle(11.0, 3.0, 2.0, 10.0);
+            }
+        }
+        let _ = cr.fill();
+    }
+    surface.flush();
+    let mut pixels = surface.data().expect("Icon surface is in use").to_vec();
+    // Cairo premultiplies alpha and icons do not: the glyphs are all white.
+    for pixel in pixels.chunks_exact_mut(4) {
+        if pixel[3] > 0 {
+            pixel[..3].fill(0xff);
+        }
+    }
+
+    unsafe {
+        let color = CreateBitmap(SIZE, SIZE, 1, 32, Some(pixels.as_ptr().cast()));
+        let mask = CreateBitmap(SIZE, SIZE, 1, 1, None);
+        let icon = CreateIconIndirect(&ICONINFO {
+            fIcon: true.into(),
+            hbmMask: mask,
+            hbmColor: color,
+            ..Default::default()
+        });
+        // The icon has its own copies.
+        DeleteObject(color);
+        DeleteObject(mask);
+        icon
+    }
+}
+
+/// A triangle pointing from `from` towards `to` along the middle line.
+fn triangle(cr: &cairo::Context, from: f64, to: f64) {
+    cr.move_to(from, 3.0);
+    cr.line_to(to, 8.0);
+    cr.line_to(from, 13.0);
+    cr.close_path();
+}
+
+extern "C" {
+    fn gdk_win32_surface_get_handle(surface: *mut gdk::ffi::GdkSurface) -> HWND;
+}
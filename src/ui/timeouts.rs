@@ -0,0 +1,145 @@
+use adw::gtk::{
+    self,
+    gio::{Menu, SimpleAction},
+    ApplicationWindow,
+};
+use adw::{prelude::*, AlertDialog, ResponseAppearance, SpinRow, SwitchRow};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::listen::Listen;
+use crate::meta::Meta;
+use crate::settings::{Settings, Timeouts};
+
+/// Add the `win.timeouts` action, which edits the network timeouts, and the
+/// `win.connect_on_play` toggle.
+pub fn install(
+    window: &ApplicationWindow,
+    menu: &Menu,
+    radio: &Rc<Listen>,
+    meta: &Rc<Meta>,
+    settings: &Rc<RefCell<Settings>>,
+) {
+    let action = SimpleAction::new("timeouts", None);
+    {
+        let window = window.clone();
+        let radio = radio.clone();
+        let meta = meta.clone();
+        let settings = settings.clone();
+        action.connect_activate(move |_, _| {
+            request_timeouts(&window, &radio, &meta, &settings);
+        });
+    }
+    window.add_action(&action);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Network timeouts…")),
+        Some("win.timeouts"),
+    );
+
+    let enabled = settings.borrow().connect_on_play;
+    let action = SimpleAction::new_stateful("connect_on_play", None, &enabled.to_variant());
+    {
+        let meta = meta.clone();
+        let settings = settings.clone();
+        action.connect_activate(move |action, _| {
+            let mut settings = settings.borrow_mut();
+            settings.connect_on_play = !settings.connect_on_play;
+            action.set_state(&settings.connect_on_play.to_variant());
+            meta.set_timeouts(settings.meta_timeouts());
+            settings.save();
+        });
+    }
+    window.add_action(&action);
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Connect only while playing")),
+        Some("win.connect_on_play"),
+    );
+}
+
+fn seconds_row(title: &str, max: f64, value: u32) -> SpinRow {
+    let row = SpinRow::with_range(1.0, max, 1.0);
+    row.set_title(title);
+    row.set_subtitle(&gettext("Seconds"));
+    row.set_value(value.into());
+    row
+}
+
+fn request_timeouts(
+    window: &ApplicationWindow,
+    radio: &Rc<Listen>,
+    meta: &Rc<Meta>,
+    settings: &Rc<RefCell<Settings>>,
+) {
+    let current = settings.borrow().timeouts;
+    let connect = seconds_row(&gettext("Connect"), 60.0, current.connect_secs);
+    let read = seconds_row(&gettext("Read"), 300.0, current.read_secs);
+    let silence = seconds_row(&gettext("Silence"), 600.0, current.silence_secs);
+    silence.set_range(0.0, 600.0);
+    silence.set_subtitle(&gettext(
+        "Seconds of dead air before reconnecting, 0 to never",
+    ));
+    let linger = SpinRow::with_range(0.0, 120.0, 1.0);
+    linger.set_title(&gettext("Stay connected after Stop"));
+    linger.set_subtitle(&gettext(
+        "Minutes track info stays connected for a quick restart, 0 to disconnect",
+    ));
+    linger.set_value(current.linger_mins.into());
+    let suspend = SpinRow::with_range(0.0, 600.0, 5.0);
+    suspend.set_title(&gettext("Disconnect while paused"));
+    suspend.set_subtitle(&gettext(
+        "Minutes paused before track info disconnects to save power, 0 to stay connected",
+    ));
+    suspend.set_value(current.suspend_mins.into());
+    let notify = SwitchRow::new();
+    notify.set_title(&gettext("Tell me when the stream goes silent"));
+    notify.set_active(settings.borrow().notify_silence);
+    let rows = gtk::ListBox::new();
+    rows.set_selection_mode(gtk::SelectionMode::None);
+    rows.add_css_class("boxed-list");
+    rows.append(&connect);
+    rows.append(&read);
+    rows.append(&silence);
+    rows.append(&linger);
+    rows.append(&suspend);
+    rows.append(&notify);
+
+    let dialog = AlertDialog::new(
+        Some(&gettext("Network timeouts")),
+        Some(&gettext(
+            "How long to wait for the stream and metadata servers. Applies from the next connection.",
+        )),
+    );
+    dialog.set_extra_child(Some(&rows));
+    dialog.add_response("cancel", &gettext("_Cancel"));
+    dialog.add_response("save", &gettext("_Save"));
+    dialog.set_response_appearance("save", ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("save"));
+    dialog.set_close_response("cancel");
+
+    let radio = radio.clone();
+    let meta = meta.clone();
+    let settings = settings.clone();
+    dialog.connect_response(None, move |_, response| {
+        if response != "save" {
+            return;
+        }
+        let timeouts = Timeouts {
+            connect_secs: connect.value() as u32,
+            read_secs: read.value() as u32,
+            silence_secs: silence.value() as u32,
+            linger_mins: linger.value() as u32,
+            suspend_mins: suspend.value() as u32,
+        };
+        radio.set_timeouts(timeouts);
+        let mut settings = settings.borrow_mut();
+        settings.timeouts = timeouts;
+        meta.set_timeouts(settings.meta_timeouts());
+        settings.notify_silence = notify.is_active();
+        settings.save();
+    });
+    dialog.present(Some(window));
+}
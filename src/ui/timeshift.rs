@@ -0,0 +1,75 @@
+use adw::gtk::{gio::SimpleAction, glib, ApplicationWindow, Button, HeaderBar};
+use adw::prelude::*;
+use gettextrs::gettext;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::listen::Listen;
+use crate::meta::Meta;
+
+use super::a11y;
+
+const REFRESH: Duration = Duration::from_secs(1);
+/// How far the replay button goes back.
+const REPLAY: Duration = Duration::from_secs(30);
+
+/// Add the `win.go_live` and `win.replay` actions with their header bar buttons: "Live"
+/// while playback is paused or runs behind the live stream, and "back 30 s" while the
+/// timeshift buffer reaches back that far.
+pub fn install(
+    window: &ApplicationWindow,
+    header: &HeaderBar,
+    radio: &Rc<Listen>,
+    meta: &Rc<Meta>,
+) {
+    add_seek_action(window, radio, meta, "go_live", Listen::go_live);
+    add_seek_action(window, radio, meta, "replay", |radio| radio.replay(REPLAY));
+
+    let live = Button::with_mnemonic(&gettext("_Live"));
+    live.set_action_name(Some("win.go_live"));
+    live.set_valign(adw::gtk::Align::Center);
+    live.add_css_class("flat");
+    a11y::label_icon_button(&live, &gettext("Jump to the live stream"));
+    live.set_visible(false);
+    header.pack_end(&live);
+
+    let replay = Button::from_icon_name("media-seek-backward-symbolic");
+    replay.set_action_name(Some("win.replay"));
+    a11y::label_icon_button(&replay, &gettext("Back 30 seconds"));
+    replay.set_visible(false);
+    header.pack_end(&replay);
+
+    let radio = radio.clone();
+    glib::timeout_add_local(REFRESH, move || {
+        live.set_visible(radio.can_go_live());
+        replay.set_visible(radio.can_replay(REPLAY));
+        glib::ControlFlow::Continue
+    });
+}
+
+/// An action that moves the playback position with `seek`, then resumes if paused and
+/// puts the track display in step with the new position.
+fn add_seek_action(
+    window: &ApplicationWindow,
+    radio: &Rc<Listen>,
+    meta: &Rc<Meta>,
+    name: &str,
+    seek: impl Fn(&Listen) + 'static,
+) {
+    let action = SimpleAction::new(name, None);
+    {
+        let window = window.clone();
+        let radio = radio.clone();
+        let meta = meta.clone();
+        action.connect_activate(move |_, _| {
+            let paused = radio.is_paused();
+            seek(&radio);
+            if paused {
+                let _ = WidgetExt::activate_action(&window, "win.play", None::<&glib::Variant>);
+            } else {
+                meta.start();
+            }
+        });
+    }
+    window.add_action(&action);
+}
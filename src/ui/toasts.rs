@@ -0,0 +1,69 @@
+use adw::gtk::{self, gio::SimpleAction, glib, ApplicationWindow};
+use adw::prelude::*;
+use adw::{Toast, ToastOverlay};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Room a toast needs; the content area is normally only a couple of caption rows tall.
+const TOAST_HEIGHT: i32 = 64;
+
+/// Error toasts over the window content, with a retry button.
+pub struct Toasts {
+    overlay: ToastOverlay,
+    content: gtk::Widget,
+    current: RefCell<Option<Toast>>,
+}
+
+impl Toasts {
+    pub fn new(window: &ApplicationWindow, content: &impl IsA<gtk::Widget>) -> Rc<Self> {
+        let overlay = ToastOverlay::new();
+        overlay.set_child(Some(content));
+
+        // Restart playback from scratch: the stream may have given up entirely.
+        let retry = SimpleAction::new("retry", None);
+        {
+            let window = window.clone();
+            retry.connect_activate(move |_, _| {
+                let _ = WidgetExt::activate_action(&window, "win.stop", None::<&glib::Variant>);
+                let _ = WidgetExt::activate_action(&window, "win.play", None::<&glib::Variant>);
+            });
+        }
+        window.add_action(&retry);
+
+        Rc::new(Self {
+            overlay,
+            content: content.clone().upcast(),
+            current: RefCell::new(None),
+        })
+    }
+
+    pub fn widget(&self) -> &ToastOverlay {
+        &self.overlay
+    }
+
+    /// Show `message`, replacing the text of a toast that is still visible rather than
+    /// queueing another one while a connection keeps failing.
+    pub fn show_error(self: &Rc<Self>, message: &str) {
+        if let Some(toast) = self.current.borrow().as_ref() {
+            toast.set_title(message);
+            return;
+        }
+
+        let toast = Toast::builder()
+            .title(message)
+            .button_label(gettext("Retry"))
+            .action_name("win.retry")
+            .build();
+        {
+            let toasts = self.clone();
+            toast.connect_dismissed(move |_| {
+                toasts.current.borrow_mut().take();
+                toasts.content.set_height_request(-1);
+            });
+        }
+        self.content.set_height_request(TOAST_HEIGHT);
+        self.overlay.add_toast(toast.clone());
+        *self.current.borrow_mut() = Some(toast);
+    }
+}
@@ -0,0 +1,42 @@
+use adw::gtk::{
+    gio::{Menu, SimpleAction},
+    ApplicationWindow,
+};
+use adw::{glib, prelude::*};
+use gettextrs::gettext;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::events::PlaybackState;
+use crate::settings::Settings;
+
+/// Add the `win.pause_on_unplug` toggle.
+pub fn install(window: &ApplicationWindow, menu: &Menu, settings: &Rc<RefCell<Settings>>) {
+    let enabled = settings.borrow().pause_on_unplug;
+    let action = SimpleAction::new_stateful("pause_on_unplug", None, &enabled.to_variant());
+    {
+        let settings = settings.clone();
+        action.connect_activate(move |action, _| {
+            let mut settings = settings.borrow_mut();
+            settings.pause_on_unplug = !settings.pause_on_unplug;
+            action.set_state(&settings.pause_on_unplug.to_variant());
+            settings.save();
+        });
+    }
+    window.add_action(&action);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Pause when headphones are unplugged")),
+        Some("win.pause_on_unplug"),
+    );
+}
+
+/// The output device went away while in `state`: pause like the pause button would, so
+/// resuming goes through the usual path and re-syncs the metadata.
+pub fn output_lost(window: &ApplicationWindow, settings: &RefCell<Settings>, state: PlaybackState) {
+    let playing = matches!(state, PlaybackState::Playing | PlaybackState::Buffering);
+    if playing && settings.borrow().pause_on_unplug {
+        let _ = WidgetExt::activate_action(window, "win.pause", None::<&glib::Variant>);
+    }
+}
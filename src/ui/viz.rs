@@ -1,8 +1,16 @@
-use adw::gtk;
+use adw::gtk::{
+    self,
+    gio::{Menu, SimpleAction},
+    ApplicationWindow,
+};
+use gettextrs::gettext;
 use gtk::prelude::*;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use crate::listen::Listen;
+use crate::settings::Settings;
+
 #[derive(Clone)]
 pub struct VizHandle {
     values: Rc<RefCell<Vec<f32>>>, // 0.0..=1.0
@@ -70,7 +78,43 @@ pub fn make_bars_visualizer(n_bars: usize, height: i32) -> (gtk::DrawingArea, Vi
     (area, handle)
 }
 
-fn widget_css_color(widget: &gtk::Widget) -> (f64, f64, f64) {
+/// Add the `win.show_visualizer` toggle, which shows or hides `area` and turns the spectrum
+/// analysis on or off with it.
+pub fn install(
+    window: &ApplicationWindow,
+    menu: &Menu,
+    radio: &Rc<Listen>,
+    settings: &Rc<RefCell<Settings>>,
+    area: &gtk::DrawingArea,
+) {
+    let enabled = settings.borrow().show_visualizer;
+    area.set_visible(enabled);
+    radio.set_visualizer(enabled);
+
+    let action = SimpleAction::new_stateful("show_visualizer", None, &enabled.to_variant());
+    {
+        let radio = radio.clone();
+        let settings = settings.clone();
+        let area = area.clone();
+        action.connect_activate(move |action, _| {
+            let mut settings = settings.borrow_mut();
+            settings.show_visualizer = !settings.show_visualizer;
+            action.set_state(&settings.show_visualizer.to_variant());
+            area.set_visible(settings.show_visualizer);
+            radio.set_visualizer(settings.show_visualizer);
+            settings.save();
+        });
+    }
+    window.add_action(&action);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Show visualizer")),
+        Some("win.show_visualizer"),
+    );
+}
+
+pub(super) fn widget_css_color(widget: &gtk::Widget) -> (f64, f64, f64) {
     // Read the resolved CSS "color" from this widget
     let ctx = widget.style_context();
     if let Some(c) = ctx.lookup_color("color") {
@@ -0,0 +1,192 @@
+use adw::gtk::{
+    self,
+    gdk::Key,
+    gio::{Menu, SimpleAction},
+    glib, ApplicationWindow, EventControllerKey, EventControllerScroll, EventControllerScrollFlags,
+    LevelBar, Orientation, PropagationPhase,
+};
+use adw::prelude::*;
+use gettextrs::gettext;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::listen::Listen;
+use crate::settings::Settings;
+
+const VOLUME_STEP: f32 = 0.05;
+const OSD_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// Transient volume indicator shown under the titlebar.
+struct Osd {
+    popover: gtk::Popover,
+    icon: gtk::Image,
+    level: LevelBar,
+    generation: Cell<u64>,
+}
+
+impl Osd {
+    fn new(parent: &impl IsA<gtk::Widget>) -> Rc<Self> {
+        let icon = gtk::Image::new();
+        let level = LevelBar::builder()
+            .min_value(0.0)
+            .max_value(1.0)
+            .width_request(160)
+            .valign(gtk::Align::Center)
+            .build();
+        let row = gtk::Box::new(Orientation::Horizontal, 8);
+        row.set_margin_start(6);
+        row.set_margin_end(6);
+        row.append(&icon);
+        row.append(&level);
+
+        let popover = gtk::Popover::builder()
+            .has_arrow(false)
+            .autohide(false)
+            .can_focus(false)
+            .child(&row)
+            .build();
+        popover.add_css_class("osd");
+        popover.set_parent(parent);
+
+        Rc::new(Self {
+            popover,
+            icon,
+            level,
+            generation: Cell::new(0),
+        })
+    }
+
+    fn show(self: &Rc<Self>, volume: f32) {
+        let icon = match volume {
+            v if v <= 0.0 => "audio-volume-muted-symbolic",
+            v if v < 0.34 => "audio-volume-low-symbolic",
+            v if v < 0.67 => "audio-volume-medium-symbolic",
+            _ => "audio-volume-high-symbolic",
+        };
+        self.icon.set_icon_name(Some(icon));
+        self.level.set_value(volume as f64);
+        self.popover.popup();
+
+        let generation = self.generation.get() + 1;
+        self.generation.set(generation);
+        let osd = self.clone();
+        glib::timeout_add_local_once(OSD_TIMEOUT, move || {
+            if osd.generation.get() == generation {
+                osd.popover.popdown();
+            }
+        });
+    }
+}
+
+/// Changes the player volume in steps and shows the new level.
+pub struct Volume {
+    radio: Rc<Listen>,
+    osd: Rc<Osd>,
+}
+
+impl Volume {
+    /// Raise the volume by `steps` steps, or lower it when negative.
+    fn step(&self, steps: f32) {
+        self.radio
+            .set_volume(self.radio.volume() + steps * VOLUME_STEP);
+        self.osd.show(self.radio.volume());
+    }
+
+    /// Scrolling over `widget` changes the volume, a step per notch.
+    pub fn scroll_on(self: &Rc<Self>, widget: &impl IsA<gtk::Widget>) {
+        let scroll = EventControllerScroll::new(
+            EventControllerScrollFlags::VERTICAL | EventControllerScrollFlags::DISCRETE,
+        );
+        let volume = self.clone();
+        scroll.connect_scroll(move |_, _, dy| {
+            volume.step(-dy as f32);
+            glib::Propagation::Stop
+        });
+        widget.add_controller(scroll);
+    }
+}
+
+/// Add `win.volume_up` and `win.volume_down` on + and -, and the `win.volume_keys` toggle
+/// that routes the volume keys to the player while it is on.
+pub fn install(
+    window: &ApplicationWindow,
+    menu: &Menu,
+    osd_parent: &impl IsA<gtk::Widget>,
+    radio: &Rc<Listen>,
+    settings: &Rc<RefCell<Settings>>,
+) -> Rc<Volume> {
+    let osd = Osd::new(osd_parent);
+    let volume = Rc::new(Volume {
+        radio: radio.clone(),
+        osd: osd.clone(),
+    });
+    for (name, steps) in [("volume_up", 1.0), ("volume_down", -1.0)] {
+        let action = SimpleAction::new(name, None);
+        let volume = volume.clone();
+        action.connect_activate(move |_, _| volume.step(steps));
+        window.add_action(&action);
+    }
+    // Not application accels, which would take the keys from the search entry.
+    let shortcuts = gtk::ShortcutController::new();
+    for (trigger, action) in [
+        ("plus|KP_Add|equal", "win.volume_up"),
+        ("minus|KP_Subtract", "win.volume_down"),
+    ] {
+        shortcuts.add_shortcut(gtk::Shortcut::new(
+            gtk::ShortcutTrigger::parse_string(trigger),
+            Some(gtk::NamedAction::new(action)),
+        ));
+    }
+    window.add_controller(shortcuts);
+
+    let unmuted = Rc::new(Cell::new(radio.volume()));
+
+    let keys = EventControllerKey::new();
+    keys.set_propagation_phase(PropagationPhase::Capture);
+    {
+        let radio = radio.clone();
+        let settings = settings.clone();
+        keys.connect_key_pressed(move |_, key, _, _| {
+            if !settings.borrow().volume_keys {
+                return glib::Propagation::Proceed;
+            }
+            let current = radio.volume();
+            let volume = match key {
+                Key::AudioRaiseVolume => current + VOLUME_STEP,
+                Key::AudioLowerVolume => current - VOLUME_STEP,
+                Key::AudioMute if current > 0.0 => {
+                    unmuted.set(current);
+                    0.0
+                }
+                Key::AudioMute => unmuted.get().max(VOLUME_STEP),
+                _ => return glib::Propagation::Proceed,
+            };
+            radio.set_volume(volume);
+            osd.show(radio.volume());
+            glib::Propagation::Stop
+        });
+    }
+    window.add_controller(keys);
+
+    let enabled = settings.borrow().volume_keys;
+    let action = SimpleAction::new_stateful("volume_keys", None, &enabled.to_variant());
+    {
+        let settings = settings.clone();
+        action.connect_activate(move |action, _| {
+            let mut settings = settings.borrow_mut();
+            settings.volume_keys = !settings.volume_keys;
+            action.set_state(&settings.volume_keys.to_variant());
+            settings.save();
+        });
+    }
+    window.add_action(&action);
+
+    menu.insert(
+        menu.n_items() - 2,
+        Some(&gettext("Volume keys control player")),
+        Some("win.volume_keys"),
+    );
+
+    volume
+}
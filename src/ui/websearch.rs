@@ -0,0 +1,99 @@
+use adw::gtk::{
+    self,
+    gio::{Menu, SimpleAction},
+    glib, ApplicationWindow,
+};
+use adw::prelude::*;
+use gettextrs::gettext;
+use reqwest::Url;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::meta::TrackInfo;
+
+/// Sites the current track can be looked up on, by action target.
+const SITES: [&str; 3] = ["youtube", "spotify", "musicbrainz"];
+
+/// Opens a search for the current track in the default browser.
+pub struct WebSearch {
+    action: SimpleAction,
+    /// Artist and title of the current track.
+    track: RefCell<Option<(String, String)>>,
+}
+
+impl WebSearch {
+    /// Add the `win.web_search` submenu next to "Copy title & artist". The action is disabled
+    /// until the first track is known.
+    pub fn install(window: &ApplicationWindow, menu: &Menu) -> Rc<Self> {
+        let action = SimpleAction::new("web_search", Some(glib::VariantTy::STRING));
+        action.set_enabled(false);
+        let search = Rc::new(Self {
+            action: action.clone(),
+            track: RefCell::new(None),
+        });
+        {
+            let window = window.clone();
+            let search = search.clone();
+            action.connect_activate(move |_, param| {
+                let Some(site) = param.and_then(|p| p.str()) else {
+                    return;
+                };
+                let Some((artist, title)) = search.track.borrow().clone() else {
+                    return;
+                };
+                if let Some(url) = search_url(site, &artist, &title) {
+                    gtk::show_uri(Some(&window), url.as_str(), gtk::gdk::CURRENT_TIME);
+                }
+            });
+        }
+        window.add_action(&action);
+
+        let submenu = Menu::new();
+        for site in SITES {
+            submenu.append(Some(label(site)), Some(&format!("win.web_search::{site}")));
+        }
+        // "Copy title & artist" is the first item at this point.
+        menu.insert_submenu(1, Some(&gettext("Search the web")), &submenu);
+        search
+    }
+
+    pub fn track_changed(&self, info: &TrackInfo) {
+        let known = !info.artist.is_empty() || !info.title.is_empty();
+        self.action.set_enabled(known);
+        *self.track.borrow_mut() = known.then(|| (info.artist.clone(), info.title.clone()));
+    }
+}
+
+fn label(site: &str) -> &'static str {
+    match site {
+        "youtube" => "YouTube",
+        "spotify" => "Spotify",
+        _ => "MusicBrainz",
+    }
+}
+
+fn search_url(site: &str, artist: &str, title: &str) -> Option<Url> {
+    let query = format!("{artist} {title}");
+    let query = query.trim();
+    match site {
+        "youtube" => {
+            Url::parse_with_params("https://www.youtube.com/results", [("search_query", query)])
+                .ok()
+        }
+        "spotify" => {
+            let mut url = Url::parse("https://open.spotify.com/search").ok()?;
+            url.path_segments_mut().ok()?.push(query);
+            Some(url)
+        }
+        "musicbrainz" => Url::parse_with_params(
+            "https://musicbrainz.org/search",
+            [
+                ("query", query),
+                ("type", "recording"),
+                ("method", "indexed"),
+            ],
+        )
+        .ok(),
+        _ => None,
+    }
+}
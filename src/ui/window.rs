@@ -1,5 +1,10 @@
-use crate::listen::Listen;
+use crate::diagnostics::Diagnostics;
+use crate::events::{EventBus, GatewayState, PlaybackState, PlayerEvent};
+use crate::history::PlayHistory;
+use crate::listen::{Listen, SinkTarget};
+use crate::locale::{n, t};
 use crate::meta::{Meta, TrackInfo};
+use crate::settings::Settings;
 use crate::station::Station;
 
 use adw::{
@@ -13,30 +18,102 @@ use adw::{
         Popover,
     },
     prelude::*,
-    Application, StyleManager, WindowTitle,
+    Application, WindowTitle,
 };
 use gettextrs::gettext;
 use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    rc::Rc,
     sync::{atomic::Ordering, mpsc},
-    thread,
     time::Duration,
 };
 
+use super::appearance::AppearanceManager;
+use super::backdrop::{self, Backdrop};
 #[cfg(target_os = "linux")]
 use super::controls::MediaControlEvent;
-use super::{actions, cover, viz};
+use super::crossfade::Crossfade;
+#[cfg(target_os = "linux")]
+use super::dbus::ControlInterface;
+use super::favorites::Favorites;
+use super::inhibit::Inhibitor;
+use super::lightbox::CoverLightbox;
+use super::lyrics::LyricsPane;
+#[cfg(target_os = "linux")]
+use super::mediakeys;
+use super::meter::LevelMeter;
+use super::mqtt::MqttBridge;
+use super::network::Network;
+use super::notify::Notifier;
+use super::nowplaying::NowPlayingFile;
+#[cfg(target_os = "macos")]
+use super::macos::{self, NowPlaying};
+use super::placeholder::Placeholder;
+use super::progress::Progress;
+use super::remote::RemoteControl;
+use super::requests::Requests;
+use super::search::SessionHistory;
+#[cfg(target_os = "windows")]
+use super::smtc::Smtc;
+use super::stations::StationSwitcher;
+use super::status::Status;
+#[cfg(target_os = "windows")]
+use super::thumbbar;
+use super::toasts::Toasts;
+use super::websearch::WebSearch;
+use super::{
+    a11y, actions, adaptive, audiosink, background, channels, cover, diagnostics, equalizer,
+    export, fade, guest, hook, marquee, mini, proxy, recording, scheduled, search, stats, timeouts,
+    timeshift, unplug, viz, volume,
+};
+#[cfg(target_os = "linux")]
+use mpris_server::PlaybackStatus;
 
 const COVER_MAX_SIZE: i32 = 250;
 const APP_NAME: &str = "Listen Moe";
 const APP_ID: &str = "io.github.noobping.listenmoe";
+const SESSION_HISTORY_LEN: usize = 200;
 
 pub fn build_ui(app: &Application) {
+    build(app, false);
+}
+
+/// Build the window; it stays hidden with `hidden` or the `start_hidden` setting, until
+/// the app is started again.
+pub fn build(app: &Application, hidden: bool) {
+    let settings = Rc::new(RefCell::new(Settings::load()));
+    let hidden = hidden || settings.borrow().start_hidden;
     let station = Station::Jpop;
-    let radio = Listen::new(station);
+    let events = EventBus::new();
+    let player_events = events.subscribe();
+    let radio = {
+        let settings = settings.borrow();
+        Listen::new(
+            station,
+            settings.stream_format,
+            settings.buffer_secs,
+            events.clone(),
+        )
+    };
     let spectrum_bits = radio.spectrum_bars();
-    let (tx, rx) = mpsc::channel::<TrackInfo>();
-    let meta = Meta::new(station, tx, radio.lag_ms());
+    let meta = Meta::new(station, events.clone(), radio.lag_ms());
+    radio.set_proxy(settings.borrow().proxy.clone());
+    meta.set_proxy(settings.borrow().proxy.clone());
+    radio.set_timeouts(settings.borrow().timeouts);
+    radio.set_equalizer(settings.borrow().equalizer);
+    radio.set_normalize(settings.borrow().normalize_loudness);
+    radio.set_timeshift(settings.borrow().timeshift_mins);
+    radio.set_audio_sink(
+        settings
+            .borrow()
+            .audio_sink
+            .as_deref()
+            .and_then(SinkTarget::parse),
+    );
+    meta.set_timeouts(settings.borrow().meta_timeouts());
     let (cover_tx, cover_rx) = mpsc::channel::<Result<Vec<u8>, String>>();
+    let (backdrop_tx, backdrop_rx) = mpsc::channel::<Result<backdrop::BlurredImage, String>>();
     let win_title = WindowTitle::new(APP_NAME, &gettext("J-POP and K-POP radio"));
 
     let play_button = Button::from_icon_name("media-playback-start-symbolic");
@@ -44,6 +121,8 @@ pub fn build_ui(app: &Application) {
     let pause_button = Button::from_icon_name("media-playback-pause-symbolic");
     pause_button.set_action_name(Some("win.pause"));
     pause_button.set_visible(false);
+    a11y::label_icon_button(&play_button, &gettext("Play"));
+    a11y::label_icon_button(&pause_button, &gettext("Pause"));
 
     let height = 50;
     let window = ApplicationWindow::builder()
@@ -52,13 +131,11 @@ pub fn build_ui(app: &Application) {
         .icon_name(APP_ID)
         .default_width(300)
         .default_height(height)
-        .resizable(false)
         .build();
 
     window.add_css_class("cover-tint");
-    let style_manager = StyleManager::default();
-    style_manager.set_color_scheme(adw::ColorScheme::Default);
     let css_provider = cover::install_css_provider();
+    cover::apply_cover_tint_css_clear(&css_provider, station.accent_rgb());
 
     #[cfg(target_os = "linux")]
     let (controls, ctrl_rx) = actions::build_actions(
@@ -79,6 +156,22 @@ pub fn build_ui(app: &Application) {
             }
         }
     };
+    #[cfg(target_os = "linux")]
+    let set_playback = {
+        let controls = controls.clone();
+        move |state: PlaybackState| {
+            if let Some(c) = controls.as_ref() {
+                c.set_playback(match state {
+                    PlaybackState::Connecting
+                    | PlaybackState::Buffering
+                    | PlaybackState::Playing
+                    | PlaybackState::Reconnecting => PlaybackStatus::Playing,
+                    PlaybackState::Paused => PlaybackStatus::Paused,
+                    PlaybackState::Stopped | PlaybackState::Error => PlaybackStatus::Stopped,
+                });
+            }
+        }
+    };
     #[cfg(not(target_os = "linux"))]
     actions::build_actions(
         &window,
@@ -93,18 +186,48 @@ pub fn build_ui(app: &Application) {
     // Build UI
     let menu = Menu::new();
     actions::populate_menu(&window, &play_button, &menu, &radio, &meta);
+    let web_search = WebSearch::install(&window, &menu);
+    actions::add_stream_format_menu(&window, &menu, &radio, &settings);
+    a11y::install(&window, &menu, &settings);
+    let appearance = AppearanceManager::install(&window, &menu, &settings);
+    let session_history: SessionHistory = Rc::new(RefCell::new(VecDeque::new()));
+    let play_history = Rc::new(RefCell::new(PlayHistory::open()));
+    {
+        // The poll loop never ends, so the current track's listening time is stored here.
+        let play_history = play_history.clone();
+        app.connect_shutdown(move |_| play_history.borrow_mut().finish());
+    }
+    let favorites = Favorites::install(&window, &menu, &play_history, &settings);
+    export::install(&window, &menu, &play_history);
+    stats::install(&window, &menu, &play_history);
+    let recorder = recording::install(&window, &menu, &radio, &session_history);
+    scheduled::install(&window, &menu, &settings);
+    let notifier = Notifier::install(&window, &menu, &radio, &settings);
+    let lyrics = LyricsPane::install(&window, &menu, &settings, radio.lag_ms());
+    let requests = Requests::install(&window, &menu, &settings, &favorites);
+    let now_playing = NowPlayingFile::install(&window, &menu, &settings);
+    let remote = RemoteControl::install(&window, &menu, &settings, radio.get_station());
+    let mqtt = MqttBridge::install(&window, &menu, &settings);
+    let diagnostics = Rc::new(RefCell::new(Diagnostics::default()));
+    diagnostics::install(&window, &menu, &radio, &settings, &diagnostics);
+    #[cfg(target_os = "linux")]
+    let control = ControlInterface::register(app, &window, &radio, &session_history);
+    menu.prepend(Some(&gettext("Search")), Some("win.search"));
     let more_button = MenuButton::builder()
         .icon_name("view-more-symbolic")
-        .tooltip_text("Main Menu")
         .menu_model(&menu)
         .build();
+    a11y::label_icon_button(&more_button, &gettext("Main Menu"));
+    #[cfg(target_os = "macos")]
+    macos::install_menubar(app, &menu);
     let buttons = gtk::Box::new(Orientation::Horizontal, 0);
     buttons.append(&more_button);
     buttons.append(&play_button);
     buttons.append(&pause_button);
     let header = HeaderBar::new();
     header.pack_start(&buttons);
-    header.set_title_widget(Some(&win_title));
+    let track_title = marquee::install(&window, &menu, &win_title, &settings);
+    header.set_title_widget(Some(&track_title));
     header.set_show_title_buttons(false);
     header.add_css_class("cover-tint");
     header.set_height_request(height);
@@ -134,38 +257,135 @@ pub fn build_ui(app: &Application) {
             }
         });
     }
-    win_title.add_controller(title_click);
+    track_title.add_controller(title_click);
+    let lightbox = Rc::new(CoverLightbox::install(&window, &menu, &settings));
+    // Clicking the cover in the popover opens it at full size.
     let close_any_click = GestureClick::new();
     {
         let art = art_popover.clone();
         close_any_click.connect_released(move |_, _, _, _| {
             art.popdown();
+            let _ = adw::prelude::WidgetExt::activate_action(
+                &art,
+                "win.view_cover",
+                None::<&glib::Variant>,
+            );
         });
     }
     art_popover.add_controller(close_any_click);
 
+    search::build_search(&window, &header, &play_history);
+    let volume = volume::install(&window, &menu, &header, &radio, &settings);
+    volume.scroll_on(&art_popover);
+    unplug::install(&window, &menu, &settings);
+    proxy::install(&window, &menu, &radio, &meta, &settings);
+    hook::install(&window, &menu, &events, radio.get_station(), &settings);
+    audiosink::install(&window, &menu, &radio, &settings);
+    equalizer::install(&window, &menu, &radio, &settings);
+    channels::install(&window, &menu, &radio, &settings);
+    fade::install(app, &window, &menu, &radio, &settings);
+    timeouts::install(&window, &menu, &radio, &meta, &settings);
+    #[cfg(all(target_os = "linux", feature = "setup"))]
+    super::setup::install(&window, &menu, &settings);
+    background::install(app, &window, &menu, &settings);
+    #[cfg(target_os = "linux")]
+    mediakeys::install(app, &window, &menu, &settings);
+
     let close_btn = Button::from_icon_name("window-close-symbolic");
-    close_btn.set_action_name(Some("win.quit"));
+    // Closes (or hides, when running in the background) rather than quitting outright.
+    close_btn.set_action_name(Some("window.close"));
+    a11y::label_icon_button(&close_btn, &gettext("Close"));
     header.pack_end(&close_btn);
+    let status = Status::new();
+    header.pack_end(status.widget());
+    timeshift::install(&window, &header, &radio, &meta);
 
     let overlay = gtk::Overlay::new();
     overlay.add_css_class("titlebar-tint");
     overlay.set_height_request(height);
 
+    // Layers from back to front: blurred cover, bars visualizer, headerbar.
+    // Only the sizing box is measured, so the cover cannot grow the titlebar.
+    let sizing = gtk::Box::new(Orientation::Vertical, 0);
+    sizing.set_height_request(height);
+    sizing.set_hexpand(true);
+    overlay.set_child(Some(&sizing));
+
+    let backdrop = Rc::new(Backdrop::new());
+    overlay.add_overlay(backdrop.widget());
+
     // Create bars visualizer and add it behind headerbar
     let (viz, viz_handle) = viz::make_bars_visualizer(48, height);
-    overlay.set_child(Some(&viz));
+    overlay.add_overlay(&viz);
+    viz::install(&window, &menu, &radio, &settings, &viz);
 
     header.add_css_class("viz-transparent");
     header.add_css_class("cover-tint");
     overlay.add_overlay(&header);
     window.set_titlebar(Some(&overlay));
 
-    // Tiny dummy content so GTK can shrink the window
-    let dummy = gtk::Box::new(Orientation::Vertical, 0);
-    dummy.set_height_request(0);
-    dummy.set_vexpand(false);
-    window.set_child(Some(&dummy));
+    // Compact details and position rows under the titlebar; they keep the window small.
+    let details = gtk::Label::builder()
+        .xalign(0.0)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .margin_start(8)
+        .margin_end(8)
+        .margin_top(4)
+        .visible(false)
+        .build();
+    details.add_css_class("caption");
+    let listeners = gtk::Label::builder()
+        .xalign(0.0)
+        .margin_start(8)
+        .margin_end(8)
+        .margin_top(4)
+        .visible(false)
+        .build();
+    listeners.add_css_class("caption");
+    listeners.add_css_class("dim-label");
+    let progress = Rc::new(Progress::new(radio.lag_ms()));
+    let track = gtk::Box::new(Orientation::Vertical, 0);
+    track.append(&listeners);
+    track.append(&details);
+    track.append(progress.widget());
+    let meter = LevelMeter::install(&window, &menu, &track, &radio, &settings);
+    track.append(lyrics.widget());
+    let switcher = StationSwitcher::install(&window, station, &track);
+    let content = gtk::Box::new(Orientation::Vertical, 0);
+    content.set_vexpand(false);
+    content.append(switcher.widget());
+    let placeholder = Placeholder::new(
+        &track,
+        station,
+        (!settings.borrow().connect_on_play).then_some(GatewayState::Connecting),
+    );
+    content.append(placeholder.widget());
+    let network = Network::new(&window, &content);
+    let toasts = Toasts::new(&window, network.widget());
+    let body = adaptive::install(
+        toasts.widget(),
+        &content,
+        switcher.widget(),
+        placeholder.widget(),
+    );
+    window.set_child(Some(&body));
+    let thumb = mini::install(&window, &header, &buttons, &body, &art_picture, &settings);
+    // After every protected action exists.
+    guest::install(&window, &menu, &settings);
+    volume.scroll_on(&thumb);
+    let title_fade = Crossfade::new(&track_title, &[track_title.clone().upcast()]);
+    // The window times the cover fade: the popover is mostly closed, the thumb mostly hidden.
+    let cover_fade = Rc::new(Crossfade::new(
+        &window,
+        &[art_picture.clone().upcast(), thumb.upcast()],
+    ));
+    {
+        let progress = progress.clone();
+        glib::timeout_add_seconds_local(1, move || {
+            progress.tick();
+            glib::ControlFlow::Continue
+        });
+    }
 
     // Poll the channels on the GTK main thread and update the UI.
     {
@@ -174,24 +394,43 @@ pub fn build_ui(app: &Application) {
         let art_picture = art_picture.clone();
         let cover_rx = cover_rx;
         let cover_tx = cover_tx.clone();
-        #[cfg(target_os = "linux")]
         let window = window.clone();
         #[cfg(target_os = "linux")]
         let set_metadata = set_metadata.clone();
 
-        let clear_art_ui = |art_picture: &gtk::Picture,
-                            art_popover: &gtk::Popover,
-                            style_manager: &adw::StyleManager,
-                            css_provider: &gtk::CssProvider| {
-            // Clear old cover so it doesn't stick around
-            art_picture.set_paintable(None::<&adw::gdk::Paintable>);
-
-            // Reset the rest of the UI state
-            art_popover.popdown();
-            style_manager.set_color_scheme(adw::ColorScheme::Default);
-            cover::apply_cover_tint_css_clear(css_provider);
+        let backdrop = backdrop.clone();
+        let radio = radio.clone();
+
+        let clear_art_ui = {
+            let backdrop = backdrop.clone();
+            let radio = radio.clone();
+            let lightbox = lightbox.clone();
+            let cover_fade = cover_fade.clone();
+            move |art_picture: &gtk::Picture,
+                  art_popover: &gtk::Popover,
+                  appearance: &AppearanceManager,
+                  css_provider: &gtk::CssProvider| {
+                // Clear old cover so it doesn't stick around
+                let art_picture = art_picture.clone();
+                cover_fade.swap(move || art_picture.set_paintable(None::<&adw::gdk::Paintable>));
+                backdrop.clear();
+
+                // Reset the rest of the UI state
+                art_popover.popdown();
+                lightbox.set_url(None);
+                appearance.set_cover(None);
+                cover::apply_cover_tint_css_clear(css_provider, radio.get_station().accent_rgb());
+            }
         };
 
+        let inhibitor = Inhibitor::new(app, &window);
+        #[cfg(target_os = "windows")]
+        let smtc = Smtc::install(&window);
+        #[cfg(target_os = "windows")]
+        thumbbar::install(&window);
+        #[cfg(target_os = "macos")]
+        let now_playing_info = NowPlaying::install(&window);
+        let mut playback = PlaybackState::Stopped;
         glib::timeout_add_local(Duration::from_millis(100), move || {
             #[cfg(target_os = "linux")]
             if let Some(ctrl_rx) = &ctrl_rx {
@@ -231,36 +470,164 @@ pub fn build_ui(app: &Application) {
                 }
             }
 
-            for info in rx.try_iter() {
-                win.set_title(&info.artist);
-                win.set_subtitle(&info.title);
-
-                #[cfg(target_os = "linux")]
-                let cover_url = info
-                    .album_cover
-                    .as_ref()
-                    .or(info.artist_image.as_ref())
-                    .map(|s| s.as_str());
-
-                #[cfg(target_os = "linux")]
-                set_metadata(info.title.clone(), info.artist.clone(), cover_url.clone());
-
-                if let Some(url) = info.album_cover.as_ref().or(info.artist_image.as_ref()) {
-                    let tx = cover_tx.clone();
-                    let url = url.to_string();
-                    thread::spawn(move || {
-                        let result =
-                            cover::fetch_cover_bytes_blocking(&url).map_err(|e| e.to_string());
-                        let _ = tx.send(result);
-                    });
-                } else {
-                    clear_art_ui(&art_picture, &art_popover, &style_manager, &css_provider);
+            for event in player_events.try_iter() {
+                diagnostics.borrow_mut().record(&event);
+                // The same track again only refreshes what is shown.
+                let new_track = matches!(event, PlayerEvent::TrackChanged(_));
+                match event {
+                    PlayerEvent::TrackChanged(info) | PlayerEvent::TrackResynced(info) => {
+                        placeholder.track_changed();
+                        {
+                            let win = win.clone();
+                            let (artist, title) = (info.artist.clone(), info.title.clone());
+                            title_fade.swap(move || {
+                                win.set_title(&artist);
+                                win.set_subtitle(&title);
+                            });
+                        }
+                        progress.set_track(info.start_time_utc, info.duration_secs);
+                        let lines = detail_lines(&info);
+                        details.set_text(&lines);
+                        details.set_tooltip_text(Some(&lines));
+                        details.set_visible(!lines.is_empty());
+                        match info.listeners {
+                            Some(count) => {
+                                listeners.set_text(&listener_line(radio.get_station(), count));
+                                listeners.set_visible(true);
+                            }
+                            None => listeners.set_visible(false),
+                        }
+
+                        if new_track {
+                            {
+                                let mut history = session_history.borrow_mut();
+                                if history.len() == SESSION_HISTORY_LEN {
+                                    history.pop_front();
+                                }
+                                history.push_back((*info).clone());
+                            }
+                            play_history.borrow_mut().track_changed(
+                                &info,
+                                radio.get_station(),
+                                playback == PlaybackState::Playing,
+                            );
+                            if let Some(recorder) = recorder.borrow().as_ref() {
+                                recorder.track_changed(&info);
+                            }
+                            notifier.track_changed(&info);
+                            mqtt.track_changed(&info, radio.get_station());
+                            lyrics.track_changed(&info);
+                            requests.track_changed(&info);
+                        }
+                        web_search.track_changed(&info);
+                        favorites.track_changed(&info);
+                        now_playing.track_changed(&info);
+                        remote.track_changed(&info);
+                        #[cfg(target_os = "linux")]
+                        if let Some(control) = control.as_ref() {
+                            control.track_changed(&info);
+                        }
+                        #[cfg(target_os = "windows")]
+                        if let Some(smtc) = smtc.as_ref() {
+                            smtc.track_changed(&info, radio.get_station());
+                        }
+                        #[cfg(target_os = "macos")]
+                        now_playing_info.track_changed(&info, radio.get_station());
+
+                        #[cfg(target_os = "linux")]
+                        let cover_url = info
+                            .album_cover
+                            .as_ref()
+                            .or(info.artist_image.as_ref())
+                            .map(|s| s.as_str());
+
+                        #[cfg(target_os = "linux")]
+                        set_metadata(info.title.clone(), info.artist.clone(), cover_url);
+
+                        if let Some(url) = info.album_cover.as_ref().or(info.artist_image.as_ref())
+                        {
+                            lightbox.set_url(Some(url));
+                            cover::load_cover_async(
+                                url.to_string(),
+                                settings.borrow().proxy.clone(),
+                                cover_tx.clone(),
+                            );
+                        } else {
+                            clear_art_ui(&art_picture, &art_popover, &appearance, &css_provider);
+                        }
+                    }
+                    PlayerEvent::StateChanged(state) => {
+                        playback = state;
+                        // Nothing keeps the track info current until the next Play.
+                        if state == PlaybackState::Stopped && settings.borrow().connect_on_play {
+                            details.set_visible(false);
+                            listeners.set_visible(false);
+                            progress.clear();
+                            clear_art_ui(&art_picture, &art_popover, &appearance, &css_provider);
+                            placeholder.disconnected();
+                        }
+                        placeholder.set_playback(state);
+                        play_history
+                            .borrow_mut()
+                            .set_playing(state == PlaybackState::Playing);
+                        status.set_playback(state);
+                        inhibitor.set_playback(state);
+                        remote.set_playback(state);
+                        mqtt.set_playback(state);
+                        meter.set_playback(state);
+                        network.set_playback(state);
+                        #[cfg(target_os = "linux")]
+                        set_playback(state);
+                        #[cfg(target_os = "windows")]
+                        if let Some(smtc) = smtc.as_ref() {
+                            smtc.set_playback(state);
+                        }
+                        #[cfg(target_os = "windows")]
+                        thumbbar::set_playback(state);
+                        #[cfg(target_os = "macos")]
+                        now_playing_info.set_playback(state);
+                    }
+                    PlayerEvent::GatewayStateChanged(state) => {
+                        status.set_gateway(state);
+                        placeholder.set_gateway(state);
+                    }
+                    PlayerEvent::StationChanged(station) => {
+                        switcher.station_changed(station);
+                        placeholder.station_changed(station);
+                        notifier.sync_station();
+                        remote.set_station(station);
+                        // The count belongs to the previous station until its next update.
+                        listeners.set_text(station.display_name());
+                    }
+                    PlayerEvent::OutputLost => unplug::output_lost(&window, &settings, playback),
+                    PlayerEvent::Silence if settings.borrow().notify_silence => {
+                        toasts.show_error(&gettext("The stream went silent, reconnecting…"))
+                    }
+                    PlayerEvent::Silence => {}
+                    PlayerEvent::BufferLow => {}
+                    // Restart from scratch rather than wait for the dead socket to time out.
+                    PlayerEvent::Resumed
+                        if playback != PlaybackState::Paused
+                            && playback != PlaybackState::Stopped =>
+                    {
+                        let _ = adw::prelude::WidgetExt::activate_action(
+                            &window,
+                            "win.retry",
+                            None::<&glib::Variant>,
+                        );
+                    }
+                    PlayerEvent::Resumed => {}
+                    PlayerEvent::Error(message) => {
+                        placeholder.error(&message);
+                        toasts.show_error(&message);
+                    }
                 }
             }
 
             for result in cover_rx.try_iter() {
                 match result {
                     Ok(bytes_vec) => {
+                        backdrop::blur_async(bytes_vec.clone(), backdrop_tx.clone());
                         let bytes = glib::Bytes::from_owned(bytes_vec);
                         let stream = MemoryInputStream::from_bytes(&bytes);
                         match Pixbuf::from_stream_at_scale(
@@ -272,26 +639,35 @@ pub fn build_ui(app: &Application) {
                         ) {
                             Ok(pixbuf) => {
                                 let texture = Texture::for_pixbuf(&pixbuf);
-                                art_picture.set_paintable(Some(&texture));
+                                {
+                                    let art_picture = art_picture.clone();
+                                    cover_fade
+                                        .swap(move || art_picture.set_paintable(Some(&texture)));
+                                }
+                                now_playing.cover_loaded(&pixbuf);
 
                                 let (r, g, b) = cover::avg_rgb_from_pixbuf(&pixbuf);
                                 let (r, g, b) = cover::boost_saturation(r, g, b, 1.15);
                                 let cover_is_light = cover::is_light_color(r, g, b);
 
-                                style_manager.set_color_scheme(if cover_is_light {
-                                    adw::ColorScheme::ForceLight
-                                } else {
-                                    adw::ColorScheme::ForceDark
-                                });
+                                appearance.set_cover(Some(cover_is_light));
 
-                                cover::apply_color(&css_provider, (r, g, b), cover_is_light);
+                                let accent = cover::dominant_rgb_from_pixbuf(&pixbuf);
+                                let accent =
+                                    cover::boost_saturation(accent.0, accent.1, accent.2, 1.15);
+                                cover::apply_color(
+                                    &css_provider,
+                                    (r, g, b),
+                                    cover_is_light,
+                                    accent,
+                                );
                             }
                             Err(err) => {
                                 eprintln!("Failed to decode cover pixbuf: {err}");
                                 clear_art_ui(
                                     &art_picture,
                                     &art_popover,
-                                    &style_manager,
+                                    &appearance,
                                     &css_provider,
                                 );
                             }
@@ -299,7 +675,17 @@ pub fn build_ui(app: &Application) {
                     }
                     Err(err) => {
                         eprintln!("Failed to load cover bytes: {err}");
-                        clear_art_ui(&art_picture, &art_popover, &style_manager, &css_provider);
+                        clear_art_ui(&art_picture, &art_popover, &appearance, &css_provider);
+                    }
+                }
+            }
+
+            for result in backdrop_rx.try_iter() {
+                match result {
+                    Ok(image) => backdrop.show(image),
+                    Err(err) => {
+                        eprintln!("Failed to blur cover: {err}");
+                        backdrop.clear();
                     }
                 }
             }
@@ -318,6 +704,9 @@ pub fn build_ui(app: &Application) {
         let mut smooth = vec![0.0f32; spectrum_bits.len()];
 
         glib::timeout_add_local(Duration::from_millis(33), move || {
+            if !viz.is_visible() {
+                return glib::ControlFlow::Continue;
+            }
             let mut bars = vec![0.0f32; spectrum_bits.len()];
             for i in 0..bars.len() {
                 bars[i] = f32::from_bits(spectrum_bits[i].load(Ordering::Relaxed)).clamp(0.0, 1.0);
@@ -333,5 +722,28 @@ pub fn build_ui(app: &Application) {
         });
     }
 
-    window.present();
+    if !hidden {
+        window.present();
+    }
+}
+
+/// Station name with the live listener count, e.g. "J-POP · 321 listening".
+fn listener_line(station: Station, count: u32) -> String {
+    let listening = n!("{} listening", "{} listening", count);
+    format!("{} · {listening}", station.display_name())
+}
+
+/// "From" / "Requested by" / event lines for the current track; empty when none apply.
+fn detail_lines(info: &TrackInfo) -> String {
+    let mut lines = Vec::new();
+    if let Some(source) = &info.source {
+        lines.push(t!("From: {}", source));
+    }
+    if let Some(requester) = &info.requester {
+        lines.push(t!("Requested by: {}", requester));
+    }
+    if let Some(event) = &info.event {
+        lines.push(t!("Event: {}", event));
+    }
+    lines.join("\n")
 }